@@ -8,9 +8,49 @@ signal connected()
 signal connection_failed(error: String)
 signal payment_sent(invoice: String, result: Dictionary)
 signal payment_received(amount: int, description: String)
+## Emitted along with payment_received; `details` has the payer's `comment` and `payer`
+## when the payment carried them (e.g. zaps)
+signal payment_received_detailed(amount: int, description: String, details: Dictionary)
 signal invoice_created(invoice: String, amount: int)
 signal balance_changed(old_balance: int, new_balance: int)
 signal breez_ready()
+signal purchase_completed(product: BreezProduct, payment_id: String)
+signal wallet_lock_changed(locked: bool)
+signal payments_received_batch(payments: Array)
+signal payment_resolved(payment_id: String, status: String)
+## Batched payment_resolved, as {payment_id, status} Dictionaries (see set_signal_batching)
+signal payments_resolved_batch(resolved: Array)
+signal deposit_confirmed(txid: String, vout: int)
+signal deposit_claim_retried(txid: String, vout: int, result: Dictionary)
+signal payments_chunk(chunk: Array, done: bool)
+signal batch_payout_progress(request_id: int, done: int, total: int)
+## Result of send_spark_batch: one Dictionary per payout, in input order
+signal batch_payout_finished(request_id: int, results: Array)
+signal zap_received(payment_id: String, amount: int, zapper_pubkey: String, comment: String)
+## Result of pay_invoice_async, with the same fields pay_invoice returns
+signal payment_result(request_id: int, result: Dictionary)
+## Emitted after each start_auto_sync cycle; `error` is empty on success
+signal wallet_synced(success: bool, error: String)
+## Result of call_async: `method`, `success`, `error`/`error_code` and the method's `result`
+signal request_completed(request_id: int, result: Dictionary)
+## SDK events, pushed as they happen; payments use the list_payments fields
+signal sdk_payment_received(payment: Dictionary)
+signal sdk_payment_sent(payment: Dictionary)
+## Batched sdk_payment_received / sdk_payment_sent (see set_signal_batching)
+signal sdk_payments_received_batch(payments: Array)
+signal sdk_payments_sent_batch(payments: Array)
+signal deposit_claimed(deposit: Dictionary)
+signal synced()
+## Result of wait_for_payment_async
+signal payment_confirmed(request_id: int, payment: Dictionary)
+signal payment_timeout(request_id: int)
+## Any failed call or background task (method, error_code, error_category, message, ...)
+signal sdk_error(error: Dictionary)
+## Health checks (check_connection, auto-sync) stopped or started reaching the Breez service
+signal connection_lost(error: String)
+signal connection_restored()
+## Breez SDK log record forwarded after init_logging, with secrets redacted
+signal log_message(level: String, target: String, message: String)
 
 # Internal Rust node
 var _breez_rust: BreezNode  # The actual Rust binding
@@ -18,17 +58,68 @@ var _last_balance: int = 0
 var _is_monitoring: bool = false
 var _timer: Timer
 
+# Signal batching (0 = emit every event immediately)
+var _batch_window_ms: int = 0
+var _batch_timer: Timer
+var _batch_received: Array = []
+var _batch_balance_from: int = -1
+var _batch_balance_to: int = 0
+var _batch_resolved: Array = []
+var _batch_sdk_received: Array = []
+var _batch_sdk_sent: Array = []
+# An SDK payment event arrived; one _check_for_changes picks it (and any others) up
+var _changes_pending := false
+
+# pay_invoice_async request id -> invoice, for payment_sent
+var _async_invoices: Dictionary = {}
+
 # State
 var initialized := false
 
 # Config
 @export var auto_monitor_payments: bool = true
 @export var check_interval: float = 2.0
+@export var breez_config: BreezConfig
+@export var products: Array[BreezProduct] = []
+## Keep the connection alive across scene changes; new nodes pick it up in _ready
+@export var persistent_connection: bool = false
 
 func _ready():
 	# Create the Rust BreezNode
 	_breez_rust = BreezNode.new()
 	add_child(_breez_rust)
+	_breez_rust.purchase_completed.connect(func(product, payment_id): purchase_completed.emit(product, payment_id))
+	_breez_rust.wallet_lock_changed.connect(func(locked): wallet_lock_changed.emit(locked))
+	_breez_rust.payment_resolved.connect(_on_payment_resolved)
+	_breez_rust.zap_received.connect(func(payment_id, amount, zapper_pubkey, comment): zap_received.emit(payment_id, amount, zapper_pubkey, comment))
+	_breez_rust.batch_payout_progress.connect(
+		func(request_id, done, total): batch_payout_progress.emit(request_id, done, total))
+	_breez_rust.batch_payout_finished.connect(
+		func(request_id, results): batch_payout_finished.emit(request_id, results))
+	_breez_rust.payments_chunk.connect(func(chunk, done): payments_chunk.emit(chunk, done))
+	_breez_rust.deposit_confirmed.connect(func(txid, vout): deposit_confirmed.emit(txid, vout))
+	_breez_rust.deposit_claim_retried.connect(func(txid, vout, result): deposit_claim_retried.emit(txid, vout, result))
+	_breez_rust.connected.connect(func(): _finish_connect(true))
+	_breez_rust.connect_failed.connect(_on_connect_failed)
+	_breez_rust.payment_result.connect(_on_payment_result)
+	_breez_rust.wallet_synced.connect(func(success, error): wallet_synced.emit(success, error))
+	_breez_rust.request_completed.connect(func(request_id, result): request_completed.emit(request_id, result))
+	_breez_rust.payment_received.connect(_on_sdk_payment_received)
+	_breez_rust.payment_sent.connect(_on_sdk_payment_sent)
+	_breez_rust.deposit_claimed.connect(func(deposit): deposit_claimed.emit(deposit))
+	_breez_rust.synced.connect(func(): synced.emit())
+	_breez_rust.payment_confirmed.connect(func(request_id, payment): payment_confirmed.emit(request_id, payment))
+	_breez_rust.payment_timeout.connect(func(request_id): payment_timeout.emit(request_id))
+	_breez_rust.sdk_error.connect(func(error): sdk_error.emit(error))
+	_breez_rust.connection_lost.connect(func(error): connection_lost.emit(error))
+	_breez_rust.connection_restored.connect(func(): connection_restored.emit())
+	_breez_rust.log_message.connect(func(level, target, message): log_message.emit(level, target, message))
+	if not products.is_empty():
+		_breez_rust.register_products(products)
+	if persistent_connection:
+		_breez_rust.set_persistent_connection(true)
+		if _breez_rust.attach_existing():
+			_finish_connect.call_deferred(true)
 	
 	# Setup monitoring timer
 	if auto_monitor_payments:
@@ -37,6 +128,11 @@ func _ready():
 		_timer.timeout.connect(_check_for_changes)
 		add_child(_timer)
 	
+	_batch_timer = Timer.new()
+	_batch_timer.one_shot = true
+	_batch_timer.timeout.connect(_flush_signal_batch)
+	add_child(_batch_timer)
+	
 	print("Breez Node ready!")
 
 ## Initialize/Connect to Lightning Network
@@ -45,15 +141,94 @@ func init(config: Dictionary) -> bool:
 	var api_key = config.get("api_key", "")
 	var network = config.get("network", "mainnet")
 	var storage_dir = config.get("storage_dir", "./breez_data")
+	var passphrase = config.get("passphrase", "")
 	
-	return connect_to_network(mnemonic, api_key, network, storage_dir)
+	return connect_to_network(mnemonic, api_key, network, storage_dir, passphrase)
+
+## New 12 or 24 word recovery phrase for a brand-new wallet; never printed or logged
+func generate_mnemonic(word_count: int = 12) -> String:
+	return _breez_rust.generate_mnemonic(word_count)
+
+## Check a typed recovery phrase: valid, word_count and error/invalid_word_index
+func validate_mnemonic(mnemonic: String) -> Dictionary:
+	return _breez_rust.validate_mnemonic(mnemonic)
 
 ## Connect to network (modern API)
-func connect_to_network(mnemonic: String, api_key: String, network: String = "mainnet", storage_dir: String = "./breez_data") -> bool:
+## passphrase is the optional BIP39 "25th word"; a wrong one opens a different, empty wallet
+## A connected wallet makes it fail with ALREADY_CONNECTED unless `force` disconnects it first
+func connect_to_network(mnemonic: String, api_key: String, network: String = "mainnet", storage_dir: String = "./breez_data", passphrase: String = "", force: bool = false) -> bool:
+	print("[Breez] Connecting to network...")
+	if force:
+		disconnect_sdk()
+	
+	var success = _breez_rust.connect_sdk_opts(mnemonic, api_key, network, storage_dir, {"passphrase": passphrase})
+	_finish_connect(success)
+	return success
+
+## Connect to network, returning success or error_code/error_message explaining the failure
+func connect_to_network_detailed(mnemonic: String, api_key: String, network: String = "mainnet", storage_dir: String = "./breez_data", passphrase: String = "", force: bool = false) -> Dictionary:
 	print("[Breez] Connecting to network...")
+	if force:
+		disconnect_sdk()
 	
-	var success = _breez_rust.connect_sdk(mnemonic, api_key, network, storage_dir)
+	var result = _breez_rust.connect_sdk_detailed_opts(mnemonic, api_key, network, storage_dir, {"passphrase": passphrase})
+	_finish_connect(result.get("success", false))
+	return result
+
+## Connect without blocking the frame; `connected` or `connection_failed` follows
+func connect_to_network_async(mnemonic: String, api_key: String, network: String = "mainnet", storage_dir: String = "./breez_data", passphrase: String = "") -> bool:
+	print("[Breez] Connecting to network in the background...")
+	return _breez_rust.connect_sdk_async_opts(mnemonic, api_key, network, storage_dir, {"passphrase": passphrase})
+
+## Connect with a phrase saved by store_mnemonic_encrypted; it is decrypted in Rust and
+## never passes through GDScript. error_code WRONG_PASSWORD or CORRUPTED_FILE on failure
+func connect_from_encrypted(path: String, password: String, api_key: String, network: String = "mainnet", storage_dir: String = "./breez_data") -> Dictionary:
+	print("[Breez] Connecting to network...")
 	
+	var result = _breez_rust.connect_sdk_from_encrypted(path, password, api_key, network, storage_dir)
+	_finish_connect(result.get("success", false))
+	return result
+
+## Save a recovery phrase encrypted with a password (Argon2id, XChaCha20-Poly1305), e.g. to "user://wallet.key"
+func store_mnemonic_encrypted(path: String, mnemonic: String, password: String) -> bool:
+	return _breez_rust.store_mnemonic_encrypted(path, mnemonic, password)
+
+## Load a phrase saved by store_mnemonic_encrypted; "" on failure, see get_last_error()
+func load_mnemonic_encrypted(path: String, password: String) -> String:
+	return _breez_rust.load_mnemonic_encrypted(path, password)
+
+## Connect using a BreezConfig resource (the mnemonic is never stored in it)
+func connect_with_config(cfg: BreezConfig, mnemonic: String) -> bool:
+	print("[Breez] Connecting to network with config...")
+	
+	var success = _breez_rust.connect_with_config(cfg, mnemonic)
+	_finish_connect(success)
+	return success
+
+## Connect with SDK settings such as {"api_key": key, "sync_interval_secs": 30}; unknown keys
+## fail with UNKNOWN_OPTION instead of being ignored
+func connect_with_config_dict(config: Dictionary, mnemonic: String, storage_dir: String = "") -> Dictionary:
+	print("[Breez] Connecting to network with config...")
+	
+	var result = _breez_rust.connect_with_config_dict(config, mnemonic, storage_dir)
+	_finish_connect(result.get("success", false))
+	return result
+
+## Connect to a per-save-slot wallet profile, disconnecting the current wallet first
+func connect_profile(profile_name: String, mnemonic: String, api_key: String, network: String = "mainnet") -> bool:
+	print("[Breez] Connecting profile %s..." % profile_name)
+	disconnect_sdk()
+	
+	var success = _breez_rust.connect_profile(profile_name, mnemonic, api_key, network)
+	_finish_connect(success)
+	return success
+
+## Provide the mnemonic at runtime; connects immediately if `breez_config.autoconnect` is set
+func set_mnemonic(mnemonic: String) -> void:
+	if breez_config and breez_config.autoconnect and not initialized:
+		connect_with_config(breez_config, mnemonic)
+
+func _finish_connect(success: bool) -> void:
 	if success:
 		initialized = true
 		_last_balance = get_balance()
@@ -67,15 +242,29 @@ func connect_to_network(mnemonic: String, api_key: String, network: String = "ma
 	else:
 		emit_signal("connection_failed", "Failed to connect to Breez SDK")
 		print("[Breez] ❌ Connection failed")
-	
-	return success
 
-## Get balance in satoshis
+func _on_connect_failed(error: String) -> void:
+	emit_signal("connection_failed", error)
+	print("[Breez] ❌ Connection failed: %s" % error)
+
+## Get balance in satoshis (deprecated: 0 on failure too; use get_balance_detailed)
 func get_balance() -> int:
 	if not initialized:
 		return 0
 	return _breez_rust.get_balance()
 
+## Get balance with success/error_code, so a failure is not mistaken for 0 sats
+func get_balance_detailed(ensure_synced: bool = true) -> Dictionary:
+	if not initialized:
+		return {"success": false, "error": "Not initialized", "error_code": "NOT_CONNECTED"}
+	return _breez_rust.get_balance_detailed(ensure_synced)
+
+## Balance, pending amounts, network and identity_pubkey in one call
+func get_info(ensure_synced: bool = true) -> Dictionary:
+	if not initialized:
+		return {"success": false, "error": "Not initialized", "error_code": "NOT_CONNECTED"}
+	return _breez_rust.get_info(ensure_synced)
+
 ## Create Lightning invoice
 func create_invoice(amount: int, description: String) -> String:
 	if not initialized:
@@ -93,6 +282,18 @@ func create_invoice(amount: int, description: String) -> String:
 	
 	return invoice
 
+## Create a Lightning invoice with payment_request, payment_hash, expiry_timestamp,
+## amount_sats and fee_sats; expiry_secs other than 0 is not supported by the SDK yet
+func create_invoice_detailed(amount: int, description: String, expiry_secs: int = 0) -> Dictionary:
+	if not initialized:
+		push_error("Breez SDK not initialized")
+		return {"success": false, "error": "Not initialized", "error_code": "NOT_CONNECTED"}
+	
+	var result = _breez_rust.create_invoice_detailed(amount, description, expiry_secs)
+	if result.get("success", false):
+		emit_signal("invoice_created", result.payment_request, amount)
+	return result
+
 ## Pay a Lightning invoice
 func pay_invoice(invoice: String, timeout: int = 30) -> Dictionary:
 	if not initialized:
@@ -101,27 +302,429 @@ func pay_invoice(invoice: String, timeout: int = 30) -> Dictionary:
 	
 	print("[Breez] Paying invoice...")
 	var result = _breez_rust.pay_invoice(invoice, timeout)
+	_report_invoice_payment(invoice, result)
+	return result
+
+## Pay a Lightning invoice with an options Dictionary, e.g. {"amount_sats": 500} for an
+## amountless invoice or {"max_fee_sats": 20} to cap the fee
+func pay_invoice_opts(invoice: String, options: Dictionary = {}) -> Dictionary:
+	if not initialized:
+		push_error("Breez SDK not initialized")
+		return {"success": false, "error": "Not initialized"}
+	
+	print("[Breez] Paying invoice...")
+	var result = _breez_rust.pay_invoice_opts(invoice, options)
+	if not result.get("dry_run", false):
+		_report_invoice_payment(invoice, result)
+	return result
+
+func _report_invoice_payment(invoice: String, result: Dictionary) -> void:
+	if result.get("success", false):
+		emit_signal("payment_sent", invoice, result)
+		print("[Breez] ✅ Payment sent")
+	elif not result.get("final", true):
+		print("[Breez] ⏳ Payment outcome pending, wait for payment_resolved")
+	else:
+		print("[Breez] ❌ Payment failed: ", result.get("error", "Unknown"))
+
+## Quote a payment so its fee can be shown before sending; pass the returned
+## `prepare_id` to send_prepared_payment before `expires_at`
+func prepare_payment(payment_request: String, amount: int = 0) -> Dictionary:
+	return _breez_rust.prepare_payment(payment_request, amount)
+
+## Send a payment quoted by prepare_payment (options: timeout_secs, prefer_spark, max_fee_sats)
+func send_prepared_payment(prepare_id: String, options: Dictionary = {}) -> Dictionary:
+	if not initialized:
+		push_error("Breez SDK not initialized")
+		return {"success": false, "error": "Not initialized"}
+	
+	var result = _breez_rust.send_prepared_payment(prepare_id, options)
+	if result.get("success", false):
+		print("[Breez] ✅ Payment sent")
+	return result
+
+## Pay an invoice, Bitcoin address or Spark address; with dry_run nothing is sent
+func pay(destination: String, amount: int = 0, dry_run: bool = false) -> Dictionary:
+	if not initialized:
+		push_error("Breez SDK not initialized")
+		return {"success": false, "error": "Not initialized"}
+	
+	return pay_opts(destination, {"amount_sats": amount, "dry_run": dry_run})
+
+## Pay with an options Dictionary, e.g. {"max_fee_sats": 50, "prefer_spark": true}
+func pay_opts(destination: String, options: Dictionary = {}) -> Dictionary:
+	if not initialized:
+		push_error("Breez SDK not initialized")
+		return {"success": false, "error": "Not initialized"}
+	
+	var result = _breez_rust.pay_opts(destination, options)
+	if result.get("success", false) and not result.get("dry_run", false):
+		emit_signal("payment_sent", destination, result)
+	return result
+
+## Send sats directly to a Spark address, e.g. one from another player's get_spark_address()
+func send_to_spark_address(address: String, amount: int) -> Dictionary:
+	if not initialized:
+		push_error("Breez SDK not initialized")
+		return {"success": false, "error": "Not initialized"}
+	
+	var result = _breez_rust.send_to_spark_address(address, amount)
+	if result.get("success", false):
+		emit_signal("payment_sent", address, result)
+	return result
+
+## Withdraw to a Bitcoin address; `fee` is {"type": "fixed", "amount": n} or
+## {"type": "rate", "sat_per_vbyte": n} (empty for medium confirmation speed)
+func send_onchain(address: String, amount: int, fee: Dictionary = {}) -> Dictionary:
+	if not initialized:
+		push_error("Breez SDK not initialized")
+		return {"success": false, "error": "Not initialized"}
+	
+	var result = _breez_rust.send_onchain_with_fee(address, amount, fee)
+	if result.get("success", false):
+		emit_signal("payment_sent", address, result)
+	return result
+
+## Pay a lightning address ("name@domain") with an optional comment
+func pay_lightning_address(address: String, amount: int, comment: String = "") -> Dictionary:
+	if not initialized:
+		push_error("Breez SDK not initialized")
+		return {"success": false, "error": "Not initialized"}
+	
+	var result = _breez_rust.pay_lightning_address(address, amount, comment)
+	if result.get("success", false):
+		emit_signal("payment_sent", address, result)
+	return result
+
+## Pay an LNURL-pay endpoint with an optional comment
+func pay_lnurl(lnurl: String, amount: int, comment: String = "") -> Dictionary:
+	if not initialized:
+		push_error("Breez SDK not initialized")
+		return {"success": false, "error": "Not initialized"}
+	
+	var result = _breez_rust.pay_lnurl(lnurl, amount, comment)
+	if result.get("success", false):
+		emit_signal("payment_sent", lnurl, result)
+	return result
+
+## Check whether a lightning address username is free
+func check_lightning_address_available(username: String) -> Dictionary:
+	if not initialized:
+		push_error("Breez SDK not initialized")
+		return {"success": false, "error": "Not initialized"}
+	return _breez_rust.check_lightning_address_available(username)
+
+## Register a lightning address ("username@domain") for this wallet
+func register_lightning_address(username: String, description: String = "") -> Dictionary:
+	if not initialized:
+		push_error("Breez SDK not initialized")
+		return {"success": false, "error": "Not initialized"}
+	return _breez_rust.register_lightning_address(username, description)
+
+## The registered lightning address, or {} if none
+func get_lightning_address() -> Dictionary:
+	if not initialized:
+		return {}
+	return _breez_rust.get_lightning_address()
+
+## Release the registered lightning address
+func delete_lightning_address() -> bool:
+	if not initialized:
+		push_error("Breez SDK not initialized")
+		return false
+	return _breez_rust.delete_lightning_address()
+
+## Decode a BOLT11 invoice locally; `payable` also checks network and expiry
+func decode_invoice(invoice: String) -> Dictionary:
+	if not initialized:
+		return BreezNode.decode_invoice(invoice)
+	return _breez_rust.check_invoice(invoice)
+
+## Identify a pasted or scanned payment string (type plus its fields)
+func parse_input(input: String) -> Dictionary:
+	if not initialized:
+		return {"type": "unknown", "input": input, "error": "Not initialized"}
+	return _breez_rust.parse_input(input)
+
+## The most recent failure of any call or background task, or {} if none
+func get_last_error() -> Dictionary:
+	return _breez_rust.get_last_error()
+
+## Unclaimed on-chain deposits with their confirmations and why a claim failed
+func list_unclaimed_deposits() -> Array:
+	if not initialized:
+		return []
+	return _breez_rust.list_unclaimed_deposits()
+
+## Claim one deposit; fee is {} for any fee, {"type": "fixed", "value": sats}
+## or {"type": "rate", "value": sat_per_vbyte}. The result has fee_sats and max_fee
+func claim_deposit(txid: String, vout: int, fee: Dictionary = {}) -> Dictionary:
+	if not initialized:
+		push_error("Breez SDK not initialized")
+		return {"success": false, "error": "Not initialized"}
+	return _breez_rust.claim_deposit_with_fee(txid, vout, fee)
+
+## Claim every unclaimed deposit; fee is {} for any fee, {"type": "fixed", "amount": n}
+## or {"type": "rate", "sat_per_vbyte": n} per claim. One result Dictionary per deposit
+func claim_all_deposits(fee: Dictionary = {}) -> Array:
+	if not initialized:
+		push_error("Breez SDK not initialized")
+		return []
+	return _breez_rust.claim_all_deposits_with_fee(fee)
+
+## Fiat currencies with code, name, symbol and fraction_digits, sorted by code
+func list_fiat_currencies() -> Array:
+	if not initialized:
+		push_error("Breez SDK not initialized")
+		return []
+	return _breez_rust.list_fiat_currencies()
+
+## BTC rates with code, rate, rate_timestamp and stale; cached rates younger than
+## max_age_secs are returned without fetching
+func fetch_fiat_rates(max_age_secs: int = 300) -> Array:
+	if not initialized:
+		push_error("Breez SDK not initialized")
+		return []
+	return _breez_rust.fetch_fiat_rates(max_age_secs)
+
+## Balance in a fiat currency, e.g. get_balance_fiat("USD"); NAN (check with is_nan)
+## for an unknown currency or when no rate is available, never a misleading 0.0
+func get_balance_fiat(currency_code: String, max_age_secs: int = 300) -> float:
+	if not initialized:
+		push_error("Breez SDK not initialized")
+		return NAN
+	var result: Dictionary = _breez_rust.get_balance_fiat_with_max_age(currency_code, max_age_secs)
+	if not result.get("success", false):
+		push_error("Breez: %s" % result.get("error", "No fiat rate"))
+		return NAN
+	return result["value"]
+
+## Send an unclaimed deposit back to a Bitcoin address; fee is {} for the recommended
+## rate, {"type": "fixed", "amount": n} or {"type": "rate", "sat_per_vbyte": n}
+func refund_deposit(txid: String, vout: int, destination_address: String, fee: Dictionary = {}) -> Dictionary:
+	if not initialized:
+		push_error("Breez SDK not initialized")
+		return {"success": false, "error": "Not initialized"}
+	return _breez_rust.refund_deposit(txid, vout, destination_address, fee)
+
+## Send the whole balance to a Bitcoin address, Spark address or amountless invoice
+func drain_wallet(destination: String, fee: Dictionary = {}) -> Dictionary:
+	if not initialized:
+		push_error("Breez SDK not initialized")
+		return {"success": false, "error": "Not initialized"}
 	
+	var result = _breez_rust.drain_wallet(destination, fee)
+	if result.get("success", false):
+		emit_signal("payment_sent", destination, result)
+	return result
+
+## List payments, newest first (limit 0 for all)
+func list_payments(offset: int = 0, limit: int = 0) -> Array:
+	if not initialized:
+		return []
+	return _breez_rust.list_payments(offset, limit)
+
+## list_payments as BreezPayment objects with typed properties
+func list_payments_typed(offset: int = 0, limit: int = 0) -> Array[BreezPayment]:
+	if not initialized:
+		return []
+	return _breez_rust.list_payments_typed(offset, limit)
+
+## Look up a payment by id: the list_payments entry with found: true, or {"found": false}
+func get_payment(payment_id: String) -> Dictionary:
+	if not initialized:
+		return {"found": false, "success": false, "error": "Not initialized", "error_code": "NOT_CONNECTED"}
+	return _breez_rust.get_payment(payment_id)
+
+## List payments narrowed by payment_type ("send"/"receive"), status and time range ("" and 0 for no filter)
+func list_payments_filtered(offset: int = 0, limit: int = 0, payment_type: String = "", status: String = "", from_timestamp: int = 0, to_timestamp: int = 0) -> Array:
+	if not initialized:
+		return []
+	return _breez_rust.list_payments_filtered(offset, limit, payment_type, status, from_timestamp, to_timestamp)
+
+## Write the payment history to a CSV file (absolute or user:// path; 0 timestamps for no bound)
+func export_payments_csv(path: String, from_timestamp: int = 0, to_timestamp: int = 0) -> Dictionary:
+	if not initialized:
+		push_error("Breez SDK not initialized")
+		return {"success": false, "error": "Not initialized"}
+	return _breez_rust.export_payments_csv(path, from_timestamp, to_timestamp)
+
+## Write the payment history to a JSON file (absolute or user:// path; 0 timestamps for no bound)
+func export_payments_json(path: String, from_timestamp: int = 0, to_timestamp: int = 0) -> Dictionary:
+	if not initialized:
+		push_error("Breez SDK not initialized")
+		return {"success": false, "error": "Not initialized"}
+	return _breez_rust.export_payments_json(path, from_timestamp, to_timestamp)
+
+## Stream the payment history through payments_chunk, one chunk per frame
+func stream_payments(filters: Dictionary = {}, chunk_size: int = 100) -> bool:
+	if not initialized:
+		push_error("Breez SDK not initialized")
+		return false
+	return _breez_rust.stream_payments(filters, chunk_size)
+
+## Pay a Lightning invoice without blocking the frame; returns a request id for payment_result
+func pay_invoice_async(invoice: String, timeout: int = 30) -> int:
+	if not initialized:
+		push_error("Breez SDK not initialized")
+		return -1
+	
+	print("[Breez] Paying invoice in the background...")
+	var request_id = _breez_rust.pay_invoice_async(invoice, timeout)
+	_async_invoices[request_id] = invoice
+	return request_id
+
+func _on_payment_result(request_id: int, result: Dictionary) -> void:
+	var invoice = _async_invoices.get(request_id, "")
+	_async_invoices.erase(request_id)
 	if result.get("success", false):
 		emit_signal("payment_sent", invoice, result)
 		print("[Breez] ✅ Payment sent")
+	elif not result.get("final", true):
+		print("[Breez] ⏳ Payment outcome pending, wait for payment_resolved")
 	else:
 		print("[Breez] ❌ Payment failed: ", result.get("error", "Unknown"))
+	emit_signal("payment_result", request_id, result)
+
+## Pay up to 64 Spark addresses ({address, amount_sats, note}) without blocking the frame;
+## returns a request id for batch_payout_progress and batch_payout_finished
+func send_spark_batch(payouts: Array[Dictionary]) -> int:
+	return _breez_rust.send_spark_batch(payouts)
+
+## Run get_balance, sync_wallet, list_payments, create_invoice, claim_deposit or pay_invoice
+## in the background; returns a request id for request_completed
+func call_async(method: String, args: Array = []) -> int:
+	return _breez_rust.call_async(method, args)
+
+## Await a call_async result: `var result = await breez.request("get_balance")`
+func request(method: String, args: Array = []) -> Dictionary:
+	var request_id = call_async(method, args)
+	while true:
+		var completed: Array = await request_completed
+		if completed[0] == request_id:
+			return completed[1]
+	return {}
+
+## Wait for an invoice to be paid, emitting payment_confirmed or payment_timeout with the
+## returned request id (timeout 0 waits until the invoice expires)
+func wait_for_payment_async(invoice: String, timeout: int = 0) -> int:
+	return _breez_rust.wait_for_payment_async(invoice, timeout)
+
+## Stop a wait_for_payment_async wait, e.g. when the player leaves the shop
+func cancel_wait(request_id: int) -> bool:
+	return _breez_rust.cancel_wait(request_id)
+
+## Sync the wallet in the background every interval_secs, emitting wallet_synced
+func start_auto_sync(interval_secs: int) -> void:
+	_breez_rust.start_auto_sync(interval_secs)
+
+func stop_auto_sync() -> void:
+	_breez_rust.stop_auto_sync()
+
+## Open the wallet again with the last connect's credentials, e.g. after returning from the
+## background; returns the same Dictionary as connect_to_network_detailed
+func reconnect() -> Dictionary:
+	print("[Breez] Reconnecting...")
 	
+	var result = _breez_rust.reconnect()
+	_finish_connect(result.get("success", false))
 	return result
 
+## Whether the Breez service answers within 5 s: success, reachable, latency_ms
+func check_connection() -> Dictionary:
+	if not initialized:
+		push_error("Breez SDK not initialized")
+		return {"success": false, "error": "Not initialized", "error_code": "NOT_CONNECTED"}
+	return _breez_rust.check_connection()
+
+## Lock outgoing payments until unlock_wallet() is called with the PIN
+func lock_wallet() -> bool:
+	return _breez_rust.lock_wallet()
+
+func unlock_wallet(pin: String) -> bool:
+	return _breez_rust.unlock_wallet(pin)
+
+func set_wallet_pin(pin: String) -> bool:
+	return _breez_rust.set_wallet_pin(pin)
+
+## Sends and claims interrupted by a crash that could not be matched to a payment
+func get_unresolved_operations() -> Array:
+	return _breez_rust.get_unresolved_operations()
+
+func acknowledge_operation(id: String) -> bool:
+	return _breez_rust.acknowledge_operation(id)
+
+## Disk usage of the SDK database, plugin files and free space
+func get_storage_stats() -> Dictionary:
+	return _breez_rust.get_storage_stats()
+
+## Prune caches, compact stores and (while disconnected) vacuum the SDK database
+func run_storage_maintenance(options: Dictionary = {}) -> Dictionary:
+	return _breez_rust.run_storage_maintenance(options)
+
+## Whether storage_dir already holds a wallet for network ("mainnet" or "regtest")
+func wallet_exists(storage_dir: String, network: String) -> bool:
+	return _breez_rust.wallet_exists(storage_dir, network)
+
+## Route plugin errors to handler(record: Dictionary); pass Callable() to stop
+func set_error_handler(handler: Callable) -> void:
+	_breez_rust.set_error_handler(handler)
+
+## Check the environment for common problems; paste `summary` into bug reports
+func run_diagnostics() -> Dictionary:
+	return _breez_rust.run_diagnostics()
+
+## Get min/max amounts for "lightning", "spark" or "onchain" so inputs can clamp early
+func get_amount_limits(method: String) -> Dictionary:
+	return _breez_rust.get_amount_limits(method)
+
 ## Get Bitcoin on-chain address
 func get_bitcoin_address() -> String:
 	if not initialized:
 		return ""
 	return _breez_rust.get_bitcoin_address()
 
+## Bitcoin address with fee_sats, the SDK's fee for receiving on it (0 when none)
+func get_bitcoin_address_detailed() -> Dictionary:
+	if not initialized:
+		push_error("Breez SDK not initialized")
+		return {"success": false, "error": "Not initialized", "error_code": "NOT_CONNECTED"}
+	return _breez_rust.get_bitcoin_address_detailed()
+
+## One bitcoin: URI with an on-chain address and a Lightning invoice for a deposit QR;
+## the result also has address and invoice to show on their own
+func create_unified_payment_request(amount_sats: int, description: String) -> Dictionary:
+	if not initialized:
+		push_error("Breez SDK not initialized")
+		return {"success": false, "error": "Not initialized"}
+	return _breez_rust.create_unified_payment_request(amount_sats, description)
+
 ## Get Spark address
 func get_spark_address() -> String:
 	if not initialized:
 		return ""
 	return _breez_rust.get_spark_address()
 
+## Spark address with fee_sats, the SDK's fee for receiving on it (0 when none)
+func get_spark_address_detailed() -> Dictionary:
+	if not initialized:
+		push_error("Breez SDK not initialized")
+		return {"success": false, "error": "Not initialized", "error_code": "NOT_CONNECTED"}
+	return _breez_rust.get_spark_address_detailed()
+
+## Network of the connected wallet ("mainnet" or "regtest"), "" when not connected
+func get_network() -> String:
+	if not initialized:
+		return ""
+	return _breez_rust.get_network()
+
+## SDK settings of the connected wallet (the API key is reported as api_key_set only)
+func get_config() -> Dictionary:
+	if not initialized:
+		push_error("Breez SDK not initialized")
+		return {"success": false, "error": "Not initialized", "error_code": "NOT_CONNECTED"}
+	return _breez_rust.get_config()
+
 ## Check if SDK is connected
 func is_sdk_connected() -> bool:
 	return initialized and _breez_rust.is_sdk_connected()
@@ -138,14 +741,16 @@ func stop_monitoring():
 		_is_monitoring = false
 		_timer.stop()
 
-## Disconnect from SDK
-func disconnect_sdk():
+## Disconnect from SDK, closing its storage; success is false if the shutdown was not clean
+func disconnect_sdk() -> Dictionary:
+	var result = {"success": true}
 	if initialized:
 		if _timer:
 			_timer.stop()
-		_breez_rust.disconnect_breez()
+		result = _breez_rust.disconnect_breez()
 		initialized = false
 		print("[Breez] Disconnected")
+	return result
 
 ## Utility: Format satoshis
 func format_sats(amount: int) -> String:
@@ -155,6 +760,54 @@ func format_sats(amount: int) -> String:
 func format_btc(amount: int) -> String:
 	return "₿ %.8f" % (amount / 100_000_000.0)
 
+## Utility: Format satoshis for a locale ("sat", "btc" or "msat")
+func format_sats_localized(amount: int, unit: String = "sat", locale: String = "") -> String:
+	if locale == "":
+		locale = TranslationServer.get_locale()
+	return _breez_rust.format_sats_localized(amount, unit, locale)
+
+## Forward SDK logs at level ("error", "warn", "info", "debug", "trace" or "off") to the console and log_message
+func init_logging(level: String) -> bool:
+	return _breez_rust.init_logging(level)
+
+## Write SDK and plugin logs to logs/breez.log in the wallet directory, rotating by size
+func enable_file_logging(max_file_kb: int = 1024, max_files: int = 3) -> bool:
+	if not initialized:
+		push_error("Breez SDK not initialized")
+		return false
+	return _breez_rust.enable_file_logging(max_file_kb, max_files)
+
+## Flush and close the log files
+func disable_file_logging() -> void:
+	_breez_rust.disable_file_logging()
+
+## Absolute paths of the log files, newest first, e.g. to attach to a support email
+func get_log_file_paths() -> Array[String]:
+	return _breez_rust.get_log_file_paths()
+
+## Plugin, breez_sdk_spark and godot-rust versions plus the git commit, for bug reports
+func get_version() -> Dictionary:
+	return _breez_rust.get_version()
+
+## Whether this build supports a feature such as "lnurl_pay", "bolt12" or "lightning_address"
+func is_feature_supported(feature: String) -> bool:
+	return _breez_rust.is_feature_supported(feature)
+
+## Utility: QR code texture for an invoice, address or URI, e.g.
+## $TextureRect.texture = breez.generate_qr_texture(invoice, 8, 4); null on failure
+func generate_qr_texture(data: String, module_size: int = 8, margin: int = 4) -> ImageTexture:
+	return _breez_rust.generate_qr_texture(data, module_size, margin)
+
+## Utility: Format a fiat amount for a locale
+func format_fiat(amount: float, currency: String, locale: String = "") -> String:
+	if locale == "":
+		locale = TranslationServer.get_locale()
+	return _breez_rust.format_fiat(amount, currency, locale)
+
+## Create an invoice for a registered product; `purchase_completed` fires once it is paid
+func purchase_product(product_id: String) -> Dictionary:
+	return _breez_rust.purchase_product(product_id)
+
 ## Check for balance changes (auto-detect payments)
 func _check_for_changes():
 	if not initialized:
@@ -166,13 +819,130 @@ func _check_for_changes():
 		# Payment received
 		if current_balance > _last_balance:
 			var received = current_balance - _last_balance
-			emit_signal("payment_received", received, "")
+			_breez_rust.check_purchases()
+			_breez_rust.check_zaps()
+			var details = _latest_receive_details(received)
+			_emit_payment_received(received, details.get("description", ""), details)
 			print("[Breez] 💰 Payment received: +%d sats" % received)
+			_breez_rust.check_auto_sweep()
 		
 		# Balance changed
-		emit_signal("balance_changed", _last_balance, current_balance)
+		_emit_balance_changed(_last_balance, current_balance)
 		_last_balance = current_balance
 
+func _on_sdk_payment_received(payment: Dictionary) -> void:
+	if _batch_window_ms == 0:
+		sdk_payment_received.emit(payment)
+	else:
+		_batch_sdk_received.append(payment)
+		_start_batch_window()
+	# Pick the balance change up before the next check_interval, but only once per
+	# frame (or batch window) however many payments arrive together
+	if _changes_pending:
+		return
+	_changes_pending = true
+	if _batch_window_ms == 0:
+		_run_pending_check.call_deferred()
+
+func _on_sdk_payment_sent(payment: Dictionary) -> void:
+	if _batch_window_ms == 0:
+		sdk_payment_sent.emit(payment)
+		return
+	_batch_sdk_sent.append(payment)
+	_start_batch_window()
+
+func _on_payment_resolved(payment_id: String, status: String) -> void:
+	if _batch_window_ms == 0:
+		payment_resolved.emit(payment_id, status)
+		return
+	_batch_resolved.append({"payment_id": payment_id, "status": status})
+	_start_batch_window()
+
+func _run_pending_check() -> void:
+	if not _changes_pending:
+		return
+	_changes_pending = false
+	_check_for_changes()
+
+## Coalesce the per-payment signals emitted within `window_ms`
+##
+## Received payments are delivered in order as one `payments_received_batch` of
+## {amount, description, comment, payer} Dictionaries, and balance changes as a single `balance_changed`
+## with the net change. sdk_payment_received, sdk_payment_sent and payment_resolved are delivered the
+## same way as `sdk_payments_received_batch`, `sdk_payments_sent_batch` and `payments_resolved_batch`.
+## payment_sent is not batched: it answers the game's own pay call, once per call.
+## 0 restores one signal per event (flushing anything pending).
+func set_signal_batching(window_ms: int) -> void:
+	_batch_window_ms = max(window_ms, 0)
+	if _batch_window_ms == 0:
+		_batch_timer.stop()
+		_flush_signal_batch()
+
+## Description, comment and payer of the newest receive, if it accounts for `amount`
+func _latest_receive_details(amount: int) -> Dictionary:
+	var latest = _breez_rust.list_payments(0, 1)
+	if latest.is_empty():
+		return {}
+	var payment: Dictionary = latest[0]
+	if payment.get("payment_type", "") != "receive" or payment.get("amount", 0) != amount:
+		return {}
+	return {
+		"payment_id": payment.get("id", ""),
+		"description": payment.get("description", ""),
+		"comment": payment.get("comment", ""),
+		"payer": payment.get("payer", {}),
+	}
+
+func _emit_payment_received(amount: int, description: String, details: Dictionary = {}) -> void:
+	if _batch_window_ms == 0:
+		emit_signal("payment_received", amount, description)
+		emit_signal("payment_received_detailed", amount, description, details)
+		return
+	_batch_received.append({
+		"amount": amount,
+		"description": description,
+		"comment": details.get("comment", ""),
+		"payer": details.get("payer", {}),
+	})
+	_start_batch_window()
+
+func _emit_balance_changed(old_balance: int, new_balance: int) -> void:
+	if _batch_window_ms == 0:
+		emit_signal("balance_changed", old_balance, new_balance)
+		return
+	if _batch_balance_from < 0:
+		_batch_balance_from = old_balance
+	_batch_balance_to = new_balance
+	_start_batch_window()
+
+func _start_batch_window() -> void:
+	if _batch_timer.is_stopped():
+		_batch_timer.start(_batch_window_ms / 1000.0)
+
+func _flush_signal_batch() -> void:
+	_run_pending_check()
+	if not _batch_sdk_received.is_empty():
+		var sdk_received = _batch_sdk_received
+		_batch_sdk_received = []
+		emit_signal("sdk_payments_received_batch", sdk_received)
+	if not _batch_sdk_sent.is_empty():
+		var sdk_sent = _batch_sdk_sent
+		_batch_sdk_sent = []
+		emit_signal("sdk_payments_sent_batch", sdk_sent)
+	if not _batch_resolved.is_empty():
+		var resolved = _batch_resolved
+		_batch_resolved = []
+		emit_signal("payments_resolved_batch", resolved)
+	if not _batch_received.is_empty():
+		var batch = _batch_received
+		_batch_received = []
+		emit_signal("payments_received_batch", batch)
+	if _batch_balance_from >= 0:
+		var from_balance = _batch_balance_from
+		_batch_balance_from = -1
+		if from_balance != _batch_balance_to:
+			emit_signal("balance_changed", from_balance, _batch_balance_to)
+
 func _format_number(num: int) -> String:
 	var s = str(num)
 	var result = ""
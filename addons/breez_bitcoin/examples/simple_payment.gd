@@ -23,7 +23,7 @@ func setup_breez():
 	# Connect signals
 	breez.connected.connect(_on_connected)
 	breez.connection_failed.connect(_on_connection_failed)
-	breez.payment_received.connect(_on_payment_received)
+	breez.payment_received_detailed.connect(_on_payment_received)
 	breez.invoice_created.connect(_on_invoice_created)
 	breez.balance_changed.connect(_on_balance_changed)
 	
@@ -88,10 +88,12 @@ func get_addresses():
 func _on_invoice_created(invoice: String, amount: int):
 	print("📄 Invoice Event: Created for ", amount, " sats")
 
-func _on_payment_received(amount: int, description: String):
+func _on_payment_received(amount: int, description: String, details: Dictionary):
 	print("\n🎉 PAYMENT RECEIVED!")
 	print("   Amount: ", breez.format_sats(amount))
 	print("   Description: ", description if description != "" else "(none)")
+	if details.get("comment", "") != "":
+		print("   Comment: ", details["comment"])
 	print()
 	
 	# Show new balance
@@ -0,0 +1,255 @@
+extends Node
+class_name BreezPaymentListAdapter
+## Keeps an ItemList or Tree filled with the wallet's payments
+##
+## Rows are keyed by payment id and updated in place when payments arrive, resolve or sync,
+## so the control is never rebuilt. Add it next to a Breez node, point `breez` and `target`
+## at them, and connect `row_activated` to open a detail screen.
+
+## Emitted when a row is double-clicked or activated with Enter
+signal row_activated(payment_id: String)
+
+## Filters accepted by `set_filters`, the same as `Breez.stream_payments`
+const FILTERS := ["payment_type", "status", "from_ts", "to_ts"]
+
+## Columns `columns` may list
+const COLUMNS := ["date", "description", "amount", "fees", "status", "method", "type", "comment"]
+
+@export var breez: Breez
+## ItemList or Tree to fill; ItemList rows join the columns with `item_list_separator`
+@export var target: Control
+## Columns shown, in order
+@export var columns: PackedStringArray = ["date", "description", "amount", "status"]
+## Tree column titles, same order as `columns` (empty to hide the titles)
+@export var column_titles: PackedStringArray = []
+## Date pattern with {year}, {month}, {day}, {hour}, {minute} and {second}, in local time
+@export var date_format: String = "{year}-{month}-{day} {hour}:{minute}"
+## Amount unit: "sat", "btc" or "msat"
+@export var unit: String = "sat"
+## Icons by status ("completed", "pending", "failed") or method ("lightning", "spark", ...);
+## a status icon wins over a method icon
+@export var icons: Dictionary = {}
+## Newest payments kept in the control
+@export var limit: int = 100
+@export var item_list_separator: String = "  ·  "
+
+var _filters: Dictionary = {}
+# payment id -> payment Dictionary currently shown
+var _rows: Dictionary = {}
+# Ids in display order, newest first
+var _order: Array = []
+var _refresh_queued := false
+var _item_list: ItemList
+var _tree: Tree
+
+func _ready():
+	_item_list = target as ItemList
+	_tree = target as Tree
+	if _item_list:
+		_item_list.item_activated.connect(_on_item_list_activated)
+	elif _tree:
+		_tree.hide_root = true
+		_tree.item_activated.connect(_on_tree_activated)
+		_setup_tree_columns()
+	else:
+		push_error("[Breez] BreezPaymentListAdapter target must be an ItemList or a Tree")
+		return
+
+	if breez:
+		breez.connected.connect(queue_refresh)
+		breez.payment_sent.connect(func(_invoice, _result): queue_refresh())
+		breez.payment_received.connect(func(_amount, _description): queue_refresh())
+		breez.payments_received_batch.connect(func(_payments): queue_refresh())
+		breez.payment_resolved.connect(func(_payment_id, _status): queue_refresh())
+		breez.payments_resolved_batch.connect(func(_resolved): queue_refresh())
+		breez.balance_changed.connect(func(_old_balance, _new_balance): queue_refresh())
+		breez.deposit_claim_retried.connect(func(_txid, _vout, _result): queue_refresh())
+		if breez.is_sdk_connected():
+			queue_refresh()
+
+## Show only payments matching `filters`: `payment_type` ("send"/"receive"), `status`
+## ("completed", "pending", "failed"), `from_ts` and `to_ts` (unix seconds, inclusive)
+func set_filters(filters: Dictionary) -> bool:
+	for key in filters:
+		if not key in FILTERS:
+			push_error("[Breez] Unknown filter '%s' (valid: %s)" % [key, ", ".join(FILTERS)])
+			return false
+		if key in ["from_ts", "to_ts"] and typeof(filters[key]) != TYPE_INT:
+			push_error("[Breez] Invalid value for filter '%s': %s" % [key, filters[key]])
+			return false
+	_filters = filters.duplicate()
+	queue_refresh()
+	return true
+
+## Refresh once at the end of the frame, however many events arrive before then
+func queue_refresh() -> void:
+	if not _refresh_queued:
+		_refresh_queued = true
+		refresh.call_deferred()
+
+## Bring the control in line with the wallet, touching only rows that changed
+func refresh() -> void:
+	_refresh_queued = false
+	if not breez or not breez.is_sdk_connected():
+		return
+
+	var payments: Array = []
+	for payment in breez.list_payments(0, limit):
+		if _matches(payment):
+			payments.append(payment)
+
+	var wanted := {}
+	for payment in payments:
+		wanted[payment["id"]] = true
+	for i in range(_order.size() - 1, -1, -1):
+		if not wanted.has(_order[i]):
+			_remove_row(i)
+
+	# Payments come newest first, so position i is where each row belongs
+	for i in payments.size():
+		var payment: Dictionary = payments[i]
+		var id: String = payment["id"]
+		if not _rows.has(id):
+			_insert_row(i, payment)
+			continue
+		var current := _order.find(id)
+		if current != i:
+			_move_row(current, i)
+		if _rows[id].hash() != payment.hash():
+			_rows[id] = payment
+			_update_row(i, payment)
+
+## Payment id of the selected row, or "" when nothing is selected
+func get_selected_payment_id() -> String:
+	if _item_list:
+		var selected := _item_list.get_selected_items()
+		return _order[selected[0]] if not selected.is_empty() else ""
+	if _tree and _tree.get_selected():
+		return _tree.get_selected().get_metadata(0)
+	return ""
+
+func clear() -> void:
+	_rows.clear()
+	_order.clear()
+	if _item_list:
+		_item_list.clear()
+	elif _tree:
+		_tree.clear()
+		_tree.create_item()
+
+func _matches(payment: Dictionary) -> bool:
+	if _filters.has("payment_type") and payment.get("payment_type", "").to_lower() != str(_filters["payment_type"]).to_lower():
+		return false
+	if _filters.has("status") and payment.get("status", "").to_lower() != str(_filters["status"]).to_lower():
+		return false
+	if _filters.has("from_ts") and payment.get("timestamp", 0) < _filters["from_ts"]:
+		return false
+	if _filters.has("to_ts") and payment.get("timestamp", 0) > _filters["to_ts"]:
+		return false
+	return true
+
+func _setup_tree_columns() -> void:
+	_tree.columns = max(columns.size(), 1)
+	_tree.column_titles_visible = not column_titles.is_empty()
+	for i in min(column_titles.size(), columns.size()):
+		_tree.set_column_title(i, column_titles[i])
+	if _tree.get_root() == null:
+		_tree.create_item()
+
+func _insert_row(index: int, payment: Dictionary) -> void:
+	var id: String = payment["id"]
+	_rows[id] = payment
+	_order.insert(index, id)
+	if _item_list:
+		var item := _item_list.add_item("")
+		_item_list.move_item(item, index)
+	else:
+		_tree.create_item(_tree.get_root(), index)
+	_update_row(index, payment)
+
+func _remove_row(index: int) -> void:
+	_rows.erase(_order[index])
+	_order.remove_at(index)
+	if _item_list:
+		_item_list.remove_item(index)
+	else:
+		_tree.get_root().get_child(index).free()
+
+func _move_row(from: int, to: int) -> void:
+	var id = _order[from]
+	_order.remove_at(from)
+	_order.insert(to, id)
+	if _item_list:
+		_item_list.move_item(from, to)
+		return
+	var root := _tree.get_root()
+	var item := root.get_child(from)
+	if to == 0:
+		item.move_before(root.get_child(0))
+	elif to > from:
+		item.move_after(root.get_child(to))
+	else:
+		item.move_after(root.get_child(to - 1))
+
+func _update_row(index: int, payment: Dictionary) -> void:
+	var status: String = payment.get("status", "").to_lower()
+	var method: String = payment.get("method", "").to_lower()
+	var icon: Texture2D = icons.get(status, icons.get(method, null))
+	if _item_list:
+		var texts := PackedStringArray()
+		for column in columns:
+			texts.append(_cell_text(column, payment))
+		_item_list.set_item_text(index, item_list_separator.join(texts))
+		_item_list.set_item_icon(index, icon)
+		_item_list.set_item_metadata(index, payment["id"])
+		return
+	var item := _tree.get_root().get_child(index)
+	for i in columns.size():
+		item.set_text(i, _cell_text(columns[i], payment))
+	item.set_icon(0, icon)
+	item.set_metadata(0, payment["id"])
+
+func _cell_text(column: String, payment: Dictionary) -> String:
+	match column:
+		"date":
+			return _format_date(payment.get("timestamp", 0))
+		"description":
+			return payment.get("description", "")
+		"amount":
+			var amount: int = payment.get("amount", 0)
+			if payment.get("payment_type", "") == "send":
+				amount = -amount
+			return breez.format_sats_localized(amount, unit)
+		"fees":
+			return breez.format_sats_localized(payment.get("fees", 0), unit)
+		"status":
+			return payment.get("status", "")
+		"method":
+			return payment.get("method", "")
+		"type":
+			return payment.get("payment_type", "")
+		"comment":
+			return payment.get("comment", "")
+	push_error("[Breez] Unknown column '%s' (valid: %s)" % [column, ", ".join(COLUMNS)])
+	return ""
+
+func _format_date(timestamp: int) -> String:
+	var bias: int = Time.get_time_zone_from_system().get("bias", 0)
+	var date := Time.get_datetime_dict_from_unix_time(timestamp + bias * 60)
+	return date_format.format({
+		"year": "%04d" % date["year"],
+		"month": "%02d" % date["month"],
+		"day": "%02d" % date["day"],
+		"hour": "%02d" % date["hour"],
+		"minute": "%02d" % date["minute"],
+		"second": "%02d" % date["second"],
+	})
+
+func _on_item_list_activated(index: int) -> void:
+	if index < _order.size():
+		row_activated.emit(_order[index])
+
+func _on_tree_activated() -> void:
+	var item := _tree.get_selected()
+	if item:
+		row_activated.emit(item.get_metadata(0))
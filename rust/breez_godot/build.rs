@@ -0,0 +1,67 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+/// Version a dependency is declared with in Cargo.toml, preferring the git tag for git
+/// dependencies
+///
+/// Cargo.lock is not committed, so the manifest is the only record that is always there.
+/// breez-sdk-spark is pulled by tag and its crate version lags behind the release number.
+fn declared_version(manifest: &str, package: &str) -> String {
+    let Some(spec) = manifest.lines().find_map(|line| {
+        let (name, spec) = line.split_once('=')?;
+        (name.trim() == package).then(|| spec.trim())
+    }) else {
+        return String::new();
+    };
+    // `godot = "0.4.0"`
+    if let Some(version) = spec.strip_prefix('"') {
+        return version.split('"').next().unwrap_or_default().to_string();
+    }
+    // `breez-sdk-spark = { git = "...", tag = "0.2.6" }`
+    let field = |key: &str| {
+        spec.trim_matches(|c| c == '{' || c == '}').split(',').find_map(|pair| {
+            let (name, value) = pair.split_once('=')?;
+            (name.trim() == key).then(|| value.trim().trim_matches('"').to_string())
+        })
+    };
+    field("tag").or_else(|| field("version")).unwrap_or_default()
+}
+
+fn git_commit() -> String {
+    Command::new("git")
+        .args(["rev-parse", "--short=12", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .unwrap_or_default()
+}
+
+/// Files whose change means a new commit: HEAD, and the branch ref it points to
+fn git_head_files(manifest_dir: &Path) -> Vec<String> {
+    let mut files = vec!["../../.git/HEAD".to_string()];
+    let head = fs::read_to_string(manifest_dir.join("../../.git/HEAD")).unwrap_or_default();
+    if let Some(reference) = head.trim().strip_prefix("ref: ") {
+        files.push(format!("../../.git/{}", reference));
+        // Refs that were packed only change there
+        files.push("../../.git/packed-refs".to_string());
+    }
+    files.retain(|file| manifest_dir.join(file).exists());
+    files
+}
+
+fn main() {
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+    let manifest_dir = Path::new(&manifest_dir);
+    let manifest = fs::read_to_string(manifest_dir.join("Cargo.toml")).unwrap_or_default();
+
+    println!("cargo:rustc-env=BREEZ_SDK_SPARK_VERSION={}", declared_version(&manifest, "breez-sdk-spark"));
+    println!("cargo:rustc-env=GODOT_RUST_VERSION={}", declared_version(&manifest, "godot"));
+    println!("cargo:rustc-env=BREEZ_GODOT_GIT_COMMIT={}", git_commit());
+
+    println!("cargo:rerun-if-changed=Cargo.toml");
+    for file in git_head_files(manifest_dir) {
+        println!("cargo:rerun-if-changed={}", file);
+    }
+}
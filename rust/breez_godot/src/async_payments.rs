@@ -0,0 +1,125 @@
+use godot::prelude::*;
+use breez_sdk_spark::SendPaymentResponse;
+use std::sync::Arc;
+
+use crate::events::NodeEvent;
+use crate::send::{invoice_amount, invoice_send_options, prepare_send, send_prepared, PreparedSend, SendOptions};
+use crate::BreezNode;
+
+/// Background step of a `pay_invoice_async` request, handed to the main thread
+pub(crate) enum AsyncPaymentStep {
+    /// Refused before anything ran; the failure is already in the request's Dictionary
+    Rejected,
+    Prepared(Result<PreparedSend, String>),
+    Sent(Result<SendPaymentResponse, String>),
+}
+
+/// A `pay_invoice_async` request between its background steps
+pub(crate) struct AsyncPayment {
+    invoice: String,
+    payment_hash: Option<String>,
+    opts: SendOptions,
+    /// Result Dictionary so far
+    dict: Dictionary,
+    journal_id: String,
+    cost: u64,
+}
+
+#[godot_api(secondary)]
+impl BreezNode {
+    /// Pay a Lightning invoice without blocking the frame
+    ///
+    /// Returns a request id right away; `payment_result` is emitted with it and the same
+    /// Dictionary `pay_invoice` returns once the payment is done, followed by
+    /// `request_completed`. Several payments can be requested at once; they are prepared in
+    /// parallel and sent one after another.
+    ///
+    /// # Arguments
+    /// * `bolt11` - The BOLT11 invoice string
+    /// * `timeout_secs` - Timeout in seconds for payment completion (0 for default)
+    #[func]
+    pub fn pay_invoice_async(&self, bolt11: GString, timeout_secs: i64) -> i64 {
+        let request_id = self.begin_request("pay_invoice");
+        let invoice = bolt11.to_string();
+        let mut opts = SendOptions::default();
+        opts.timeout_secs = timeout_secs;
+
+        let checked = self.check_unlocked().and_then(|()| invoice_amount(&invoice, &opts));
+        let mut payment = AsyncPayment {
+            invoice: invoice.clone(),
+            payment_hash: None,
+            opts,
+            dict: Dictionary::new(),
+            journal_id: String::new(),
+            cost: 0,
+        };
+        match checked {
+            Ok((amount, payment_hash)) => {
+                payment.payment_hash = payment_hash;
+                let events = self.events.sender();
+                let prepare = prepare_send(self.sdk_handle(), invoice, amount, self.low_balance_threshold());
+                self.runtime.spawn(async move {
+                    let step = AsyncPaymentStep::Prepared(prepare.await);
+                    let _ = events.send(NodeEvent::AsyncPayment { request_id, step });
+                });
+            }
+            Err(error) => {
+                payment.dict = error;
+                self.events.push(NodeEvent::AsyncPayment {
+                    request_id,
+                    step: AsyncPaymentStep::Rejected,
+                });
+            }
+        }
+        // Steps are only handled on the main thread, so this runs before the first one
+        self.async_payments.lock().unwrap().insert(request_id, payment);
+        request_id
+    }
+}
+
+impl BreezNode {
+    /// Run the main-thread part of a `pay_invoice_async` step, emitting `payment_result`
+    /// once the request is done
+    pub(crate) fn advance_async_payment(&mut self, request_id: i64, step: AsyncPaymentStep) {
+        let Some(mut payment) = self.async_payments.lock().unwrap().remove(&request_id) else {
+            return;
+        };
+        let result = match step {
+            AsyncPaymentStep::Rejected => payment.dict,
+            // The wallet may have been locked while the quote was prepared; checking before
+            // check_prepared leaves nothing counted against the throttle to give back
+            AsyncPaymentStep::Prepared(prepared) => match self
+                .check_unlocked()
+                .and_then(|()| self.check_prepared(prepared, &payment.opts))
+            {
+                Ok(checked) => {
+                    payment.journal_id =
+                        self.journal_begin("send", &payment.invoice, checked.prepare_response.amount_sats);
+                    payment.dict = checked.dict;
+                    payment.cost = checked.cost;
+                    let events = self.events.sender();
+                    let send = send_prepared(
+                        self.sdk_handle(),
+                        Arc::clone(&self.in_flight),
+                        checked.prepare_response,
+                        invoice_send_options(&payment.opts),
+                    );
+                    self.runtime.spawn(async move {
+                        let step = AsyncPaymentStep::Sent(send.await);
+                        let _ = events.send(NodeEvent::AsyncPayment { request_id, step });
+                    });
+                    self.async_payments.lock().unwrap().insert(request_id, payment);
+                    return;
+                }
+                Err(dict) => dict,
+            },
+            AsyncPaymentStep::Sent(result) => {
+                let dict = self.finish_send(payment.dict, &payment.journal_id, payment.cost, result);
+                self.track_invoice_result(dict, payment.payment_hash)
+            }
+        };
+        let result = self.report_failure("pay_invoice_async", result);
+        self.signals().payment_result().emit(request_id, &result);
+        self.complete_request(request_id, "pay_invoice", Ok(result.to_variant()));
+    }
+}
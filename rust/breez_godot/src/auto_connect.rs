@@ -0,0 +1,62 @@
+use godot::prelude::*;
+
+use crate::{BreezNode, ConnectOptions};
+
+/// Network name for a value of the exported `network` enum
+fn network_name(network: i32) -> &'static str {
+    match network {
+        1 => "regtest",
+        _ => "mainnet",
+    }
+}
+
+#[godot_api(secondary)]
+impl BreezNode {
+    /// Hand over the mnemonic and API key for `auto_connect_on_ready`
+    ///
+    /// Connects in the background with the Inspector's `network` and `storage_dir`, right
+    /// away when the node is ready or else from `_ready`, and emits `connected` or
+    /// `connect_failed`. The secrets are only kept in memory, never saved with the scene.
+    /// Returns false and keeps nothing when `auto_connect_on_ready` is off.
+    #[func]
+    pub fn set_credentials(&mut self, mnemonic: GString, api_key: GString) -> bool {
+        if !self.auto_connect_on_ready {
+            godot_warn!("set_credentials needs auto_connect_on_ready; use connect_sdk_async instead");
+            return false;
+        }
+        *self.pending_credentials.lock().unwrap() = Some((mnemonic.to_string(), api_key.to_string()));
+        if self.base().is_node_ready() {
+            self.start_auto_connect();
+        }
+        true
+    }
+
+    #[func]
+    fn get_connect_network(&self) -> i32 {
+        self.network
+    }
+
+    #[func]
+    fn set_connect_network(&mut self, network: i32) {
+        self.network = network;
+    }
+}
+
+impl BreezNode {
+    /// Start the connect requested by `set_credentials`
+    pub(crate) fn start_auto_connect(&mut self) {
+        if !self.auto_connect_on_ready {
+            return;
+        }
+        let Some((mnemonic, api_key)) = self.pending_credentials.lock().unwrap().take() else {
+            godot_print!("Waiting for set_credentials to connect");
+            return;
+        };
+        let network = network_name(self.network).to_string();
+        let storage_dir = self.storage_dir.clone();
+        if let Err(e) = self.start_connect(mnemonic, api_key, network, &storage_dir, None, ConnectOptions::default()) {
+            godot_error!("{}", e.message);
+            self.signals().connect_failed().emit(&GString::from(&e.message));
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use godot::prelude::*;
+use breez_sdk_spark::SyncWalletRequest;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::{interval_at, Instant, MissedTickBehavior};
+
+use crate::events::NodeEvent;
+use crate::reconnect::ping;
+use crate::{lock_sdk, BreezNode};
+
+#[godot_api(secondary)]
+impl BreezNode {
+    /// Sync the wallet in the background every `interval_secs`, emitting `wallet_synced`
+    /// after each sync
+    ///
+    /// Runs the same checks as `sync_wallet` once a sync succeeds, without blocking the frame.
+    /// Each sync doubles as a health check: a failed sync is followed by a ping, and
+    /// `connection_lost` or `connection_restored` is emitted when reachability changes.
+    /// Calling it again replaces the interval; the loop stops on `stop_auto_sync`,
+    /// `disconnect_breez` or when the node leaves the tree.
+    #[func]
+    pub fn start_auto_sync(&mut self, interval_secs: i64) {
+        self.stop_auto_sync();
+        if interval_secs <= 0 {
+            return;
+        }
+
+        let sdk_arc = Arc::clone(&self.sdk);
+        let events = self.events.sender();
+        let period = Duration::from_secs(interval_secs as u64);
+
+        let handle = self.runtime.spawn(async move {
+            let mut interval = interval_at(Instant::now() + period, period);
+            // A slow sync delays the next one instead of queueing a burst
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                let sdk = lock_sdk(&sdk_arc).clone();
+                let result = match &sdk {
+                    Some(sdk) => sdk
+                        .sync_wallet(SyncWalletRequest {})
+                        .await
+                        .map(|_| ())
+                        .map_err(|e| format!("Failed to sync: {:?}", e)),
+                    None => Err("SDK not initialized".to_string()),
+                };
+                if let Some(sdk) = &sdk {
+                    // A sync can fail for other reasons than the connection
+                    let reachable = match &result {
+                        Ok(()) => Ok(()),
+                        Err(_) => ping(sdk).await,
+                    };
+                    let _ = events.send(NodeEvent::ConnectionChecked { result: reachable });
+                }
+                let _ = events.send(NodeEvent::WalletSynced { result });
+            }
+        });
+
+        *self.auto_sync.lock().unwrap() = Some(handle);
+    }
+
+    /// Stop the background sync started by `start_auto_sync`
+    #[func]
+    pub fn stop_auto_sync(&mut self) {
+        if let Some(handle) = self.auto_sync.lock().unwrap().take() {
+            handle.abort();
+        }
+    }
+}
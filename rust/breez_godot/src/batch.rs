@@ -0,0 +1,411 @@
+use godot::prelude::*;
+use breez_sdk_spark::{
+    BreezSdk, GetInfoRequest, Payment, PaymentStatus, PrepareSendPaymentRequest, PrepareSendPaymentResponse,
+    SendPaymentRequest,
+};
+use std::sync::Arc;
+use tokio::sync::{OwnedMutexGuard, Semaphore};
+use tokio::task::JoinSet;
+
+use crate::events::NodeEvent;
+use crate::fees::{quoted_fee_sats, send_options_for};
+use crate::limits::{check_amount, AmountMethod};
+use crate::resolution::is_timeout_error;
+use crate::validation::validate_spark_address;
+use crate::BreezNode;
+
+/// Largest number of payouts in one batch
+const MAX_BATCH_SIZE: usize = 64;
+
+/// Keys accepted in a payout entry
+const PAYOUT_KEYS: &[&str] = &["address", "amount_sats", "note"];
+
+/// Default number of payouts sent at the same time
+pub(crate) const DEFAULT_BATCH_PARALLELISM: usize = 4;
+
+/// Most payouts the parallelism setting allows at the same time
+const MAX_BATCH_PARALLELISM: usize = 16;
+
+struct Payout {
+    address: String,
+    amount_sats: u64,
+    note: String,
+}
+
+/// Background step of a `send_spark_batch` request, handed to the main thread
+pub(crate) enum BatchStep {
+    /// Refused before anything was sent; the results are already in the request's state
+    Rejected,
+    /// Balance and one quote per payout, in input order, read while holding the in-flight lock
+    Quoted {
+        in_flight: OwnedMutexGuard<()>,
+        quoted: Result<(u64, Vec<PrepareSendPaymentResponse>), String>,
+    },
+    /// A payout finished, or its task failed before reporting which one it was
+    PayoutSent(Result<(usize, Result<Payment, String>), String>),
+    /// Every payout finished
+    Finished,
+}
+
+/// A `send_spark_batch` request between its background steps
+pub(crate) struct BatchPayout {
+    payouts: Vec<Payout>,
+    /// Results so far, in input order
+    results: Vec<Option<Dictionary>>,
+    /// Set when the whole batch was refused
+    rejection: Option<Dictionary>,
+    fees: Vec<u64>,
+    journal_ids: Vec<String>,
+    /// Sats counted against the throttle by `check_throttle`
+    reserved: u64,
+    /// Sats of failed payouts, given back to the throttle once the batch is done
+    released: u64,
+    done: i64,
+}
+
+impl BatchPayout {
+    fn new(payouts: Vec<Payout>) -> Self {
+        Self {
+            results: (0..payouts.len()).map(|_| None).collect(),
+            payouts,
+            rejection: None,
+            fees: Vec::new(),
+            journal_ids: Vec::new(),
+            reserved: 0,
+            released: 0,
+            done: 0,
+        }
+    }
+
+    fn rejected(results: Array<Dictionary>, rejection: Option<Dictionary>) -> Self {
+        Self {
+            results: results.iter_shared().map(Some).collect(),
+            rejection,
+            ..Self::new(Vec::new())
+        }
+    }
+}
+
+fn parse_payout(entry: &Dictionary) -> Result<Payout, Dictionary> {
+    for key in entry.keys_array().iter_shared() {
+        let key = key.to_string();
+        if !PAYOUT_KEYS.contains(&key.as_str()) {
+            return Err(payout_error(
+                format!("Unknown payout key '{}' (valid: {})", key, PAYOUT_KEYS.join(", ")),
+                "UNKNOWN_OPTION",
+            ));
+        }
+    }
+    let address = entry
+        .get("address")
+        .and_then(|v| v.try_to::<GString>().ok())
+        .map(|v| v.to_string().trim().to_string())
+        .unwrap_or_default();
+    validate_spark_address(&address).map_err(|e| payout_error(e, "INVALID_DESTINATION"))?;
+    let amount_sats = entry
+        .get("amount_sats")
+        .and_then(|v| v.try_to::<i64>().ok())
+        .unwrap_or(0);
+    check_amount(AmountMethod::Spark, amount_sats)?;
+    let note = entry
+        .get("note")
+        .and_then(|v| v.try_to::<GString>().ok())
+        .map(|v| v.to_string())
+        .unwrap_or_default();
+    Ok(Payout {
+        address,
+        amount_sats: amount_sats as u64,
+        note,
+    })
+}
+
+fn payout_error(error: String, code: &str) -> Dictionary {
+    let mut dict = Dictionary::new();
+    dict.set("success", false);
+    dict.set("error", error);
+    dict.set("error_code", code);
+    dict
+}
+
+/// One result per entry: the entry's own error, or `rejection` for the valid ones
+fn reject_batch<'a>(
+    entries: impl Iterator<Item = Result<&'a Payout, &'a Dictionary>>,
+    rejection: &Dictionary,
+) -> Array<Dictionary> {
+    godot_error!("Batch payout rejected: {}", rejection.get("error").unwrap_or_default());
+    entries
+        .map(|entry| match entry {
+            Ok(payout) => {
+                let mut dict = rejection.duplicate_shallow();
+                dict.set("address", payout.address.as_str());
+                dict.set("amount_sats", payout.amount_sats as i64);
+                dict
+            }
+            Err(error) => error.clone(),
+        })
+        .collect()
+}
+
+/// Quote every payout, `parallelism` at a time, and read the balance to check them against
+async fn quote_payouts(
+    sdk: BreezSdk,
+    requests: Vec<(String, u64)>,
+    parallelism: usize,
+) -> Result<(u64, Vec<PrepareSendPaymentResponse>), String> {
+    let balance = sdk
+        .get_info(GetInfoRequest { ensure_synced: Some(false) })
+        .await
+        .map_err(|e| format!("Failed to get balance: {:?}", e))?
+        .balance_sats;
+    let semaphore = Arc::new(Semaphore::new(parallelism));
+    let mut tasks = JoinSet::new();
+    for (index, (address, amount)) in requests.into_iter().enumerate() {
+        let sdk = sdk.clone();
+        let semaphore = Arc::clone(&semaphore);
+        tasks.spawn(async move {
+            let _permit = semaphore.acquire().await;
+            let prepared = sdk.prepare_send_payment(PrepareSendPaymentRequest {
+                payment_request: address,
+                amount_sats: Some(amount),
+            }).await;
+            (index, prepared.map_err(|e| format!("Failed to prepare payout: {:?}", e)))
+        });
+    }
+    let mut prepared: Vec<Option<PrepareSendPaymentResponse>> = (0..tasks.len()).map(|_| None).collect();
+    while let Some(joined) = tasks.join_next().await {
+        let (index, result) = joined.map_err(|e| format!("Payout task failed: {}", e))?;
+        prepared[index] = Some(result?);
+    }
+    Ok((balance, prepared.into_iter().flatten().collect()))
+}
+
+#[godot_api(secondary)]
+impl BreezNode {
+    /// Set how many payouts `send_spark_batch` sends at the same time (1–16, default 4)
+    #[func]
+    pub fn set_batch_parallelism(&mut self, parallelism: i64) {
+        *self.batch_parallelism.lock().unwrap() = parallelism.clamp(1, MAX_BATCH_PARALLELISM as i64) as usize;
+    }
+
+    /// Pay up to 64 Spark addresses, several at a time, without blocking the frame
+    ///
+    /// Each entry has `address`, `amount_sats` and an optional `note` that is echoed back in
+    /// its result. Nothing is sent unless every entry is valid and the balance covers the
+    /// summed amounts plus quoted fees; otherwise every entry fails with its own error or
+    /// BATCH_REJECTED / INSUFFICIENT_BALANCE. The throttle policy sees the batch as one
+    /// payment of the total.
+    ///
+    /// Returns a request id right away. `batch_payout_progress` is emitted with it after each
+    /// payout completes, and `batch_payout_finished` once the batch is done, with one result
+    /// per entry in input order: `success`, `address`, `amount_sats`, `note`, `final` and
+    /// `payment_id`/`fee_sats`/`status` or `error`. A payout with `final: false` has not
+    /// settled yet and stays in `get_unresolved_operations` until it does.
+    /// `request_completed` follows.
+    #[func]
+    pub fn send_spark_batch(&self, payouts: Array<Dictionary>) -> i64 {
+        let request_id = self.begin_request("send_spark_batch");
+        let entries: Vec<Result<Payout, Dictionary>> =
+            payouts.iter_shared().map(|entry| parse_payout(&entry)).collect();
+        if entries.is_empty() {
+            self.events.push(NodeEvent::BatchPayout { request_id, step: BatchStep::Rejected });
+            self.batch_payouts.lock().unwrap().insert(request_id, BatchPayout::rejected(Array::new(), None));
+            return request_id;
+        }
+        let checked = self.check_batch(&entries).and_then(|()| {
+            self.sdk_handle()
+                .map_err(|e| self.report_failure("send_spark_batch", payout_error(e, "BATCH_REJECTED")))
+        });
+        let batch = match checked {
+            Ok(sdk) => {
+                let payouts: Vec<Payout> = entries.into_iter().flatten().collect();
+                let requests = payouts.iter().map(|p| (p.address.clone(), p.amount_sats)).collect();
+                let parallelism = *self.batch_parallelism.lock().unwrap();
+                let in_flight = Arc::clone(&self.in_flight);
+                let events = self.events.sender();
+                self.runtime.spawn(async move {
+                    // The whole batch counts as the one operation moving funds, so no other send
+                    // can spend the balance between the check and the payouts
+                    let in_flight = in_flight.lock_owned().await;
+                    let quoted = quote_payouts(sdk, requests, parallelism).await;
+                    let step = BatchStep::Quoted { in_flight, quoted };
+                    let _ = events.send(NodeEvent::BatchPayout { request_id, step });
+                });
+                BatchPayout::new(payouts)
+            }
+            Err(rejection) => {
+                self.events.push(NodeEvent::BatchPayout { request_id, step: BatchStep::Rejected });
+                let results = reject_batch(entries.iter().map(Result::as_ref), &rejection);
+                BatchPayout::rejected(results, Some(rejection))
+            }
+        };
+        // Steps are only handled on the main thread, so this runs before the first one
+        self.batch_payouts.lock().unwrap().insert(request_id, batch);
+        request_id
+    }
+}
+
+impl BreezNode {
+    /// Refuse a batch that is too large, has invalid entries or cannot be paid right now
+    fn check_batch(&self, entries: &[Result<Payout, Dictionary>]) -> Result<(), Dictionary> {
+        if entries.len() > MAX_BATCH_SIZE {
+            let error = format!("Batch has {} payouts, the limit is {}", entries.len(), MAX_BATCH_SIZE);
+            return Err(self.report_failure("send_spark_batch", payout_error(error, "BATCH_TOO_LARGE")));
+        }
+        if entries.iter().any(Result::is_err) {
+            let error = "Batch contains invalid payouts".to_string();
+            return Err(self.report_failure("send_spark_batch", payout_error(error, "BATCH_REJECTED")));
+        }
+        self.check_unlocked().map_err(|locked| self.report_failure("send_spark_batch", locked))
+    }
+
+    /// Run the main-thread part of a `send_spark_batch` step, emitting `batch_payout_finished`
+    /// once the batch is done
+    pub(crate) fn advance_batch_payout(&mut self, request_id: i64, step: BatchStep) {
+        let Some(mut batch) = self.batch_payouts.lock().unwrap().remove(&request_id) else {
+            return;
+        };
+        match step {
+            BatchStep::Rejected => {}
+            BatchStep::Quoted { in_flight, quoted } => {
+                if let Err(rejection) = self.send_batch(request_id, &mut batch, in_flight, quoted) {
+                    let rejection = self.report_failure("send_spark_batch", rejection);
+                    batch.results = reject_batch(batch.payouts.iter().map(Ok), &rejection)
+                        .iter_shared()
+                        .map(Some)
+                        .collect();
+                    batch.rejection = Some(rejection);
+                } else {
+                    self.batch_payouts.lock().unwrap().insert(request_id, batch);
+                    return;
+                }
+            }
+            BatchStep::PayoutSent(outcome) => {
+                self.record_payout(&mut batch, outcome);
+                self.signals().batch_payout_progress().emit(request_id, batch.done, batch.payouts.len() as i64);
+                self.batch_payouts.lock().unwrap().insert(request_id, batch);
+                return;
+            }
+            BatchStep::Finished => {
+                if batch.released > 0 {
+                    self.release_throttled_send(batch.reserved, batch.released);
+                }
+                let sent = batch.results.iter().flatten().filter(|r| r.get("success").is_some_and(|s| s.to::<bool>()));
+                godot_print!("✅ Batch payout finished: {} of {} sent", sent.count(), batch.payouts.len());
+                self.invalidate_balance_history();
+                self.refresh_low_balance();
+                self.record_balance_snapshot();
+            }
+        }
+
+        let results: Array<Dictionary> = batch
+            .results
+            .into_iter()
+            .map(|result| result.unwrap_or_else(|| payout_error("Payout task failed".to_string(), "BATCH_REJECTED")))
+            .map(|result| self.report_failure("send_spark_batch", result))
+            .collect();
+        self.signals().batch_payout_finished().emit(request_id, &results);
+        let outcome = match batch.rejection {
+            Some(rejection) => Err(rejection),
+            None => Ok(results.to_variant()),
+        };
+        self.complete_request(request_id, "send_spark_batch", outcome);
+    }
+
+    /// Check the quoted batch against the balance and the throttle, then send every payout
+    fn send_batch(
+        &self,
+        request_id: i64,
+        batch: &mut BatchPayout,
+        in_flight: OwnedMutexGuard<()>,
+        quoted: Result<(u64, Vec<PrepareSendPaymentResponse>), String>,
+    ) -> Result<(), Dictionary> {
+        let (balance, prepared) = quoted.map_err(|e| payout_error(e, "BATCH_REJECTED"))?;
+        let sdk = self.sdk_handle().map_err(|e| payout_error(e, "BATCH_REJECTED"))?;
+
+        batch.fees = prepared.iter().map(quoted_fee_sats).collect();
+        let total = batch.payouts.iter().map(|p| p.amount_sats).sum::<u64>() + batch.fees.iter().sum::<u64>();
+        if total > balance {
+            let error = format!("Batch total {} sats (fees included) exceeds the {} sats balance", total, balance);
+            return Err(payout_error(error, "INSUFFICIENT_BALANCE"));
+        }
+        self.check_throttle(total, false)?;
+        batch.reserved = total;
+
+        batch.journal_ids = batch
+            .payouts
+            .iter()
+            .map(|payout| self.journal_begin("batch_payout", &payout.address, payout.amount_sats))
+            .collect();
+        let parallelism = *self.batch_parallelism.lock().unwrap();
+        let events = self.events.sender();
+        self.runtime.spawn(async move {
+            let _in_flight = in_flight;
+            let semaphore = Arc::new(Semaphore::new(parallelism));
+            let mut tasks = JoinSet::new();
+            for (index, prepare_response) in prepared.into_iter().enumerate() {
+                let sdk = sdk.clone();
+                let semaphore = Arc::clone(&semaphore);
+                tasks.spawn(async move {
+                    let _permit = semaphore.acquire().await;
+                    let options = send_options_for(&prepare_response);
+                    let sent = sdk.send_payment(SendPaymentRequest { prepare_response, options }).await;
+                    (index, sent.map(|response| response.payment).map_err(|e| format!("Payout failed: {:?}", e)))
+                });
+            }
+            while let Some(joined) = tasks.join_next().await {
+                let outcome = joined.map_err(|e| format!("Payout task failed: {}", e));
+                let _ = events.send(NodeEvent::BatchPayout { request_id, step: BatchStep::PayoutSent(outcome) });
+            }
+            let _ = events.send(NodeEvent::BatchPayout { request_id, step: BatchStep::Finished });
+        });
+        Ok(())
+    }
+
+    /// Record a finished payout in the batch's results
+    fn record_payout(&self, batch: &mut BatchPayout, outcome: Result<(usize, Result<Payment, String>), String>) {
+        batch.done += 1;
+        let (index, result) = match outcome {
+            Ok(outcome) => outcome,
+            Err(e) => {
+                // Its journal entry stays, the payout is checked at the next connect
+                godot_error!("{}", e);
+                return;
+            }
+        };
+        // As in finish_send, a pending or timed-out payout keeps its journal entry and its share
+        // of the throttle until it is seen settled
+        let unsettled = match &result {
+            Ok(payment) => payment.status == PaymentStatus::Pending,
+            Err(e) => is_timeout_error(e),
+        };
+        if !unsettled {
+            self.journal_end(&batch.journal_ids[index]);
+        }
+        let payout = &batch.payouts[index];
+        let mut dict = Dictionary::new();
+        match result {
+            Ok(payment) => {
+                self.queue_webhook("payment_sent", &payment);
+                dict.set("success", true);
+                dict.set("payment_id", payment.id.as_str());
+                dict.set("fee_sats", payment.fees as i64);
+                dict.set("status", payment.status.to_string());
+                dict.set("final", !unsettled);
+            }
+            Err(e) => {
+                godot_error!("{}", e);
+                // Failed payouts give their share of the throttle reservation back
+                if !unsettled {
+                    batch.released += payout.amount_sats + batch.fees[index];
+                }
+                dict.set("final", !unsettled);
+                dict.set("success", false);
+                dict.set("error", e);
+            }
+        }
+        dict.set("address", payout.address.as_str());
+        dict.set("amount_sats", payout.amount_sats as i64);
+        dict.set("note", payout.note.as_str());
+        batch.results[index] = Some(dict);
+    }
+}
@@ -0,0 +1,92 @@
+use lightning_invoice::{Bolt11Invoice, Bolt11ParseError, ParseOrSemanticError};
+use std::str::FromStr;
+
+/// Parse a BOLT11 invoice locally, accepting an optional `lightning:` prefix
+pub(crate) fn parse_bolt11(input: &str) -> Result<Bolt11Invoice, String> {
+    parse_bolt11_coded(input).map_err(|(error, _)| error)
+}
+
+/// Parse a BOLT11 invoice like `parse_bolt11`, with an error code for the failure:
+/// INVALID_BECH32 when the string is not valid bech32, INVALID_INVOICE otherwise
+pub(crate) fn parse_bolt11_coded(input: &str) -> Result<Bolt11Invoice, (String, &'static str)> {
+    let trimmed = input.trim();
+    let stripped = trimmed
+        .strip_prefix("lightning:")
+        .or_else(|| trimmed.strip_prefix("LIGHTNING:"))
+        .unwrap_or(trimmed);
+    Bolt11Invoice::from_str(&stripped.to_lowercase()).map_err(|e| {
+        let code = match e {
+            ParseOrSemanticError::ParseError(Bolt11ParseError::Bech32Error(_)) => "INVALID_BECH32",
+            _ => "INVALID_INVOICE",
+        };
+        (format!("Invalid BOLT11 invoice: {}", e), code)
+    })
+}
+
+/// Hex-encoded payment hash of an invoice
+pub(crate) fn payment_hash_hex(invoice: &Bolt11Invoice) -> String {
+    invoice.payment_hash().to_string()
+}
+
+/// Hex-encoded payee public key, recovered from the signature if not included
+pub(crate) fn payee_pubkey_hex(invoice: &Bolt11Invoice) -> String {
+    match invoice.payee_pub_key() {
+        Some(pubkey) => pubkey.to_string(),
+        None => invoice.recover_payee_pub_key().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validation::{check_description, MAX_DESCRIPTION_BYTES};
+    use bitcoin::hashes::{sha256, Hash};
+    use bitcoin::secp256k1::{Secp256k1, SecretKey};
+    use lightning_invoice::{Bolt11InvoiceDescriptionRef, Currency, InvoiceBuilder, PaymentSecret};
+    use std::time::Duration;
+
+    /// Sign an invoice carrying `description`, as the SDK does after `check_description`
+    fn invoice_with(description: &str) -> String {
+        let key = SecretKey::from_slice(&[0x11; 32]).unwrap();
+        InvoiceBuilder::new(Currency::Bitcoin)
+            .description(description.to_string())
+            .payment_hash(sha256::Hash::hash(b"preimage"))
+            .payment_secret(PaymentSecret([0x22; 32]))
+            .duration_since_epoch(Duration::from_secs(1_700_000_000))
+            .min_final_cltv_expiry_delta(144)
+            .build_signed(|hash| Secp256k1::new().sign_ecdsa_recoverable(hash, &key))
+            .unwrap()
+            .to_string()
+    }
+
+    fn parsed_description(invoice: &str) -> String {
+        match parse_bolt11(invoice).unwrap().description() {
+            Bolt11InvoiceDescriptionRef::Direct(description) => description.to_string(),
+            Bolt11InvoiceDescriptionRef::Hash(_) => panic!("expected a direct description"),
+        }
+    }
+
+    #[test]
+    fn multilingual_descriptions_survive_the_invoice_round_trip() {
+        for text in ["Cafe\u{301} ☕", "ポーション x3 🧪", "שלום עולם", "👨‍👩‍👧 family pack"] {
+            let (description, _) = check_description(text, false).ok().unwrap();
+            assert_eq!(parsed_description(&invoice_with(&description)), description);
+        }
+    }
+
+    #[test]
+    fn truncated_description_fits_in_an_invoice() {
+        let long = "🇯🇵 ".repeat(100);
+        let (description, truncated) = check_description(&long, true).ok().unwrap();
+        assert!(truncated);
+        assert!(description.len() <= MAX_DESCRIPTION_BYTES);
+        assert_eq!(parsed_description(&invoice_with(&description)), description);
+    }
+
+    #[test]
+    fn uppercase_invoice_with_prefix_parses() {
+        let invoice = invoice_with("gg");
+        let parsed = parse_bolt11(&format!("LIGHTNING:{}", invoice.to_uppercase())).unwrap();
+        assert_eq!(payment_hash_hex(&parsed), sha256::Hash::hash(b"preimage").to_string());
+    }
+}
@@ -0,0 +1,21 @@
+use godot::prelude::*;
+
+/// Whether `input` is a BOLT12 offer ("lno1..."), with or without a "lightning:" prefix
+pub(crate) fn is_bolt12_offer(input: &str) -> bool {
+    let lower = input.trim().to_lowercase();
+    lower.strip_prefix("lightning:").unwrap_or(&lower).starts_with("lno1")
+}
+
+/// Error for a BOLT12 offer passed where a BOLT11 invoice is expected
+///
+/// The SDK cannot pay offers yet (see the `bolt12` capability), so there is no other method
+/// to point at.
+pub(crate) fn offer_in_invoice_error() -> Dictionary {
+    let error = "BOLT12 offers are not supported yet; ask for a BOLT11 invoice or lightning address";
+    godot_error!("{}", error);
+    let mut dict = Dictionary::new();
+    dict.set("success", false);
+    dict.set("error", error);
+    dict.set("error_code", "INVALID_DESTINATION");
+    dict
+}
@@ -0,0 +1,134 @@
+use godot::prelude::*;
+
+use crate::BreezNode;
+
+/// Error code shared by every method whose capability is unavailable
+pub(crate) const UNSUPPORTED: &str = "UNSUPPORTED";
+
+/// A feature area and what it needs to work
+struct Capability {
+    name: &'static str,
+    /// Whether this build of the extension implements it
+    compiled: bool,
+    /// Whether it needs a connected wallet
+    needs_connection: bool,
+    /// Networks it works on (empty for all)
+    networks: &'static [&'static str],
+}
+
+const CAPABILITIES: &[Capability] = &[
+    Capability { name: "lightning_send", compiled: true, needs_connection: true, networks: &[] },
+    Capability { name: "lightning_receive", compiled: true, needs_connection: true, networks: &[] },
+    Capability { name: "onchain_send", compiled: true, needs_connection: true, networks: &[] },
+    Capability { name: "onchain_receive", compiled: true, needs_connection: true, networks: &[] },
+    Capability { name: "spark_address", compiled: true, needs_connection: true, networks: &[] },
+    Capability { name: "lightning_address", compiled: true, needs_connection: true, networks: &[] },
+    Capability { name: "lnurl_pay", compiled: true, needs_connection: true, networks: &[] },
+    Capability { name: "invoice_expiry", compiled: false, needs_connection: true, networks: &[] },
+    Capability { name: "bolt12", compiled: false, needs_connection: true, networks: &[] },
+    Capability { name: "tokens", compiled: false, needs_connection: true, networks: &[] },
+    Capability { name: "fiat", compiled: true, needs_connection: true, networks: &[] },
+    Capability { name: "webhooks", compiled: true, needs_connection: true, networks: &[] },
+    Capability { name: "receipts", compiled: true, needs_connection: true, networks: &[] },
+    Capability { name: "profiles", compiled: true, needs_connection: false, networks: &[] },
+    Capability { name: "qr", compiled: true, needs_connection: false, networks: &[] },
+    Capability { name: "secure_storage", compiled: false, needs_connection: false, networks: &[] },
+    Capability { name: "regtest_utils", compiled: false, needs_connection: true, networks: &["regtest"] },
+];
+
+fn find(name: &str) -> Option<&'static Capability> {
+    CAPABILITIES.iter().find(|capability| capability.name == name)
+}
+
+/// Error Dictionary returned by methods of an unavailable capability
+pub(crate) fn unsupported(name: &str, reason: &str) -> Dictionary {
+    let mut dict = Dictionary::new();
+    dict.set("success", false);
+    dict.set("error", format!("{} is not supported: {}", name, reason));
+    dict.set("error_code", UNSUPPORTED);
+    dict.set("capability", name);
+    dict
+}
+
+#[godot_api(secondary)]
+impl BreezNode {
+    /// Report which feature areas this build and the connected network support
+    ///
+    /// Keyed by capability name, each entry has `compiled`, `available` and, when
+    /// unavailable, a `reason`. Methods of an unavailable capability fail with
+    /// `error_code` UNSUPPORTED.
+    #[func]
+    pub fn get_capabilities(&self) -> Dictionary {
+        let mut dict = Dictionary::new();
+        for capability in CAPABILITIES {
+            let mut entry = Dictionary::new();
+            entry.set("compiled", capability.compiled);
+            match self.capability_status(capability) {
+                Ok(()) => entry.set("available", true),
+                Err(reason) => {
+                    entry.set("available", false);
+                    entry.set("reason", reason);
+                }
+            }
+            dict.set(capability.name, entry);
+        }
+        dict
+    }
+
+    /// Whether this build and SDK version support a capability (see `get_capabilities`);
+    /// false for unknown names
+    ///
+    /// Meant for hiding UI of features the plugin was built without. Before connecting only
+    /// the build is checked; once connected, capabilities limited to other networks (such as
+    /// `regtest_utils` on mainnet) are reported as unsupported too.
+    #[func]
+    pub fn is_feature_supported(&self, name: GString) -> bool {
+        self.require_capability(&name.to_string()).is_ok()
+    }
+
+    /// Versions of this build, for bug reports
+    ///
+    /// Returns `plugin` (the extension's crate version), `breez_sdk_spark` and `godot_rust` (as
+    /// declared in Cargo.toml, the SDK by its git tag) and `git_commit`, which is empty when the
+    /// build did not happen in a git checkout.
+    #[func]
+    pub fn get_version(&self) -> Dictionary {
+        let mut dict = Dictionary::new();
+        dict.set("plugin", env!("CARGO_PKG_VERSION"));
+        dict.set("breez_sdk_spark", env!("BREEZ_SDK_SPARK_VERSION"));
+        dict.set("godot_rust", env!("GODOT_RUST_VERSION"));
+        dict.set("git_commit", env!("BREEZ_GODOT_GIT_COMMIT"));
+        dict
+    }
+}
+
+impl BreezNode {
+    fn capability_status(&self, capability: &Capability) -> Result<(), String> {
+        if !capability.compiled {
+            return Err("not included in this build".to_string());
+        }
+        if !capability.needs_connection {
+            return Ok(());
+        }
+        let Some(network) = self.wallet_network.lock().unwrap().clone() else {
+            return Err("not connected".to_string());
+        };
+        if !capability.networks.is_empty() && !capability.networks.contains(&network.as_str()) {
+            return Err(format!("not available on {}", network));
+        }
+        Ok(())
+    }
+
+    /// Fail with UNSUPPORTED if this build or the connected network lacks a capability
+    ///
+    /// Missing connections are left for the method itself to report.
+    pub(crate) fn require_capability(&self, name: &str) -> Result<(), Dictionary> {
+        let Some(capability) = find(name) else {
+            return Err(unsupported(name, "unknown capability"));
+        };
+        match self.capability_status(capability) {
+            Err(reason) if reason != "not connected" => Err(unsupported(name, &reason)),
+            _ => Ok(()),
+        }
+    }
+}
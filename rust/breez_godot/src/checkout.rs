@@ -0,0 +1,312 @@
+use godot::classes::{DirAccess, ResourceLoader, Texture2D};
+use godot::prelude::*;
+use breez_sdk_spark::{ListPaymentsRequest, PaymentDetails, PaymentStatus, PaymentType};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::bolt11::{parse_bolt11, payment_hash_hex};
+use crate::events::NodeEvent;
+use crate::invoices::description_error;
+use crate::limits::{check_amount, AmountMethod};
+use crate::validation::check_description;
+use crate::BreezNode;
+
+/// Number of recent payments scanned for completed purchases
+const PURCHASE_SCAN_LIMIT: u32 = 50;
+
+/// A purchasable item defined in the editor as a `.tres` Resource
+///
+/// Priced in sats, or in a fiat currency when `fiat_currency` is set, in which case the
+/// price is converted to sats at purchase time.
+#[derive(GodotClass)]
+#[class(base=Resource, init)]
+pub struct BreezProduct {
+    /// Unique product id
+    #[export]
+    id: GString,
+    /// Title shown to the player, also used as the invoice description
+    #[export]
+    title: GString,
+    /// Price in sats (used when `fiat_currency` is empty)
+    #[export]
+    price_sats: i64,
+    /// Price in `fiat_currency`
+    #[export]
+    price_fiat: f64,
+    /// Currency code for fiat pricing, e.g. "USD" (empty to price in sats)
+    #[export]
+    fiat_currency: GString,
+    #[export]
+    icon: Option<Gd<Texture2D>>,
+    /// Custom data for fulfillment code
+    #[export]
+    metadata: Dictionary,
+}
+
+impl BreezProduct {
+    fn is_fiat_priced(&self) -> bool {
+        !self.fiat_currency.is_empty()
+    }
+
+    /// Check the product can be sold
+    fn validate(&self) -> Result<(), String> {
+        if self.id.is_empty() {
+            return Err("Product has an empty id".to_string());
+        }
+        if self.is_fiat_priced() {
+            if !(self.price_fiat > 0.0) {
+                return Err(format!("Product {} has a non-positive fiat price", self.id));
+            }
+        } else if self.price_sats <= 0 {
+            return Err(format!("Product {} has a non-positive price", self.id));
+        }
+        Ok(())
+    }
+}
+
+/// An invoice created for a product and not paid yet
+struct PendingPurchase {
+    product_id: String,
+    amount_sats: i64,
+}
+
+/// Registered products and purchases awaiting payment, keyed by payment hash
+#[derive(Default)]
+pub(crate) struct CheckoutState {
+    products: HashMap<String, Gd<BreezProduct>>,
+    pending: HashMap<String, PendingPurchase>,
+}
+
+#[godot_api(secondary)]
+impl BreezNode {
+    /// Register the products available for purchase, replacing any previous catalog
+    ///
+    /// Invalid products (empty id, zero or negative price) and duplicate ids are skipped.
+    /// Returns a Dictionary with `success`, `count` and `errors`.
+    #[func]
+    pub fn register_products(&mut self, products: Array<Gd<BreezProduct>>) -> Dictionary {
+        let result = self.register_products_with(products);
+        self.report_failure("register_products", result)
+    }
+
+    /// Load and register every `BreezProduct` Resource in a directory
+    ///
+    /// Same result as `register_products`.
+    #[func]
+    pub fn load_products_from_dir(&mut self, path: GString) -> Dictionary {
+        let result = self.load_products_from_dir_with(path);
+        self.report_failure("load_products_from_dir", result)
+    }
+
+    /// Get a registered product by id, or null
+    #[func]
+    pub fn get_product(&self, product_id: GString) -> Option<Gd<BreezProduct>> {
+        self.checkout.lock().unwrap().products.get(&product_id.to_string()).cloned()
+    }
+
+    /// Create an invoice for a registered product
+    ///
+    /// Fiat-priced products are converted at the current rate, refusing rates older than
+    /// the max staleness. `purchase_completed` is emitted once the invoice is paid.
+    /// Returns a Dictionary with `success`, `invoice`, `amount_sats`, `product_id` and
+    /// `payment_hash`.
+    #[func]
+    pub fn purchase_product(&self, product_id: GString) -> Dictionary {
+        let mut dict = Dictionary::new();
+        let id = product_id.to_string();
+
+        let amount_sats = self.product_price_sats(&id);
+        let (amount_sats, title) = match amount_sats {
+            Ok(priced) => priced,
+            Err(e) => {
+                godot_error!("{}", e);
+                dict.set("success", false);
+                dict.set("error", e);
+                return self.report_failure("purchase_product", dict);
+            }
+        };
+
+        if let Err(error) = check_amount(AmountMethod::Lightning, amount_sats) {
+            return self.report_failure("purchase_product", error);
+        }
+
+        // Titles are editor data, so trim long ones rather than make the product unsellable
+        let (title, truncated) = match check_description(&title, true) {
+            Ok(checked) => checked,
+            Err(e) => return self.report_failure("purchase_product", description_error(&e)),
+        };
+
+        let invoice = self.create_invoice(amount_sats, GString::from(&title));
+        let payment_hash = parse_bolt11(&invoice.to_string()).map(|parsed| payment_hash_hex(&parsed));
+        let payment_hash = match payment_hash {
+            Ok(payment_hash) => payment_hash,
+            Err(_) => {
+                dict.set("success", false);
+                dict.set("error", "Failed to create invoice");
+                return self.report_failure("purchase_product", dict);
+            }
+        };
+
+        self.checkout.lock().unwrap().pending.insert(
+            payment_hash.clone(),
+            PendingPurchase {
+                product_id: id.clone(),
+                amount_sats,
+            },
+        );
+
+        dict.set("success", true);
+        dict.set("invoice", invoice);
+        dict.set("amount_sats", amount_sats);
+        dict.set("product_id", id);
+        dict.set("payment_hash", payment_hash);
+        dict.set("description_truncated", truncated);
+        self.report_failure("purchase_product", dict)
+    }
+
+    /// Check recent payments for paid product invoices, queueing `purchase_completed`
+    ///
+    /// Runs after each wallet sync. Returns the number of purchases completed.
+    #[func]
+    pub fn check_purchases(&self) -> i64 {
+        if self.checkout.lock().unwrap().pending.is_empty() {
+            return 0;
+        }
+
+        let sdk_handle = self.sdk_handle();
+        let runtime = Arc::clone(&self.runtime);
+
+        let payments = runtime.block_on(async move {
+            match sdk_handle {
+                Ok(sdk) => sdk
+                    .list_payments(ListPaymentsRequest {
+                        offset: None,
+                        limit: Some(PURCHASE_SCAN_LIMIT),
+                    })
+                    .await
+                    .map(|response| response.payments)
+                    .unwrap_or_default(),
+                Err(_) => Vec::new(),
+            }
+        });
+
+        let mut checkout = self.checkout.lock().unwrap();
+        let mut completed = 0;
+        for payment in payments {
+            if payment.payment_type != PaymentType::Receive || payment.status != PaymentStatus::Completed {
+                continue;
+            }
+            let Some(PaymentDetails::Lightning { payment_hash, .. }) = &payment.details else {
+                continue;
+            };
+            if let Some(purchase) = checkout.pending.remove(payment_hash) {
+                godot_print!("✅ Purchase completed: {} ({} sats)", purchase.product_id, purchase.amount_sats);
+                self.events.push(NodeEvent::PurchaseCompleted {
+                    product_id: purchase.product_id,
+                    payment_id: payment.id.clone(),
+                });
+                completed += 1;
+            }
+        }
+        completed
+    }
+}
+
+impl BreezNode {
+    fn register_products_with(&mut self, products: Array<Gd<BreezProduct>>) -> Dictionary {
+        let mut catalog = HashMap::new();
+        let mut errors = PackedStringArray::new();
+
+        for product in products.iter_shared() {
+            let id = product.bind().id.to_string();
+            if let Err(e) = product.bind().validate() {
+                errors.push(&GString::from(&e));
+                continue;
+            }
+            if catalog.contains_key(&id) {
+                errors.push(&GString::from(&format!("Duplicate product id: {}", id)));
+                continue;
+            }
+            catalog.insert(id, product);
+        }
+
+        for error in errors.as_slice() {
+            godot_warn!("{}", error);
+        }
+
+        let count = catalog.len() as i64;
+        self.checkout.lock().unwrap().products = catalog;
+
+        let mut dict = Dictionary::new();
+        dict.set("success", errors.is_empty());
+        dict.set("count", count);
+        dict.set("errors", errors);
+        dict
+    }
+
+    fn load_products_from_dir_with(&mut self, path: GString) -> Dictionary {
+        let dir_path = path.to_string().trim_end_matches('/').to_string();
+        let mut products = Array::new();
+
+        let Some(dir) = DirAccess::open(&dir_path) else {
+            let mut dict = Dictionary::new();
+            dict.set("success", false);
+            dict.set("count", 0);
+            dict.set("error", format!("Failed to open product directory: {}", dir_path));
+            return dict;
+        };
+
+        let mut loader = ResourceLoader::singleton();
+        for file in dir.get_files().as_slice() {
+            // Exported projects list imported resources with a .remap suffix
+            let file = file.to_string();
+            let file = file.trim_end_matches(".remap");
+            if !file.ends_with(".tres") && !file.ends_with(".res") {
+                continue;
+            }
+            let file_path = format!("{}/{}", dir_path, file);
+            if let Some(product) = loader
+                .load(&file_path)
+                .and_then(|resource| resource.try_cast::<BreezProduct>().ok())
+            {
+                products.push(&product);
+            }
+        }
+
+        self.register_products(products)
+    }
+
+    /// Drop purchases awaiting payment, e.g. when switching wallets
+    pub(crate) fn clear_pending_purchases(&self) {
+        self.checkout.lock().unwrap().pending.clear();
+    }
+
+    /// Price of a registered product in sats, with its title
+    fn product_price_sats(&self, product_id: &str) -> Result<(i64, String), String> {
+        let product = self
+            .checkout
+            .lock()
+            .unwrap()
+            .products
+            .get(product_id)
+            .cloned()
+            .ok_or_else(|| format!("Unknown product: {}", product_id))?;
+        let product = product.bind();
+        let title = product.title.to_string();
+
+        if !product.is_fiat_priced() {
+            return Ok((product.price_sats, title));
+        }
+
+        let quote = self.rate_quote(&product.fiat_currency.to_string())?;
+        if self.fiat.lock().unwrap().is_too_old(&quote) {
+            return Err("Fiat rate is too old to price a product".to_string());
+        }
+        if quote.rate <= 0.0 {
+            return Err("Invalid fiat rate".to_string());
+        }
+
+        let amount_sats = (product.price_fiat / quote.rate * 100_000_000.0).round() as i64;
+        Ok((amount_sats, title))
+    }
+}
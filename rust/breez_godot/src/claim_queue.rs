@@ -0,0 +1,264 @@
+use godot::prelude::*;
+use breez_sdk_spark::ListUnclaimedDepositsRequest;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::deposits::ClaimFee;
+use crate::events::NodeEvent;
+use crate::fees::CLAIM_TX_VBYTES;
+use crate::storage::{load_json, now_secs, save_json};
+use crate::BreezNode;
+
+const CLAIM_QUEUE_FILE: &str = "claim_queue.json";
+
+/// Delay before the first retry, doubled after each failed attempt
+const RETRY_BASE_SECS: u64 = 60;
+
+/// Longest delay between two retries
+const RETRY_MAX_SECS: u64 = 6 * 3_600;
+
+/// A deposit claim that failed and is waiting to be retried
+#[derive(Serialize, Deserialize, Clone)]
+pub(crate) struct QueuedClaim {
+    txid: String,
+    vout: u32,
+    /// Fixed fee ceiling the claim was requested with (0 for any fee or a rate)
+    max_fee_sats: u64,
+    /// Fee limit the claim was requested with; queues written before rates were supported
+    /// only have `max_fee_sats`
+    #[serde(default)]
+    fee: Option<ClaimFee>,
+    attempts: u32,
+    next_retry_at: u64,
+    last_error: String,
+    enqueued_at: u64,
+}
+
+impl QueuedClaim {
+    fn fee(&self) -> ClaimFee {
+        self.fee.unwrap_or_else(|| ClaimFee::from_max_fee_sats(self.max_fee_sats as i64))
+    }
+}
+
+/// Outcome of a retried claim: (amount_sats, fee_sats, payment_id) or the error
+pub(crate) type ClaimRetryResult = Result<(i64, i64, String), String>;
+
+fn retry_delay(attempts: u32) -> u64 {
+    RETRY_BASE_SECS
+        .saturating_mul(1u64 << attempts.saturating_sub(1).min(16))
+        .min(RETRY_MAX_SECS)
+}
+
+/// Result Dictionary passed with `deposit_claim_retried`
+pub(crate) fn claim_retry_dictionary(result: &ClaimRetryResult) -> Dictionary {
+    let mut dict = Dictionary::new();
+    match result {
+        Ok((amount, fee, payment_id)) => {
+            dict.set("success", true);
+            dict.set("amount_sats", *amount);
+            dict.set("fee_sats", *fee);
+            dict.set("payment_id", payment_id.clone());
+        }
+        Err(e) => {
+            dict.set("success", false);
+            dict.set("error", e.clone());
+        }
+    }
+    dict
+}
+
+#[godot_api(secondary)]
+impl BreezNode {
+    /// Deposit claims waiting to be retried
+    ///
+    /// Each entry has `txid`, `vout`, `max_fee_sats`, `fee` (the fee limit as in
+    /// `claim_all_deposits_with_fee`), `attempts`, `next_retry_at`, `last_error` and
+    /// `enqueued_at`.
+    #[func]
+    pub fn list_claim_queue(&self) -> Array<Dictionary> {
+        let mut array = Array::new();
+        for entry in self.claim_queue.lock().unwrap().iter() {
+            let mut dict = Dictionary::new();
+            dict.set("txid", entry.txid.clone());
+            dict.set("vout", entry.vout as i64);
+            dict.set("max_fee_sats", entry.max_fee_sats as i64);
+            dict.set("fee", entry.fee().to_dictionary());
+            dict.set("attempts", entry.attempts as i64);
+            dict.set("next_retry_at", entry.next_retry_at as i64);
+            dict.set("last_error", entry.last_error.clone());
+            dict.set("enqueued_at", entry.enqueued_at as i64);
+            array.push(&dict);
+        }
+        array
+    }
+
+    /// Stop retrying a queued deposit claim
+    #[func]
+    pub fn remove_from_claim_queue(&self, txid: GString, vout: i64) -> bool {
+        let txid = txid.to_string();
+        let removed = {
+            let mut queue = self.claim_queue.lock().unwrap();
+            let before = queue.len();
+            queue.retain(|entry| !(entry.txid == txid && entry.vout as i64 == vout));
+            queue.len() != before
+        };
+        if removed {
+            self.save_claim_queue();
+        }
+        removed
+    }
+}
+
+impl BreezNode {
+    /// Load the claim queue of the connected wallet, or clear it when disconnected
+    pub(crate) fn load_claim_queue(&self) {
+        let entries = self
+            .plugin_path(CLAIM_QUEUE_FILE)
+            .and_then(|path| load_json::<Vec<QueuedClaim>>(&path))
+            .unwrap_or_default();
+        *self.claim_queue.lock().unwrap() = entries;
+    }
+
+    fn save_claim_queue(&self) {
+        let Some(path) = self.plugin_path(CLAIM_QUEUE_FILE) else {
+            return;
+        };
+        let queue = self.claim_queue.lock().unwrap().clone();
+        if let Err(e) = save_json(&path, &queue) {
+            godot_warn!("Failed to persist claim queue: {}", e);
+        }
+    }
+
+    /// Queue a failed claim for retry, or update the entry if it is already queued
+    pub(crate) fn enqueue_claim(&self, txid: &str, vout: u32, fee: ClaimFee, error: &str) {
+        if self.wallet_dir.lock().unwrap().is_none() {
+            return;
+        }
+        let now = now_secs();
+        {
+            let mut queue = self.claim_queue.lock().unwrap();
+            match queue.iter_mut().find(|entry| entry.txid == txid && entry.vout == vout) {
+                Some(entry) => {
+                    entry.max_fee_sats = fee.max_fee_sats();
+                    entry.fee = Some(fee);
+                    entry.attempts += 1;
+                    entry.next_retry_at = now + retry_delay(entry.attempts);
+                    entry.last_error = error.to_string();
+                }
+                None => queue.push(QueuedClaim {
+                    txid: txid.to_string(),
+                    vout,
+                    max_fee_sats: fee.max_fee_sats(),
+                    fee: Some(fee),
+                    attempts: 1,
+                    next_retry_at: now + retry_delay(1),
+                    last_error: error.to_string(),
+                    enqueued_at: now,
+                }),
+            }
+        }
+        self.save_claim_queue();
+    }
+
+    /// Forget a queued claim after it succeeded
+    pub(crate) fn dequeue_claim(&self, txid: &str, vout: u32) {
+        let removed = {
+            let mut queue = self.claim_queue.lock().unwrap();
+            let before = queue.len();
+            queue.retain(|entry| !(entry.txid == txid && entry.vout == vout));
+            queue.len() != before
+        };
+        if removed {
+            self.save_claim_queue();
+        }
+    }
+
+    /// Retry queued claims whose backoff has elapsed
+    ///
+    /// With `rate_sat_vb`, claims whose fee ceiling now covers the estimated claim fee are
+    /// retried right away. Deposits that are no longer unclaimed are dropped from the queue.
+    pub(crate) fn retry_queued_claims(&self, rate_sat_vb: Option<u64>) {
+        let now = now_secs();
+        let due: Vec<QueuedClaim> = self
+            .claim_queue
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|entry| {
+                entry.next_retry_at <= now
+                    || rate_sat_vb.is_some_and(|rate| {
+                        entry.max_fee_sats == 0 || rate * CLAIM_TX_VBYTES <= entry.max_fee_sats
+                    })
+            })
+            .cloned()
+            .collect();
+        if due.is_empty() {
+            return;
+        }
+
+        let sdk_handle = self.sdk_handle();
+        let runtime = Arc::clone(&self.runtime);
+        let unclaimed = runtime.block_on(async move {
+            let sdk = sdk_handle?;
+            match sdk.list_unclaimed_deposits(ListUnclaimedDepositsRequest {}).await {
+                Ok(response) => Ok(response.deposits),
+                Err(e) => Err(format!("Failed to list deposits: {:?}", e)),
+            }
+        });
+        let unclaimed = match unclaimed {
+            Ok(deposits) => deposits,
+            Err(e) => {
+                // Push the due entries back so a failing backend is not polled every frame
+                godot_warn!("Claim queue retry postponed: {}", e);
+                for entry in self.claim_queue.lock().unwrap().iter_mut() {
+                    if due.iter().any(|d| d.txid == entry.txid && d.vout == entry.vout) {
+                        entry.next_retry_at = now + RETRY_BASE_SECS;
+                    }
+                }
+                self.save_claim_queue();
+                return;
+            }
+        };
+
+        // Claimed elsewhere or reorged away, nothing left to retry
+        let dropped = {
+            let mut queue = self.claim_queue.lock().unwrap();
+            let before = queue.len();
+            queue.retain(|entry| unclaimed.iter().any(|d| d.txid == entry.txid && d.vout == entry.vout));
+            before - queue.len()
+        };
+        if dropped > 0 {
+            godot_print!("Dropped {} claim(s) whose deposits are gone", dropped);
+            self.save_claim_queue();
+        }
+
+        for entry in due {
+            if !unclaimed.iter().any(|d| d.txid == entry.txid && d.vout == entry.vout) {
+                continue;
+            }
+            // Not an attempt yet, check again later without counting it against the backoff
+            if !matches!(self.confirmations_remaining(&entry.txid), Ok(0)) {
+                for queued in self.claim_queue.lock().unwrap().iter_mut() {
+                    if queued.txid == entry.txid && queued.vout == entry.vout {
+                        queued.next_retry_at = now + RETRY_BASE_SECS;
+                    }
+                }
+                self.save_claim_queue();
+                continue;
+            }
+            let result = match self.claim_deposit_now(&entry.txid, entry.vout, entry.fee()) {
+                Ok(payment) => Ok((payment.amount as i64, payment.fees as i64, payment.id)),
+                Err(e) => {
+                    self.enqueue_claim(&entry.txid, entry.vout, entry.fee(), &e);
+                    self.report_background_error("claim_retry", &e);
+                    Err(e)
+                }
+            };
+            self.events.push(NodeEvent::DepositClaimRetried {
+                txid: entry.txid,
+                vout: entry.vout as i64,
+                result,
+            });
+        }
+    }
+}
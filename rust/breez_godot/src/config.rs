@@ -0,0 +1,330 @@
+use godot::classes::{Os, ProjectSettings};
+use godot::prelude::*;
+use breez_sdk_spark::{Config, Fee};
+
+use crate::deposits::ClaimFee;
+use crate::fees::FeeWarningPolicy;
+use crate::{connect_storage_dir, BreezNode, ConnectOptions};
+
+/// Connection settings saved as a `.tres` Resource
+///
+/// The mnemonic is deliberately not part of this Resource; it is passed to
+/// `BreezNode.connect_with_config` at runtime so it never ends up in a saved file.
+#[derive(GodotClass)]
+#[class(base=Resource, init)]
+pub struct BreezConfig {
+    /// Network to connect to
+    #[export(enum = (Mainnet, Regtest))]
+    network: i32,
+    /// Wallet data directory (`user://` paths are supported)
+    #[export]
+    #[init(val = GString::from("user://breez"))]
+    storage_dir: GString,
+    /// API key source: "env:NAME", "setting:path/in/project_settings", or the key itself
+    #[export]
+    api_key_source: GString,
+    /// Connect as soon as credentials are provided (used by the Breez wrapper node)
+    #[export]
+    autoconnect: bool,
+    /// SDK background sync interval in seconds (0 for the SDK default)
+    #[export]
+    sync_interval_secs: i64,
+    /// Maximum fee for automatic deposit claims (0 for the SDK default)
+    #[export]
+    max_deposit_claim_fee_sats: i64,
+    /// Absolute on-chain fee warning ceiling (0 to disable)
+    #[export]
+    max_fee_sats: i64,
+    /// On-chain fee warning ceiling as a percentage of the amount (0 to disable)
+    #[export]
+    #[init(val = 10.0)]
+    max_fee_percent: f64,
+    /// Recommended rate above which `high_fee_environment` fires (0 to disable)
+    #[export]
+    high_fee_rate_sat_vb: i64,
+    /// HTTP(S) proxy for the plugin's own requests, e.g. "http://127.0.0.1:8080"
+    #[export]
+    proxy_url: GString,
+}
+
+impl BreezConfig {
+    fn network_name(&self) -> &'static str {
+        match self.network {
+            1 => "regtest",
+            _ => "mainnet",
+        }
+    }
+
+    /// Resolve the API key from its configured source
+    fn resolve_api_key(&self) -> Result<String, String> {
+        let source = self.api_key_source.to_string();
+        if let Some(name) = source.strip_prefix("env:") {
+            return std::env::var(name).map_err(|_| format!("Environment variable {} is not set", name));
+        }
+        if let Some(setting) = source.strip_prefix("setting:") {
+            let settings = ProjectSettings::singleton();
+            if !settings.has_setting(setting) {
+                return Err(format!("Project setting {} is not set", setting));
+            }
+            return settings
+                .get_setting(setting)
+                .try_to::<GString>()
+                .map(|key| key.to_string())
+                .map_err(|_| format!("Project setting {} is not a string", setting));
+        }
+        if source.is_empty() {
+            return Err("No API key source configured".to_string());
+        }
+        Ok(source)
+    }
+}
+
+/// Keys accepted by `connect_with_config_dict`
+const CONFIG_KEYS: &[&str] = &[
+    "api_key",
+    "network",
+    "sync_interval_secs",
+    "max_deposit_claim_fee",
+    "lnurl_domain",
+    "prefer_spark_over_lightning",
+];
+
+/// SDK config settings from a Dictionary, applied on top of the network's default config
+struct ConfigOverrides {
+    api_key: String,
+    network: String,
+    sync_interval_secs: Option<u32>,
+    max_deposit_claim_fee: Option<ClaimFee>,
+    /// Some("") removes the default domain
+    lnurl_domain: Option<String>,
+    prefer_spark_over_lightning: Option<bool>,
+}
+
+impl ConfigOverrides {
+    /// Parse `config`, rejecting keys outside `CONFIG_KEYS` with UNKNOWN_OPTION
+    fn from_dictionary(config: &Dictionary) -> Result<Self, Dictionary> {
+        let mut parsed = ConfigOverrides {
+            api_key: String::new(),
+            network: "mainnet".to_string(),
+            sync_interval_secs: None,
+            max_deposit_claim_fee: None,
+            lnurl_domain: None,
+            prefer_spark_over_lightning: None,
+        };
+
+        for (key, value) in config.iter_shared() {
+            let key = key.to_string();
+            if !CONFIG_KEYS.contains(&key.as_str()) {
+                let mut dict = config_error(
+                    format!("Unknown config key '{}' (valid: {})", key, CONFIG_KEYS.join(", ")),
+                    "UNKNOWN_OPTION",
+                );
+                let valid: PackedStringArray = CONFIG_KEYS.iter().map(|k| GString::from(*k)).collect();
+                dict.set("valid_options", valid);
+                return Err(dict);
+            }
+            // The value is left out, as it may be the API key
+            let invalid = || config_error(format!("Invalid value for config key '{}'", key), "INVALID_OPTION");
+            let text = || {
+                let text = value.try_to::<GString>().map_err(|_| invalid())?;
+                Ok::<_, Dictionary>(text.to_string().trim().to_string())
+            };
+            match key.as_str() {
+                "api_key" => parsed.api_key = text()?,
+                "network" => parsed.network = text()?.to_lowercase(),
+                "sync_interval_secs" => {
+                    let secs = value.try_to::<i64>().map_err(|_| invalid())?;
+                    let secs = u32::try_from(secs).ok().filter(|secs| *secs > 0).ok_or_else(invalid)?;
+                    parsed.sync_interval_secs = Some(secs);
+                }
+                "max_deposit_claim_fee" => {
+                    let fee = match value.try_to::<i64>() {
+                        Ok(sats) if sats >= 0 => ClaimFee::from_max_fee_sats(sats),
+                        Ok(_) => return Err(invalid()),
+                        Err(_) => {
+                            let fee = value.try_to::<Dictionary>().map_err(|_| invalid())?;
+                            ClaimFee::from_dictionary(&fee).map_err(|_| invalid())?
+                        }
+                    };
+                    parsed.max_deposit_claim_fee = Some(fee);
+                }
+                "lnurl_domain" => parsed.lnurl_domain = Some(text()?),
+                "prefer_spark_over_lightning" => {
+                    parsed.prefer_spark_over_lightning = Some(value.try_to::<bool>().map_err(|_| invalid())?)
+                }
+                _ => unreachable!("config key listed as valid but not handled: {}", key),
+            }
+        }
+
+        if parsed.api_key.is_empty() {
+            return Err(config_error("Config key 'api_key' is required".to_string(), "INVALID_OPTION"));
+        }
+        Ok(parsed)
+    }
+
+    fn apply(&self, config: &mut Config) {
+        if let Some(secs) = self.sync_interval_secs {
+            config.sync_interval_secs = secs;
+        }
+        if let Some(fee) = self.max_deposit_claim_fee {
+            config.max_deposit_claim_fee = fee.to_sdk();
+        }
+        if let Some(domain) = &self.lnurl_domain {
+            config.lnurl_domain = (!domain.is_empty()).then(|| domain.clone());
+        }
+        if let Some(prefer_spark) = self.prefer_spark_over_lightning {
+            config.prefer_spark_over_lightning = prefer_spark;
+        }
+    }
+}
+
+fn config_error(error: String, code: &str) -> Dictionary {
+    godot_error!("{}", error);
+    let mut dict = Dictionary::new();
+    dict.set("success", false);
+    dict.set("error", error);
+    dict.set("error_code", code);
+    dict
+}
+
+#[godot_api(secondary)]
+impl BreezNode {
+    /// Connect with SDK settings from a Dictionary, starting from the network's defaults
+    ///
+    /// Recognized keys; all but `api_key` are optional:
+    /// * `api_key` - Your Breez API key
+    /// * `network` - "mainnet" (default) or "regtest"
+    /// * `sync_interval_secs` - Background sync interval in seconds
+    /// * `max_deposit_claim_fee` - Limit for automatic deposit claims, in sats or as a fee
+    ///   Dictionary as in `claim_deposit_with_fee`; 0 or `{}` for no limit
+    /// * `lnurl_domain` - Domain of lightning addresses and LNURLs, "" for none
+    /// * `prefer_spark_over_lightning` - Pay Spark-capable invoices over Spark
+    ///
+    /// Unknown keys fail with UNKNOWN_OPTION (with `valid_options`) rather than being
+    /// ignored, and bad values with INVALID_OPTION. Otherwise returns the Dictionary of
+    /// `connect_sdk_detailed`. `get_config` shows the settings in effect.
+    ///
+    /// # Arguments
+    /// * `config` - SDK settings
+    /// * `mnemonic` - 12 or 24 word BIP39 mnemonic phrase
+    /// * `storage_dir` - Directory to store wallet data (`user://` paths are supported, empty
+    ///   for `user://breez/`)
+    #[func]
+    pub fn connect_with_config_dict(
+        &mut self,
+        config: Dictionary,
+        mnemonic: GString,
+        storage_dir: GString,
+    ) -> Dictionary {
+        let overrides = match ConfigOverrides::from_dictionary(&config) {
+            Ok(overrides) => overrides,
+            Err(error) => return self.report_failure("connect_with_config_dict", error),
+        };
+        let result = connect_storage_dir(&storage_dir, &overrides.network).and_then(|storage_dir| {
+            self.connect_with(
+                mnemonic.to_string(),
+                None,
+                overrides.api_key.clone(),
+                overrides.network.clone(),
+                storage_dir,
+                ConnectOptions::default(),
+                |sdk_config| overrides.apply(sdk_config),
+            )
+        });
+        let dict = match result {
+            Ok(()) => {
+                let mut dict = Dictionary::new();
+                dict.set("success", true);
+                dict
+            }
+            Err(e) => e.to_dictionary(),
+        };
+        self.report_failure("connect_with_config_dict", dict)
+    }
+
+    /// SDK settings the connected wallet runs with
+    ///
+    /// Returns `success`, `network`, `sync_interval_secs`, `max_deposit_claim_fee` (a fee
+    /// Dictionary, type "any" without a limit), `lnurl_domain` ("" for none),
+    /// `prefer_spark_over_lightning` and `api_key_set` (the key itself is never returned),
+    /// or NOT_CONNECTED.
+    #[func]
+    pub fn get_config(&self) -> Dictionary {
+        let config = self.sdk_config.lock().unwrap().clone();
+        let Some(config) = config.filter(|_| self.is_sdk_connected()) else {
+            return self.report_failure("get_config", config_error("SDK not initialized".to_string(), "NOT_CONNECTED"));
+        };
+        let mut dict = Dictionary::new();
+        dict.set("success", true);
+        dict.set("network", self.wallet_network.lock().unwrap().clone().unwrap_or_default());
+        dict.set("sync_interval_secs", config.sync_interval_secs as i64);
+        dict.set("max_deposit_claim_fee", ClaimFee::from_sdk(config.max_deposit_claim_fee).to_dictionary());
+        dict.set("lnurl_domain", config.lnurl_domain.unwrap_or_default());
+        dict.set("prefer_spark_over_lightning", config.prefer_spark_over_lightning);
+        dict.set("api_key_set", config.api_key.is_some_and(|key| !key.is_empty()));
+        dict
+    }
+
+    /// Connect using a `BreezConfig` Resource
+    ///
+    /// # Arguments
+    /// * `config` - Connection settings
+    /// * `mnemonic` - 12 or 24 word BIP39 mnemonic phrase (never stored in the Resource)
+    #[func]
+    pub fn connect_with_config(&mut self, config: Gd<BreezConfig>, mnemonic: GString) -> bool {
+        let config = config.bind();
+
+        let api_key = match config.resolve_api_key() {
+            Ok(api_key) => api_key,
+            Err(e) => {
+                godot_error!("{}", e);
+                return false;
+            }
+        };
+
+        let network = config.network_name();
+        if network == "mainnet" && Os::singleton().is_debug_build() {
+            godot_warn!("⚠️ BreezConfig targets MAINNET from a debug build — real funds are at stake");
+        }
+
+        *self.fee_policy.lock().unwrap() = FeeWarningPolicy {
+            max_fee_sats: config.max_fee_sats.max(0) as u64,
+            max_fee_percent: config.max_fee_percent.max(0.0),
+            high_rate_sat_vb: config.high_fee_rate_sat_vb.max(0) as u64,
+        };
+
+        let proxy = config.proxy_url.to_string();
+        *self.http_proxy.lock().unwrap() = if proxy.is_empty() { None } else { Some(proxy) };
+
+        let storage_dir = match connect_storage_dir(&config.storage_dir, network) {
+            Ok(storage_dir) => storage_dir,
+            Err(e) => {
+                godot_error!("{}", e.message);
+                return false;
+            }
+        };
+        let sync_interval_secs = config.sync_interval_secs;
+        let max_claim_fee = config.max_deposit_claim_fee_sats;
+        drop(config);
+
+        self.connect_with(
+            mnemonic.to_string(),
+            None,
+            api_key,
+            network.to_string(),
+            storage_dir,
+            ConnectOptions::default(),
+            |sdk_config| {
+                if sync_interval_secs > 0 {
+                    sdk_config.sync_interval_secs = sync_interval_secs as u32;
+                }
+                if max_claim_fee > 0 {
+                    sdk_config.max_deposit_claim_fee = Some(Fee::Fixed {
+                        amount: max_claim_fee as u64,
+                    });
+                }
+            },
+        )
+        .is_ok()
+    }
+}
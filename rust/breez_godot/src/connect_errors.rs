@@ -0,0 +1,73 @@
+use godot::prelude::*;
+use std::path::Path;
+
+use crate::mnemonic::check_mnemonic_words;
+use crate::network_storage::NETWORKS;
+
+/// Why connecting failed, with the `error_code` reported by `connect_sdk_detailed`
+pub(crate) struct ConnectError {
+    pub(crate) message: String,
+    pub(crate) code: &'static str,
+}
+
+impl ConnectError {
+    pub(crate) fn new(message: String, code: &'static str) -> Self {
+        ConnectError { message, code }
+    }
+
+    /// Classify an error of the SDK's connect call by its message
+    pub(crate) fn from_sdk(message: String) -> Self {
+        let lower = message.to_lowercase();
+        let code = if ["mnemonic", "bip39", "checksum", "seed"].iter().any(|word| lower.contains(word)) {
+            "INVALID_MNEMONIC"
+        } else if ["api key", "api_key", "apikey", "unauthorized", "unauthenticated", "forbidden", "401", "403"]
+            .iter()
+            .any(|word| lower.contains(word))
+        {
+            "AUTH_FAILED"
+        } else if ["permission denied", "read-only", "readonly", "database", "sqlite", "storage"]
+            .iter()
+            .any(|word| lower.contains(word))
+        {
+            "STORAGE_NOT_WRITABLE"
+        } else if ["connect", "dns", "timed out", "timeout", "unreachable", "transport", "network", "http"]
+            .iter()
+            .any(|word| lower.contains(word))
+        {
+            "NETWORK_UNREACHABLE"
+        } else {
+            "SDK_ERROR"
+        };
+        ConnectError::new(message, code)
+    }
+
+    pub(crate) fn to_dictionary(&self) -> Dictionary {
+        let mut dict = Dictionary::new();
+        dict.set("success", false);
+        dict.set("error", self.message.as_str());
+        dict.set("error_code", self.code);
+        if self.code == "INVALID_NETWORK" {
+            let networks: Array<GString> = NETWORKS.iter().map(|network| GString::from(*network)).collect();
+            dict.set("valid_networks", networks);
+        }
+        dict
+    }
+}
+
+/// Check a mnemonic before handing it to the SDK (see `check_mnemonic_words`)
+pub(crate) fn check_mnemonic(mnemonic: &str) -> Result<(), ConnectError> {
+    check_mnemonic_words(mnemonic)
+        .map(|_| ())
+        .map_err(|problem| ConnectError::new(problem.message, "INVALID_MNEMONIC"))
+}
+
+/// Create the storage directory if needed and check that files can be written in it
+pub(crate) fn check_storage_writable(dir: &Path) -> Result<(), ConnectError> {
+    let not_writable =
+        |e: std::io::Error| ConnectError::new(format!("Cannot write to {}: {}", dir.display(), e), "STORAGE_NOT_WRITABLE");
+    std::fs::create_dir_all(dir).map_err(not_writable)?;
+    let probe = dir.join(".write_check");
+    std::fs::write(&probe, b"").map_err(not_writable)?;
+    let _ = std::fs::remove_file(&probe);
+    Ok(())
+}
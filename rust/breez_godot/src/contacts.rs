@@ -0,0 +1,284 @@
+use godot::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::storage::{globalize_path, load_json, now_secs, save_json};
+use crate::validation::{normalize_text, truncate_utf8, validate_destination};
+use crate::BreezNode;
+
+const CONTACTS_FILE: &str = "contacts.json";
+
+/// Version of the contact export format
+const CONTACTS_EXPORT_VERSION: u32 = 1;
+
+/// Longest contact name, in UTF-8 bytes
+const MAX_CONTACT_NAME_BYTES: usize = 128;
+
+/// Longest contact note, in UTF-8 bytes (longer notes are truncated)
+const MAX_CONTACT_NOTE_BYTES: usize = 1024;
+
+/// Normalize a contact name, rejecting empty or overlong names
+fn check_contact_name(name: &str) -> Result<String, String> {
+    let name = normalize_text(name.trim());
+    if name.is_empty() {
+        return Err("Contact name is empty".to_string());
+    }
+    if name.len() > MAX_CONTACT_NAME_BYTES {
+        return Err(format!(
+            "Contact name is {} bytes, the limit is {}",
+            name.len(),
+            MAX_CONTACT_NAME_BYTES
+        ));
+    }
+    Ok(name)
+}
+
+/// A saved payment recipient
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct Contact {
+    pub(crate) name: String,
+    pub(crate) destination: String,
+    #[serde(default)]
+    pub(crate) note: String,
+    #[serde(default)]
+    pub(crate) created_at: u64,
+}
+
+impl Contact {
+    fn to_dictionary(&self) -> Dictionary {
+        let mut dict = Dictionary::new();
+        dict.set("name", self.name.as_str());
+        dict.set("destination", self.destination.as_str());
+        dict.set("note", self.note.as_str());
+        dict.set("created_at", self.created_at as i64);
+        if let Ok(kind) = validate_destination(&self.destination) {
+            dict.set("kind", kind.as_str());
+        }
+        dict
+    }
+}
+
+/// On-disk export format
+#[derive(Serialize, Deserialize)]
+struct ContactExport {
+    version: u32,
+    exported_at: u64,
+    contacts: Vec<Contact>,
+}
+
+fn error_dict(error: String) -> Dictionary {
+    godot_error!("{}", error);
+    let mut dict = Dictionary::new();
+    dict.set("success", false);
+    dict.set("error", error);
+    dict
+}
+
+#[godot_api(secondary)]
+impl BreezNode {
+    /// Save a recipient under a name, replacing any contact with the same name
+    ///
+    /// # Arguments
+    /// * `name` - Display name, unique within the contact book
+    /// * `destination` - Bitcoin, Spark or lightning address, or LNURL
+    /// * `note` - Free-form note, truncated to 1024 bytes (`note_truncated` in the result)
+    #[func]
+    pub fn add_contact(&self, name: GString, destination: GString, note: GString) -> Dictionary {
+        let result = self.add_contact_with(name, destination, note);
+        self.report_failure("add_contact", result)
+    }
+
+    /// Remove a contact by name
+    #[func]
+    pub fn remove_contact(&self, name: GString) -> bool {
+        let name = normalize_text(name.to_string().trim());
+        let mut contacts = self.load_contacts();
+        let before = contacts.len();
+        contacts.retain(|c| c.name != name);
+        if contacts.len() == before {
+            return false;
+        }
+        match self.save_contacts(&contacts) {
+            Ok(()) => true,
+            Err(e) => {
+                godot_error!("{}", e);
+                false
+            }
+        }
+    }
+
+    /// List saved contacts sorted by name
+    #[func]
+    pub fn list_contacts(&self) -> Array<Dictionary> {
+        let mut contacts = self.load_contacts();
+        contacts.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+        contacts.iter().map(Contact::to_dictionary).collect()
+    }
+
+    /// Export the contact book to a versioned JSON file
+    ///
+    /// Accepts absolute paths and `user://` paths. Returns `success`, `path` and `count`.
+    #[func]
+    pub fn export_contacts(&self, path: GString) -> Dictionary {
+        let result = self.export_contacts_with(path);
+        self.report_failure("export_contacts", result)
+    }
+
+    /// Import contacts from a file written by `export_contacts`
+    ///
+    /// Every entry is re-validated. With `merge`, entries are merged by name (same name
+    /// replaces the existing contact); otherwise the contact book is replaced wholesale.
+    /// An entry whose destination is already saved under another name is skipped and listed
+    /// in `duplicates`; invalid entries are listed in `failures`.
+    #[func]
+    pub fn import_contacts(&self, path: GString, merge: bool) -> Dictionary {
+        let result = self.import_contacts_with(path, merge);
+        self.report_failure("import_contacts", result)
+    }
+}
+
+impl BreezNode {
+    fn add_contact_with(&self, name: GString, destination: GString, note: GString) -> Dictionary {
+        let name = match check_contact_name(&name.to_string()) {
+            Ok(name) => name,
+            Err(e) => return error_dict(e),
+        };
+        let destination = destination.to_string().trim().to_string();
+        if let Err(e) = validate_destination(&destination) {
+            return error_dict(e);
+        }
+        let (note, note_truncated) = truncate_utf8(&normalize_text(&note.to_string()), MAX_CONTACT_NOTE_BYTES);
+
+        let mut contacts = self.load_contacts();
+        contacts.retain(|c| c.name != name);
+        let contact = Contact {
+            name,
+            destination,
+            note,
+            created_at: now_secs(),
+        };
+        let mut dict = contact.to_dictionary();
+        dict.set("note_truncated", note_truncated);
+        contacts.push(contact);
+
+        if let Err(e) = self.save_contacts(&contacts) {
+            return error_dict(e);
+        }
+        dict.set("success", true);
+        dict
+    }
+
+    fn export_contacts_with(&self, path: GString) -> Dictionary {
+        let path = globalize_path(&path.to_string());
+        let contacts = self.load_contacts();
+        let export = ContactExport {
+            version: CONTACTS_EXPORT_VERSION,
+            exported_at: now_secs(),
+            contacts,
+        };
+
+        if let Err(e) = save_json(std::path::Path::new(&path), &export) {
+            return error_dict(format!("Failed to export contacts: {}", e));
+        }
+
+        let mut dict = Dictionary::new();
+        dict.set("success", true);
+        dict.set("path", path.as_str());
+        dict.set("count", export.contacts.len() as i64);
+        dict
+    }
+
+    fn import_contacts_with(&self, path: GString, merge: bool) -> Dictionary {
+        let path = globalize_path(&path.to_string());
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) => return error_dict(format!("Failed to read contacts file: {}", e)),
+        };
+        let export: ContactExport = match serde_json::from_str(&contents) {
+            Ok(export) => export,
+            Err(e) => return error_dict(format!("Invalid contacts file: {}", e)),
+        };
+        if export.version > CONTACTS_EXPORT_VERSION {
+            return error_dict(format!("Unsupported contacts file version: {}", export.version));
+        }
+
+        let mut contacts = if merge { self.load_contacts() } else { Vec::new() };
+        let mut failures = Array::<Dictionary>::new();
+        let mut duplicates = Array::<Dictionary>::new();
+        let (mut added, mut updated) = (0i64, 0i64);
+
+        for (index, mut entry) in export.contacts.into_iter().enumerate() {
+            entry.destination = entry.destination.trim().to_string();
+            entry.note = truncate_utf8(&normalize_text(&entry.note), MAX_CONTACT_NOTE_BYTES).0;
+
+            let validation = check_contact_name(&entry.name).and_then(|name| {
+                entry.name = name;
+                validate_destination(&entry.destination).map(|_| ())
+            });
+            if let Err(e) = validation {
+                let mut failure = Dictionary::new();
+                failure.set("index", index as i64);
+                failure.set("name", entry.name.as_str());
+                failure.set("error", e);
+                failures.push(&failure);
+                continue;
+            }
+
+            let owners: HashMap<&str, &str> = contacts
+                .iter()
+                .map(|c| (c.destination.as_str(), c.name.as_str()))
+                .collect();
+            if let Some(existing) = owners.get(entry.destination.as_str()) {
+                if *existing != entry.name {
+                    let mut duplicate = Dictionary::new();
+                    duplicate.set("index", index as i64);
+                    duplicate.set("name", entry.name.as_str());
+                    duplicate.set("existing_name", *existing);
+                    duplicate.set("destination", entry.destination.as_str());
+                    duplicates.push(&duplicate);
+                    continue;
+                }
+            }
+
+            if entry.created_at == 0 {
+                entry.created_at = now_secs();
+            }
+            match contacts.iter_mut().find(|c| c.name == entry.name) {
+                Some(existing) => {
+                    *existing = entry;
+                    updated += 1;
+                }
+                None => {
+                    contacts.push(entry);
+                    added += 1;
+                }
+            }
+        }
+
+        if let Err(e) = self.save_contacts(&contacts) {
+            return error_dict(e);
+        }
+
+        let mut dict = Dictionary::new();
+        dict.set("success", true);
+        dict.set("added", added);
+        dict.set("updated", updated);
+        dict.set("failures", failures);
+        dict.set("duplicates", duplicates);
+        dict.set("total", contacts.len() as i64);
+        dict
+    }
+
+    pub(crate) fn load_contacts(&self) -> Vec<Contact> {
+        self.plugin_path(CONTACTS_FILE)
+            .and_then(|path| load_json(&path))
+            .unwrap_or_default()
+    }
+
+    fn save_contacts(&self, contacts: &[Contact]) -> Result<(), String> {
+        let path = self
+            .plugin_path(CONTACTS_FILE)
+            .ok_or_else(|| "SDK not initialized".to_string())?;
+        save_json(&path, &contacts).map_err(|e| format!("Failed to save contacts: {}", e))
+    }
+}
@@ -0,0 +1,448 @@
+use godot::prelude::*;
+use breez_sdk_spark::{DepositClaimError, DepositInfo, Fee, ListUnclaimedDepositsRequest, Payment, PaymentDetails};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use crate::events::NodeEvent;
+use crate::storage::{load_json, now_secs, save_json};
+use crate::BreezNode;
+
+const CLAIMED_DEPOSITS_FILE: &str = "claimed_deposits.json";
+
+/// A deposit claim performed through the plugin
+#[derive(Serialize, Deserialize, Clone)]
+pub(crate) struct ClaimedDeposit {
+    txid: String,
+    vout: u32,
+    amount_sats: u64,
+    fee_sats: u64,
+    payment_id: String,
+    /// Timestamp of the claim payment
+    payment_timestamp: u64,
+    /// When the claim returned, in unix seconds
+    claimed_at: u64,
+}
+
+/// Fee limit of a deposit claim
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub(crate) enum ClaimFee {
+    /// No limit of our own; the SDK's `max_deposit_claim_fee` applies
+    #[default]
+    Any,
+    /// At most this many sats
+    Fixed { amount: u64 },
+    /// At most this fee rate in sat/vB
+    Rate { sat_per_vbyte: u64 },
+}
+
+impl ClaimFee {
+    /// Fee of the integer `max_fee_sats` arguments, where 0 means any fee
+    pub(crate) fn from_max_fee_sats(max_fee_sats: i64) -> Self {
+        if max_fee_sats > 0 {
+            ClaimFee::Fixed { amount: max_fee_sats as u64 }
+        } else {
+            ClaimFee::Any
+        }
+    }
+
+    /// Parse `{"type": "fixed", "amount": n}` or `{"type": "rate", "sat_per_vbyte": n}`,
+    /// as for `send_onchain_with_fee`, with `value` accepted for either number; empty means
+    /// any fee
+    pub(crate) fn from_dictionary(fee: &Dictionary) -> Result<Self, String> {
+        if fee.is_empty() {
+            return Ok(ClaimFee::Any);
+        }
+        let invalid = || format!("Invalid claim fee: {} (expected a \"fixed\" amount or a \"rate\" in sat_per_vbyte)", fee);
+        let kind = fee
+            .get("type")
+            .and_then(|kind| kind.try_to::<GString>().ok())
+            .ok_or_else(invalid)?
+            .to_string();
+        let number = |key: &str| {
+            fee.get(key)
+                .or_else(|| fee.get("value"))
+                .and_then(|value| value.try_to::<i64>().ok())
+                .filter(|n| *n > 0)
+                .map(|n| n as u64)
+                .ok_or_else(invalid)
+        };
+        match kind.as_str() {
+            "fixed" => Ok(ClaimFee::Fixed { amount: number("amount")? }),
+            "rate" => Ok(ClaimFee::Rate { sat_per_vbyte: number("sat_per_vbyte")? }),
+            _ => Err(invalid()),
+        }
+    }
+
+    /// Fee limit of an SDK claim fee, e.g. the config's `max_deposit_claim_fee`
+    pub(crate) fn from_sdk(fee: Option<Fee>) -> Self {
+        match fee {
+            None => ClaimFee::Any,
+            Some(Fee::Fixed { amount }) => ClaimFee::Fixed { amount },
+            Some(Fee::Rate { sat_per_vbyte }) => ClaimFee::Rate { sat_per_vbyte },
+        }
+    }
+
+    /// `max_fee` of the SDK's claim request
+    pub(crate) fn to_sdk(self) -> Option<Fee> {
+        match self {
+            ClaimFee::Any => None,
+            ClaimFee::Fixed { amount } => Some(Fee::Fixed { amount }),
+            ClaimFee::Rate { sat_per_vbyte } => Some(Fee::Rate { sat_per_vbyte }),
+        }
+    }
+
+    /// Fixed ceiling in sats, 0 for rate limits and any fee
+    pub(crate) fn max_fee_sats(self) -> u64 {
+        match self {
+            ClaimFee::Fixed { amount } => amount,
+            _ => 0,
+        }
+    }
+
+    pub(crate) fn to_dictionary(self) -> Dictionary {
+        let mut dict = Dictionary::new();
+        match self {
+            ClaimFee::Any => dict.set("type", "any"),
+            ClaimFee::Fixed { amount } => {
+                dict.set("type", "fixed");
+                dict.set("amount", amount as i64);
+            }
+            ClaimFee::Rate { sat_per_vbyte } => {
+                dict.set("type", "rate");
+                dict.set("sat_per_vbyte", sat_per_vbyte as i64);
+            }
+        }
+        dict
+    }
+}
+
+/// Confirmations required before deposits are claimed
+#[derive(Default)]
+pub(crate) struct DepositPolicy {
+    min_confirmations: u64,
+    /// Deposits already reported through `deposit_confirmed`
+    confirmed: HashSet<(String, u32)>,
+}
+
+/// Failure of a deposit call, e.g. INVALID_INPUT for a txid or vout rejected by
+/// `validate_outpoint`
+pub(crate) fn deposit_error(error: String, code: &str) -> Dictionary {
+    godot_error!("{}", error);
+    let mut dict = Dictionary::new();
+    dict.set("success", false);
+    dict.set("error", error);
+    dict.set("error_code", code);
+    dict
+}
+
+/// INSUFFICIENT_CONFIRMATIONS failure for a deposit that cannot be claimed yet
+pub(crate) fn unconfirmed_deposit_error(txid: &str, remaining: Result<u64, String>) -> Dictionary {
+    let mut dict = Dictionary::new();
+    dict.set("success", false);
+    dict.set("error_code", "INSUFFICIENT_CONFIRMATIONS");
+    match remaining {
+        Ok(remaining) => {
+            godot_warn!("Deposit {} needs {} more confirmations", txid, remaining);
+            dict.set("error", format!("Deposit needs {} more confirmations", remaining));
+            dict.set("confirmations_remaining", remaining as i64);
+        }
+        Err(e) => {
+            godot_error!("{}", e);
+            dict.set("error", format!("Could not check confirmations: {}", e));
+        }
+    }
+    dict
+}
+
+#[godot_api(secondary)]
+impl BreezNode {
+    /// Require `confirmations` confirmations before deposits are claimed (0 to claim as soon
+    /// as the SDK allows)
+    ///
+    /// Enforced in `claim_deposit` and the claim retry queue, and reported by
+    /// `list_unclaimed_deposits`. Confirmations are looked up through the esplora endpoint
+    /// (see `set_esplora_url`). Claims the SDK makes on its own are not affected, so leave
+    /// the SDK's automatic claiming off when using this.
+    #[func]
+    pub fn set_min_confirmations(&mut self, confirmations: i64) {
+        let mut policy = self.deposit_policy.lock().unwrap();
+        policy.min_confirmations = confirmations.max(0) as u64;
+        policy.confirmed.clear();
+    }
+
+    /// Check unclaimed deposits against `min_confirmations`, queueing `deposit_confirmed` for
+    /// each deposit that reached it
+    ///
+    /// Runs after each wallet sync. Returns the number of newly confirmed deposits.
+    #[func]
+    pub fn check_deposit_confirmations(&self) -> i64 {
+        if self.deposit_policy.lock().unwrap().min_confirmations == 0 {
+            return 0;
+        }
+        let deposits = match self.fetch_unclaimed_deposits() {
+            Ok(deposits) => deposits,
+            Err(e) => {
+                godot_warn!("{}", e);
+                return 0;
+            }
+        };
+
+        let mut newly_confirmed = 0;
+        for deposit in deposits {
+            let key = (deposit.txid.clone(), deposit.vout);
+            if self.deposit_policy.lock().unwrap().confirmed.contains(&key) {
+                continue;
+            }
+            if let Ok(0) = self.confirmations_remaining(&deposit.txid) {
+                self.deposit_policy.lock().unwrap().confirmed.insert(key);
+                self.events.push(NodeEvent::DepositConfirmed {
+                    txid: deposit.txid,
+                    vout: deposit.vout as i64,
+                });
+                newly_confirmed += 1;
+            }
+        }
+        newly_confirmed
+    }
+
+    /// Claim every unclaimed deposit, e.g. after a top-up split over several outputs
+    ///
+    /// One failed claim does not stop the others. Returns one Dictionary per deposit with
+    /// `txid`, `vout`, `amount_sats` and the result of `claim_deposit` (`success` and
+    /// `payment_id`, or `error`); failed claims are queued for retry as there. An empty Array
+    /// means there was nothing to claim or the deposits could not be listed (logged).
+    ///
+    /// # Arguments
+    /// * `max_fee_sats_per_claim` - Maximum fee for each claim (0 for any fee)
+    #[func]
+    pub fn claim_all_deposits(&self, max_fee_sats_per_claim: i64) -> Array<Dictionary> {
+        self.claim_all_deposits_with(ClaimFee::from_max_fee_sats(max_fee_sats_per_claim))
+    }
+
+    /// Claim every unclaimed deposit with a fee limit for each claim: `{"type": "fixed",
+    /// "amount": n}` in sats, `{"type": "rate", "sat_per_vbyte": n}`, or empty for any fee
+    ///
+    /// Returns the same Array as `claim_all_deposits`; an invalid `fee` logs an error and
+    /// claims nothing.
+    #[func]
+    pub fn claim_all_deposits_with_fee(&self, fee: Dictionary) -> Array<Dictionary> {
+        match ClaimFee::from_dictionary(&fee) {
+            Ok(fee) => self.claim_all_deposits_with(fee),
+            Err(e) => {
+                godot_error!("{}", e);
+                Array::new()
+            }
+        }
+    }
+
+    /// Claim a deposit with a fee limit: `{"type": "fixed", "value": n}` for at most n sats,
+    /// `{"type": "rate", "value": n}` for at most n sat/vB, or empty for any fee
+    ///
+    /// `amount` and `sat_per_vbyte` are accepted in place of `value`, as in
+    /// `send_onchain_with_fee`. Returns the same Dictionary as `claim_deposit`, whose
+    /// `fee_sats` and `max_fee` report the fee paid and the limit it was claimed under. An
+    /// invalid `fee` fails with INVALID_OPTION.
+    #[func]
+    pub fn claim_deposit_with_fee(&self, txid: GString, vout: i64, fee: Dictionary) -> Dictionary {
+        let result = match ClaimFee::from_dictionary(&fee) {
+            Ok(fee) => self.claim_deposit_with(txid.to_string().trim(), vout, fee),
+            Err(e) => deposit_error(e, "INVALID_OPTION"),
+        };
+        self.report_failure("claim_deposit_with_fee", result)
+    }
+
+    /// List claimed deposits, newest first
+    ///
+    /// Combines claims made through `claim_deposit` with deposit payments from history, so
+    /// deposits claimed automatically by the SDK are included too. Those have `vout` -1 and
+    /// `claimed_at` 0 because the SDK does not report them.
+    ///
+    /// Each entry has `txid`, `vout`, `amount_sats`, `fee_sats`, `payment_id`,
+    /// `payment_timestamp` and `claimed_at`.
+    ///
+    /// # Arguments
+    /// * `offset` - Number of deposits to skip (for pagination)
+    /// * `limit` - Maximum number of deposits to return (0 for all)
+    #[func]
+    pub fn list_claimed_deposits(&self, offset: i64, limit: i64) -> Array<Dictionary> {
+        let mut array = Array::new();
+
+        let mut deposits = self.load_claimed_deposits();
+        match self.fetch_all_payments() {
+            Ok(payments) => {
+                for payment in payments {
+                    let Some(PaymentDetails::Deposit { tx_id }) = &payment.details else {
+                        continue;
+                    };
+                    if deposits.iter().any(|d| d.payment_id == payment.id) {
+                        continue;
+                    }
+                    deposits.push(ClaimedDeposit {
+                        txid: tx_id.clone(),
+                        vout: u32::MAX,
+                        amount_sats: payment.amount,
+                        fee_sats: payment.fees,
+                        payment_id: payment.id.clone(),
+                        payment_timestamp: payment.timestamp,
+                        claimed_at: 0,
+                    });
+                }
+            }
+            Err(e) => godot_error!("{}", e),
+        }
+        deposits.sort_by(|a, b| b.payment_timestamp.cmp(&a.payment_timestamp));
+
+        let limit = if limit > 0 { limit as usize } else { usize::MAX };
+        for deposit in deposits.into_iter().skip(offset.max(0) as usize).take(limit) {
+            let mut dict = Dictionary::new();
+            dict.set("txid", deposit.txid);
+            dict.set("vout", if deposit.vout == u32::MAX { -1 } else { deposit.vout as i64 });
+            dict.set("amount_sats", deposit.amount_sats as i64);
+            dict.set("fee_sats", deposit.fee_sats as i64);
+            dict.set("payment_id", deposit.payment_id);
+            dict.set("payment_timestamp", deposit.payment_timestamp as i64);
+            dict.set("claimed_at", deposit.claimed_at as i64);
+            array.push(&dict);
+        }
+
+        array
+    }
+}
+
+impl BreezNode {
+    fn claim_all_deposits_with(&self, fee: ClaimFee) -> Array<Dictionary> {
+        let mut array = Array::new();
+        let deposits = match self.fetch_unclaimed_deposits() {
+            Ok(deposits) => deposits,
+            Err(e) => {
+                godot_error!("{}", e);
+                return array;
+            }
+        };
+
+        for deposit in deposits {
+            let mut dict = match self.confirmations_remaining(&deposit.txid) {
+                Ok(0) => {
+                    let result = self.claim_deposit_now(&deposit.txid, deposit.vout, fee);
+                    self.claim_result_dictionary(&deposit.txid, deposit.vout, fee, result)
+                }
+                remaining => unconfirmed_deposit_error(&deposit.txid, remaining),
+            };
+            dict.set("txid", deposit.txid.as_str());
+            dict.set("vout", deposit.vout as i64);
+            dict.set("amount_sats", deposit.amount_sats as i64);
+            array.push(&self.report_failure("claim_all_deposits", dict));
+        }
+        array
+    }
+
+    /// Entry of `list_unclaimed_deposits`
+    pub(crate) fn unclaimed_deposit_dictionary(&self, deposit: &DepositInfo) -> Dictionary {
+        let mut dict = Dictionary::new();
+        dict.set("txid", deposit.txid.as_str());
+        dict.set("vout", deposit.vout as i64);
+        dict.set("amount_sats", deposit.amount_sats as i64);
+
+        let status = self.fetch_tx_status(&deposit.txid);
+        if let Err(e) = &status {
+            godot_warn!("{}", e);
+        }
+        let status = status.ok();
+        let min_confirmations = self.deposit_policy.lock().unwrap().min_confirmations;
+        // Unknown confirmations (-1) count as not claimable yet
+        let remaining = match &status {
+            _ if min_confirmations == 0 => 0,
+            Some(status) => min_confirmations.saturating_sub(status.confirmations) as i64,
+            None => -1,
+        };
+        dict.set("claimable", remaining == 0);
+        dict.set("confirmations_remaining", remaining);
+        dict.set("confirmed", status.as_ref().is_some_and(|status| status.confirmed));
+        dict.set("confirmations", status.as_ref().map(|status| status.confirmations as i64).unwrap_or(-1));
+        let address = status.and_then(|status| status.output_addresses.get(deposit.vout as usize).cloned().flatten());
+        if let Some(address) = address {
+            dict.set("address", address);
+        }
+
+        let (code, message) = match &deposit.claim_error {
+            None => ("", String::new()),
+            Some(DepositClaimError::DepositClaimFeeExceeded { actual_fee, .. }) => {
+                dict.set("claim_fee_sats", *actual_fee as i64);
+                ("FEE_EXCEEDED", format!("Claiming needs a {} sats fee, above the fee limit", actual_fee))
+            }
+            Some(DepositClaimError::MissingUtxo { .. }) => {
+                ("MISSING_UTXO", "The deposit output was not found on-chain".to_string())
+            }
+            Some(DepositClaimError::Generic { message }) => ("CLAIM_FAILED", message.clone()),
+        };
+        dict.set("claim_error", message);
+        dict.set("claim_error_code", code);
+        dict
+    }
+
+    /// Confirmations a deposit transaction still needs before it may be claimed
+    pub(crate) fn confirmations_remaining(&self, txid: &str) -> Result<u64, String> {
+        self.runtime.block_on(self.confirmations_remaining_lookup(txid))
+    }
+
+    /// `confirmations_remaining` as a future that can be awaited off the main thread
+    pub(crate) fn confirmations_remaining_lookup(
+        &self,
+        txid: &str,
+    ) -> impl std::future::Future<Output = Result<u64, String>> + Send + 'static {
+        let min_confirmations = self.deposit_policy.lock().unwrap().min_confirmations;
+        let lookup = (min_confirmations > 0).then(|| self.tx_status_lookup(txid));
+        async move {
+            match lookup {
+                None => Ok(0),
+                Some(lookup) => Ok(min_confirmations.saturating_sub(lookup?.await?.confirmations)),
+            }
+        }
+    }
+
+    pub(crate) fn fetch_unclaimed_deposits(&self) -> Result<Vec<DepositInfo>, String> {
+        let sdk_handle = self.sdk_handle();
+        let runtime = Arc::clone(&self.runtime);
+
+        runtime.block_on(async move {
+            let sdk = sdk_handle?;
+            match sdk.list_unclaimed_deposits(ListUnclaimedDepositsRequest {}).await {
+                Ok(response) => Ok(response.deposits),
+                Err(e) => Err(format!("Failed to list deposits: {:?}", e)),
+            }
+        })
+    }
+
+    /// Forget which deposits were reported as confirmed, e.g. when switching wallets
+    pub(crate) fn reset_deposit_confirmations(&self) {
+        self.deposit_policy.lock().unwrap().confirmed.clear();
+    }
+
+    fn load_claimed_deposits(&self) -> Vec<ClaimedDeposit> {
+        self.plugin_path(CLAIMED_DEPOSITS_FILE)
+            .and_then(|path| load_json(&path))
+            .unwrap_or_default()
+    }
+
+    /// Remember a claim made through the plugin, since the payment alone does not carry the vout
+    pub(crate) fn record_claimed_deposit(&self, txid: &str, vout: u32, payment: &Payment) {
+        let Some(path) = self.plugin_path(CLAIMED_DEPOSITS_FILE) else {
+            return;
+        };
+        let mut deposits = self.load_claimed_deposits();
+        deposits.push(ClaimedDeposit {
+            txid: txid.to_string(),
+            vout,
+            amount_sats: payment.amount,
+            fee_sats: payment.fees,
+            payment_id: payment.id.clone(),
+            payment_timestamp: payment.timestamp,
+            claimed_at: now_secs(),
+        });
+        if let Err(e) = save_json(&path, &deposits) {
+            godot_warn!("Failed to record claimed deposit: {}", e);
+        }
+    }
+}
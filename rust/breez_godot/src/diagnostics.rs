@@ -0,0 +1,289 @@
+use godot::prelude::*;
+use breez_sdk_spark::{BreezSdk, ListPaymentsRequest, ListUnclaimedDepositsRequest, PaymentStatus};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, UNIX_EPOCH};
+
+use crate::events::NodeEvent;
+use crate::storage::{now_secs, plugin_file};
+use crate::{lock_sdk, BreezNode};
+
+/// Server whose HTTP `Date` header is used to measure clock skew
+const TIME_REFERENCE_URL: &str = "https://breez.technology";
+
+const NETWORK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Pending payments older than this are reported as stuck
+const STUCK_PENDING_SECS: u64 = 3_600;
+
+const FREE_SPACE_WARN_BYTES: u64 = 100 * 1024 * 1024;
+const FREE_SPACE_FAIL_BYTES: u64 = 10 * 1024 * 1024;
+
+#[derive(Clone, Copy, PartialEq, PartialOrd)]
+pub(crate) enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+impl CheckStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            CheckStatus::Pass => "pass",
+            CheckStatus::Warn => "warn",
+            CheckStatus::Fail => "fail",
+        }
+    }
+}
+
+pub(crate) struct Check {
+    name: &'static str,
+    status: CheckStatus,
+    message: String,
+}
+
+fn check(name: &'static str, status: CheckStatus, message: impl Into<String>) -> Check {
+    Check { name, status, message: message.into() }
+}
+
+/// Everything the checks need, cloned out of the node so they can run off the main thread
+struct DiagnosticsContext {
+    sdk: Option<BreezSdk>,
+    storage_dir: Option<PathBuf>,
+    network: Option<String>,
+    last_sync: Option<u64>,
+    client: Option<reqwest::Client>,
+}
+
+/// Replace long tokens (keys, hashes, ids) and the storage path so reports are safe to share
+fn redact(text: &str, storage_dir: Option<&PathBuf>) -> String {
+    let mut text = text.to_string();
+    if let Some(dir) = storage_dir {
+        text = text.replace(&dir.to_string_lossy().to_string(), "<storage_dir>");
+    }
+
+    let mut result = String::with_capacity(text.len());
+    let mut token = String::new();
+    for c in text.chars().chain(std::iter::once(' ')) {
+        if c.is_ascii_alphanumeric() {
+            token.push(c);
+            continue;
+        }
+        if token.len() >= 32 {
+            result.push_str("[redacted]");
+        } else {
+            result.push_str(&token);
+        }
+        token.clear();
+        result.push(c);
+    }
+    result.pop();
+    result
+}
+
+async fn run_checks(ctx: &DiagnosticsContext) -> Vec<Check> {
+    let mut checks = Vec::new();
+
+    let runtime_ok = tokio::time::timeout(Duration::from_secs(2), tokio::spawn(async {})).await;
+    checks.push(match runtime_ok {
+        Ok(Ok(())) => check("runtime", CheckStatus::Pass, "Async runtime is responsive"),
+        _ => check("runtime", CheckStatus::Fail, "Async runtime did not run a task within 2 s"),
+    });
+
+    match &ctx.sdk {
+        Some(_) => checks.push(check(
+            "sdk_connected",
+            CheckStatus::Pass,
+            format!("Connected to {}", ctx.network.as_deref().unwrap_or("unknown network")),
+        )),
+        None => checks.push(check("sdk_connected", CheckStatus::Fail, "SDK is not connected")),
+    }
+
+    if let Some(sdk) = &ctx.sdk {
+        checks.push(match tokio::time::timeout(NETWORK_TIMEOUT, sdk.list_fiat_rates()).await {
+            Ok(Ok(_)) => check("endpoint", CheckStatus::Pass, "Breez service is reachable"),
+            Ok(Err(e)) => check("endpoint", CheckStatus::Fail, format!("Breez service error: {:?}", e)),
+            Err(_) => check("endpoint", CheckStatus::Fail, "Breez service did not answer within 10 s"),
+        });
+    }
+
+    checks.push(storage_check(ctx.storage_dir.as_ref()));
+
+    checks.push(match ctx.last_sync {
+        Some(at) => {
+            let age = now_secs().saturating_sub(at);
+            let status = if age > 3_600 { CheckStatus::Warn } else { CheckStatus::Pass };
+            check("last_sync", status, format!("Last successful sync {} s ago", age))
+        }
+        None => check("last_sync", CheckStatus::Warn, "No successful sync this session"),
+    });
+
+    if let Some(sdk) = &ctx.sdk {
+        checks.push(match sdk.list_payments(ListPaymentsRequest { offset: None, limit: Some(100) }).await {
+            Ok(response) => {
+                let cutoff = now_secs().saturating_sub(STUCK_PENDING_SECS);
+                let stuck = response
+                    .payments
+                    .iter()
+                    .filter(|p| p.status == PaymentStatus::Pending && p.timestamp < cutoff)
+                    .count();
+                if stuck == 0 {
+                    check("pending_payments", CheckStatus::Pass, "No stuck pending payments")
+                } else {
+                    check("pending_payments", CheckStatus::Warn, format!("{} payments pending for over an hour", stuck))
+                }
+            }
+            Err(e) => check("pending_payments", CheckStatus::Fail, format!("Failed to list payments: {:?}", e)),
+        });
+
+        checks.push(match sdk.list_unclaimed_deposits(ListUnclaimedDepositsRequest {}).await {
+            Ok(response) if response.deposits.is_empty() => {
+                check("unclaimed_deposits", CheckStatus::Pass, "No unclaimed deposits")
+            }
+            Ok(response) => check(
+                "unclaimed_deposits",
+                CheckStatus::Warn,
+                format!("{} unclaimed deposits", response.deposits.len()),
+            ),
+            Err(e) => check("unclaimed_deposits", CheckStatus::Fail, format!("Failed to list deposits: {:?}", e)),
+        });
+    }
+
+    checks.push(clock_check(ctx.client.as_ref()).await);
+    checks
+}
+
+fn storage_check(storage_dir: Option<&PathBuf>) -> Check {
+    let Some(dir) = storage_dir else {
+        return check("storage", CheckStatus::Fail, "No storage_dir (not connected)");
+    };
+
+    let probe = plugin_file(dir, ".write_probe");
+    let writable = probe
+        .parent()
+        .map(|parent| fs::create_dir_all(parent).and_then(|_| fs::write(&probe, b"ok")))
+        .unwrap_or_else(|| Err(std::io::ErrorKind::NotFound.into()));
+    let _ = fs::remove_file(&probe);
+    if let Err(e) = writable {
+        return check("storage", CheckStatus::Fail, format!("storage_dir is not writable: {}", e));
+    }
+
+    match fs2::available_space(dir) {
+        Ok(free) => {
+            let message = format!("storage_dir is writable, {} MB free", free / (1024 * 1024));
+            let status = if free < FREE_SPACE_FAIL_BYTES {
+                CheckStatus::Fail
+            } else if free < FREE_SPACE_WARN_BYTES {
+                CheckStatus::Warn
+            } else {
+                CheckStatus::Pass
+            };
+            check("storage", status, message)
+        }
+        Err(e) => check("storage", CheckStatus::Warn, format!("storage_dir is writable, free space unknown: {}", e)),
+    }
+}
+
+async fn clock_check(client: Option<&reqwest::Client>) -> Check {
+    let Some(client) = client else {
+        return check("clock_skew", CheckStatus::Warn, "No HTTP client to compare the clock against");
+    };
+
+    let response = match client.head(TIME_REFERENCE_URL).send().await {
+        Ok(response) => response,
+        Err(e) => return check("clock_skew", CheckStatus::Warn, format!("Could not fetch reference time: {}", e)),
+    };
+    let server_time = response
+        .headers()
+        .get(reqwest::header::DATE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| httpdate::parse_http_date(value).ok())
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok());
+    let Some(server_time) = server_time else {
+        return check("clock_skew", CheckStatus::Warn, "Reference server sent no usable Date header");
+    };
+
+    let skew = now_secs() as i64 - server_time.as_secs() as i64;
+    let status = match skew.abs() {
+        0..=60 => CheckStatus::Pass,
+        61..=300 => CheckStatus::Warn,
+        _ => CheckStatus::Fail,
+    };
+    check("clock_skew", status, format!("Local clock is {} s off", skew))
+}
+
+/// Build the report Dictionary: `status`, `checks` and a redacted plain-text `summary`
+pub(crate) fn report_dictionary(checks: &[Check], storage_dir: Option<&PathBuf>, network: Option<&str>) -> Dictionary {
+    let overall = checks
+        .iter()
+        .map(|c| c.status)
+        .fold(CheckStatus::Pass, |worst, s| if s > worst { s } else { worst });
+
+    let mut array: Array<Dictionary> = Array::new();
+    let mut summary = format!(
+        "Breez Godot plugin {} diagnostics ({}, {}): {}\n",
+        env!("CARGO_PKG_VERSION"),
+        std::env::consts::OS,
+        network.unwrap_or("not connected"),
+        overall.as_str().to_uppercase()
+    );
+    for c in checks {
+        let message = redact(&c.message, storage_dir);
+        summary.push_str(&format!("[{}] {}: {}\n", c.status.as_str().to_uppercase(), c.name, message));
+
+        let mut dict = Dictionary::new();
+        dict.set("name", c.name);
+        dict.set("status", c.status.as_str());
+        dict.set("message", message);
+        array.push(&dict);
+    }
+
+    let mut dict = Dictionary::new();
+    dict.set("status", overall.as_str());
+    dict.set("checks", array);
+    dict.set("summary", summary);
+    dict
+}
+
+#[godot_api(secondary)]
+impl BreezNode {
+    /// Check the environment for common problems
+    ///
+    /// Each entry of `checks` has `name`, `status` ("pass", "warn" or "fail") and `message`.
+    /// `summary` is a plain-text version with long tokens and the storage path redacted,
+    /// suitable for bug reports. Blocks for up to a few network timeouts; see
+    /// `run_diagnostics_async`.
+    #[func]
+    pub fn run_diagnostics(&self) -> Dictionary {
+        let ctx = self.diagnostics_context();
+        let checks = self.runtime.block_on(async { run_checks(&ctx).await });
+        report_dictionary(&checks, ctx.storage_dir.as_ref(), ctx.network.as_deref())
+    }
+
+    /// Run the diagnostics in the background, emitting `diagnostics_completed` with the report
+    #[func]
+    pub fn run_diagnostics_async(&self) {
+        let ctx = self.diagnostics_context();
+        let events = self.events.sender();
+        self.runtime.spawn(async move {
+            let checks = run_checks(&ctx).await;
+            let _ = events.send(NodeEvent::DiagnosticsCompleted {
+                checks,
+                storage_dir: ctx.storage_dir,
+                network: ctx.network,
+            });
+        });
+    }
+}
+
+impl BreezNode {
+    fn diagnostics_context(&self) -> DiagnosticsContext {
+        DiagnosticsContext {
+            sdk: lock_sdk(&self.sdk).clone(),
+            storage_dir: self.wallet_dir.lock().unwrap().clone(),
+            network: self.wallet_network.lock().unwrap().clone(),
+            last_sync: *self.last_sync.lock().unwrap(),
+            client: self.http_client().ok(),
+        }
+    }
+}
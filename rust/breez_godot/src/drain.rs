@@ -0,0 +1,149 @@
+use godot::prelude::*;
+use breez_sdk_spark::GetInfoRequest;
+use std::sync::Arc;
+
+use crate::bolt11::parse_bolt11;
+use crate::fees::{onchain_fee_sats, onchain_speed, quoted_fee_sats};
+use crate::limits::AmountMethod;
+use crate::send::{prepare_send, SendOptions};
+use crate::validation::{validate_destination, DestinationKind};
+use crate::BreezNode;
+
+/// Quotes taken at most to find the amount that leaves room for its own fee
+const DRAIN_QUOTE_ROUNDS: usize = 3;
+
+fn drain_error(error: String, code: &str) -> Dictionary {
+    godot_error!("{}", error);
+    let mut dict = Dictionary::new();
+    dict.set("success", false);
+    dict.set("error", error);
+    if !code.is_empty() {
+        dict.set("error_code", code);
+    }
+    dict
+}
+
+fn insufficient_funds(balance: u64, method: AmountMethod) -> Dictionary {
+    let mut dict = drain_error(
+        format!(
+            "A balance of {} sats does not cover the {} minimum of {} sats plus fees",
+            balance,
+            method.as_str(),
+            method.min_sats()
+        ),
+        "INSUFFICIENT_FUNDS",
+    );
+    dict.set("balance_sats", balance as i64);
+    dict
+}
+
+#[godot_api(secondary)]
+impl BreezNode {
+    /// Send the whole spendable balance to an amountless BOLT11 invoice, a Spark address
+    /// or a Bitcoin address
+    ///
+    /// The amount is the balance minus the quoted fee. `fee` chooses the on-chain fee as in
+    /// `send_onchain_with_fee` (empty for medium speed) and is ignored for other
+    /// destinations. Returns the send result, with `amount` sent and `fee_sats` paid. Fails
+    /// with INSUFFICIENT_FUNDS when what is left after fees is below the minimum amount.
+    #[func]
+    pub fn drain_wallet(&self, destination: GString, fee: Dictionary) -> Dictionary {
+        let result = self.drain_with(destination.to_string().trim().to_string(), &fee);
+        self.report_failure("drain_wallet", result)
+    }
+}
+
+impl BreezNode {
+    fn drain_with(&self, destination: String, fee: &Dictionary) -> Dictionary {
+        if let Err(locked) = self.check_unlocked() {
+            return locked;
+        }
+
+        let lower = destination.to_lowercase();
+        let body = lower.strip_prefix("lightning:").unwrap_or(&lower);
+        let method = if body.starts_with("lnbc") || body.starts_with("lntb") {
+            match parse_bolt11(&destination) {
+                Ok(invoice) if invoice.amount_milli_satoshis().is_some() => {
+                    return drain_error(
+                        "Cannot drain into an invoice with its own amount; use an amountless invoice".to_string(),
+                        "AMOUNT_MISMATCH",
+                    );
+                }
+                Ok(_) => AmountMethod::Lightning,
+                Err(e) => return drain_error(e, "INVALID_DESTINATION"),
+            }
+        } else {
+            match validate_destination(&destination) {
+                Ok(DestinationKind::BitcoinAddress) => AmountMethod::Onchain,
+                Ok(DestinationKind::SparkAddress) => AmountMethod::Spark,
+                Ok(kind) => {
+                    return drain_error(format!("Cannot drain into a {}", kind.as_str()), "INVALID_DESTINATION");
+                }
+                Err(e) => return drain_error(e, "INVALID_DESTINATION"),
+            }
+        };
+        let onchain = matches!(method, AmountMethod::Onchain);
+
+        let mut fee_options = Dictionary::new();
+        if onchain && !fee.is_empty() {
+            fee_options.set("fee", fee);
+        }
+        let mut opts = match SendOptions::from_dictionary(&fee_options, &["fee"]) {
+            Ok(opts) => opts,
+            Err(error) => return error,
+        };
+        if let Err(error) = self.resolve_fee_rate(&mut opts) {
+            return error;
+        }
+
+        let runtime = Arc::clone(&self.runtime);
+        let sdk_handle = self.sdk_handle();
+        let balance = runtime.block_on(async move {
+            sdk_handle?
+                .get_info(GetInfoRequest {
+                    ensure_synced: Some(true),
+                })
+                .await
+                .map(|info| info.balance_sats)
+                .map_err(|e| format!("Failed to get balance: {:?}", e))
+        });
+        let balance = match balance {
+            Ok(balance) => balance,
+            Err(e) => return drain_error(e, ""),
+        };
+
+        // The fee can depend on the amount, so quote again until amount plus fee fits
+        let mut amount = balance;
+        let mut settled = false;
+        for _ in 0..DRAIN_QUOTE_ROUNDS {
+            if amount < method.min_sats() {
+                return insufficient_funds(balance, method);
+            }
+            let prepared = runtime.block_on(prepare_send(self.sdk_handle(), destination.clone(), Some(amount), None));
+            let prepare_response = match prepared {
+                Ok((prepare_response, _)) => prepare_response,
+                Err(e) => return drain_error(e, ""),
+            };
+            let quoted_fee = if onchain {
+                match onchain_speed(&prepare_response, opts.onchain_fee.as_ref()) {
+                    Ok(speed) => onchain_fee_sats(&prepare_response, &speed).unwrap_or_default(),
+                    Err(cheapest) => cheapest,
+                }
+            } else {
+                quoted_fee_sats(&prepare_response)
+            };
+            if amount + quoted_fee <= balance {
+                settled = true;
+                break;
+            }
+            amount = balance.saturating_sub(quoted_fee);
+        }
+        if !settled {
+            return drain_error("The fee quote kept changing; try again".to_string(), "");
+        }
+
+        let mut options = fee_options;
+        options.set("amount_sats", amount as i64);
+        self.pay_opts(GString::from(&destination), options)
+    }
+}
@@ -0,0 +1,301 @@
+use godot::prelude::*;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::events::NodeEvent;
+use crate::log_files::{write_file_log, PLUGIN_TARGET};
+use crate::logging::{redact_secrets, LogLevel};
+use crate::resolution::is_timeout_error;
+use crate::storage::now_secs;
+use crate::validation::truncate_utf8;
+use crate::BreezNode;
+
+/// Longest message handed to the error handler, in UTF-8 bytes
+const MAX_MESSAGE_BYTES: usize = 512;
+
+/// Tokens at least this long (invoices, keys, addresses, hashes) are redacted from messages
+const MIN_REDACTED_TOKEN_CHARS: usize = 26;
+
+static NEXT_CORRELATION_ID: AtomicU64 = AtomicU64::new(1);
+
+/// A failure as delivered to the error handler and `sdk_error`
+#[derive(Clone)]
+pub(crate) struct ErrorRecord {
+    method: String,
+    error_code: String,
+    category: i64,
+    message: String,
+    timestamp: u64,
+    correlation_id: String,
+}
+
+impl ErrorRecord {
+    fn to_dictionary(&self) -> Dictionary {
+        let mut dict = Dictionary::new();
+        dict.set("method", self.method.clone());
+        dict.set("error_code", self.error_code.clone());
+        dict.set("error_category", self.category);
+        dict.set("message", self.message.clone());
+        dict.set("timestamp", self.timestamp as i64);
+        dict.set("correlation_id", self.correlation_id.clone());
+        dict
+    }
+}
+
+fn next_correlation_id() -> String {
+    format!("err-{:x}-{}", now_secs(), NEXT_CORRELATION_ID.fetch_add(1, Ordering::Relaxed))
+}
+
+/// Strip what could identify a wallet or leak credentials from an error message
+///
+/// URL query strings are dropped (webhook and API URLs can carry tokens) and long
+/// alphanumeric runs such as invoices, addresses, keys and hashes are replaced.
+fn sanitize_message(message: &str) -> String {
+    let sanitized = message
+        .split(' ')
+        .map(|word| {
+            let word = match word.find("://").and_then(|_| word.find('?')) {
+                Some(query) => &word[..query],
+                None => word,
+            };
+            let longest_run = word
+                .split(|c: char| !c.is_ascii_alphanumeric())
+                .map(str::len)
+                .max()
+                .unwrap_or(0);
+            if longest_run >= MIN_REDACTED_TOKEN_CHARS {
+                "[redacted]".to_string()
+            } else {
+                word.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    truncate_utf8(&sanitized, MAX_MESSAGE_BYTES).0
+}
+
+/// `BreezNode.ERROR_*` category of a failure, from its `error_code` or else its message
+///
+/// Every code the plugin returns is listed; unknown ones are SDK errors.
+fn error_category(code: &str, message: &str) -> i64 {
+    match code {
+        "NOT_CONNECTED" => return BreezNode::ERROR_NOT_CONNECTED,
+        "INVALID_INPUT" | "INVALID_ARGUMENT" | "INVALID_OPTION" | "UNKNOWN_OPTION" | "UNKNOWN_METHOD"
+        | "INVALID_MNEMONIC" | "INVALID_NETWORK" | "WRONG_NETWORK" | "INVALID_DESTINATION" | "INVALID_INVOICE"
+        | "INVALID_BECH32" | "INVOICE_EXPIRED" | "INVALID_AMOUNT" | "AMOUNT_TOO_SMALL" | "AMOUNT_TOO_LARGE"
+        | "AMOUNT_REQUIRED" | "AMOUNT_MISMATCH" | "UNSUPPORTED_UNIT" | "UNKNOWN_CURRENCY" | "INVALID_DESCRIPTION"
+        | "DESCRIPTION_TOO_LONG" | "COMMENT_TOO_LONG" | "INVALID_USERNAME" | "BATCH_TOO_LARGE"
+        | "UNKNOWN_PREPARE_ID" | "DEPOSIT_NOT_FOUND" | "FILE_NOT_FOUND" | "WRONG_PASSWORD" => {
+            return BreezNode::ERROR_INVALID_INPUT
+        }
+        "INSUFFICIENT_FUNDS" | "INSUFFICIENT_BALANCE" => return BreezNode::ERROR_INSUFFICIENT_FUNDS,
+        "NETWORK_ERROR" | "LNURL_ERROR" | "NETWORK_UNREACHABLE" => return BreezNode::ERROR_NETWORK_ERROR,
+        "PAYMENT_FAILED" | "CLAIM_FAILED" | "MISSING_UTXO" => return BreezNode::ERROR_PAYMENT_FAILED,
+        "TIMEOUT" => return BreezNode::ERROR_TIMEOUT,
+        "SDK_ERROR" | "WRITE_FAILED" | "READ_FAILED" | "STORAGE_NOT_WRITABLE" | "CORRUPTED_FILE" => {
+            return BreezNode::ERROR_SDK_ERROR
+        }
+        "UNSUPPORTED" => return BreezNode::ERROR_UNSUPPORTED,
+        "REJECTED" | "WALLET_LOCKED" | "THROTTLED" | "FEE_TOO_HIGH" | "FEE_RATE_TOO_LOW" | "FEE_EXCEEDED"
+        | "BELOW_DUST" | "BATCH_REJECTED" | "INSUFFICIENT_CONFIRMATIONS" | "PREPARE_EXPIRED" | "AUTH_FAILED"
+        | "USERNAME_TAKEN" | "NETWORK_MISMATCH" | "CONNECT_IN_PROGRESS" | "ALREADY_CONNECTED" | "CONNECTED"
+        | "STORAGE_IN_USE" => return BreezNode::ERROR_REJECTED,
+        "" => {}
+        _ => return BreezNode::ERROR_SDK_ERROR,
+    }
+
+    let lower = message.to_lowercase();
+    if lower.contains("not initialized") || lower.contains("not connected") {
+        BreezNode::ERROR_NOT_CONNECTED
+    } else if is_timeout_error(message) {
+        BreezNode::ERROR_TIMEOUT
+    } else if lower.contains("insufficient") {
+        BreezNode::ERROR_INSUFFICIENT_FUNDS
+    } else if lower.starts_with("payment failed") {
+        BreezNode::ERROR_PAYMENT_FAILED
+    } else if ["network", "connection", "http", "dns", "unreachable"].iter().any(|word| lower.contains(word)) {
+        BreezNode::ERROR_NETWORK_ERROR
+    } else if lower.starts_with("invalid") {
+        BreezNode::ERROR_INVALID_INPUT
+    } else {
+        BreezNode::ERROR_SDK_ERROR
+    }
+}
+
+/// `error_code` string for a category, given to failures that have none
+fn category_code(category: i64) -> &'static str {
+    match category {
+        BreezNode::ERROR_NOT_CONNECTED => "NOT_CONNECTED",
+        BreezNode::ERROR_INVALID_INPUT => "INVALID_INPUT",
+        BreezNode::ERROR_INSUFFICIENT_FUNDS => "INSUFFICIENT_FUNDS",
+        BreezNode::ERROR_NETWORK_ERROR => "NETWORK_ERROR",
+        BreezNode::ERROR_PAYMENT_FAILED => "PAYMENT_FAILED",
+        BreezNode::ERROR_TIMEOUT => "TIMEOUT",
+        BreezNode::ERROR_UNSUPPORTED => "UNSUPPORTED",
+        BreezNode::ERROR_REJECTED => "REJECTED",
+        _ => "SDK_ERROR",
+    }
+}
+
+/// Readable form of an error message, unwrapping SDK debug output such as
+/// `Generic("no route")` to `no route`
+fn error_message(message: &str) -> String {
+    let mut cleaned = String::new();
+    let mut rest = message;
+    while let Some(open) = rest.find("(\"") {
+        let name_start = rest[..open]
+            .rfind(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+            .map_or(0, |i| i + 1);
+        let is_variant = rest[name_start..open].starts_with(|c: char| c.is_ascii_uppercase());
+        let Some(close) = rest[open..].find("\")").map(|i| open + i) else {
+            break;
+        };
+        if is_variant {
+            cleaned.push_str(&rest[..name_start]);
+            cleaned.push_str(&rest[open + 2..close].replace("\\\"", "\""));
+        } else {
+            cleaned.push_str(&rest[..close + 2]);
+        }
+        rest = &rest[close + 2..];
+    }
+    cleaned.push_str(rest);
+    cleaned
+}
+
+#[godot_api(secondary)]
+impl BreezNode {
+    /// Route plugin errors to `handler`, e.g. for analytics
+    ///
+    /// The handler is called from `_process` on the main thread, after the failing call has
+    /// returned, with one Dictionary per error: `method`, `error_code`, `error_category`,
+    /// `message` (sanitized, without invoices, addresses, keys or URL queries), `timestamp`
+    /// and `correlation_id`, which is also set on the failure Dictionary the method returned.
+    /// Errors of background work (claim retries, auto-sweep, payment streams) are reported
+    /// with a correlation id of their own.
+    ///
+    /// Pass an empty Callable to stop deliveries; records not yet delivered are dropped.
+    #[func]
+    pub fn set_error_handler(&mut self, handler: Callable) {
+        *self.error_handler.lock().unwrap() = handler.is_valid().then_some(handler);
+    }
+
+    /// Get the most recent failure of any call or background task
+    ///
+    /// Returns the Dictionary passed to the error handler (`method`, `error_code`,
+    /// `error_category`, `message`, `timestamp` and `correlation_id`), or an empty one if
+    /// nothing has failed yet. It is set as soon as the failing call returns.
+    #[func]
+    pub fn get_last_error(&self) -> Dictionary {
+        self.last_error
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(ErrorRecord::to_dictionary)
+            .unwrap_or_default()
+    }
+}
+
+impl BreezNode {
+    /// Report a failure Dictionary returned by `method` to the error handler
+    ///
+    /// Successful results pass through untouched. Failures get a `correlation_id`, an
+    /// `error_category` (`BreezNode.ERROR_*`), an `error_message` without SDK debug
+    /// formatting and, when they had none, an `error_code` naming the category. Ones that
+    /// already carry a correlation id were reported by an inner method and are not
+    /// reported twice.
+    pub(crate) fn report_failure(&self, method: &str, mut result: Dictionary) -> Dictionary {
+        let failed = result.get("success").is_some_and(|success| !success.booleanize());
+        if !failed || result.contains_key("correlation_id") {
+            return result;
+        }
+
+        let message = result
+            .get("error")
+            .map(|error| error.to_string())
+            .unwrap_or_default();
+        let mut error_code = result
+            .get("error_code")
+            .map(|code| code.to_string())
+            .unwrap_or_default();
+        let category = error_category(&error_code, &message);
+        if error_code.is_empty() {
+            error_code = category_code(category).to_string();
+            result.set("error_code", error_code.as_str());
+        }
+        result.set("error_category", category);
+        result.set("error_message", error_message(&message));
+
+        let correlation_id = next_correlation_id();
+        result.set("correlation_id", correlation_id.clone());
+        self.record_error(ErrorRecord {
+            method: method.to_string(),
+            error_code,
+            category,
+            message: sanitize_message(&message),
+            timestamp: now_secs(),
+            correlation_id,
+        });
+        result
+    }
+
+    /// Report an error of background work, which has no result to carry the correlation id
+    pub(crate) fn report_background_error(&self, method: &str, message: &str) {
+        let category = error_category("", message);
+        self.record_error(ErrorRecord {
+            method: method.to_string(),
+            error_code: category_code(category).to_string(),
+            category,
+            message: sanitize_message(message),
+            timestamp: now_secs(),
+            correlation_id: next_correlation_id(),
+        });
+    }
+
+    /// Keep a failure for `get_last_error`, queue it for `sdk_error` and the handler, and
+    /// write it to the log files
+    fn record_error(&self, record: ErrorRecord) {
+        let line = format!("{} failed ({}): {}", record.method, record.error_code, record.message);
+        write_file_log(LogLevel::Error, PLUGIN_TARGET, &redact_secrets(&line));
+        *self.last_error.lock().unwrap() = Some(record.clone());
+        self.events.push(NodeEvent::ErrorReported(record));
+    }
+
+    /// Emit `sdk_error` for a record and hand it to the error handler, if one is still set
+    pub(crate) fn deliver_error_record(&mut self, record: ErrorRecord) {
+        let record = record.to_dictionary();
+        self.signals().sdk_error().emit(&record);
+        // Checked per record, so unsetting the handler inside a delivery stops the rest
+        let Some(handler) = self.error_handler.lock().unwrap().clone() else {
+            return;
+        };
+        // Releases this node's bind so the handler can call back into the plugin
+        let _guard = self.base_mut();
+        handler.call(&[record.to_variant()]);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_map_to_their_category() {
+        assert_eq!(error_category("WRITE_FAILED", ""), BreezNode::ERROR_SDK_ERROR);
+        assert_eq!(error_category("CORRUPTED_FILE", ""), BreezNode::ERROR_SDK_ERROR);
+        assert_eq!(error_category("CLAIM_FAILED", ""), BreezNode::ERROR_PAYMENT_FAILED);
+        assert_eq!(error_category("FEE_EXCEEDED", ""), BreezNode::ERROR_REJECTED);
+        assert_eq!(error_category("AUTH_FAILED", ""), BreezNode::ERROR_REJECTED);
+        assert_eq!(error_category("AMOUNT_TOO_SMALL", ""), BreezNode::ERROR_INVALID_INPUT);
+    }
+
+    #[test]
+    fn unknown_codes_are_sdk_errors() {
+        assert_eq!(error_category("SOMETHING_NEW", "invalid"), BreezNode::ERROR_SDK_ERROR);
+    }
+
+    #[test]
+    fn codeless_failures_fall_back_to_the_message() {
+        assert_eq!(error_category("", "Payment failed: no route"), BreezNode::ERROR_PAYMENT_FAILED);
+        assert_eq!(error_category("", "Request timed out"), BreezNode::ERROR_TIMEOUT);
+    }
+}
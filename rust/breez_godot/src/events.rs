@@ -0,0 +1,223 @@
+use godot::prelude::*;
+use breez_sdk_spark::{BreezSdk, Payment, SdkEvent};
+use std::path::PathBuf;
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+use crate::async_payments::AsyncPaymentStep;
+use crate::batch::BatchStep;
+use crate::claim_queue::{claim_retry_dictionary, ClaimRetryResult};
+use crate::diagnostics::{report_dictionary, Check};
+use crate::error_hook::ErrorRecord;
+use crate::fiat::{quotes_dictionary, RateQuote};
+use crate::logging::LogLevel;
+use crate::reconnect::ConnectCredentials;
+use crate::requests::RequestStep;
+use crate::BreezNode;
+
+/// Events produced by SDK calls or background work, emitted as signals on the main thread
+pub(crate) enum NodeEvent {
+    Swept { amount_sats: i64, payment_id: String },
+    LowBalance { current_sats: i64 },
+    BalanceRecovered { current_sats: i64 },
+    HighFeeEnvironment { rate_sat_vb: i64 },
+    PriceTick { quotes: Vec<(String, RateQuote)> },
+    ThrottleTripped { reason: String, retry_after_secs: i64 },
+    PaymentResolved { payment_id: String, status: String },
+    ZapReceived { payment_id: String, amount_sats: i64, zapper_pubkey: String, comment: String },
+    PurchaseCompleted { product_id: String, payment_id: String },
+    WalletLockChanged { locked: bool },
+    DepositConfirmed { txid: String, vout: i64 },
+    DepositClaimRetried { txid: String, vout: i64, result: ClaimRetryResult },
+    ErrorReported(ErrorRecord),
+    DiagnosticsCompleted { checks: Vec<Check>, storage_dir: Option<PathBuf>, network: Option<String> },
+    AsyncPayment { request_id: i64, step: AsyncPaymentStep },
+    BatchPayout { request_id: i64, step: BatchStep },
+    WalletSynced { result: Result<(), String> },
+    Request { request_id: i64, step: RequestStep },
+    ConnectFinished { attempt: u64, result: Result<BreezSdk, String>, credentials: ConnectCredentials },
+    ConnectionChecked { result: Result<(), String> },
+    LogMessage { level: LogLevel, target: String, message: String, to_console: bool },
+    Sdk(SdkEvent),
+    PaymentWaitsChecked { payments: Vec<Payment> },
+}
+
+/// Whether a finished `connect_sdk_async` attempt is still the one the node waits for
+#[derive(Debug, PartialEq)]
+pub(crate) enum ConnectAttempt {
+    Current,
+    /// Cancelled by `disconnect_breez` or replaced by a later attempt
+    Superseded,
+}
+
+/// Match a finished attempt against the pending one, clearing it when they match
+pub(crate) fn take_connect_attempt(pending: &mut Option<u64>, attempt: u64) -> ConnectAttempt {
+    if *pending != Some(attempt) {
+        return ConnectAttempt::Superseded;
+    }
+    *pending = None;
+    ConnectAttempt::Current
+}
+
+/// Queue used to hand events over to the main thread
+///
+/// Senders can be cloned into async tasks; the node drains the receiver every frame.
+pub(crate) struct EventQueue {
+    tx: Sender<NodeEvent>,
+    rx: Receiver<NodeEvent>,
+}
+
+impl EventQueue {
+    pub(crate) fn new() -> Self {
+        let (tx, rx) = channel();
+        Self { tx, rx }
+    }
+
+    pub(crate) fn sender(&self) -> Sender<NodeEvent> {
+        self.tx.clone()
+    }
+
+    pub(crate) fn push(&self, event: NodeEvent) {
+        let _ = self.tx.send(event);
+    }
+
+    fn drain(&self) -> Vec<NodeEvent> {
+        self.rx.try_iter().collect()
+    }
+}
+
+impl BreezNode {
+    /// Emit every queued event as its Godot signal
+    pub(crate) fn dispatch_events(&mut self) {
+        for event in self.events.drain() {
+            match event {
+                NodeEvent::Swept { amount_sats, payment_id } => {
+                    self.signals().swept().emit(amount_sats, &GString::from(&payment_id));
+                }
+                NodeEvent::LowBalance { current_sats } => {
+                    self.signals().low_balance().emit(current_sats);
+                }
+                NodeEvent::BalanceRecovered { current_sats } => {
+                    self.signals().balance_recovered().emit(current_sats);
+                }
+                NodeEvent::HighFeeEnvironment { rate_sat_vb } => {
+                    self.signals().high_fee_environment().emit(rate_sat_vb);
+                }
+                NodeEvent::PriceTick { quotes } => {
+                    self.signals().price_ticker_updated().emit(&quotes_dictionary(&quotes));
+                    for (alert_id, currency, rate) in self.evaluate_price_alerts(&quotes) {
+                        self.signals().price_alert_triggered().emit(alert_id, &GString::from(&currency), rate);
+                    }
+                }
+                NodeEvent::ThrottleTripped { reason, retry_after_secs } => {
+                    self.signals().throttle_tripped().emit(&GString::from(&reason), retry_after_secs);
+                }
+                NodeEvent::DepositConfirmed { txid, vout } => {
+                    self.signals().deposit_confirmed().emit(&GString::from(&txid), vout);
+                }
+                NodeEvent::DepositClaimRetried { txid, vout, result } => {
+                    let result = claim_retry_dictionary(&result);
+                    self.signals().deposit_claim_retried().emit(&GString::from(&txid), vout, &result);
+                }
+                NodeEvent::DiagnosticsCompleted { checks, storage_dir, network } => {
+                    let report = report_dictionary(&checks, storage_dir.as_ref(), network.as_deref());
+                    self.signals().diagnostics_completed().emit(&report);
+                }
+                NodeEvent::WalletSynced { result } => {
+                    if let Err(e) = &result {
+                        godot_error!("{}", e);
+                        self.report_background_error("auto_sync", e);
+                    } else {
+                        self.on_synced();
+                    }
+                    let error = result.err().unwrap_or_default();
+                    self.signals().wallet_synced().emit(error.is_empty(), &GString::from(&error));
+                }
+                NodeEvent::Request { request_id, step } => {
+                    self.advance_request(request_id, step);
+                }
+                NodeEvent::AsyncPayment { request_id, step } => {
+                    self.advance_async_payment(request_id, step);
+                }
+                NodeEvent::BatchPayout { request_id, step } => {
+                    self.advance_batch_payout(request_id, step);
+                }
+                NodeEvent::ConnectFinished { attempt, result, credentials } => {
+                    let outcome = take_connect_attempt(&mut self.pending_connect.lock().unwrap(), attempt);
+                    if outcome == ConnectAttempt::Superseded {
+                        // disconnect_breez was called while connecting, possibly followed by
+                        // another connect
+                        if let Ok(sdk) = result {
+                            self.close_superseded_sdk(sdk);
+                        }
+                        continue;
+                    }
+                    match self.finish_connect(result, credentials) {
+                        Ok(()) => self.signals().connected().emit(),
+                        Err(e) => self.signals().connect_failed().emit(&GString::from(&e)),
+                    }
+                }
+                NodeEvent::ConnectionChecked { result } => {
+                    self.on_connection_checked(result);
+                }
+                NodeEvent::LogMessage { level, target, message, to_console } => {
+                    self.on_log_message(level, target, message, to_console);
+                }
+                NodeEvent::Sdk(event) => {
+                    self.emit_sdk_event(event);
+                }
+                NodeEvent::PaymentWaitsChecked { payments } => {
+                    self.on_payment_waits_checked(payments);
+                }
+                NodeEvent::ErrorReported(record) => {
+                    self.deliver_error_record(record);
+                }
+                NodeEvent::WalletLockChanged { locked } => {
+                    self.signals().wallet_lock_changed().emit(locked);
+                }
+                NodeEvent::PaymentResolved { payment_id, status } => {
+                    self.signals().payment_resolved().emit(&GString::from(&payment_id), &GString::from(&status));
+                }
+                NodeEvent::ZapReceived { payment_id, amount_sats, zapper_pubkey, comment } => {
+                    self.signals().zap_received().emit(
+                        &GString::from(&payment_id),
+                        amount_sats,
+                        &GString::from(&zapper_pubkey),
+                        &GString::from(&comment),
+                    );
+                }
+                NodeEvent::PurchaseCompleted { product_id, payment_id } => {
+                    match self.get_product(GString::from(&product_id)) {
+                        Some(product) => {
+                            self.signals().purchase_completed().emit(&product, &GString::from(&payment_id));
+                        }
+                        None => godot_warn!("Purchased product {} is no longer registered", product_id),
+                    }
+                }
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overlapping_connect_attempts_supersede_the_earlier_one() {
+        // Attempt 1 was cancelled by disconnect_breez and attempt 2 started before 1 finished
+        let mut pending = Some(2);
+
+        assert_eq!(take_connect_attempt(&mut pending, 1), ConnectAttempt::Superseded);
+        assert_eq!(pending, Some(2), "a stale attempt must not clear the pending one");
+        assert_eq!(take_connect_attempt(&mut pending, 2), ConnectAttempt::Current);
+        assert_eq!(pending, None);
+    }
+
+    #[test]
+    fn attempt_finishing_after_disconnect_is_superseded() {
+        let mut pending = None;
+        assert_eq!(take_connect_attempt(&mut pending, 3), ConnectAttempt::Superseded);
+        assert_eq!(pending, None);
+    }
+}
@@ -0,0 +1,203 @@
+use godot::prelude::*;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::BreezNode;
+
+/// Explorer links and esplora endpoints per network
+pub(crate) struct ExplorerSettings {
+    /// Transaction URL templates containing `{txid}`
+    url_templates: HashMap<String, String>,
+    /// Esplora-style API base URLs used for confirmation status
+    esplora_urls: HashMap<String, String>,
+}
+
+impl Default for ExplorerSettings {
+    fn default() -> Self {
+        // Regtest has no public explorer; it stays empty until a local one is configured
+        Self {
+            url_templates: HashMap::from([("mainnet".to_string(), "https://mempool.space/tx/{txid}".to_string())]),
+            esplora_urls: HashMap::from([("mainnet".to_string(), "https://mempool.space/api".to_string())]),
+        }
+    }
+}
+
+/// Confirmation status of an on-chain transaction
+pub(crate) struct TxStatus {
+    pub(crate) confirmed: bool,
+    pub(crate) confirmations: u64,
+    pub(crate) block_height: u64,
+    pub(crate) tip_height: u64,
+    /// Address of each output, None for scripts without one
+    pub(crate) output_addresses: Vec<Option<String>>,
+}
+
+#[derive(Deserialize)]
+struct EsploraTx {
+    status: EsploraTxStatus,
+    vout: Vec<EsploraOutput>,
+}
+
+#[derive(Deserialize)]
+struct EsploraTxStatus {
+    confirmed: bool,
+    block_height: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct EsploraOutput {
+    scriptpubkey_address: Option<String>,
+}
+
+fn is_txid(txid: &str) -> bool {
+    txid.len() == 64 && txid.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+#[godot_api(secondary)]
+impl BreezNode {
+    /// Override the explorer link for a network, e.g. "http://localhost:8080/tx/{txid}"
+    ///
+    /// An empty template disables links for that network.
+    #[func]
+    pub fn set_explorer_url_template(&mut self, network: GString, template: GString) {
+        let mut explorer = self.explorer.lock().unwrap();
+        let network = network.to_string().to_lowercase();
+        if template.is_empty() {
+            explorer.url_templates.remove(&network);
+        } else {
+            explorer.url_templates.insert(network, template.to_string());
+        }
+    }
+
+    /// Set the esplora-style API (e.g. "https://mempool.space/api") used for a network's
+    /// transaction status; an empty URL disables status lookups
+    #[func]
+    pub fn set_esplora_url(&mut self, network: GString, url: GString) {
+        let mut explorer = self.explorer.lock().unwrap();
+        let network = network.to_string().to_lowercase();
+        let url = url.to_string().trim_end_matches('/').to_string();
+        if url.is_empty() {
+            explorer.esplora_urls.remove(&network);
+        } else {
+            explorer.esplora_urls.insert(network, url);
+        }
+    }
+
+    /// Explorer link for a transaction on the connected network (mainnet if not connected),
+    /// or an empty string if no explorer is configured
+    #[func]
+    pub fn get_explorer_url(&self, txid: GString) -> GString {
+        let txid = txid.to_string().trim().to_lowercase();
+        if !is_txid(&txid) {
+            godot_error!("Invalid txid: {}", txid);
+            return GString::from("");
+        }
+        let network = self.explorer_network();
+        match self.explorer.lock().unwrap().url_templates.get(&network) {
+            Some(template) => GString::from(&template.replace("{txid}", &txid)),
+            None => GString::from(""),
+        }
+    }
+
+    /// Confirmation status of an on-chain transaction from the network's esplora endpoint
+    ///
+    /// Returns a Dictionary with `success`, `confirmed`, `confirmations`, `block_height`
+    /// and `tip_height` (0 when unconfirmed).
+    #[func]
+    pub fn get_onchain_tx_status(&self, txid: GString) -> Dictionary {
+        let result = self.get_onchain_tx_status_with(txid);
+        self.report_failure("get_onchain_tx_status", result)
+    }
+}
+
+impl BreezNode {
+    fn get_onchain_tx_status_with(&self, txid: GString) -> Dictionary {
+        let mut dict = Dictionary::new();
+        match self.fetch_tx_status(&txid.to_string()) {
+            Ok(status) => {
+                dict.set("success", true);
+                dict.set("confirmed", status.confirmed);
+                dict.set("confirmations", status.confirmations as i64);
+                dict.set("block_height", status.block_height as i64);
+                dict.set("tip_height", status.tip_height as i64);
+            }
+            Err(e) => {
+                godot_error!("{}", e);
+                dict.set("success", false);
+                dict.set("error", e);
+            }
+        }
+        dict
+    }
+
+    fn explorer_network(&self) -> String {
+        self.wallet_network.lock().unwrap().clone().unwrap_or_else(|| "mainnet".to_string())
+    }
+
+    /// Confirmation status of a transaction from the connected network's esplora endpoint
+    pub(crate) fn fetch_tx_status(&self, txid: &str) -> Result<TxStatus, String> {
+        let lookup = self.tx_status_lookup(txid)?;
+        self.runtime.block_on(lookup)
+    }
+
+    /// Prepare a transaction status lookup that can be awaited off the main thread
+    pub(crate) fn tx_status_lookup(
+        &self,
+        txid: &str,
+    ) -> Result<impl std::future::Future<Output = Result<TxStatus, String>> + Send + 'static, String> {
+        let txid = txid.trim().to_lowercase();
+        if !is_txid(&txid) {
+            return Err(format!("Invalid txid: {}", txid));
+        }
+        let network = self.explorer_network();
+        let base = self
+            .explorer
+            .lock()
+            .unwrap()
+            .esplora_urls
+            .get(&network)
+            .cloned()
+            .ok_or_else(|| format!("No esplora endpoint configured for {}", network))?;
+        let client = self.http_client()?;
+        Ok(fetch_esplora_status(client, base, txid))
+    }
+}
+
+async fn fetch_esplora_status(client: reqwest::Client, base: String, txid: String) -> Result<TxStatus, String> {
+    let tx: EsploraTx = client
+        .get(format!("{}/tx/{}", base, txid))
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Failed to fetch transaction status: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("Invalid transaction status: {}", e))?;
+    let status = tx.status;
+    let tip: u64 = if status.confirmed {
+        client
+            .get(format!("{}/blocks/tip/height", base))
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| format!("Failed to fetch tip height: {}", e))?
+            .text()
+            .await
+            .map_err(|e| format!("Invalid tip height: {}", e))?
+            .trim()
+            .parse()
+            .map_err(|e| format!("Invalid tip height: {}", e))?
+    } else {
+        0
+    };
+
+    let block_height = status.block_height.unwrap_or(0);
+    let confirmations = if status.confirmed && tip >= block_height { tip - block_height + 1 } else { 0 };
+    Ok(TxStatus {
+        confirmed: status.confirmed,
+        confirmations,
+        block_height,
+        tip_height: tip,
+        output_addresses: tx.vout.into_iter().map(|output| output.scriptpubkey_address).collect(),
+    })
+}
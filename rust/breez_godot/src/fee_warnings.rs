@@ -0,0 +1,198 @@
+use godot::prelude::*;
+use breez_sdk_spark::{ListUnclaimedDepositsRequest, PrepareSendPaymentRequest, RecommendedFees};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use crate::deposits::deposit_error;
+use crate::events::NodeEvent;
+use crate::fees::{quoted_fee_sats, FeeWarningPolicy, CLAIM_TX_VBYTES};
+use crate::limits::{check_amount, AmountMethod};
+use crate::validation::validate_outpoint;
+use crate::BreezNode;
+
+#[godot_api(secondary)]
+impl BreezNode {
+    /// Configure when on-chain fees are flagged as too high
+    ///
+    /// # Arguments
+    /// * `max_fee_sats` - Absolute fee ceiling (0 to disable)
+    /// * `max_fee_percent` - Fee ceiling as a percentage of the amount (0 to disable)
+    /// * `high_rate_sat_vb` - Recommended rate above which `high_fee_environment` is emitted (0 to disable)
+    #[func]
+    pub fn set_fee_warning_policy(&mut self, max_fee_sats: i64, max_fee_percent: f64, high_rate_sat_vb: i64) {
+        *self.fee_policy.lock().unwrap() = FeeWarningPolicy {
+            max_fee_sats: max_fee_sats.max(0) as u64,
+            max_fee_percent: max_fee_percent.max(0.0),
+            high_rate_sat_vb: high_rate_sat_vb.max(0) as u64,
+        };
+        self.high_fee_active.store(false, Ordering::SeqCst);
+    }
+
+    /// Get the recommended on-chain fee rates in sat/vB
+    ///
+    /// Emits `high_fee_environment` when the fastest rate crosses the configured level.
+    #[func]
+    pub fn get_fee_recommendations(&self) -> Dictionary {
+        let result = self.get_fee_recommendations_with();
+        self.report_failure("get_fee_recommendations", result)
+    }
+
+    /// Estimate the fee for claiming an unclaimed deposit
+    ///
+    /// The result carries `fee_sats`, `rate_sat_vb`, `amount_sats` and `fee_warning`
+    /// ("" when acceptable, "ABOVE_CEILING" or "ABOVE_PERCENT" otherwise).
+    #[func]
+    pub fn estimate_claim_fee(&self, txid: GString, vout: i64) -> Dictionary {
+        let result = self.estimate_claim_fee_with(txid, vout);
+        self.report_failure("estimate_claim_fee", result)
+    }
+
+    /// Estimate the fee for sending on-chain without sending
+    ///
+    /// The result carries `fee_sats`, `amount_sats` and `fee_warning`.
+    #[func]
+    pub fn estimate_onchain_send(&self, address: GString, amount_sats: i64) -> Dictionary {
+        let result = self.estimate_onchain_send_with(address, amount_sats);
+        self.report_failure("estimate_onchain_send", result)
+    }
+}
+
+impl BreezNode {
+    fn get_fee_recommendations_with(&self) -> Dictionary {
+        let mut dict = Dictionary::new();
+
+        match self.fetch_recommended_fees() {
+            Ok(fees) => {
+                dict.set("success", true);
+                dict.set("fastest", fees.fastest_fee as i64);
+                dict.set("half_hour", fees.half_hour_fee as i64);
+                dict.set("hour", fees.hour_fee as i64);
+                dict.set("economy", fees.economy_fee as i64);
+                dict.set("minimum", fees.minimum_fee as i64);
+                let policy = self.fee_policy.lock().unwrap().clone();
+                dict.set("high_fee_environment", policy.is_high_rate(fees.fastest_fee));
+                self.retry_queued_claims(Some(fees.half_hour_fee));
+            }
+            Err(e) => {
+                godot_error!("{}", e);
+                dict.set("success", false);
+                dict.set("error", e);
+            }
+        }
+
+        dict
+    }
+
+    fn estimate_claim_fee_with(&self, txid: GString, vout: i64) -> Dictionary {
+        let txid_str = txid.to_string().trim().to_string();
+        if let Err(e) = validate_outpoint(&txid_str, vout) {
+            return deposit_error(e, "INVALID_INPUT");
+        }
+        let sdk_handle = self.sdk_handle();
+        let runtime = Arc::clone(&self.runtime);
+
+        let amount = runtime.block_on(async move {
+            let sdk = sdk_handle?;
+            match sdk.list_unclaimed_deposits(ListUnclaimedDepositsRequest {}).await {
+                Ok(response) => response
+                    .deposits
+                    .into_iter()
+                    .find(|d| d.txid == txid_str && d.vout as i64 == vout)
+                    .map(|d| d.amount_sats)
+                    .ok_or_else(|| "Deposit not found".to_string()),
+                Err(e) => Err(format!("Failed to list deposits: {:?}", e)),
+            }
+        });
+
+        let mut dict = Dictionary::new();
+
+        match amount.and_then(|amount| self.fetch_recommended_fees().map(|fees| (amount, fees))) {
+            Ok((amount, fees)) => {
+                let fee = fees.half_hour_fee * CLAIM_TX_VBYTES;
+                let policy = self.fee_policy.lock().unwrap().clone();
+                dict.set("success", true);
+                dict.set("amount_sats", amount as i64);
+                dict.set("fee_sats", fee as i64);
+                dict.set("rate_sat_vb", fees.half_hour_fee as i64);
+                dict.set("fee_warning", policy.warning(fee, amount));
+            }
+            Err(e) => {
+                godot_error!("{}", e);
+                dict.set("success", false);
+                dict.set("error", e);
+            }
+        }
+
+        dict
+    }
+
+    fn estimate_onchain_send_with(&self, address: GString, amount_sats: i64) -> Dictionary {
+        if let Err(error) = self.require_capability("onchain_send") {
+            return error;
+        }
+        if let Err(error) = check_amount(AmountMethod::Onchain, amount_sats) {
+            return error;
+        }
+
+        let sdk_handle = self.sdk_handle();
+        let runtime = Arc::clone(&self.runtime);
+        let address_str = address.to_string();
+
+        let result = runtime.block_on(async move {
+            let sdk = sdk_handle?;
+            match sdk.prepare_send_payment(PrepareSendPaymentRequest {
+                payment_request: address_str,
+                amount_sats: Some(amount_sats.max(0) as u64),
+            }).await {
+                Ok(response) => Ok(response),
+                Err(e) => Err(format!("Failed to estimate fee: {:?}", e)),
+            }
+        });
+
+        let mut dict = Dictionary::new();
+
+        match result {
+            Ok(prepare) => {
+                let fee = quoted_fee_sats(&prepare);
+                let policy = self.fee_policy.lock().unwrap().clone();
+                dict.set("success", true);
+                dict.set("amount_sats", prepare.amount_sats as i64);
+                dict.set("fee_sats", fee as i64);
+                dict.set("fee_warning", policy.warning(fee, prepare.amount_sats));
+            }
+            Err(e) => {
+                godot_error!("{}", e);
+                dict.set("success", false);
+                dict.set("error", e);
+            }
+        }
+
+        dict
+    }
+
+    /// Fetch recommended fees and queue `high_fee_environment` when the level is first exceeded
+    pub(crate) fn fetch_recommended_fees(&self) -> Result<RecommendedFees, String> {
+        let sdk_handle = self.sdk_handle();
+        let runtime = Arc::clone(&self.runtime);
+
+        let fees = runtime.block_on(async move {
+            sdk_handle?
+                .recommended_fees()
+                .await
+                .map_err(|e| format!("Failed to get fee recommendations: {:?}", e))
+        })?;
+
+        let policy = self.fee_policy.lock().unwrap().clone();
+        if policy.is_high_rate(fees.fastest_fee) {
+            if !self.high_fee_active.swap(true, Ordering::SeqCst) {
+                self.events.push(NodeEvent::HighFeeEnvironment {
+                    rate_sat_vb: fees.fastest_fee as i64,
+                });
+            }
+        } else {
+            self.high_fee_active.store(false, Ordering::SeqCst);
+        }
+
+        Ok(fees)
+    }
+}
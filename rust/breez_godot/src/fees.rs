@@ -0,0 +1,138 @@
+use breez_sdk_spark::{
+    OnchainConfirmationSpeed, PrepareSendPaymentResponse, RecommendedFees, SendPaymentMethod, SendPaymentOptions,
+};
+
+/// Fee chosen for an on-chain send
+#[derive(Clone)]
+pub(crate) enum OnchainFee {
+    /// Largest total fee in sats; the fastest speed quoted within it is used
+    Fixed(u64),
+    /// Fee rate in sat/vB, matched against the recommended rates before preparing
+    Rate(u64),
+    /// Confirmation speed picked for a `Rate`
+    Speed(OnchainConfirmationSpeed),
+}
+
+/// Fee quoted by a prepare response, in satoshis
+///
+/// On-chain sends are quoted at medium confirmation speed, matching `send_options_for`.
+pub(crate) fn quoted_fee_sats(prepare: &PrepareSendPaymentResponse) -> u64 {
+    match &prepare.payment_method {
+        SendPaymentMethod::BitcoinAddress { .. } => {
+            onchain_fee_sats(prepare, &OnchainConfirmationSpeed::Medium).unwrap_or_default()
+        }
+        SendPaymentMethod::Bolt11Invoice { lightning_fee_sats, .. } => *lightning_fee_sats,
+        SendPaymentMethod::SparkAddress { fee_sats, .. } => *fee_sats,
+    }
+}
+
+/// Fee quoted for an on-chain send at `speed`, None for other payment methods
+pub(crate) fn onchain_fee_sats(prepare: &PrepareSendPaymentResponse, speed: &OnchainConfirmationSpeed) -> Option<u64> {
+    let SendPaymentMethod::BitcoinAddress { fee_quote, .. } = &prepare.payment_method else {
+        return None;
+    };
+    let quote = match speed {
+        OnchainConfirmationSpeed::Fast => &fee_quote.speed_fast,
+        OnchainConfirmationSpeed::Medium => &fee_quote.speed_medium,
+        OnchainConfirmationSpeed::Slow => &fee_quote.speed_slow,
+    };
+    Some(quote.user_fee_sat + quote.l1_broadcast_fee_sat)
+}
+
+/// Confirmation speed for an on-chain send under `fee` (medium without one)
+///
+/// The error is the cheapest quoted fee, when even that is above a `Fixed` cap.
+pub(crate) fn onchain_speed(
+    prepare: &PrepareSendPaymentResponse,
+    fee: Option<&OnchainFee>,
+) -> Result<OnchainConfirmationSpeed, u64> {
+    match fee {
+        None | Some(OnchainFee::Rate(_)) => Ok(OnchainConfirmationSpeed::Medium),
+        Some(OnchainFee::Speed(speed)) => Ok(speed.clone()),
+        Some(OnchainFee::Fixed(max_fee)) => [
+            OnchainConfirmationSpeed::Fast,
+            OnchainConfirmationSpeed::Medium,
+            OnchainConfirmationSpeed::Slow,
+        ]
+        .into_iter()
+        .find(|speed| onchain_fee_sats(prepare, speed).is_some_and(|fee| fee <= *max_fee))
+        .ok_or_else(|| onchain_fee_sats(prepare, &OnchainConfirmationSpeed::Slow).unwrap_or_default()),
+    }
+}
+
+/// Fastest confirmation speed a fee rate pays for, or None below the one-hour rate
+pub(crate) fn speed_for_rate(sat_per_vbyte: u64, fees: &RecommendedFees) -> Option<OnchainConfirmationSpeed> {
+    if sat_per_vbyte >= fees.fastest_fee {
+        Some(OnchainConfirmationSpeed::Fast)
+    } else if sat_per_vbyte >= fees.half_hour_fee {
+        Some(OnchainConfirmationSpeed::Medium)
+    } else if sat_per_vbyte >= fees.hour_fee {
+        Some(OnchainConfirmationSpeed::Slow)
+    } else {
+        None
+    }
+}
+
+pub(crate) fn speed_name(speed: &OnchainConfirmationSpeed) -> &'static str {
+    match speed {
+        OnchainConfirmationSpeed::Fast => "fast",
+        OnchainConfirmationSpeed::Medium => "medium",
+        OnchainConfirmationSpeed::Slow => "slow",
+    }
+}
+
+/// Send options matching the fee quoted by `quoted_fee_sats`
+pub(crate) fn send_options_for(prepare: &PrepareSendPaymentResponse) -> Option<SendPaymentOptions> {
+    match &prepare.payment_method {
+        SendPaymentMethod::BitcoinAddress { .. } => Some(SendPaymentOptions::BitcoinAddress {
+            confirmation_speed: OnchainConfirmationSpeed::Medium,
+        }),
+        _ => None,
+    }
+}
+
+/// Approximate virtual size of a deposit claim transaction, used for fee estimates
+pub(crate) const CLAIM_TX_VBYTES: u64 = 110;
+
+/// Thresholds above which an on-chain fee is flagged to the player
+#[derive(Clone)]
+pub(crate) struct FeeWarningPolicy {
+    /// Absolute fee ceiling in satoshis (0 to disable)
+    pub(crate) max_fee_sats: u64,
+    /// Fee ceiling as a percentage of the amount (0 to disable)
+    pub(crate) max_fee_percent: f64,
+    /// Recommended fee rate above which `high_fee_environment` fires (0 to disable)
+    pub(crate) high_rate_sat_vb: u64,
+}
+
+impl Default for FeeWarningPolicy {
+    fn default() -> Self {
+        Self {
+            max_fee_sats: 0,
+            max_fee_percent: 10.0,
+            high_rate_sat_vb: 0,
+        }
+    }
+}
+
+impl FeeWarningPolicy {
+    /// Warning code for a fee paid on `amount_sats`, or an empty string if the fee is acceptable
+    pub(crate) fn warning(&self, fee_sats: u64, amount_sats: u64) -> &'static str {
+        if self.max_fee_sats > 0 && fee_sats > self.max_fee_sats {
+            return "ABOVE_CEILING";
+        }
+        if self.max_fee_percent > 0.0 {
+            if amount_sats == 0 {
+                return if fee_sats > 0 { "ABOVE_PERCENT" } else { "" };
+            }
+            if fee_sats as f64 * 100.0 / amount_sats as f64 > self.max_fee_percent {
+                return "ABOVE_PERCENT";
+            }
+        }
+        ""
+    }
+
+    pub(crate) fn is_high_rate(&self, rate_sat_vb: u64) -> bool {
+        self.high_rate_sat_vb > 0 && rate_sat_vb > self.high_rate_sat_vb
+    }
+}
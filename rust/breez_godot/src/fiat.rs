@@ -0,0 +1,579 @@
+use godot::prelude::*;
+use breez_sdk_spark::{BreezSdk, GetInfoRequest};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+use crate::events::NodeEvent;
+use crate::format::fallback_currency_info;
+use crate::invoices::description_error;
+use crate::limits::{check_amount, AmountMethod};
+use crate::storage::{load_json, now_secs, save_json};
+use crate::validation::check_description;
+use crate::{lock_sdk, BreezNode};
+
+pub(crate) const FIAT_CACHE_FILE: &str = "fiat_rates.json";
+
+/// Default maximum age of a rate used to price an invoice
+const DEFAULT_MAX_STALENESS_SECS: u64 = 15 * 60;
+
+/// Last successfully fetched rate for a currency
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct CachedRate {
+    rate: f64,
+    timestamp: u64,
+}
+
+/// A rate as served to GDScript, with its age information
+#[derive(Clone)]
+pub(crate) struct RateQuote {
+    pub(crate) rate: f64,
+    pub(crate) timestamp: u64,
+    pub(crate) stale: bool,
+}
+
+impl RateQuote {
+    pub(crate) fn to_dictionary(&self) -> Dictionary {
+        let mut dict = Dictionary::new();
+        dict.set("rate", self.rate);
+        dict.set("rate_timestamp", self.timestamp as i64);
+        dict.set("stale", self.stale);
+        dict
+    }
+}
+
+/// A fiat currency from the SDK's list
+#[derive(Clone)]
+pub(crate) struct FiatCurrencyInfo {
+    name: String,
+    symbol: String,
+    fraction_digits: u32,
+}
+
+/// In-memory fiat rate cache, persisted under storage_dir
+pub(crate) struct FiatState {
+    rates: HashMap<String, CachedRate>,
+    last_refresh_failed: bool,
+    max_staleness_secs: u64,
+    ticker: Option<JoinHandle<()>>,
+    /// Currencies by code, from the SDK
+    currencies: HashMap<String, FiatCurrencyInfo>,
+}
+
+impl Default for FiatState {
+    fn default() -> Self {
+        Self {
+            rates: HashMap::new(),
+            last_refresh_failed: false,
+            max_staleness_secs: DEFAULT_MAX_STALENESS_SECS,
+            ticker: None,
+            currencies: HashMap::new(),
+        }
+    }
+}
+
+impl FiatState {
+    fn quote(&self, currency: &str) -> Option<RateQuote> {
+        self.rates.get(currency).map(|cached| RateQuote {
+            rate: cached.rate,
+            timestamp: cached.timestamp,
+            stale: self.last_refresh_failed
+                || now_secs().saturating_sub(cached.timestamp) > self.max_staleness_secs,
+        })
+    }
+
+    fn quotes(&self) -> Vec<(String, RateQuote)> {
+        let mut codes: Vec<&String> = self.rates.keys().collect();
+        codes.sort();
+        codes
+            .into_iter()
+            .filter_map(|code| self.quote(code).map(|q| (code.clone(), q)))
+            .collect()
+    }
+
+    pub(crate) fn is_too_old(&self, quote: &RateQuote) -> bool {
+        now_secs().saturating_sub(quote.timestamp) > self.max_staleness_secs
+    }
+
+    /// When rates were last fetched, if ever
+    fn last_fetched(&self) -> Option<u64> {
+        self.rates.values().map(|cached| cached.timestamp).max()
+    }
+}
+
+/// Fetch current BTC rates from the SDK as (currency code, rate) pairs
+async fn fetch_rates(sdk: &BreezSdk) -> Result<Vec<(String, f64)>, String> {
+    match sdk.list_fiat_rates().await {
+        Ok(response) => Ok(response
+            .rates
+            .into_iter()
+            .map(|rate| (rate.coin.to_uppercase(), rate.value))
+            .collect()),
+        Err(e) => Err(format!("Failed to fetch fiat rates: {:?}", e)),
+    }
+}
+
+/// Record the outcome of a refresh, persisting successful results
+fn record_refresh(
+    fiat: &Mutex<FiatState>,
+    fetched: &Result<Vec<(String, f64)>, String>,
+    cache_path: Option<&Path>,
+) {
+    let mut state = fiat.lock().unwrap();
+    match fetched {
+        Ok(rates) => {
+            let timestamp = now_secs();
+            for (code, rate) in rates {
+                state.rates.insert(code.clone(), CachedRate { rate: *rate, timestamp });
+            }
+            state.last_refresh_failed = false;
+            if let Some(path) = cache_path {
+                if let Err(e) = save_json(path, &state.rates) {
+                    godot_warn!("Failed to persist fiat rates: {}", e);
+                }
+            }
+        }
+        Err(_) => state.last_refresh_failed = true,
+    }
+}
+
+pub(crate) fn quotes_dictionary(quotes: &[(String, RateQuote)]) -> Dictionary {
+    let mut dict = Dictionary::new();
+    for (code, quote) in quotes {
+        dict.set(code.as_str(), quote.to_dictionary());
+    }
+    dict
+}
+
+fn fiat_error(error: String, code: &str) -> Dictionary {
+    godot_error!("{}", error);
+    let mut dict = Dictionary::new();
+    dict.set("success", false);
+    dict.set("error", error);
+    if !code.is_empty() {
+        dict.set("error_code", code);
+    }
+    dict
+}
+
+#[godot_api(secondary)]
+impl BreezNode {
+    /// Fiat currencies the SDK has rates for, sorted by code
+    ///
+    /// Each entry has `code`, `name`, `symbol` and `fraction_digits` (the digits shown after
+    /// the decimal point, e.g. 2 for USD and 0 for JPY). The list is fetched once per
+    /// session; it is empty when it cannot be fetched.
+    #[func]
+    pub fn list_fiat_currencies(&self) -> Array<Dictionary> {
+        if let Err(error) = self.require_capability("fiat") {
+            self.report_failure("list_fiat_currencies", error);
+            return Array::new();
+        }
+        if let Err(e) = self.load_fiat_currencies() {
+            self.report_failure("list_fiat_currencies", fiat_error(e, "NETWORK_ERROR"));
+        }
+        let state = self.fiat.lock().unwrap();
+        let mut codes: Vec<&String> = state.currencies.keys().collect();
+        codes.sort();
+        codes
+            .into_iter()
+            .map(|code| {
+                let currency = &state.currencies[code];
+                let mut dict = Dictionary::new();
+                dict.set("code", code.as_str());
+                dict.set("name", currency.name.as_str());
+                dict.set("symbol", currency.symbol.as_str());
+                dict.set("fraction_digits", currency.fraction_digits as i64);
+                dict
+            })
+            .collect()
+    }
+
+    /// BTC rates for every currency, sorted by code, fetched again only when the cached
+    /// ones are older than `max_age_secs`
+    ///
+    /// Each entry has `code`, `rate` (price of one BTC), `rate_timestamp` and `stale`. When
+    /// the fetch fails the cached rates are returned, marked stale.
+    ///
+    /// # Arguments
+    /// * `max_age_secs` - Oldest acceptable cached rates in seconds; 0 always fetches
+    #[func]
+    pub fn fetch_fiat_rates(&self, max_age_secs: i64) -> Array<Dictionary> {
+        if let Err(error) = self.require_capability("fiat") {
+            self.report_failure("fetch_fiat_rates", error);
+            return Array::new();
+        }
+        let last_fetched = self.fiat.lock().unwrap().last_fetched();
+        let fresh = last_fetched.is_some_and(|timestamp| {
+            max_age_secs > 0 && now_secs().saturating_sub(timestamp) <= max_age_secs as u64
+        });
+        if !fresh {
+            if let Err(e) = self.refresh_fiat_cache() {
+                self.report_failure("fetch_fiat_rates", fiat_error(e, "NETWORK_ERROR"));
+            }
+        }
+        let quotes = self.fiat.lock().unwrap().quotes();
+        quotes
+            .iter()
+            .map(|(code, quote)| {
+                let mut dict = quote.to_dictionary();
+                dict.set("code", code.as_str());
+                dict
+            })
+            .collect()
+    }
+
+    /// Refresh fiat rates, falling back to cached rates when the fetch fails
+    ///
+    /// Returns `rates` keyed by currency code, each with `rate`, `rate_timestamp` and `stale`.
+    #[func]
+    pub fn refresh_fiat_rates(&self) -> Dictionary {
+        let result = self.refresh_fiat_rates_with();
+        self.report_failure("refresh_fiat_rates", result)
+    }
+
+    /// Get the BTC rate for a currency code, refreshing it if it is older than the max staleness
+    #[func]
+    pub fn get_fiat_rate(&self, currency: GString) -> Dictionary {
+        let result = self.get_fiat_rate_with(currency);
+        self.report_failure("get_fiat_rate", result)
+    }
+
+    /// Get the wallet balance converted to a fiat currency
+    ///
+    /// The result carries `value`, `balance_sats`, `rate`, `rate_timestamp` and `stale`, or
+    /// UNKNOWN_CURRENCY for a code the SDK has no rate for and NETWORK_ERROR when no rate
+    /// could be fetched. Rates older than the max staleness are fetched again.
+    #[func]
+    pub fn get_balance_fiat(&self, currency: GString) -> Dictionary {
+        let result = self.get_balance_fiat_with(currency, None);
+        self.report_failure("get_balance_fiat", result)
+    }
+
+    /// `get_balance_fiat`, fetching the rate again when it is older than `max_age_secs`
+    #[func]
+    pub fn get_balance_fiat_with_max_age(&self, currency: GString, max_age_secs: i64) -> Dictionary {
+        let result = self.get_balance_fiat_with(currency, Some(max_age_secs.max(0) as u64));
+        self.report_failure("get_balance_fiat", result)
+    }
+
+    /// Create a Lightning invoice priced in a fiat currency
+    ///
+    /// Refuses to price off a rate older than the max staleness, since this moves money.
+    ///
+    /// # Arguments
+    /// * `amount` - Amount in the fiat currency
+    /// * `currency` - Currency code, e.g. "USD"
+    /// * `description` - Invoice description
+    #[func]
+    pub fn create_invoice_fiat(&self, amount: f64, currency: GString, description: GString) -> Dictionary {
+        let result = self.create_invoice_fiat_with(amount, currency, description);
+        self.report_failure("create_invoice_fiat", result)
+    }
+
+    /// Set the maximum age in seconds of a rate used by `create_invoice_fiat`
+    #[func]
+    pub fn set_fiat_max_staleness(&mut self, max_staleness_secs: i64) {
+        self.fiat.lock().unwrap().max_staleness_secs = max_staleness_secs.max(0) as u64;
+    }
+
+    /// Start refreshing fiat rates periodically, emitting `price_ticker_updated` after each refresh
+    #[func]
+    pub fn start_price_ticker(&mut self, interval_secs: i64) {
+        self.stop_price_ticker();
+        if interval_secs <= 0 {
+            return;
+        }
+
+        let sdk_arc = Arc::clone(&self.sdk);
+        let fiat = Arc::clone(&self.fiat);
+        let cache_path = self.plugin_path(FIAT_CACHE_FILE);
+        let events = self.events.sender();
+
+        let handle = self.runtime.spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(interval_secs as u64));
+            loop {
+                interval.tick().await;
+                let sdk = lock_sdk(&sdk_arc).clone();
+                let Some(sdk) = sdk else {
+                    continue;
+                };
+
+                let fetched = fetch_rates(&sdk).await;
+                record_refresh(&fiat, &fetched, cache_path.as_deref());
+                let quotes = fiat.lock().unwrap().quotes();
+                let _ = events.send(NodeEvent::PriceTick { quotes });
+            }
+        });
+
+        self.fiat.lock().unwrap().ticker = Some(handle);
+    }
+
+    /// Stop the price ticker
+    #[func]
+    pub fn stop_price_ticker(&mut self) {
+        if let Some(handle) = self.fiat.lock().unwrap().ticker.take() {
+            handle.abort();
+        }
+    }
+}
+
+/// Drop cached rates older than `max_age_secs` from a fiat cache file, returning how many
+pub(crate) fn prune_fiat_cache(path: &Path, max_age_secs: u64) -> usize {
+    let Some(mut rates) = load_json::<HashMap<String, CachedRate>>(path) else {
+        return 0;
+    };
+    let cutoff = now_secs().saturating_sub(max_age_secs);
+    let before = rates.len();
+    rates.retain(|_, cached| cached.timestamp >= cutoff);
+    let pruned = before - rates.len();
+    if pruned > 0 {
+        if let Err(e) = save_json(path, &rates) {
+            godot_warn!("Failed to persist pruned fiat cache: {}", e);
+            return 0;
+        }
+    }
+    pruned
+}
+
+impl BreezNode {
+    fn refresh_fiat_rates_with(&self) -> Dictionary {
+        if let Err(error) = self.require_capability("fiat") {
+            return error;
+        }
+        let error = self.refresh_fiat_cache().err();
+        let quotes = self.fiat.lock().unwrap().quotes();
+
+        let mut dict = Dictionary::new();
+        dict.set("success", !quotes.is_empty());
+        dict.set("rates", quotes_dictionary(&quotes));
+        if let Some(e) = error {
+            godot_warn!("{}", e);
+            dict.set("stale", true);
+            dict.set("error", e);
+        } else {
+            dict.set("stale", false);
+        }
+        dict
+    }
+
+    fn get_fiat_rate_with(&self, currency: GString) -> Dictionary {
+        if let Err(error) = self.require_capability("fiat") {
+            return error;
+        }
+        match self.rate_quote(&currency.to_string()) {
+            Ok(quote) => {
+                let mut dict = quote.to_dictionary();
+                dict.set("success", true);
+                dict
+            }
+            Err(e) => {
+                godot_error!("{}", e);
+                let mut dict = Dictionary::new();
+                dict.set("success", false);
+                dict.set("error", e);
+                dict
+            }
+        }
+    }
+
+    fn get_balance_fiat_with(&self, currency: GString, max_age_secs: Option<u64>) -> Dictionary {
+        if let Err(error) = self.require_capability("fiat") {
+            return error;
+        }
+        let sdk_handle = self.sdk_handle();
+        let runtime = Arc::clone(&self.runtime);
+
+        let balance = runtime.block_on(async move {
+            let sdk = sdk_handle?;
+            match sdk.get_info(GetInfoRequest {
+                ensure_synced: Some(false),
+            }).await {
+                Ok(info) => Ok(info.balance_sats),
+                Err(e) => Err(format!("Failed to get balance: {:?}", e)),
+            }
+        });
+
+        let balance = match balance {
+            Ok(balance) => balance,
+            Err(e) => return fiat_error(e, ""),
+        };
+        let code = currency.to_string().trim().to_uppercase();
+        let quote = match max_age_secs {
+            Some(max_age_secs) => self.rate_quote_within(&code, max_age_secs),
+            None => self.rate_quote(&code),
+        };
+        match quote {
+            Ok(quote) => {
+                let mut dict = quote.to_dictionary();
+                dict.set("success", true);
+                dict.set("balance_sats", balance as i64);
+                dict.set("value", balance as f64 / 100_000_000.0 * quote.rate);
+                dict
+            }
+            // Rates are fetched for every currency at once, so any cached rate means the
+            // fetch worked and the code is unknown
+            Err(e) if self.fiat.lock().unwrap().last_fetched().is_some() => fiat_error(e, "UNKNOWN_CURRENCY"),
+            Err(e) => fiat_error(e, "NETWORK_ERROR"),
+        }
+    }
+
+    fn create_invoice_fiat_with(&self, amount: f64, currency: GString, description: GString) -> Dictionary {
+        if let Err(error) = self.require_capability("fiat") {
+            return error;
+        }
+        let mut dict = Dictionary::new();
+
+        let quote = match self.rate_quote(&currency.to_string()) {
+            Ok(quote) => quote,
+            Err(e) => {
+                godot_error!("{}", e);
+                dict.set("success", false);
+                dict.set("error", e);
+                return dict;
+            }
+        };
+
+        if self.fiat.lock().unwrap().is_too_old(&quote) {
+            let e = "Fiat rate is too old to price an invoice".to_string();
+            godot_error!("{}", e);
+            dict = quote.to_dictionary();
+            dict.set("success", false);
+            dict.set("error", e);
+            return dict;
+        }
+
+        if !(amount > 0.0) || quote.rate <= 0.0 {
+            dict.set("success", false);
+            dict.set("error", "Invalid fiat amount");
+            return dict;
+        }
+
+        let amount_sats = (amount / quote.rate * 100_000_000.0).round() as i64;
+        if let Err(error) = check_amount(AmountMethod::Lightning, amount_sats) {
+            return error;
+        }
+        if let Err(e) = check_description(&description.to_string(), false) {
+            return description_error(&e);
+        }
+        let invoice = self.create_invoice(amount_sats, description);
+
+        dict = quote.to_dictionary();
+        if invoice.is_empty() {
+            dict.set("success", false);
+            dict.set("error", "Failed to create invoice");
+        } else {
+            dict.set("success", true);
+            dict.set("invoice", invoice);
+            dict.set("amount_sats", amount_sats);
+        }
+        dict
+    }
+
+    /// Load the persisted fiat rate cache for the connected wallet
+    pub(crate) fn load_fiat_cache(&self) {
+        let Some(path) = self.plugin_path(FIAT_CACHE_FILE) else {
+            return;
+        };
+        if let Some(rates) = load_json::<HashMap<String, CachedRate>>(&path) {
+            self.fiat.lock().unwrap().rates = rates;
+        }
+    }
+
+    /// Fetch rates from the SDK into the cache
+    fn refresh_fiat_cache(&self) -> Result<(), String> {
+        let sdk_handle = self.sdk_handle();
+        let runtime = Arc::clone(&self.runtime);
+        let cache_path: Option<PathBuf> = self.plugin_path(FIAT_CACHE_FILE);
+
+        let fetched = runtime.block_on(async move {
+            let sdk = sdk_handle?;
+            fetch_rates(&sdk).await
+        });
+
+        record_refresh(&self.fiat, &fetched, cache_path.as_deref());
+        fetched.map(|_| ())
+    }
+
+    /// Fetch the SDK's currency list into the cache, once per session
+    fn load_fiat_currencies(&self) -> Result<(), String> {
+        if !self.fiat.lock().unwrap().currencies.is_empty() {
+            return Ok(());
+        }
+        let sdk_handle = self.sdk_handle();
+        let runtime = Arc::clone(&self.runtime);
+
+        let response = runtime.block_on(async move {
+            sdk_handle?
+                .list_fiat_currencies()
+                .await
+                .map_err(|e| format!("Failed to fetch fiat currencies: {:?}", e))
+        })?;
+
+        let mut state = self.fiat.lock().unwrap();
+        for currency in response.currencies {
+            let symbol = currency
+                .info
+                .symbol
+                .and_then(|s| s.grapheme)
+                .unwrap_or_else(|| currency.id.clone());
+            state.currencies.insert(
+                currency.id.to_uppercase(),
+                FiatCurrencyInfo {
+                    name: currency.info.name,
+                    symbol,
+                    fraction_digits: currency.info.fraction_size,
+                },
+            );
+        }
+        Ok(())
+    }
+
+    /// Display symbol and fraction digits for a currency code
+    ///
+    /// Fetches the SDK's currency list once per session, falling back to a built-in table.
+    pub(crate) fn currency_display_info(&self, code: &str) -> (String, u32) {
+        if self.is_sdk_connected() {
+            let _ = self.load_fiat_currencies();
+        }
+
+        self.fiat
+            .lock()
+            .unwrap()
+            .currencies
+            .get(code)
+            .map(|currency| (currency.symbol.clone(), currency.fraction_digits))
+            .unwrap_or_else(|| fallback_currency_info(code))
+    }
+
+    /// Rate for a currency, refreshed first if missing or older than the max staleness
+    pub(crate) fn rate_quote(&self, currency: &str) -> Result<RateQuote, String> {
+        let max_age_secs = self.fiat.lock().unwrap().max_staleness_secs;
+        self.rate_quote_within(currency, max_age_secs)
+    }
+
+    /// Rate for a currency, refreshed first if missing or older than `max_age_secs`
+    fn rate_quote_within(&self, currency: &str, max_age_secs: u64) -> Result<RateQuote, String> {
+        let code = currency.trim().to_uppercase();
+        let cached = self.fiat.lock().unwrap().quote(&code);
+
+        let needs_refresh = match &cached {
+            Some(quote) => now_secs().saturating_sub(quote.timestamp) > max_age_secs,
+            None => true,
+        };
+        if needs_refresh {
+            if let Err(e) = self.refresh_fiat_cache() {
+                godot_warn!("{}", e);
+            }
+        }
+
+        self.fiat
+            .lock()
+            .unwrap()
+            .quote(&code)
+            .ok_or_else(|| format!("No rate available for currency: {}", code))
+    }
+}
@@ -0,0 +1,348 @@
+use godot::prelude::*;
+
+use crate::BreezNode;
+
+/// Separators and symbol placement for a locale
+#[derive(Clone, Copy)]
+pub(crate) struct LocaleFormat {
+    decimal: char,
+    group: char,
+    symbol_after: bool,
+}
+
+const NARROW_NBSP: char = '\u{202F}';
+const NBSP: char = '\u{00A0}';
+
+/// Resolve a locale such as "de", "de_DE" or "pt-BR" to its number format
+///
+/// Unknown locales fall back to English formatting so output stays deterministic.
+pub(crate) fn locale_format(locale: &str) -> LocaleFormat {
+    let normalized = locale.trim().replace('-', "_").to_lowercase();
+    let language = normalized.split('_').next().unwrap_or("");
+
+    if normalized == "de_ch" {
+        return LocaleFormat { decimal: '.', group: '\u{2019}', symbol_after: false };
+    }
+
+    match language {
+        "de" | "es" | "it" | "pt" | "nl" | "id" | "tr" | "da" | "el" => {
+            LocaleFormat { decimal: ',', group: '.', symbol_after: true }
+        }
+        "fr" => LocaleFormat { decimal: ',', group: NARROW_NBSP, symbol_after: true },
+        "ru" | "pl" | "cs" | "sv" | "nb" | "fi" | "uk" | "sk" | "hu" => {
+            LocaleFormat { decimal: ',', group: NBSP, symbol_after: true }
+        }
+        _ => LocaleFormat { decimal: '.', group: ',', symbol_after: false },
+    }
+}
+
+/// Insert group separators every three digits
+fn group_digits(digits: &str, group: char) -> String {
+    let mut result = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            result.push(group);
+        }
+        result.push(c);
+    }
+    result
+}
+
+/// Join an integer and fraction part using the locale separators
+fn join_parts(negative: bool, int_part: &str, frac_part: &str, fmt: LocaleFormat) -> String {
+    let mut result = String::new();
+    if negative {
+        result.push('-');
+    }
+    result.push_str(&group_digits(int_part, fmt.group));
+    if !frac_part.is_empty() {
+        result.push(fmt.decimal);
+        result.push_str(frac_part);
+    }
+    result
+}
+
+fn with_symbol(number: String, symbol: &str, fmt: LocaleFormat) -> String {
+    if fmt.symbol_after {
+        format!("{}{}{}", number, NBSP, symbol)
+    } else {
+        format!("{}{}", symbol, number)
+    }
+}
+
+/// Format a satoshi amount in "sat", "btc" or "msat" units for a locale
+pub(crate) fn format_sats(sats: i64, unit: &str, locale: &str) -> Result<String, String> {
+    let fmt = locale_format(locale);
+    let negative = sats < 0;
+    let abs = sats.unsigned_abs();
+
+    match unit.trim().to_lowercase().as_str() {
+        "sat" | "sats" => {
+            let number = join_parts(negative, &abs.to_string(), "", fmt);
+            let label = if abs == 1 { "sat" } else { "sats" };
+            Ok(format!("{}{}{}", number, NBSP, label))
+        }
+        "btc" => {
+            let int_part = (abs / 100_000_000).to_string();
+            let frac_part = format!("{:08}", abs % 100_000_000);
+            Ok(with_symbol(join_parts(negative, &int_part, &frac_part, fmt), "₿", fmt))
+        }
+        "msat" | "msats" => {
+            let msats = abs as u128 * 1000;
+            let number = join_parts(negative, &msats.to_string(), "", fmt);
+            Ok(format!("{}{}msat", number, NBSP))
+        }
+        other => Err(format!("Unknown unit: {}", other)),
+    }
+}
+
+/// Format a fiat amount with the given fraction digits and currency symbol for a locale
+pub(crate) fn format_fiat_amount(amount: f64, symbol: &str, fraction_digits: u32, locale: &str) -> String {
+    let fmt = locale_format(locale);
+    let rounded = format!("{:.*}", fraction_digits as usize, amount.abs());
+    let (int_part, frac_part) = match rounded.split_once('.') {
+        Some((int_part, frac_part)) => (int_part.to_string(), frac_part.to_string()),
+        None => (rounded.clone(), String::new()),
+    };
+    let negative = amount < 0.0 && rounded.chars().any(|c| c.is_ascii_digit() && c != '0');
+    with_symbol(join_parts(negative, &int_part, &frac_part, fmt), symbol, fmt)
+}
+
+/// Parse a non-negative decimal string into an integer scaled by 10^`scale`, without rounding
+pub(crate) fn parse_scaled(number: &str, scale: u32) -> Option<u64> {
+    let (int_part, frac_part) = number.split_once('.').unwrap_or((number, ""));
+    if int_part.is_empty() && frac_part.is_empty() {
+        return None;
+    }
+    if !int_part.chars().chain(frac_part.chars()).all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    if frac_part.len() > scale as usize {
+        return None;
+    }
+
+    let int_value: u64 = if int_part.is_empty() { 0 } else { int_part.parse().ok()? };
+    let frac_value: u64 = if frac_part.is_empty() {
+        0
+    } else {
+        frac_part.parse::<u64>().ok()? * 10u64.pow(scale - frac_part.len() as u32)
+    };
+    int_value.checked_mul(10u64.pow(scale))?.checked_add(frac_value)
+}
+
+/// Parse an amount typed by a player, e.g. "1,000 sats", "₿0.0005" or "0,5 BTC", into satoshis
+///
+/// A unit written in the text wins over `default_unit`. Separators follow the locale.
+pub(crate) fn parse_amount_text(text: &str, default_unit: &str, locale: &str) -> Result<i64, String> {
+    let fmt = locale_format(locale);
+    let mut body = text.trim().to_lowercase();
+
+    let mut unit = default_unit.trim().to_lowercase();
+    if let Some(rest) = body.strip_prefix('₿') {
+        body = rest.to_string();
+        unit = "btc".to_string();
+    }
+    for (suffix, suffix_unit) in [("msats", "msat"), ("msat", "msat"), ("sats", "sat"), ("sat", "sat"), ("btc", "btc"), ("₿", "btc")] {
+        if let Some(rest) = body.strip_suffix(suffix) {
+            body = rest.to_string();
+            unit = suffix_unit.to_string();
+            break;
+        }
+    }
+
+    let invalid = || format!("Invalid amount: {}", text.trim());
+
+    let compact: String = body
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != NBSP && *c != NARROW_NBSP)
+        .collect();
+    let (int_part, frac_part) = match compact.split_once(fmt.decimal) {
+        Some((int_part, frac_part)) => (int_part, Some(frac_part)),
+        None => (compact.as_str(), None),
+    };
+
+    // Only accept group separators in their proper places, so "0.5" typed in a locale
+    // that groups with '.' is rejected instead of read as 5
+    let mut groups = int_part.split(fmt.group);
+    let first = groups.next().unwrap_or("");
+    let rest: Vec<&str> = groups.collect();
+    if !rest.is_empty() && (first.is_empty() || first.len() > 3 || rest.iter().any(|g| g.len() != 3)) {
+        return Err(invalid());
+    }
+    let mut number: String = int_part.chars().filter(|c| *c != fmt.group).collect();
+    if let Some(frac_part) = frac_part {
+        number.push('.');
+        number.push_str(frac_part);
+    }
+
+    let sats = match unit.as_str() {
+        "sat" | "sats" => parse_scaled(&number, 0),
+        "btc" => parse_scaled(&number, 8),
+        "msat" | "msats" => parse_scaled(&number, 0).map(|msats| msats / 1000),
+        other => return Err(format!("Unknown unit: {}", other)),
+    };
+    sats.and_then(|sats| i64::try_from(sats).ok()).ok_or_else(invalid)
+}
+
+/// Symbol and fraction digits for common currencies, used when the SDK has no info
+pub(crate) fn fallback_currency_info(code: &str) -> (String, u32) {
+    match code {
+        "USD" => ("$".to_string(), 2),
+        "EUR" => ("€".to_string(), 2),
+        "GBP" => ("£".to_string(), 2),
+        "JPY" => ("¥".to_string(), 0),
+        "CHF" => ("CHF".to_string(), 2),
+        "BRL" => ("R$".to_string(), 2),
+        "ARS" => ("$".to_string(), 2),
+        "KRW" => ("₩".to_string(), 0),
+        "INR" => ("₹".to_string(), 2),
+        other => (other.to_string(), 2),
+    }
+}
+
+#[godot_api(secondary)]
+impl BreezNode {
+    /// Format a satoshi amount for display in a locale
+    ///
+    /// # Arguments
+    /// * `sats` - Amount in satoshis
+    /// * `unit` - "sat", "btc" or "msat"
+    /// * `locale` - Locale code, e.g. "en", "de_DE" (see `TranslationServer.get_locale()`)
+    #[func]
+    pub fn format_sats_localized(&self, sats: i64, unit: GString, locale: GString) -> GString {
+        match format_sats(sats, &unit.to_string(), &locale.to_string()) {
+            Ok(text) => GString::from(&text),
+            Err(e) => {
+                godot_error!("{}", e);
+                GString::from("")
+            }
+        }
+    }
+
+    /// Format a fiat amount for display in a locale
+    ///
+    /// Symbol and fraction digits come from the SDK's currency info when available.
+    #[func]
+    pub fn format_fiat(&self, amount: f64, currency: GString, locale: GString) -> GString {
+        let code = currency.to_string().trim().to_uppercase();
+        let (symbol, fraction_digits) = self.currency_display_info(&code);
+        GString::from(&format_fiat_amount(amount, &symbol, fraction_digits, &locale.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The same amounts rendered for `locale`, one per line with separators escaped so they
+    /// stay visible
+    fn snapshot(locale: &str) -> String {
+        [
+            format_sats(1_234_567, "sat", locale).unwrap(),
+            format_sats(1, "sat", locale).unwrap(),
+            format_sats(123_456_789, "btc", locale).unwrap(),
+            format_sats(-1_000, "sats", locale).unwrap(),
+            format_sats(21, "msat", locale).unwrap(),
+            format_fiat_amount(1234.5, "€", 2, locale),
+            format_fiat_amount(1234.4, "¥", 0, locale),
+            format_fiat_amount(-0.001, "$", 2, locale),
+        ]
+        .iter()
+        .map(|text| text.escape_debug().to_string())
+        .collect::<Vec<_>>()
+        .join("\n")
+    }
+
+    #[test]
+    fn english_snapshot() {
+        assert_eq!(
+            snapshot("en_US"),
+            r"1,234,567\u{a0}sats
+1\u{a0}sat
+₿1.23456789
+-1,000\u{a0}sats
+21,000\u{a0}msat
+€1,234.50
+¥1,234
+$0.00"
+        );
+    }
+
+    #[test]
+    fn german_snapshot() {
+        assert_eq!(
+            snapshot("de_DE"),
+            r"1.234.567\u{a0}sats
+1\u{a0}sat
+1,23456789\u{a0}₿
+-1.000\u{a0}sats
+21.000\u{a0}msat
+1.234,50\u{a0}€
+1.234\u{a0}¥
+0,00\u{a0}$"
+        );
+        assert_eq!(snapshot("pt-BR"), snapshot("de_DE"));
+    }
+
+    #[test]
+    fn french_snapshot() {
+        assert_eq!(
+            snapshot("fr_FR"),
+            r"1\u{202f}234\u{202f}567\u{a0}sats
+1\u{a0}sat
+1,23456789\u{a0}₿
+-1\u{202f}000\u{a0}sats
+21\u{202f}000\u{a0}msat
+1\u{202f}234,50\u{a0}€
+1\u{202f}234\u{a0}¥
+0,00\u{a0}$"
+        );
+    }
+
+    #[test]
+    fn swiss_german_snapshot() {
+        assert_eq!(
+            snapshot("de-CH"),
+            r"1’234’567\u{a0}sats
+1\u{a0}sat
+₿1.23456789
+-1’000\u{a0}sats
+21’000\u{a0}msat
+€1’234.50
+¥1’234
+$0.00"
+        );
+    }
+
+    #[test]
+    fn russian_snapshot() {
+        assert_eq!(
+            snapshot("ru_RU"),
+            r"1\u{a0}234\u{a0}567\u{a0}sats
+1\u{a0}sat
+1,23456789\u{a0}₿
+-1\u{a0}000\u{a0}sats
+21\u{a0}000\u{a0}msat
+1\u{a0}234,50\u{a0}€
+1\u{a0}234\u{a0}¥
+0,00\u{a0}$"
+        );
+    }
+
+    #[test]
+    fn unknown_locales_format_like_english() {
+        assert_eq!(snapshot("ja"), snapshot("en_US"));
+        assert_eq!(snapshot(""), snapshot("en_US"));
+    }
+
+    #[test]
+    fn typed_amounts_follow_the_locale_separators() {
+        assert_eq!(parse_amount_text("1.000 sats", "sat", "de"), Ok(1_000));
+        assert_eq!(parse_amount_text("0,5 BTC", "sat", "de"), Ok(50_000_000));
+        assert_eq!(parse_amount_text("₿0.0005", "sat", "en"), Ok(50_000));
+        assert_eq!(parse_amount_text("21000 msat", "sat", "en"), Ok(21));
+        // A misplaced group separator is rejected rather than read as a different amount
+        assert!(parse_amount_text("0.5", "btc", "de").is_err());
+        assert!(parse_amount_text("1,00,000", "sat", "en").is_err());
+    }
+}
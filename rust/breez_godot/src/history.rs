@@ -0,0 +1,545 @@
+use godot::prelude::*;
+use breez_sdk_spark::{
+    GetInfoRequest, GetPaymentRequest, ListPaymentsRequest, Payment, PaymentDetails, PaymentStatus, PaymentType,
+};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::payment_stream::PaymentFilters;
+use crate::BreezNode;
+
+/// Page size used when walking the full payment history
+const HISTORY_PAGE_SIZE: u32 = 100;
+
+/// Upper bound on histogram buckets, to keep the returned Array reasonable
+const MAX_BUCKETS: i64 = 10_000;
+
+/// Unix timestamp of Monday 1970-01-05 00:00, used to align weekly buckets
+const FIRST_MONDAY: i64 = 4 * 86_400;
+
+/// Length in seconds of a histogram bucket
+fn bucket_secs(bucket: &str) -> Option<i64> {
+    match bucket {
+        "hour" => Some(3_600),
+        "day" => Some(86_400),
+        "week" => Some(7 * 86_400),
+        _ => None,
+    }
+}
+
+/// Start (UTC) of the bucket containing `timestamp`, aligned to the player's local time
+fn bucket_start(timestamp: i64, size: i64, utc_offset_secs: i64) -> i64 {
+    let origin = if size == 7 * 86_400 { FIRST_MONDAY } else { 0 };
+    let local = timestamp + utc_offset_secs - origin;
+    local.div_euclid(size) * size + origin - utc_offset_secs
+}
+
+/// Cached balance history samples keyed by the (from_ts, to_ts, points) request
+pub(crate) type BalanceHistoryCache = Option<((i64, i64, i64), Vec<(i64, i64)>)>;
+
+/// Change a payment made to the wallet balance
+///
+/// Completed receives add their amount and sends remove amount plus fees. Pending sends are
+/// treated like completed ones because the SDK balance already excludes them; pending
+/// receives and failed payments do not affect the balance.
+fn balance_delta(payment: &Payment) -> i64 {
+    match (&payment.payment_type, &payment.status) {
+        (PaymentType::Receive, PaymentStatus::Completed) => payment.amount as i64,
+        (PaymentType::Send, PaymentStatus::Completed | PaymentStatus::Pending) => {
+            -(payment.amount as i64 + payment.fees as i64)
+        }
+        _ => 0,
+    }
+}
+
+/// Method-specific fields of a payment, each present only when the SDK reports it
+///
+/// Lightning payments have `invoice`, `payment_hash`, `preimage` (once settled),
+/// `destination_pubkey` and `description`, plus `lightning_address` and `lnurl_domain` when
+/// paid through LNURL; on-chain deposits and withdrawals have `txid`. The SDK does not report
+/// the counterparty of Spark transfers.
+pub(crate) fn details_dictionary(details: Option<&PaymentDetails>) -> Dictionary {
+    let mut dict = Dictionary::new();
+    match details {
+        Some(PaymentDetails::Lightning {
+            description,
+            preimage,
+            invoice,
+            payment_hash,
+            destination_pubkey,
+            lnurl_pay_info,
+            ..
+        }) => {
+            dict.set("invoice", invoice.as_str());
+            dict.set("payment_hash", payment_hash.as_str());
+            dict.set("destination_pubkey", destination_pubkey.as_str());
+            if let Some(preimage) = preimage {
+                dict.set("preimage", preimage.as_str());
+            }
+            if let Some(description) = description.as_ref().filter(|d| !d.is_empty()) {
+                dict.set("description", description.as_str());
+            }
+            if let Some(info) = lnurl_pay_info {
+                if let Some(address) = &info.ln_address {
+                    dict.set("lightning_address", address.as_str());
+                }
+                if let Some(domain) = &info.domain {
+                    dict.set("lnurl_domain", domain.as_str());
+                }
+            }
+        }
+        Some(PaymentDetails::Withdraw { tx_id } | PaymentDetails::Deposit { tx_id }) => {
+            dict.set("txid", tx_id.as_str());
+        }
+        _ => {}
+    }
+    dict
+}
+
+/// Payment as returned by `list_payments`
+///
+/// `payers` maps payment ids to the payer's comment and pubkey (see `zap_payers`); other
+/// payments get an empty `comment` and `payer`.
+pub(crate) fn payment_dictionary(payment: &Payment, payers: &HashMap<String, (String, String)>) -> Dictionary {
+    let mut dict = Dictionary::new();
+    dict.set("id", payment.id.as_str());
+    dict.set("amount", payment.amount as i64);
+    dict.set("fees", payment.fees as i64);
+    dict.set("timestamp", payment.timestamp as i64);
+    dict.set("status", payment.status.to_string());
+    dict.set("payment_type", payment.payment_type.to_string());
+    dict.set("method", payment.method.to_string());
+
+    let description = match &payment.details {
+        Some(PaymentDetails::Lightning { description, .. }) => description.clone().unwrap_or_default(),
+        _ => String::new(),
+    };
+    dict.set("description", description);
+    dict.set("details", details_dictionary(payment.details.as_ref()));
+
+    let mut payer = Dictionary::new();
+    let comment = match payers.get(&payment.id) {
+        Some((comment, pubkey)) => {
+            payer.set("pubkey", pubkey.as_str());
+            comment.as_str()
+        }
+        None => "",
+    };
+    dict.set("comment", comment);
+    dict.set("payer", &payer);
+    dict
+}
+
+#[derive(Default, Clone)]
+struct Bucket {
+    received_sats: i64,
+    sent_sats: i64,
+    fees_sats: i64,
+    received_count: i64,
+    sent_count: i64,
+}
+
+#[godot_api(secondary)]
+impl BreezNode {
+    /// List payment history narrowed by type, status and time range
+    ///
+    /// Entries are the same Dictionaries as `list_payments`. The SDK can only page, so the
+    /// filtering happens here; `offset` and `limit` count matching payments. An unknown
+    /// `payment_type` or `status` logs an error and returns an empty Array.
+    ///
+    /// # Arguments
+    /// * `offset` - Number of matching payments to skip
+    /// * `limit` - Maximum number of payments to return (0 for all)
+    /// * `payment_type` - "send", "receive" or "" for both
+    /// * `status` - "completed", "pending", "failed" or "" for any
+    /// * `from_timestamp` - Oldest payment time (unix seconds, 0 for no bound)
+    /// * `to_timestamp` - Newest payment time (unix seconds, inclusive, 0 for no bound)
+    #[func]
+    pub fn list_payments_filtered(
+        &self,
+        offset: i64,
+        limit: i64,
+        payment_type: GString,
+        status: GString,
+        from_timestamp: i64,
+        to_timestamp: i64,
+    ) -> Array<Dictionary> {
+        let payment_type = payment_type.to_string().trim().to_lowercase();
+        let status = status.to_string().trim().to_lowercase();
+        if !["", "send", "receive"].contains(&payment_type.as_str()) {
+            godot_error!("Invalid payment type: {} (expected \"send\" or \"receive\")", payment_type);
+            return Array::new();
+        }
+        if !["", "completed", "pending", "failed"].contains(&status.as_str()) {
+            godot_error!("Invalid payment status: {} (expected \"completed\", \"pending\" or \"failed\")", status);
+            return Array::new();
+        }
+        let filters = PaymentFilters {
+            payment_type: (!payment_type.is_empty()).then_some(payment_type),
+            status: (!status.is_empty()).then_some(status),
+            from_ts: (from_timestamp > 0).then_some(from_timestamp as u64),
+            to_ts: (to_timestamp > 0).then_some(to_timestamp as u64),
+        };
+        let limit = (limit > 0).then_some(limit as usize);
+
+        let mut array = Array::new();
+        match self.fetch_filtered_payments(&filters, offset.max(0) as usize, limit) {
+            Ok(payments) => {
+                let payers = self.zap_payers();
+                for payment in &payments {
+                    array.push(&payment_dictionary(payment, &payers));
+                }
+            }
+            Err(e) => godot_error!("{}", e),
+        }
+        array
+    }
+
+    /// Look up a payment by the `payment_id` a send or receive returned, e.g. to see whether
+    /// a payment still pending before a restart has completed
+    ///
+    /// Returns the `list_payments` entry with `found: true`, or `{"found": false}` for an
+    /// unknown id. When the lookup itself fails, `found` is false and the Dictionary also has
+    /// `success: false`, `error` and `error_code` (NOT_CONNECTED without a wallet).
+    #[func]
+    pub fn get_payment(&self, payment_id: GString) -> Dictionary {
+        let result = self.get_payment_with(payment_id.to_string().trim());
+        self.report_failure("get_payment", result)
+    }
+
+    /// Aggregate completed payments into time buckets for charts
+    ///
+    /// Every bucket between `from_ts` and `to_ts` is returned, empty ones included, with
+    /// `start`, `received_sats`, `sent_sats`, `fees_sats`, `received_count` and `sent_count`.
+    /// Pending and failed payments are not counted.
+    ///
+    /// # Arguments
+    /// * `from_ts` - Start of the range (unix seconds)
+    /// * `to_ts` - End of the range (unix seconds, inclusive)
+    /// * `bucket` - "hour", "day" or "week" (weeks start on Monday)
+    /// * `utc_offset_secs` - Player's UTC offset, so "day" means the player's day
+    #[func]
+    pub fn get_payment_histogram(
+        &self,
+        from_ts: i64,
+        to_ts: i64,
+        bucket: GString,
+        utc_offset_secs: i64,
+    ) -> Array<Dictionary> {
+        let mut array = Array::new();
+
+        let Some(size) = bucket_secs(&bucket.to_string()) else {
+            godot_error!("Invalid bucket: {} (expected hour, day or week)", bucket);
+            return array;
+        };
+        if to_ts < from_ts {
+            godot_error!("Invalid range: to_ts is before from_ts");
+            return array;
+        }
+
+        let first = bucket_start(from_ts, size, utc_offset_secs);
+        let count = (bucket_start(to_ts, size, utc_offset_secs) - first) / size + 1;
+        if count > MAX_BUCKETS {
+            godot_error!("Too many buckets requested: {}", count);
+            return array;
+        }
+
+        let payments = match self.fetch_all_payments() {
+            Ok(payments) => payments,
+            Err(e) => {
+                godot_error!("{}", e);
+                return array;
+            }
+        };
+
+        let mut buckets = vec![Bucket::default(); count as usize];
+        for payment in payments {
+            let timestamp = payment.timestamp as i64;
+            if timestamp < from_ts || timestamp > to_ts || payment.status != PaymentStatus::Completed {
+                continue;
+            }
+            let index = ((bucket_start(timestamp, size, utc_offset_secs) - first) / size) as usize;
+            let Some(entry) = buckets.get_mut(index) else {
+                continue;
+            };
+            entry.fees_sats += payment.fees as i64;
+            match payment.payment_type {
+                PaymentType::Receive => {
+                    entry.received_sats += payment.amount as i64;
+                    entry.received_count += 1;
+                }
+                PaymentType::Send => {
+                    entry.sent_sats += payment.amount as i64;
+                    entry.sent_count += 1;
+                }
+            }
+        }
+
+        for (i, entry) in buckets.into_iter().enumerate() {
+            let mut dict = Dictionary::new();
+            dict.set("start", first + i as i64 * size);
+            dict.set("received_sats", entry.received_sats);
+            dict.set("sent_sats", entry.sent_sats);
+            dict.set("fees_sats", entry.fees_sats);
+            dict.set("received_count", entry.received_count);
+            dict.set("sent_count", entry.sent_count);
+            array.push(&dict);
+        }
+
+        array
+    }
+
+    /// Reconstruct the balance over time by replaying payments back from the current balance
+    ///
+    /// Returns `points` samples evenly spaced between `from_ts` and `to_ts`, each with
+    /// `timestamp` and `balance_sats`. See `balance_delta` for how pending and failed payments
+    /// are treated. Results are cached until the next wallet sync or outgoing payment.
+    #[func]
+    pub fn get_balance_history(&self, from_ts: i64, to_ts: i64, points: i64) -> Array<Dictionary> {
+        let mut array = Array::new();
+
+        if points < 2 || to_ts <= from_ts {
+            godot_error!("Invalid balance history request: need points >= 2 and to_ts > from_ts");
+            return array;
+        }
+
+        let key = (from_ts, to_ts, points);
+        let cached = match self.balance_history_cache.lock().unwrap().as_ref() {
+            Some((cached_key, samples)) if *cached_key == key => Some(samples.clone()),
+            _ => None,
+        };
+
+        let samples = match cached {
+            Some(samples) => samples,
+            None => match self.compute_balance_history(from_ts, to_ts, points) {
+                Ok(samples) => {
+                    *self.balance_history_cache.lock().unwrap() = Some((key, samples.clone()));
+                    samples
+                }
+                Err(e) => {
+                    godot_error!("{}", e);
+                    return array;
+                }
+            },
+        };
+
+        for (timestamp, balance) in samples {
+            let mut dict = Dictionary::new();
+            dict.set("timestamp", timestamp);
+            dict.set("balance_sats", balance);
+            array.push(&dict);
+        }
+
+        array
+    }
+
+    /// Completed payments between `from_ts` and `to_ts` (inclusive) as parallel packed arrays
+    ///
+    /// Returns `timestamps`, `amounts` (positive for receives, negative for sends) and `fees`,
+    /// oldest first, ready for a Line2D or graph without building a Dictionary per payment.
+    ///
+    /// # Arguments
+    /// * `type_filter` - "send", "receive", or "" for both
+    #[func]
+    pub fn get_payment_series(&self, from_ts: i64, to_ts: i64, type_filter: GString) -> Dictionary {
+        let result = self.get_payment_series_with(from_ts, to_ts, type_filter);
+        self.report_failure("get_payment_series", result)
+    }
+}
+
+impl BreezNode {
+    fn get_payment_with(&self, payment_id: &str) -> Dictionary {
+        let mut dict = Dictionary::new();
+        dict.set("found", false);
+        if payment_id.is_empty() {
+            return dict;
+        }
+        let sdk = match self.sdk_handle() {
+            Ok(sdk) => sdk,
+            Err(e) => {
+                dict.set("success", false);
+                dict.set("error", e);
+                dict.set("error_code", "NOT_CONNECTED");
+                return dict;
+            }
+        };
+        let runtime = Arc::clone(&self.runtime);
+        let request = GetPaymentRequest {
+            payment_id: payment_id.to_string(),
+        };
+        match runtime.block_on(sdk.get_payment(request)) {
+            Ok(response) => {
+                let mut dict = payment_dictionary(&response.payment, &self.zap_payers());
+                dict.set("found", true);
+                dict
+            }
+            Err(e) => {
+                let error = format!("Failed to get payment: {:?}", e);
+                if !error.to_lowercase().contains("not found") {
+                    godot_error!("{}", error);
+                    dict.set("success", false);
+                    dict.set("error", error);
+                }
+                dict
+            }
+        }
+    }
+
+    fn get_payment_series_with(&self, from_ts: i64, to_ts: i64, type_filter: GString) -> Dictionary {
+        let mut dict = Dictionary::new();
+
+        let type_filter = type_filter.to_string().trim().to_lowercase();
+        let (include_send, include_receive) = match type_filter.as_str() {
+            "" | "all" => (true, true),
+            "send" => (true, false),
+            "receive" => (false, true),
+            other => {
+                godot_error!("Invalid type filter: {} (expected send, receive or empty)", other);
+                return dict;
+            }
+        };
+
+        let mut payments = match self.fetch_all_payments() {
+            Ok(payments) => payments,
+            Err(e) => {
+                godot_error!("{}", e);
+                return dict;
+            }
+        };
+        payments.sort_by_key(|payment| payment.timestamp);
+
+        let mut timestamps = PackedInt64Array::new();
+        let mut amounts = PackedInt64Array::new();
+        let mut fees = PackedInt64Array::new();
+        for payment in &payments {
+            let timestamp = payment.timestamp as i64;
+            if timestamp < from_ts || timestamp > to_ts || payment.status != PaymentStatus::Completed {
+                continue;
+            }
+            let amount = match payment.payment_type {
+                PaymentType::Receive if include_receive => payment.amount as i64,
+                PaymentType::Send if include_send => -(payment.amount as i64),
+                _ => continue,
+            };
+            timestamps.push(timestamp);
+            amounts.push(amount);
+            fees.push(payment.fees as i64);
+        }
+
+        dict.set("timestamps", timestamps);
+        dict.set("amounts", amounts);
+        dict.set("fees", fees);
+        dict
+    }
+
+    /// Drop cached balance history, e.g. after a sync
+    pub(crate) fn invalidate_balance_history(&self) {
+        *self.balance_history_cache.lock().unwrap() = None;
+    }
+
+    fn compute_balance_history(&self, from_ts: i64, to_ts: i64, points: i64) -> Result<Vec<(i64, i64)>, String> {
+        let sdk_handle = self.sdk_handle();
+        let runtime = Arc::clone(&self.runtime);
+
+        let balance = runtime.block_on(async move {
+            let sdk = sdk_handle?;
+            match sdk.get_info(GetInfoRequest {
+                ensure_synced: Some(false),
+            }).await {
+                Ok(info) => Ok(info.balance_sats as i64),
+                Err(e) => Err(format!("Failed to get balance: {:?}", e)),
+            }
+        })?;
+
+        let mut payments = self.fetch_all_payments()?;
+        // Newest first, so walking forward moves back in time
+        payments.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+
+        let step = (to_ts - from_ts) as f64 / (points - 1) as f64;
+        let mut samples = Vec::with_capacity(points as usize);
+        let mut running = balance;
+        let mut next = payments.iter().peekable();
+
+        // Sample from the newest point back to the oldest, undoing payments made after each sample
+        for i in (0..points).rev() {
+            let timestamp = from_ts + (step * i as f64).round() as i64;
+            while let Some(payment) = next.peek() {
+                if (payment.timestamp as i64) <= timestamp {
+                    break;
+                }
+                running -= balance_delta(payment);
+                next.next();
+            }
+            samples.push((timestamp, running.max(0)));
+        }
+
+        samples.reverse();
+        Ok(samples)
+    }
+
+    /// Payments matching `filters`, newest first, skipping `offset` matches and returning at
+    /// most `limit` (None for all)
+    pub(crate) fn fetch_filtered_payments(
+        &self,
+        filters: &PaymentFilters,
+        offset: usize,
+        limit: Option<usize>,
+    ) -> Result<Vec<Payment>, String> {
+        let sdk_handle = self.sdk_handle();
+        let runtime = Arc::clone(&self.runtime);
+
+        runtime.block_on(async move {
+            let sdk = sdk_handle?;
+            let mut matches = Vec::new();
+            let mut skipped = 0;
+            let mut sdk_offset = 0;
+            loop {
+                let page = match sdk.list_payments(ListPaymentsRequest {
+                    offset: Some(sdk_offset),
+                    limit: Some(HISTORY_PAGE_SIZE),
+                }).await {
+                    Ok(response) => response.payments,
+                    Err(e) => return Err(format!("Failed to list payments: {:?}", e)),
+                };
+                sdk_offset += page.len() as u32;
+                let done = (page.len() as u32) < HISTORY_PAGE_SIZE || filters.past_range(&page);
+                for payment in page.into_iter().filter(|p| filters.matches(p)) {
+                    if skipped < offset {
+                        skipped += 1;
+                    } else if limit.is_none_or(|limit| matches.len() < limit) {
+                        matches.push(payment);
+                    }
+                }
+                if done || limit.is_some_and(|limit| matches.len() >= limit) {
+                    return Ok(matches);
+                }
+            }
+        })
+    }
+
+    /// Page through the whole payment history, newest first
+    pub(crate) fn fetch_all_payments(&self) -> Result<Vec<Payment>, String> {
+        let sdk_handle = self.sdk_handle();
+        let runtime = Arc::clone(&self.runtime);
+
+        runtime.block_on(async move {
+            let sdk = sdk_handle?;
+            let mut payments = Vec::new();
+            loop {
+                let page = match sdk.list_payments(ListPaymentsRequest {
+                    offset: Some(payments.len() as u32),
+                    limit: Some(HISTORY_PAGE_SIZE),
+                }).await {
+                    Ok(response) => response.payments,
+                    Err(e) => return Err(format!("Failed to list payments: {:?}", e)),
+                };
+                let done = (page.len() as u32) < HISTORY_PAGE_SIZE;
+                payments.extend(page);
+                if done {
+                    break;
+                }
+            }
+            Ok(payments)
+        })
+    }
+}
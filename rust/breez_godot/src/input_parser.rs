@@ -0,0 +1,113 @@
+use godot::prelude::*;
+use breez_sdk_spark::{InputType, LnurlPayRequestDetails};
+use std::sync::Arc;
+
+use crate::BreezNode;
+
+/// Fields of an LNURL-pay endpoint, with the sendable range in whole sats
+fn set_pay_request(dict: &mut Dictionary, pay_request: &LnurlPayRequestDetails) {
+    dict.set("min_sendable_sats", pay_request.min_sendable.div_ceil(1000) as i64);
+    dict.set("max_sendable_sats", (pay_request.max_sendable / 1000) as i64);
+    dict.set("comment_allowed", pay_request.comment_allowed as i64);
+    dict.set("domain", pay_request.domain.as_str());
+}
+
+/// Dictionary for a parsed input: `type` plus the fields of that type
+fn input_dictionary(input: &InputType) -> Dictionary {
+    let mut dict = Dictionary::new();
+    match input {
+        InputType::Bolt11Invoice(details) => {
+            dict.set("type", "bolt11");
+            dict.set("amount_sats", details.amount_msat.map(|msat| (msat / 1000) as i64).unwrap_or(0));
+            dict.set("description", details.description.clone().unwrap_or_default());
+            dict.set("payment_hash", details.payment_hash.as_str());
+            dict.set("expiry_secs", details.expiry as i64);
+            dict.set("expires_at", (details.timestamp + details.expiry) as i64);
+        }
+        InputType::BitcoinAddress(details) => {
+            dict.set("type", "bitcoin_address");
+            dict.set("address", details.address.as_str());
+        }
+        InputType::SparkAddress(details) => {
+            dict.set("type", "spark_address");
+            dict.set("address", details.address.as_str());
+        }
+        InputType::LightningAddress(details) => {
+            dict.set("type", "lnurl_pay");
+            dict.set("address", details.address.as_str());
+            set_pay_request(&mut dict, &details.pay_request);
+        }
+        InputType::LnurlPay(pay_request) => {
+            dict.set("type", "lnurl_pay");
+            set_pay_request(&mut dict, pay_request);
+        }
+        InputType::LnurlWithdraw(details) => {
+            dict.set("type", "lnurl_withdraw");
+            dict.set("min_withdrawable_sats", details.min_withdrawable.div_ceil(1000) as i64);
+            dict.set("max_withdrawable_sats", (details.max_withdrawable / 1000) as i64);
+            dict.set("description", details.default_description.as_str());
+        }
+        InputType::Bip21(details) => {
+            dict.set("type", "bip21");
+            dict.set("amount_sats", details.amount_sat.unwrap_or(0) as i64);
+            dict.set("label", details.label.clone().unwrap_or_default());
+            dict.set("message", details.message.clone().unwrap_or_default());
+            let methods: Array<Dictionary> = details.payment_methods.iter().map(input_dictionary).collect();
+            dict.set("payment_methods", methods);
+            // The address to fall back to when no other method is usable
+            if let Some(address) = details.payment_methods.iter().find_map(|method| match method {
+                InputType::BitcoinAddress(address) => Some(address.address.clone()),
+                _ => None,
+            }) {
+                dict.set("address", address);
+            }
+        }
+        InputType::Bolt12Offer(details) => {
+            dict.set("type", "bolt12");
+            dict.set("description", details.description.clone().unwrap_or_default());
+        }
+        _ => {
+            dict.set("type", "unknown");
+            dict.set("error", "Unsupported payment input");
+        }
+    }
+    dict
+}
+
+#[godot_api(secondary)]
+impl BreezNode {
+    /// Parse anything a player pastes or scans with the SDK's parser
+    ///
+    /// The Dictionary always has `type` ("bolt11", "bitcoin_address", "lnurl_pay",
+    /// "lnurl_withdraw", "spark_address", "bip21", "bolt12" or "unknown") and `input`.
+    /// BOLT11 adds `amount_sats`, `description`, `payment_hash`, `expiry_secs` and
+    /// `expires_at`; addresses add `address`; LNURL-pay (including lightning addresses)
+    /// adds `min_sendable_sats`, `max_sendable_sats`, `comment_allowed` and `domain`;
+    /// LNURL-withdraw adds `min_withdrawable_sats`, `max_withdrawable_sats` and
+    /// `description`; BIP21 adds `amount_sats`, `label`, `message` and `payment_methods`.
+    /// Unknown input gets `error` with the reason. LNURLs are resolved over the network,
+    /// so this needs a connected wallet; `classify_input` works offline.
+    #[func]
+    pub fn parse_input(&self, input: GString) -> Dictionary {
+        let input = input.to_string().trim().to_string();
+        let sdk_handle = self.sdk_handle();
+        let runtime = Arc::clone(&self.runtime);
+        let parsed = runtime.block_on(async {
+            sdk_handle?
+                .parse(&input)
+                .await
+                .map_err(|e| format!("Unrecognized input: {:?}", e))
+        });
+        let mut dict = match parsed {
+            Ok(parsed) => input_dictionary(&parsed),
+            Err(e) => {
+                let mut dict = Dictionary::new();
+                dict.set("type", "unknown");
+                dict.set("error", e);
+                dict
+            }
+        };
+        dict.set("input", input.as_str());
+        dict
+    }
+}
@@ -0,0 +1,360 @@
+use godot::prelude::*;
+use breez_sdk_spark::{PaymentDetails, PaymentType};
+use serde::{Deserialize, Serialize};
+
+use crate::bolt11::{parse_bolt11, payee_pubkey_hex, payment_hash_hex};
+use crate::capabilities::unsupported;
+use crate::limits::{check_amount, AmountMethod};
+use crate::storage::{load_json, now_secs, save_json};
+use crate::utils::{bip21_uri, btc_decimal};
+use crate::validation::{check_description, DescriptionError, MAX_DESCRIPTION_BYTES};
+use crate::BreezNode;
+
+const INVOICE_REGISTRY_FILE: &str = "invoices.json";
+
+/// Number of created invoices remembered by the registry
+const MAX_REGISTRY_ENTRIES: usize = 1_000;
+
+/// An invoice created by this wallet
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct InvoiceRecord {
+    pub(crate) invoice: String,
+    pub(crate) payment_hash: String,
+    pub(crate) payee_pubkey: String,
+    pub(crate) amount_sats: Option<u64>,
+    pub(crate) created_at: u64,
+}
+
+impl InvoiceRecord {
+    fn to_dictionary(&self) -> Dictionary {
+        let mut dict = Dictionary::new();
+        dict.set("invoice", self.invoice.as_str());
+        dict.set("payment_hash", self.payment_hash.as_str());
+        dict.set("payee_pubkey", self.payee_pubkey.as_str());
+        dict.set("amount_sats", self.amount_sats.unwrap_or(0) as i64);
+        dict.set("created_at", self.created_at as i64);
+        dict
+    }
+}
+
+/// Error Dictionary for a rejected invoice description
+pub(crate) fn description_error(error: &DescriptionError) -> Dictionary {
+    let mut dict = Dictionary::new();
+    dict.set("success", false);
+    dict.set("error", error.message());
+    dict.set("error_code", error.code());
+    dict.set("max_description_bytes", MAX_DESCRIPTION_BYTES as i64);
+    dict
+}
+
+#[godot_api(secondary)]
+impl BreezNode {
+    /// Create a Lightning invoice, reporting validation problems instead of an empty string
+    ///
+    /// Descriptions with control characters are rejected. Descriptions over the BOLT11
+    /// limit are rejected with DESCRIPTION_TOO_LONG, or cut on a character boundary when
+    /// `truncate` is set. Returns a Dictionary with `success`, `invoice`, `description`
+    /// and `description_truncated`.
+    #[func]
+    pub fn create_invoice_with_options(&self, amount_sats: i64, description: GString, truncate: bool) -> Dictionary {
+        if amount_sats != 0 {
+            if let Err(error) = check_amount(AmountMethod::Lightning, amount_sats) {
+                return self.report_failure("create_invoice_with_options", error);
+            }
+        }
+        let (description, truncated) = match check_description(&description.to_string(), truncate) {
+            Ok(checked) => checked,
+            Err(e) => return self.report_failure("create_invoice_with_options", description_error(&e)),
+        };
+        if truncated {
+            godot_warn!("Invoice description truncated to {} bytes", description.len());
+        }
+
+        let invoice = self.create_invoice(amount_sats, GString::from(&description));
+
+        let mut dict = Dictionary::new();
+        if invoice.is_empty() {
+            dict.set("success", false);
+            dict.set("error", "Failed to create invoice");
+        } else {
+            dict.set("success", true);
+            dict.set("invoice", invoice);
+        }
+        dict.set("description", description);
+        dict.set("description_truncated", truncated);
+        self.report_failure("create_invoice_with_options", dict)
+    }
+
+    /// Create a Lightning invoice, returning what is needed to track it
+    ///
+    /// Returns `success`, `payment_request` (the BOLT11 invoice), `payment_hash` (to match
+    /// it to the payment later), `expiry_secs`, `expiry_timestamp` (unix seconds),
+    /// `amount_sats` (0 for amountless) and `fee_sats`, the fee the SDK reports for
+    /// receiving it (0 when none). The SDK sets the expiry itself, so a nonzero
+    /// `expiry_secs` fails with UNSUPPORTED (capability "invoice_expiry"); check
+    /// `expiry_timestamp` and stop showing the invoice once a shorter in-game timer ends.
+    ///
+    /// # Arguments
+    /// * `amount_sats` - Amount in satoshis (0 for any amount)
+    /// * `description` - Invoice description
+    /// * `expiry_secs` - Seconds until the invoice expires, 0 for the SDK default
+    #[func]
+    pub fn create_invoice_detailed(&self, amount_sats: i64, description: GString, expiry_secs: i64) -> Dictionary {
+        let result = self.create_invoice_detailed_with(amount_sats, &description.to_string(), expiry_secs);
+        self.report_failure("create_invoice_detailed", result)
+    }
+
+    /// Create one BIP21 payment request payable on-chain or over Lightning, for a single
+    /// deposit QR code: `bitcoin:<address>?amount=<btc>&label=<description>&lightning=<invoice>`
+    ///
+    /// Fetches a fresh Bitcoin address and a BOLT11 invoice for the same amount. Returns
+    /// `success`, `uri`, `address`, `invoice`, `amount_sats` and `amount_btc`; show the
+    /// address and invoice on their own too for wallets that cannot read unified URIs.
+    /// An amount of 0 leaves the amount out and creates an amountless invoice.
+    ///
+    /// # Arguments
+    /// * `amount_sats` - Amount requested (0 for any amount)
+    /// * `description` - Invoice description, also used as the URI label
+    #[func]
+    pub fn create_unified_payment_request(&self, amount_sats: i64, description: GString) -> Dictionary {
+        let result = self.create_unified_payment_request_with(amount_sats, &description.to_string());
+        self.report_failure("create_unified_payment_request", result)
+    }
+
+    /// Check whether an invoice was issued by the connected wallet
+    ///
+    /// Matches the payment hash against invoices created through this plugin and the SDK's
+    /// receive history. `confidence` is "high" for a registry or history match, "low" when
+    /// only the payee node matches (Spark invoices share payee nodes), and "none" otherwise.
+    #[func]
+    pub fn is_own_invoice(&self, bolt11: GString) -> Dictionary {
+        let result = self.is_own_invoice_with(bolt11);
+        self.report_failure("is_own_invoice", result)
+    }
+
+    /// Decode an invoice for a payment confirmation screen, without network calls
+    ///
+    /// Returns the fields of `decode_invoice` plus `payable`. An invoice that decodes but
+    /// cannot be paid has `error_code` WRONG_NETWORK (its `network` differs from the
+    /// connected wallet's) or INVOICE_EXPIRED; one that does not decode has INVALID_BECH32
+    /// or INVALID_INVOICE.
+    #[func]
+    pub fn check_invoice(&self, bolt11: GString) -> Dictionary {
+        let result = self.check_invoice_with(bolt11);
+        self.report_failure("check_invoice", result)
+    }
+}
+
+impl BreezNode {
+    fn create_invoice_detailed_with(&self, amount_sats: i64, description: &str, expiry_secs: i64) -> Dictionary {
+        let mut dict = Dictionary::new();
+        if expiry_secs < 0 {
+            dict.set("success", false);
+            dict.set("error", format!("Invalid expiry: {} s", expiry_secs));
+            dict.set("error_code", "INVALID_INPUT");
+            return dict;
+        }
+        if expiry_secs > 0 {
+            if let Err(error) = self.require_capability("invoice_expiry") {
+                return error;
+            }
+            return unsupported("invoice_expiry", "the SDK's BOLT11 receive method has no expiry setting");
+        }
+        if amount_sats != 0 {
+            if let Err(error) = check_amount(AmountMethod::Lightning, amount_sats) {
+                return error;
+            }
+        }
+        let description = match check_description(description, false) {
+            Ok((description, _)) => description,
+            Err(e) => return description_error(&e),
+        };
+
+        let (payment_request, fee_sats) = match self.create_bolt11_with_fee(amount_sats, description) {
+            Ok(created) => created,
+            Err(e) => {
+                godot_error!("{}", e);
+                dict.set("success", false);
+                dict.set("error", e);
+                return dict;
+            }
+        };
+        let invoice = match parse_bolt11(&payment_request) {
+            Ok(invoice) => invoice,
+            Err(e) => {
+                dict.set("success", false);
+                dict.set("error", format!("The SDK returned an unreadable invoice: {}", e));
+                return dict;
+            }
+        };
+        let expiry_secs = invoice.expiry_time().as_secs() as i64;
+        dict.set("success", true);
+        dict.set("payment_request", payment_request.as_str());
+        dict.set("payment_hash", payment_hash_hex(&invoice));
+        dict.set("expiry_secs", expiry_secs);
+        dict.set("expiry_timestamp", invoice.duration_since_epoch().as_secs() as i64 + expiry_secs);
+        dict.set("amount_sats", invoice.amount_milli_satoshis().map_or(0, |msat| (msat / 1000) as i64));
+        dict.set("fee_sats", fee_sats as i64);
+        dict
+    }
+
+    fn create_unified_payment_request_with(&self, amount_sats: i64, description: &str) -> Dictionary {
+        if amount_sats != 0 {
+            if let Err(error) = check_amount(AmountMethod::Lightning, amount_sats) {
+                return error;
+            }
+        }
+        let description = match check_description(description, false) {
+            Ok((description, _)) => description,
+            Err(e) => return description_error(&e),
+        };
+
+        let mut dict = Dictionary::new();
+        let parts = self
+            .receive_bitcoin_address()
+            .and_then(|address| self.create_bolt11(amount_sats, description.clone()).map(|invoice| (address, invoice)));
+        let (address, invoice) = match parts {
+            Ok(parts) => parts,
+            Err(e) => {
+                godot_error!("{}", e);
+                dict.set("success", false);
+                dict.set("error", e);
+                return dict;
+            }
+        };
+
+        let amount_sats = amount_sats.max(0) as u64;
+        let uri = bip21_uri(&address, amount_sats, &description, "", Some(&invoice));
+        dict.set("success", true);
+        dict.set("uri", uri);
+        dict.set("address", address);
+        dict.set("invoice", invoice);
+        dict.set("amount_sats", amount_sats as i64);
+        dict.set("amount_btc", btc_decimal(amount_sats));
+        dict
+    }
+
+    fn is_own_invoice_with(&self, bolt11: GString) -> Dictionary {
+        let mut dict = Dictionary::new();
+
+        let invoice = match parse_bolt11(&bolt11.to_string()) {
+            Ok(invoice) => invoice,
+            Err(e) => {
+                dict.set("success", false);
+                dict.set("own", false);
+                dict.set("confidence", "none");
+                dict.set("error", e);
+                return dict;
+            }
+        };
+
+        let payment_hash = payment_hash_hex(&invoice);
+        let payee_pubkey = payee_pubkey_hex(&invoice);
+        dict.set("success", true);
+        dict.set("payment_hash", payment_hash.as_str());
+        dict.set("payee_pubkey", payee_pubkey.as_str());
+
+        let registry = self.load_invoice_registry();
+        if let Some(record) = registry.iter().find(|r| r.payment_hash == payment_hash) {
+            dict.set("own", true);
+            dict.set("confidence", "high");
+            dict.set("source", "registry");
+            dict.set("record", record.to_dictionary());
+            return dict;
+        }
+
+        match self.fetch_all_payments() {
+            Ok(payments) => {
+                let found = payments.into_iter().find(|p| {
+                    p.payment_type == PaymentType::Receive
+                        && matches!(
+                            &p.details,
+                            Some(PaymentDetails::Lightning { payment_hash: hash, .. }) if *hash == payment_hash
+                        )
+                });
+                if let Some(payment) = found {
+                    let mut record = Dictionary::new();
+                    record.set("payment_id", payment.id);
+                    record.set("amount", payment.amount as i64);
+                    record.set("timestamp", payment.timestamp as i64);
+                    record.set("status", payment.status.to_string());
+                    dict.set("own", true);
+                    dict.set("confidence", "high");
+                    dict.set("source", "sdk");
+                    dict.set("record", record);
+                    return dict;
+                }
+            }
+            Err(e) => godot_warn!("{}", e),
+        }
+
+        let same_payee = registry.iter().any(|r| r.payee_pubkey == payee_pubkey);
+        dict.set("own", false);
+        dict.set("confidence", if same_payee { "low" } else { "none" });
+        dict
+    }
+
+    fn check_invoice_with(&self, bolt11: GString) -> Dictionary {
+        let mut dict = BreezNode::decode_invoice(bolt11);
+        if !dict.get("success").is_some_and(|success| success.to::<bool>()) {
+            dict.set("payable", false);
+            return dict;
+        }
+
+        let network = dict.get("network").map(|network| network.to::<GString>().to_string());
+        let wallet_network = self.wallet_network.lock().unwrap().clone();
+        let problem = match wallet_network {
+            Some(wallet_network) if network.as_deref() != Some(wallet_network.as_str()) => Some((
+                format!("The invoice is for {}, the wallet is on {}", network.unwrap_or_default(), wallet_network),
+                "WRONG_NETWORK",
+            )),
+            _ if dict.get("is_expired").is_some_and(|expired| expired.to::<bool>()) => {
+                Some(("The invoice has expired".to_string(), "INVOICE_EXPIRED"))
+            }
+            _ => None,
+        };
+        dict.set("payable", problem.is_none());
+        if let Some((error, code)) = problem {
+            dict.set("error", error);
+            dict.set("error_code", code);
+        }
+        dict
+    }
+
+    fn load_invoice_registry(&self) -> Vec<InvoiceRecord> {
+        self.plugin_path(INVOICE_REGISTRY_FILE)
+            .and_then(|path| load_json(&path))
+            .unwrap_or_default()
+    }
+
+    /// Remember an invoice created by this wallet
+    pub(crate) fn register_invoice(&self, bolt11: &str) {
+        let Some(path) = self.plugin_path(INVOICE_REGISTRY_FILE) else {
+            return;
+        };
+        let invoice = match parse_bolt11(bolt11) {
+            Ok(invoice) => invoice,
+            Err(e) => {
+                godot_warn!("Not registering invoice: {}", e);
+                return;
+            }
+        };
+
+        let mut registry = self.load_invoice_registry();
+        registry.push(InvoiceRecord {
+            invoice: bolt11.to_string(),
+            payment_hash: payment_hash_hex(&invoice),
+            payee_pubkey: payee_pubkey_hex(&invoice),
+            amount_sats: invoice.amount_milli_satoshis().map(|msat| msat / 1000),
+            created_at: now_secs(),
+        });
+        if registry.len() > MAX_REGISTRY_ENTRIES {
+            let excess = registry.len() - MAX_REGISTRY_ENTRIES;
+            registry.drain(..excess);
+        }
+
+        if let Err(e) = save_json(&path, &registry) {
+            godot_warn!("Failed to persist invoice registry: {}", e);
+        }
+    }
+}
@@ -0,0 +1,229 @@
+use godot::prelude::*;
+use breez_sdk_spark::{ListPaymentsRequest, Payment, PaymentDetails, PaymentType};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use crate::storage::{load_json, now_secs, save_json};
+use crate::BreezNode;
+
+const JOURNAL_FILE: &str = "journal.json";
+
+/// Number of recent payments searched when resolving interrupted operations
+const RESOLVE_SCAN_LIMIT: u32 = 200;
+
+/// Allowed clock difference between the journal and the SDK's payment timestamps
+const RESOLVE_CLOCK_SLACK_SECS: u64 = 120;
+
+/// A mutating operation, written before it runs and removed once its outcome is known
+#[derive(Serialize, Deserialize, Clone)]
+pub(crate) struct JournalEntry {
+    /// Idempotency key of the operation
+    id: String,
+    /// "send", "sweep", "batch_payout", "claim" or "refund"
+    operation: String,
+    /// Invoice or address paid, or "txid:vout" for claims and refunds
+    destination: String,
+    amount_sats: u64,
+    started_at: u64,
+    /// Set when the entry was found at connect, i.e. the process running it went away
+    #[serde(default)]
+    interrupted: bool,
+}
+
+fn new_operation_id() -> String {
+    let mut bytes = [0u8; 16];
+    if getrandom::getrandom(&mut bytes).is_err() {
+        // Unique enough within one wallet's journal
+        return format!("{:x}-{:x}", now_secs(), std::process::id());
+    }
+    hex::encode(bytes)
+}
+
+/// Whether `payment` is the outcome of the interrupted `entry`
+fn matches_entry(entry: &JournalEntry, payment: &Payment) -> bool {
+    if entry.operation == "claim" {
+        let txid = entry.destination.split(':').next().unwrap_or_default();
+        return matches!(&payment.details, Some(PaymentDetails::Deposit { tx_id }) if tx_id == txid);
+    }
+    // A refund leaves no payment behind, so the game has to check it and acknowledge it
+    if entry.operation == "refund" {
+        return false;
+    }
+    if payment.payment_type != PaymentType::Send
+        || payment.timestamp + RESOLVE_CLOCK_SLACK_SECS < entry.started_at
+    {
+        return false;
+    }
+    match &payment.details {
+        Some(PaymentDetails::Lightning { invoice, .. }) => invoice.eq_ignore_ascii_case(&entry.destination),
+        // Nothing else identifies the destination, the amount and time have to do
+        _ => payment.amount == entry.amount_sats,
+    }
+}
+
+#[godot_api(secondary)]
+impl BreezNode {
+    /// Operations that were interrupted (e.g. the game was killed mid-send) and could not be
+    /// matched to a payment
+    ///
+    /// Each entry has `id`, `operation` ("send", "sweep", "batch_payout", "claim" or "refund"),
+    /// `destination`, `amount_sats` and `started_at`. Entries stay until
+    /// `acknowledge_operation` is called, or until they are matched by
+    /// `resolve_unresolved_operations`.
+    #[func]
+    pub fn get_unresolved_operations(&self) -> Array<Dictionary> {
+        let mut array = Array::new();
+        for entry in self.journal.lock().unwrap().iter().filter(|entry| entry.interrupted) {
+            let mut dict = Dictionary::new();
+            dict.set("id", entry.id.as_str());
+            dict.set("operation", entry.operation.as_str());
+            dict.set("destination", entry.destination.as_str());
+            dict.set("amount_sats", entry.amount_sats as i64);
+            dict.set("started_at", entry.started_at as i64);
+            array.push(&dict);
+        }
+        array
+    }
+
+    /// Drop an unresolved operation once the game has dealt with it
+    #[func]
+    pub fn acknowledge_operation(&self, id: GString) -> bool {
+        let id = id.to_string();
+        let removed = {
+            let mut journal = self.journal.lock().unwrap();
+            let before = journal.len();
+            journal.retain(|entry| !(entry.interrupted && entry.id == id));
+            journal.len() != before
+        };
+        if removed {
+            self.save_journal();
+        }
+        removed
+    }
+
+    /// Match unresolved operations against the SDK's payments, pruning the ones that
+    /// completed, and return how many were resolved
+    ///
+    /// Runs on connect and after each wallet sync while operations are unresolved.
+    #[func]
+    pub fn resolve_unresolved_operations(&self) -> i64 {
+        if !self.journal.lock().unwrap().iter().any(|entry| entry.interrupted) {
+            return 0;
+        }
+
+        let sdk_handle = self.sdk_handle();
+        let runtime = Arc::clone(&self.runtime);
+        let payments = runtime.block_on(async move {
+            let sdk = sdk_handle?;
+            sdk.list_payments(ListPaymentsRequest {
+                offset: None,
+                limit: Some(RESOLVE_SCAN_LIMIT),
+            })
+            .await
+            .map(|response| response.payments)
+            .map_err(|e| format!("Failed to list payments: {:?}", e))
+        });
+        let payments = match payments {
+            Ok(payments) => payments,
+            Err(e) => {
+                godot_warn!("Could not resolve interrupted operations: {}", e);
+                return 0;
+            }
+        };
+
+        let mut used: HashSet<&str> = HashSet::new();
+        let resolved = {
+            let mut journal = self.journal.lock().unwrap();
+            let before = journal.len();
+            journal.retain(|entry| {
+                if !entry.interrupted {
+                    return true;
+                }
+                let found = payments
+                    .iter()
+                    .find(|payment| !used.contains(payment.id.as_str()) && matches_entry(entry, payment));
+                match found {
+                    Some(payment) => {
+                        godot_print!("Interrupted {} resolved as payment {} ({})", entry.operation, payment.id, payment.status);
+                        used.insert(payment.id.as_str());
+                        false
+                    }
+                    None => true,
+                }
+            });
+            before - journal.len()
+        };
+        if resolved > 0 {
+            self.save_journal();
+        }
+        resolved as i64
+    }
+}
+
+impl BreezNode {
+    /// Load the journal of the connected wallet, marking what it holds as interrupted,
+    /// and try to resolve those entries
+    pub(crate) fn load_journal(&self) {
+        let mut entries = self
+            .plugin_path(JOURNAL_FILE)
+            .and_then(|path| load_json::<Vec<JournalEntry>>(&path))
+            .unwrap_or_default();
+        for entry in entries.iter_mut() {
+            entry.interrupted = true;
+        }
+        let interrupted = entries.len();
+        *self.journal.lock().unwrap() = entries;
+        if interrupted > 0 {
+            godot_warn!("{} operation(s) were interrupted, checking their outcome", interrupted);
+            self.save_journal();
+            self.resolve_unresolved_operations();
+        }
+    }
+
+    /// Forget the disconnected wallet's journal (the file keeps it for the next connect)
+    pub(crate) fn clear_journal(&self) {
+        self.journal.lock().unwrap().clear();
+    }
+
+    fn save_journal(&self) {
+        let Some(path) = self.plugin_path(JOURNAL_FILE) else {
+            return;
+        };
+        let journal = self.journal.lock().unwrap().clone();
+        if let Err(e) = save_json(&path, &journal) {
+            godot_warn!("Failed to persist operation journal: {}", e);
+        }
+    }
+
+    /// Record the intent to run an operation before it is executed, returning its id
+    pub(crate) fn journal_begin(&self, operation: &str, destination: &str, amount_sats: u64) -> String {
+        let id = new_operation_id();
+        self.journal.lock().unwrap().push(JournalEntry {
+            id: id.clone(),
+            operation: operation.to_string(),
+            destination: destination.to_string(),
+            amount_sats,
+            started_at: now_secs(),
+            interrupted: false,
+        });
+        self.save_journal();
+        id
+    }
+
+    /// Prune an operation whose outcome is now known
+    ///
+    /// Operations left pending (e.g. a Lightning payment that timed out) should not be
+    /// ended, so they are checked again at the next connect if the game goes away first.
+    pub(crate) fn journal_end(&self, id: &str) {
+        let removed = {
+            let mut journal = self.journal.lock().unwrap();
+            let before = journal.len();
+            journal.retain(|entry| entry.id != id);
+            journal.len() != before
+        };
+        if removed {
+            self.save_journal();
+        }
+    }
+}
@@ -1,13 +1,290 @@
 use godot::prelude::*;
 use breez_sdk_spark::{
-    connect, default_config, ConnectRequest, Network, Seed, BreezSdk,
+    connect, default_config, Config, ConnectRequest, Network, Seed, BreezSdk,
     GetInfoRequest, ReceivePaymentRequest, ReceivePaymentMethod,
-    PrepareSendPaymentRequest, SendPaymentRequest, SendPaymentOptions,
-    ListPaymentsRequest, SyncWalletRequest, ListUnclaimedDepositsRequest,
-    ClaimDepositRequest, Fee, PaymentDetails,
+    SyncWalletRequest,
+    ClaimDepositRequest, Payment,
 };
-use std::sync::{Arc, Mutex};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+use std::time::Duration;
 use tokio::runtime::Runtime;
+use tokio::task::JoinHandle;
+
+mod async_payments;
+mod auto_connect;
+mod auto_sync;
+mod batch;
+mod bolt11;
+mod bolt12;
+mod capabilities;
+mod checkout;
+mod claim_queue;
+mod config;
+mod connect_errors;
+mod contacts;
+mod deposits;
+mod error_hook;
+mod diagnostics;
+mod drain;
+mod events;
+mod explorer;
+mod fee_warnings;
+mod fees;
+mod fiat;
+mod format;
+mod history;
+mod input_parser;
+mod invoices;
+mod journal;
+mod lightning_address;
+mod limits;
+mod lnurl;
+mod log_files;
+mod logging;
+mod low_balance;
+mod maintenance;
+mod mnemonic;
+mod mnemonic_store;
+mod network_storage;
+mod payment_export;
+mod payment_stream;
+mod payment_waits;
+mod persistent;
+mod prepared_payments;
+mod price_alerts;
+mod profiles;
+mod qr;
+mod receipts;
+mod reconnect;
+mod refunds;
+mod requests;
+mod resolution;
+mod send;
+mod snapshots;
+mod storage;
+mod sweep;
+mod throttle;
+mod typed_payments;
+mod utils;
+mod validation;
+mod wallet_info;
+mod wallet_lock;
+mod webhook;
+mod zaps;
+
+use async_payments::AsyncPayment;
+use batch::BatchPayout;
+use checkout::CheckoutState;
+use claim_queue::QueuedClaim;
+use connect_errors::{check_mnemonic, check_storage_writable, ConnectError};
+use deposits::{deposit_error, unconfirmed_deposit_error, ClaimFee, DepositPolicy};
+use error_hook::ErrorRecord;
+use events::{EventQueue, NodeEvent};
+use explorer::ExplorerSettings;
+use fees::FeeWarningPolicy;
+use fiat::FiatState;
+use history::{payment_dictionary, BalanceHistoryCache};
+use journal::JournalEntry;
+use limits::{amount_to_sats, check_amount, AmountMethod};
+use low_balance::LowBalanceWatch;
+use network_storage::{check_network, network_storage_dir, parse_network, record_network};
+use payment_stream::PaymentStream;
+use payment_waits::PaymentWaits;
+use prepared_payments::PreparedPayment;
+use price_alerts::PriceAlerts;
+use reconnect::ConnectCredentials;
+use requests::PendingRequest;
+use resolution::PaymentResolution;
+use snapshots::SnapshotState;
+use storage::{claim_wallet_dir, release_wallet_dirs, resolve_storage_dir, wallet_dir_in_use, DEFAULT_STORAGE_DIR};
+use sweep::AutoSweep;
+use throttle::ThrottleState;
+use validation::{check_description, validate_outpoint};
+use wallet_lock::WalletLock;
+use webhook::WebhookState;
+
+pub use checkout::BreezProduct;
+pub use config::BreezConfig;
+pub use receipts::verify_signed_receipt;
+pub use typed_payments::BreezPayment;
+
+static NEXT_CONNECT_ATTEMPT: AtomicU64 = AtomicU64::new(1);
+
+/// Worker threads of the shared runtime; SDK calls mostly wait on the network, so a few
+/// threads serve every node, even on mobile
+const MAX_RUNTIME_WORKERS: usize = 4;
+
+/// Runtime shared by every BreezNode in the process
+static RUNTIME: OnceLock<Arc<Runtime>> = OnceLock::new();
+
+/// How long a disconnect waits for a payment in progress and the SDK's shutdown
+const DISCONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Lock the SDK slot, recovering it if a panic poisoned the lock
+///
+/// The slot only holds a handle that is replaced whole, so it is never left half-updated.
+pub(crate) fn lock_sdk<T>(sdk: &Mutex<Option<T>>) -> MutexGuard<'_, Option<T>> {
+    sdk.lock().unwrap_or_else(|poisoned| {
+        godot_warn!("⚠️ Recovering the SDK lock after a panic");
+        sdk.clear_poison();
+        poisoned.into_inner()
+    })
+}
+
+/// Clone the handle out of the SDK slot, releasing the lock before any SDK call
+fn clone_sdk<T: Clone>(sdk: &Mutex<Option<T>>) -> Result<T, String> {
+    lock_sdk(sdk).clone().ok_or_else(|| "SDK not initialized".to_string())
+}
+
+/// The process-wide runtime, created by the first node
+///
+/// Lives as long as the process, so freeing one node never stops work another node or a
+/// persistent connection still runs on it.
+pub(crate) fn shared_runtime() -> Arc<Runtime> {
+    let runtime = RUNTIME.get_or_init(|| {
+        let workers = std::thread::available_parallelism().map_or(1, |n| n.get()).min(MAX_RUNTIME_WORKERS);
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(workers)
+            .thread_name("breez-runtime")
+            .enable_all()
+            .build()
+            .expect("Failed to create tokio runtime");
+        Arc::new(runtime)
+    });
+    Arc::clone(runtime)
+}
+
+/// Connect the SDK for a prepared config
+pub(crate) async fn connect_request(credentials: &ConnectCredentials) -> Result<BreezSdk, String> {
+    let seed = Seed::Mnemonic {
+        mnemonic: credentials.mnemonic.clone(),
+        passphrase: credentials.passphrase.clone(),
+    };
+    connect(ConnectRequest {
+        config: credentials.config.clone(),
+        seed,
+        storage_dir: credentials.storage_dir.clone(),
+    })
+    .await
+    .map_err(|e| format!("Failed to connect: {:?}", e))
+}
+
+/// Shut the SDK down on the runtime once no operation is moving funds, so its storage is
+/// closed and its background tasks stop; fails with the error and its `error_code`
+pub(crate) async fn disconnect_request(
+    sdk: BreezSdk,
+    in_flight: Arc<tokio::sync::Mutex<()>>,
+) -> Result<(), (String, &'static str)> {
+    let shutdown = async {
+        let _in_flight = in_flight.lock().await;
+        sdk.disconnect().await
+    };
+    match tokio::time::timeout(DISCONNECT_TIMEOUT, shutdown).await {
+        Ok(Ok(())) => Ok(()),
+        Ok(Err(e)) => Err((format!("Failed to disconnect: {:?}", e), "SDK_ERROR")),
+        Err(_) => {
+            let message = format!("Timed out after {}s shutting down the SDK", DISCONNECT_TIMEOUT.as_secs());
+            Err((message, "TIMEOUT"))
+        }
+    }
+}
+
+/// Switches of a connect call; the defaults refuse anything that could harm a wallet
+#[derive(Clone, Copy, Default)]
+pub(crate) struct ConnectOptions {
+    /// Open a wallet directory recorded for another network anyway
+    pub(crate) override_network: bool,
+    /// Disconnect the connected wallet first instead of failing with ALREADY_CONNECTED
+    pub(crate) force: bool,
+}
+
+/// BIP39 passphrase argument of the connect methods; empty means none
+///
+/// Not trimmed: spaces are part of a passphrase.
+fn passphrase_option(passphrase: GString) -> Option<String> {
+    let passphrase = passphrase.to_string();
+    (!passphrase.is_empty()).then_some(passphrase)
+}
+
+/// Parse the options Dictionary of the `_opts` connect methods into the switches and the
+/// passphrase
+fn parse_connect_options(options: &Dictionary) -> Result<(ConnectOptions, Option<String>), ConnectError> {
+    let mut connect_options = ConnectOptions::default();
+    let mut passphrase = None;
+    for (key, value) in options.iter_shared() {
+        let key = key.to_string();
+        let valid = match key.as_str() {
+            "override_network" => value.try_to::<bool>().map(|on| connect_options.override_network = on).is_ok(),
+            "force" => value.try_to::<bool>().map(|on| connect_options.force = on).is_ok(),
+            "passphrase" => value.try_to::<GString>().map(|p| passphrase = passphrase_option(p)).is_ok(),
+            _ => false,
+        };
+        if !valid {
+            // The value is left out, as it may be the passphrase
+            let message = format!("Invalid connect option '{}' (valid: override_network, force, passphrase)", key);
+            return Err(ConnectError::new(message, "INVALID_INPUT"));
+        }
+    }
+    Ok((connect_options, passphrase))
+}
+
+/// Wallet directory for `network` under the storage_dir argument of a connect method
+///
+/// See `resolve_storage_dir`; creating and checking the directory is left to the connect.
+pub(crate) fn connect_storage_dir(storage_dir: &GString, network: &str) -> Result<String, ConnectError> {
+    let storage_dir = resolve_storage_dir(&storage_dir.to_string())
+        .map_err(|e| ConnectError::new(e, "STORAGE_NOT_WRITABLE"))?;
+    Ok(network_storage_dir(&storage_dir, network).to_string_lossy().to_string())
+}
+
+/// Create a BOLT11 invoice on the runtime (0 sats for an amountless invoice), with the fee
+/// the SDK reports for receiving it
+pub(crate) async fn receive_bolt11(
+    sdk_handle: Result<BreezSdk, String>,
+    amount_sats: i64,
+    description: String,
+) -> Result<(String, u64), String> {
+    let sdk = sdk_handle?;
+    let amount = if amount_sats > 0 {
+        Some(amount_sats as u64)
+    } else {
+        None
+    };
+
+    match sdk.receive_payment(ReceivePaymentRequest {
+        payment_method: ReceivePaymentMethod::Bolt11Invoice {
+            description,
+            amount_sats: amount,
+        },
+    }).await {
+        Ok(response) => Ok((response.payment_request, response.fee_sats)),
+        Err(e) => Err(format!("Failed to create invoice: {:?}", e)),
+    }
+}
+
+/// Claim a deposit on the runtime, after any other operation moving funds
+pub(crate) async fn claim_deposit_request(
+    sdk_handle: Result<BreezSdk, String>,
+    in_flight: Arc<tokio::sync::Mutex<()>>,
+    txid: String,
+    vout: u32,
+    fee: ClaimFee,
+) -> Result<Payment, String> {
+    let sdk = sdk_handle?;
+    let _in_flight = in_flight.lock().await;
+
+    match sdk.claim_deposit(ClaimDepositRequest {
+        txid,
+        vout,
+        max_fee: fee.to_sdk(),
+    }).await {
+        Ok(response) => Ok(response.payment),
+        Err(e) => Err(format!("Failed to claim deposit: {:?}", e)),
+    }
+}
 
 struct BreezExtension;
 
@@ -22,6 +299,75 @@ pub struct BreezNode {
     base: Base<Node>,
     sdk: Arc<Mutex<Option<BreezSdk>>>,
     runtime: Arc<Runtime>,
+    events: EventQueue,
+    auto_sweep: Arc<Mutex<Option<AutoSweep>>>,
+    sweep_in_flight: Arc<AtomicBool>,
+    low_balance: Arc<Mutex<Option<LowBalanceWatch>>>,
+    fee_policy: Arc<Mutex<FeeWarningPolicy>>,
+    high_fee_active: Arc<AtomicBool>,
+    /// Wallet directory of the connected wallet
+    wallet_dir: Arc<Mutex<Option<PathBuf>>>,
+    /// Network of the connected wallet
+    wallet_network: Mutex<Option<String>>,
+    last_sync: Mutex<Option<u64>>,
+    fiat: Arc<Mutex<FiatState>>,
+    balance_history_cache: Mutex<BalanceHistoryCache>,
+    throttle: Mutex<ThrottleState>,
+    webhook: Arc<Mutex<WebhookState>>,
+    http_proxy: Arc<Mutex<Option<String>>>,
+    checkout: Mutex<CheckoutState>,
+    profiles_dir: Mutex<String>,
+    price_alerts: Mutex<PriceAlerts>,
+    wallet_lock: Mutex<WalletLock>,
+    snapshots: Arc<Mutex<SnapshotState>>,
+    persistent: bool,
+    explorer: Mutex<ExplorerSettings>,
+    claim_queue: Mutex<Vec<QueuedClaim>>,
+    resolution: Mutex<PaymentResolution>,
+    /// Held by the operation that is currently moving funds
+    in_flight: Arc<tokio::sync::Mutex<()>>,
+    payment_stream: Arc<Mutex<PaymentStream>>,
+    batch_parallelism: Mutex<usize>,
+    deposit_policy: Mutex<DepositPolicy>,
+    error_handler: Mutex<Option<Callable>>,
+    /// Most recent failure, for `get_last_error`
+    last_error: Mutex<Option<ErrorRecord>>,
+    journal: Mutex<Vec<JournalEntry>>,
+    /// Attempt number of the `connect_sdk_async` in progress
+    pending_connect: Mutex<Option<u64>>,
+    async_payments: Mutex<HashMap<i64, AsyncPayment>>,
+    batch_payouts: Mutex<HashMap<i64, BatchPayout>>,
+    auto_sync: Mutex<Option<JoinHandle<()>>>,
+    requests: Mutex<HashMap<i64, PendingRequest>>,
+    /// Id of the SDK event listener registered while connected
+    event_listener: Mutex<Option<String>>,
+    payment_waits: Mutex<PaymentWaits>,
+    /// Quotes from `prepare_payment` by prepare_id
+    prepared_payments: Mutex<HashMap<String, PreparedPayment>>,
+    /// SDK config of the connected wallet, for `get_config`
+    sdk_config: Mutex<Option<Config>>,
+    /// What the connected wallet was opened with, for `reconnect`
+    connect_credentials: Mutex<Option<ConnectCredentials>>,
+    /// Outcome of the last health check, to emit `connection_lost` and `connection_restored`
+    /// on changes only
+    connection_reachable: Mutex<Option<bool>>,
+    /// Network the node connects to once `set_credentials` is called; `get_network` keeps
+    /// returning the network of the connected wallet
+    #[export(enum = (Mainnet, Regtest))]
+    #[var(get = get_connect_network, set = set_connect_network)]
+    network: i32,
+    /// Wallet data directory for `set_credentials` (`user://` paths are supported)
+    #[export]
+    storage_dir: GString,
+    /// Seconds between background syncs after each connect (0 to disable)
+    #[export]
+    auto_sync_interval: i64,
+    /// Connect in the background when `set_credentials` supplies the mnemonic and API key
+    #[export]
+    auto_connect_on_ready: bool,
+    /// Mnemonic and API key handed to `set_credentials` before `_ready`; not exported, so
+    /// they are never saved with the scene
+    pending_credentials: Mutex<Option<(String, String)>>,
 }
 
 #[godot_api]
@@ -31,124 +377,478 @@ impl INode for BreezNode {
         Self {
             base,
             sdk: Arc::new(Mutex::new(None)),
-            runtime: Arc::new(Runtime::new().expect("Failed to create tokio runtime")),
+            runtime: shared_runtime(),
+            events: EventQueue::new(),
+            auto_sweep: Arc::new(Mutex::new(None)),
+            sweep_in_flight: Arc::new(AtomicBool::new(false)),
+            low_balance: Arc::new(Mutex::new(None)),
+            fee_policy: Arc::new(Mutex::new(FeeWarningPolicy::default())),
+            high_fee_active: Arc::new(AtomicBool::new(false)),
+            wallet_dir: Arc::new(Mutex::new(None)),
+            wallet_network: Mutex::new(None),
+            last_sync: Mutex::new(None),
+            fiat: Arc::new(Mutex::new(FiatState::default())),
+            balance_history_cache: Mutex::new(None),
+            throttle: Mutex::new(ThrottleState::default()),
+            webhook: Arc::new(Mutex::new(WebhookState::default())),
+            http_proxy: Arc::new(Mutex::new(None)),
+            checkout: Mutex::new(CheckoutState::default()),
+            profiles_dir: Mutex::new(profiles::DEFAULT_PROFILES_DIR.to_string()),
+            price_alerts: Mutex::new(PriceAlerts::default()),
+            wallet_lock: Mutex::new(WalletLock::default()),
+            snapshots: Arc::new(Mutex::new(SnapshotState::default())),
+            persistent: false,
+            explorer: Mutex::new(ExplorerSettings::default()),
+            claim_queue: Mutex::new(Vec::new()),
+            resolution: Mutex::new(PaymentResolution::default()),
+            in_flight: Arc::new(tokio::sync::Mutex::new(())),
+            payment_stream: Arc::new(Mutex::new(PaymentStream::default())),
+            batch_parallelism: Mutex::new(batch::DEFAULT_BATCH_PARALLELISM),
+            deposit_policy: Mutex::new(DepositPolicy::default()),
+            error_handler: Mutex::new(None),
+            last_error: Mutex::new(None),
+            journal: Mutex::new(Vec::new()),
+            pending_connect: Mutex::new(None),
+            async_payments: Mutex::new(HashMap::new()),
+            batch_payouts: Mutex::new(HashMap::new()),
+            auto_sync: Mutex::new(None),
+            requests: Mutex::new(HashMap::new()),
+            event_listener: Mutex::new(None),
+            payment_waits: Mutex::new(PaymentWaits::default()),
+            prepared_payments: Mutex::new(HashMap::new()),
+            sdk_config: Mutex::new(None),
+            connect_credentials: Mutex::new(None),
+            connection_reachable: Mutex::new(None),
+            network: 0,
+            storage_dir: GString::from(DEFAULT_STORAGE_DIR),
+            auto_sync_interval: 0,
+            auto_connect_on_ready: false,
+            pending_credentials: Mutex::new(None),
         }
     }
+
+    fn ready(&mut self) {
+        self.start_auto_connect();
+    }
+
+    fn exit_tree(&mut self) {
+        if !self.persistent && self.is_sdk_connected() {
+            // Freeing the node would otherwise drop the SDK without closing its storage
+            self.disconnect_breez();
+            return;
+        }
+        self.stop_auto_sync();
+        self.unregister_event_listener();
+        self.detach_persistent();
+    }
+
+    fn process(&mut self, _delta: f64) {
+        self.check_auto_relock();
+        self.retry_queued_claims(None);
+        self.poll_tracked_payments();
+        self.poll_payment_waits();
+        self.dispatch_events();
+        self.dispatch_payment_chunk();
+    }
 }
 
 #[godot_api]
 impl BreezNode {
+    /// Emitted when a health check (`check_connection` or auto-sync) first fails to reach
+    /// the Breez service
+    #[signal]
+    fn connection_lost(error: GString);
+
+    /// Emitted when a health check reaches the Breez service again after `connection_lost`
+    #[signal]
+    fn connection_restored();
+
+    /// Emitted for each record forwarded after `init_logging`, already redacted
+    #[signal]
+    fn log_message(level: GString, target: GString, message: GString);
+
+    /// Emitted after auto-sweep moved funds out of the wallet
+    #[signal]
+    fn swept(amount: i64, payment_id: GString);
+
+    /// Emitted when the balance drops below the low-balance threshold
+    #[signal]
+    fn low_balance(current_sats: i64);
+
+    /// Emitted when the balance climbs back above the low-balance threshold
+    #[signal]
+    fn balance_recovered(current_sats: i64);
+
+    /// Emitted when the recommended on-chain fee rate exceeds the configured level
+    #[signal]
+    fn high_fee_environment(rate_sat_vb: i64);
+
+    /// Emitted by the price ticker with rates keyed by currency code
+    #[signal]
+    fn price_ticker_updated(rates: Dictionary);
+
+    /// Emitted when a price alert's threshold is crossed
+    #[signal]
+    fn price_alert_triggered(alert_id: i64, currency: GString, rate: f64);
+
+    /// Emitted when the throttle policy blocks an outgoing payment
+    #[signal]
+    fn throttle_tripped(reason: GString, retry_after_secs: i64);
+
+    /// Emitted when outgoing payments are locked or unlocked
+    #[signal]
+    fn wallet_lock_changed(locked: bool);
+
+    /// Emitted when `run_diagnostics_async` finishes, with the same report as `run_diagnostics`
+    #[signal]
+    fn diagnostics_completed(report: Dictionary);
+
+    /// Emitted when a payment whose outcome was unknown (`final: false`) completes or fails
+    ///
+    /// `status` is "completed", "failed", or "unknown" if no outcome was seen within a day.
+    #[signal]
+    fn payment_resolved(payment_id: GString, status: GString);
+
+    /// Emitted by `send_spark_batch` each time a payout finishes
+    #[signal]
+    fn batch_payout_progress(request_id: i64, done: i64, total: i64);
+
+    /// Emitted when a `send_spark_batch` request is done, with one result per payout in input order
+    #[signal]
+    fn batch_payout_finished(request_id: i64, results: Array<Dictionary>);
+
+    /// Emitted once per frame while `stream_payments` runs; `done` is set on the last chunk
+    #[signal]
+    fn payments_chunk(chunk: Array<Dictionary>, done: bool);
+
+    /// Emitted when an unclaimed deposit reaches `set_min_confirmations`
+    #[signal]
+    fn deposit_confirmed(txid: GString, vout: i64);
+
+    /// Emitted after a queued deposit claim was retried, with `success` and either
+    /// `amount_sats`, `fee_sats` and `payment_id` or `error`
+    #[signal]
+    fn deposit_claim_retried(txid: GString, vout: i64, result: Dictionary);
+
+    /// Emitted after each background sync started by `start_auto_sync`; `error` is empty
+    /// on success
+    #[signal]
+    fn wallet_synced(success: bool, error: GString);
+
+    /// Emitted when a request from `call_async` or an `_async` method is done, with
+    /// `method`, `success`, `error`/`error_code` on failure and the method's `result`
+    #[signal]
+    fn request_completed(request_id: i64, result: Dictionary);
+
+    /// Emitted when a `pay_invoice_async` request is done, with the same Dictionary
+    /// `pay_invoice` returns
+    #[signal]
+    fn payment_result(request_id: i64, result: Dictionary);
+
+    /// Emitted when a `connect_sdk_async` call has connected the wallet
+    #[signal]
+    fn connected();
+
+    /// Emitted when a `connect_sdk_async` call failed
+    #[signal]
+    fn connect_failed(error: GString);
+
+    /// Emitted when the SDK reports a received payment, with the same Dictionary as
+    /// `list_payments`
+    #[signal]
+    fn payment_received(payment: Dictionary);
+
+    /// Emitted when the SDK reports a completed outgoing payment, with the same Dictionary
+    /// as `list_payments`
+    #[signal]
+    fn payment_sent(payment: Dictionary);
+
+    /// Emitted when the SDK has claimed a deposit, with `txid`, `vout` and `amount_sats`
+    #[signal]
+    fn deposit_claimed(deposit: Dictionary);
+
+    /// Emitted when the SDK finished syncing the wallet
+    #[signal]
+    fn synced();
+
+    /// Emitted when the invoice of a `wait_for_payment_async` request has been paid, with
+    /// the payment as in `list_payments`
+    #[signal]
+    fn payment_confirmed(request_id: i64, payment: Dictionary);
+
+    /// Emitted when a `wait_for_payment_async` request ran out of time
+    #[signal]
+    fn payment_timeout(request_id: i64);
+
+    /// Emitted when an invoice from `create_zap_invoice` has been paid
+    #[signal]
+    fn zap_received(payment_id: GString, amount_sats: i64, zapper_pubkey: GString, comment: GString);
+
+    /// Emitted when a product invoice from `purchase_product` has been paid
+    #[signal]
+    fn purchase_completed(product: Gd<BreezProduct>, payment_id: GString);
+
+    /// Emitted on the main thread after any call or background task fails, with the
+    /// Dictionary of `get_last_error`
+    #[signal]
+    fn sdk_error(error: Dictionary);
+
+    /// `error_category` of a failure that needs a connected wallet
+    #[constant]
+    pub const ERROR_NOT_CONNECTED: i64 = 1;
+
+    /// `error_category` of a rejected argument, amount, destination or option
+    #[constant]
+    pub const ERROR_INVALID_INPUT: i64 = 2;
+
+    /// `error_category` of a balance too small for the request
+    #[constant]
+    pub const ERROR_INSUFFICIENT_FUNDS: i64 = 3;
+
+    /// `error_category` of a server or connection failure
+    #[constant]
+    pub const ERROR_NETWORK_ERROR: i64 = 4;
+
+    /// `error_category` of a payment the network did not complete
+    #[constant]
+    pub const ERROR_PAYMENT_FAILED: i64 = 5;
+
+    /// `error_category` of an operation that ran out of time; it may still complete
+    #[constant]
+    pub const ERROR_TIMEOUT: i64 = 6;
+
+    /// `error_category` of any other SDK failure, a storage failure or an unknown error code
+    #[constant]
+    pub const ERROR_SDK_ERROR: i64 = 7;
+
+    /// `error_category` of a feature this build or network does not support
+    #[constant]
+    pub const ERROR_UNSUPPORTED: i64 = 8;
+
+    /// `error_category` of a request refused by a wallet policy (lock, throttle, fee cap) or
+    /// the wallet's current state (already connected, API key refused, quote expired)
+    #[constant]
+    pub const ERROR_REJECTED: i64 = 9;
+
     /// Connect to Breez SDK
     /// 
     /// # Arguments
     /// * `mnemonic` - 12 or 24 word BIP39 mnemonic phrase
     /// * `api_key` - Your Breez API key
-    /// * `network` - "mainnet" or "regtest"
-    /// * `storage_dir` - Directory to store wallet data
+    /// * `network` - "mainnet" or "regtest", in any case; anything else fails with the valid
+    ///   networks in the error
+    /// * `storage_dir` - Directory to store wallet data; each network gets its own
+    ///   `<storage_dir>/<network>/` subdirectory. Empty uses `user://breez/`, which is
+    ///   writable on every platform; `user://` paths are translated and `res://` paths fail
+    ///   with STORAGE_NOT_WRITABLE, as they are read-only in exported games
+    ///
+    /// Use `connect_sdk_opts` for a wallet protected by a BIP39 passphrase.
+    #[func]
+    pub fn connect_sdk(&mut self, mnemonic: GString, api_key: GString, network: GString, storage_dir: GString) -> bool {
+        self.connect_sdk_opts(mnemonic, api_key, network, storage_dir, Dictionary::new())
+    }
+
+    /// Connect to Breez SDK with an options Dictionary
+    /// 
+    /// Recognized keys, all optional:
+    /// * `override_network` - Open a wallet directory recorded for another network anyway,
+    ///   e.g. after deliberately moving data (default false)
+    /// * `force` - Disconnect a connected wallet first instead of failing (default false)
+    /// * `passphrase` - BIP39 passphrase ("25th word") protecting the mnemonic, empty for
+    ///   none. A wrong passphrase opens a different, empty wallet rather than failing, so
+    ///   it must be exactly the one the wallet was created with. Never logged.
     #[func]
-    pub fn connect_sdk(
+    pub fn connect_sdk_opts(
         &mut self,
         mnemonic: GString,
         api_key: GString,
         network: GString,
         storage_dir: GString,
+        options: Dictionary,
     ) -> bool {
-        godot_print!("Connecting to Breez Spark SDK...");
-        
-        let sdk_arc = Arc::clone(&self.sdk);
-        let runtime = Arc::clone(&self.runtime);
-        
-        let mnemonic_str = mnemonic.to_string();
-        let api_key_str = api_key.to_string();
-        let network_str = network.to_string();
-        let storage_dir_str = storage_dir.to_string();
-        
-        let result: Result<(), Box<dyn std::error::Error>> = runtime.block_on(async move {
-            let seed = Seed::Mnemonic {
-                mnemonic: mnemonic_str,
-                passphrase: None,
-            };
-
-            let network_type = match network_str.as_str() {
-                "mainnet" => Network::Mainnet,
-                "regtest" => Network::Regtest,
-                _ => {
-                    godot_error!("Invalid network: {}", network_str);
-                    return Err("Invalid network".into());
-                }
-            };
-
-            let mut config = default_config(network_type);
-            config.api_key = Some(api_key_str);
-
-            match connect(ConnectRequest {
-                config,
-                seed,
-                storage_dir: storage_dir_str,
-            }).await {
-                Ok(sdk) => {
-                    *sdk_arc.lock().unwrap() = Some(sdk);
-                    godot_print!("✅ Connected to Breez Spark SDK");
-                    Ok(())
-                }
-                Err(e) => {
-                    godot_error!("Failed to connect: {:?}", e);
-                    Err(format!("Failed to connect: {:?}", e).into())
-                }
+        let (connect_options, passphrase) = match parse_connect_options(&options) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                godot_error!("{}", e.message);
+                return false;
+            }
+        };
+
+        let network = network.to_string().trim().to_lowercase();
+        let storage_dir = match connect_storage_dir(&storage_dir, &network) {
+            Ok(storage_dir) => storage_dir,
+            Err(e) => {
+                godot_error!("{}", e.message);
+                return false;
             }
+        };
+        self.connect_with(
+            mnemonic.to_string(),
+            passphrase,
+            api_key.to_string(),
+            network,
+            storage_dir,
+            connect_options,
+            |_config| {},
+        )
+        .is_ok()
+    }
+
+    /// Connect to Breez SDK, reporting why connecting failed
+    /// 
+    /// Takes the same arguments as `connect_sdk`; `connect_sdk_detailed_opts` also takes the
+    /// options of `connect_sdk_opts`. Returns a Dictionary with `success`, and on
+    /// failure `error`, `error_message` and `error_code`: INVALID_MNEMONIC (checked locally for
+    /// the word count, word list and checksum, without repeating the phrase), INVALID_NETWORK,
+    /// NETWORK_MISMATCH (the storage_dir holds a wallet of another network),
+    /// STORAGE_NOT_WRITABLE, STORAGE_IN_USE (another BreezNode has the storage_dir open),
+    /// ALREADY_CONNECTED (disconnect first, or use `connect_sdk_opts` with `force`),
+    /// AUTH_FAILED (bad API key), NETWORK_UNREACHABLE, CONNECT_IN_PROGRESS or SDK_ERROR.
+    #[func]
+    pub fn connect_sdk_detailed(
+        &mut self,
+        mnemonic: GString,
+        api_key: GString,
+        network: GString,
+        storage_dir: GString,
+    ) -> Dictionary {
+        self.connect_sdk_detailed_opts(mnemonic, api_key, network, storage_dir, Dictionary::new())
+    }
+
+    /// `connect_sdk_detailed` with the options of `connect_sdk_opts`; an unknown option
+    /// fails with INVALID_INPUT
+    #[func]
+    pub fn connect_sdk_detailed_opts(
+        &mut self,
+        mnemonic: GString,
+        api_key: GString,
+        network: GString,
+        storage_dir: GString,
+        options: Dictionary,
+    ) -> Dictionary {
+        let network = network.to_string().trim().to_lowercase();
+        let result = parse_connect_options(&options).and_then(|(connect_options, passphrase)| {
+            let storage_dir = connect_storage_dir(&storage_dir, &network)?;
+            self.connect_with(
+                mnemonic.to_string(),
+                passphrase,
+                api_key.to_string(),
+                network,
+                storage_dir,
+                connect_options,
+                |_config| {},
+            )
         });
+        let dict = match result {
+            Ok(()) => {
+                let mut dict = Dictionary::new();
+                dict.set("success", true);
+                dict
+            }
+            Err(e) => e.to_dictionary(),
+        };
+        self.report_failure("connect_sdk_detailed", dict)
+    }
 
-        result.is_ok()
+    /// Connect in the background so the frame is not blocked while the SDK starts
+    /// 
+    /// Takes the same arguments as `connect_sdk`. Emits `connected` once the wallet is ready
+    /// or `connect_failed` with the error. Returns false without starting when the arguments
+    /// are invalid or another `connect_sdk_async` is still in progress.
+    #[func]
+    pub fn connect_sdk_async(
+        &mut self,
+        mnemonic: GString,
+        api_key: GString,
+        network: GString,
+        storage_dir: GString,
+    ) -> bool {
+        self.connect_sdk_async_opts(mnemonic, api_key, network, storage_dir, Dictionary::new())
+    }
+
+    /// `connect_sdk_async` with the options of `connect_sdk_opts`
+    #[func]
+    pub fn connect_sdk_async_opts(
+        &mut self,
+        mnemonic: GString,
+        api_key: GString,
+        network: GString,
+        storage_dir: GString,
+        options: Dictionary,
+    ) -> bool {
+        let network = network.to_string().trim().to_lowercase();
+        let result = parse_connect_options(&options).and_then(|(connect_options, passphrase)| {
+            self.start_connect(
+                mnemonic.to_string(),
+                api_key.to_string(),
+                network,
+                &storage_dir,
+                passphrase,
+                connect_options,
+            )
+        });
+        match result {
+            Ok(()) => true,
+            Err(e) => {
+                godot_error!("{}", e.message);
+                false
+            }
+        }
     }
 
     /// Get wallet balance in satoshis
+    /// 
+    /// Deprecated: returns 0 on failure too, so an error looks like an empty wallet. Use
+    /// `get_balance_detailed`.
     #[func]
     pub fn get_balance(&self) -> i64 {
-        let sdk_arc = Arc::clone(&self.sdk);
+        let result = self.get_balance_detailed(true);
+        result.get("balance_sats").map(|balance| balance.to::<i64>()).unwrap_or(0)
+    }
+
+    /// Get wallet balance, telling failures apart from an empty wallet
+    /// 
+    /// Returns a Dictionary with `success` and `balance_sats`, or `error` and `error_code`
+    /// (NOT_CONNECTED without a wallet).
+    /// 
+    /// # Arguments
+    /// * `ensure_synced` - Sync with the network first; false reads the cached balance,
+    ///   which is instant but can be behind
+    #[func]
+    pub fn get_balance_detailed(&self, ensure_synced: bool) -> Dictionary {
+        let sdk_handle = self.sdk_handle();
         let runtime = Arc::clone(&self.runtime);
-        
-        let result: Result<i64, Box<dyn std::error::Error>> = runtime.block_on(async move {
-            let sdk_guard = sdk_arc.lock().unwrap();
-            if let Some(sdk) = sdk_guard.as_ref() {
-                match sdk.get_info(GetInfoRequest {
-                    ensure_synced: Some(true),
-                }).await {
-                    Ok(info) => Ok(info.balance_sats as i64),
-                    Err(e) => {
-                        godot_error!("Failed to get balance: {:?}", e);
-                        Ok(0)
-                    }
-                }
-            } else {
-                godot_warn!("SDK not initialized");
-                Ok(0)
-            }
-        });
+        let mut dict = Dictionary::new();
 
-        result.unwrap_or(0)
+        let Ok(sdk) = sdk_handle else {
+            godot_warn!("SDK not initialized");
+            dict.set("success", false);
+            dict.set("error", "SDK not initialized");
+            dict.set("error_code", "NOT_CONNECTED");
+            return self.report_failure("get_balance_detailed", dict);
+        };
+        let result = runtime.block_on(sdk.get_info(GetInfoRequest {
+            ensure_synced: Some(ensure_synced),
+        }));
+        match result {
+            Ok(info) => {
+                self.evaluate_low_balance(info.balance_sats);
+                dict.set("success", true);
+                dict.set("balance_sats", info.balance_sats as i64);
+            }
+            Err(e) => {
+                godot_error!("Failed to get balance: {:?}", e);
+                dict.set("success", false);
+                dict.set("error", format!("Failed to get balance: {:?}", e));
+            }
+        }
+        self.report_failure("get_balance_detailed", dict)
     }
 
     /// Get a Bitcoin address for receiving on-chain funds
     #[func]
     pub fn get_bitcoin_address(&self) -> GString {
-        let sdk_arc = Arc::clone(&self.sdk);
-        let runtime = Arc::clone(&self.runtime);
-        
-        let result = runtime.block_on(async move {
-            let sdk_guard = sdk_arc.lock().unwrap();
-            if let Some(sdk) = sdk_guard.as_ref() {
-                match sdk.receive_payment(ReceivePaymentRequest {
-                    payment_method: ReceivePaymentMethod::BitcoinAddress,
-                }).await {
-                    Ok(response) => Ok(response.payment_request),
-                    Err(e) => Err(format!("Failed to get address: {:?}", e)),
-                }
-            } else {
-                Err("SDK not initialized".to_string())
-            }
-        });
-
-        match result {
+        match self.receive_bitcoin_address() {
             Ok(address) => GString::from(&address),  // Use &String instead of String
             Err(e) => {
                 godot_error!("{}", e);
@@ -164,38 +864,20 @@ impl BreezNode {
     /// * `description` - Invoice description
     #[func]
     pub fn create_invoice(&self, amount_sats: i64, description: GString) -> GString {
-        let sdk_arc = Arc::clone(&self.sdk);
-        let runtime = Arc::clone(&self.runtime);
-        let desc = description.to_string();
-        
-        let result = runtime.block_on(async move {
-            let sdk_guard = sdk_arc.lock().unwrap();
-            if let Some(sdk) = sdk_guard.as_ref() {
-                let amount = if amount_sats > 0 {
-                    Some(amount_sats as u64)
-                } else {
-                    None
-                };
-
-                match sdk.receive_payment(ReceivePaymentRequest {
-                    payment_method: ReceivePaymentMethod::Bolt11Invoice {
-                        description: desc,
-                        amount_sats: amount,
-                    },
-                }).await {
-                    Ok(response) => Ok(response.payment_request),
-                    Err(e) => Err(format!("Failed to create invoice: {:?}", e)),
-                }
-            } else {
-                Err("SDK not initialized".to_string())
+        // 0 requests an amountless invoice
+        if amount_sats != 0 && check_amount(AmountMethod::Lightning, amount_sats).is_err() {
+            return GString::from("");
+        }
+        let desc = match check_description(&description.to_string(), false) {
+            Ok((desc, _)) => desc,
+            Err(e) => {
+                godot_error!("{}", e.message());
+                return GString::from("");
             }
-        });
+        };
 
-        match result {
-            Ok(invoice) => {
-                godot_print!("✅ Invoice created");
-                GString::from(&invoice)  // Use &String instead of String
-            }
+        match self.create_bolt11(amount_sats, desc) {
+            Ok(invoice) => GString::from(&invoice),  // Use &String instead of String
             Err(e) => {
                 godot_error!("{}", e);
                 GString::from("")
@@ -203,92 +885,50 @@ impl BreezNode {
         }
     }
 
+    /// Create a Lightning invoice with the amount in another unit
+    /// 
+    /// # Arguments
+    /// * `amount` - Decimal string, e.g. "0.0005" BTC or "250000" msat ("0" for amountless)
+    /// * `unit` - "sat", "btc" or "msat"; amounts that do not convert to whole sats exactly
+    ///   are rejected rather than rounded
+    /// * `description` - Invoice description
+    #[func]
+    pub fn create_invoice_with_unit(&self, amount: GString, unit: GString, description: GString) -> GString {
+        let unit = unit.to_string().trim().to_lowercase();
+        match amount_to_sats(&amount.to_string(), &unit, AmountMethod::Lightning) {
+            Ok(amount_sats) => self.create_invoice(amount_sats, description),
+            Err(_) => GString::from(""),
+        }
+    }
+
     /// Pay a Lightning invoice (two-step process: prepare then send)
     /// 
+    /// If the payment has not settled when `timeout_secs` elapses, the result has
+    /// `final: false` and the `payment_hash`. It is either still pending (`success: true`,
+    /// `status: "pending"`) or timed out (`success: false`, `error_code: "TIMEOUT"`); in both
+    /// cases it may still complete, so treat it as in flight until `payment_resolved` is
+    /// emitted or `resolve_payment` reports a final status.
+    ///
+    /// Amountless invoices fail with AMOUNT_REQUIRED. Use `pay_invoice_opts` to pay them
+    /// with `amount_sats`, or to cap the fee with `max_fee_sats`.
+    /// 
     /// # Arguments
     /// * `bolt11` - The BOLT11 invoice string
     /// * `timeout_secs` - Timeout in seconds for payment completion (0 for default)
     #[func]
     pub fn pay_invoice(&self, bolt11: GString, timeout_secs: i64) -> Dictionary {
-        let sdk_arc = Arc::clone(&self.sdk);
-        let runtime = Arc::clone(&self.runtime);
-        let invoice = bolt11.to_string();
-        
-        let result = runtime.block_on(async move {
-            let sdk_guard = sdk_arc.lock().unwrap();
-            if let Some(sdk) = sdk_guard.as_ref() {
-                // Step 1: Prepare the payment
-                let prepare_response = match sdk.prepare_send_payment(PrepareSendPaymentRequest {
-                    payment_request: invoice.clone(),
-                    amount_sats: None,  // Only needed for amountless invoices
-                }).await {
-                    Ok(response) => response,
-                    Err(e) => return Err(format!("Failed to prepare payment: {:?}", e)),
-                };
-
-                // Step 2: Send the payment with optional timeout
-                let options = if timeout_secs > 0 {
-                    Some(SendPaymentOptions::Bolt11Invoice {
-                        prefer_spark: false,  // Can be set to true to prefer Spark transfer
-                        completion_timeout_secs: Some(timeout_secs as u32),
-                    })
-        } else {
-                    None
-                };
-
-                match sdk.send_payment(SendPaymentRequest {
-                    prepare_response,
-                    options,
-                }).await {
-                    Ok(response) => Ok(response),
-                    Err(e) => Err(format!("Payment failed: {:?}", e)),
-                }
-            } else {
-                Err("SDK not initialized".to_string())
-            }
-        });
-
-        let mut dict = Dictionary::new();
-        
-        match result {
-            Ok(payment) => {
-                godot_print!("✅ Payment sent");
-                dict.set("success", true);
-                dict.set("payment_id", payment.payment.id);
-                dict.set("amount", payment.payment.amount as i64);
-            }
-            Err(e) => {
-                godot_error!("{}", e);
-                dict.set("success", false);
-                dict.set("error", e);
-            }
-        }
-        
-        dict
+        let mut options = Dictionary::new();
+        options.set("timeout_secs", timeout_secs);
+        self.pay_invoice_opts(bolt11, options)
     }
 
     /// Get Spark address for receiving payments
     #[func]
     pub fn get_spark_address(&self) -> GString {
-        let sdk_arc = Arc::clone(&self.sdk);
-        let runtime = Arc::clone(&self.runtime);
-        
-        let result = runtime.block_on(async move {
-            let sdk_guard = sdk_arc.lock().unwrap();
-            if let Some(sdk) = sdk_guard.as_ref() {
-                match sdk.receive_payment(ReceivePaymentRequest {
-                    payment_method: ReceivePaymentMethod::SparkAddress,
-                }).await {
-                    Ok(response) => Ok(response.payment_request),
-                    Err(e) => Err(format!("Failed to get Spark address: {:?}", e)),
-                }
-            } else {
-                Err("SDK not initialized".to_string())
-            }
-        });
+        let result = self.receive_address(ReceivePaymentMethod::SparkAddress, "Spark address");
 
         match result {
-            Ok(address) => GString::from(&address),  // Use &String instead of String
+            Ok((address, _)) => GString::from(&address),  // Use &String instead of String
             Err(e) => {
                 godot_error!("{}", e);
                 GString::from("")
@@ -296,43 +936,92 @@ impl BreezNode {
         }
     }
 
+    /// Get a Bitcoin address for receiving on-chain funds, with the fee of receiving on it
+    ///
+    /// Returns `success`, `address` and `fee_sats`, the fee the SDK reports for receiving
+    /// (0 when none), so the game can warn that small deposits are eaten by fees. Fails with
+    /// NOT_CONNECTED without a wallet.
+    #[func]
+    pub fn get_bitcoin_address_detailed(&self) -> Dictionary {
+        let result = self.receive_address_dictionary(ReceivePaymentMethod::BitcoinAddress, "address");
+        self.report_failure("get_bitcoin_address_detailed", result)
+    }
+
+    /// Get the Spark address with the fee of receiving on it; returns the fields of
+    /// `get_bitcoin_address_detailed`
+    #[func]
+    pub fn get_spark_address_detailed(&self) -> Dictionary {
+        let result = self.receive_address_dictionary(ReceivePaymentMethod::SparkAddress, "Spark address");
+        self.report_failure("get_spark_address_detailed", result)
+    }
+
     /// Check if SDK is connected
     #[func]
     pub fn is_sdk_connected(&self) -> bool {
-        self.sdk.lock().unwrap().is_some()
+        lock_sdk(&self.sdk).is_some()
     }
 
     /// Disconnect from SDK
+    ///
+    /// Waits for a payment in progress, then shuts the SDK down so its storage is closed
+    /// and its background tasks stop. Runs by itself when the node leaves the tree without
+    /// a persistent connection. Returns `success`, or `error` (error_code SDK_ERROR or
+    /// TIMEOUT) when the shutdown was not clean; the wallet is released either way.
+    /// Succeeds when not connected.
     #[func]
-    pub fn disconnect_breez(&mut self) {
-        let mut sdk_guard = self.sdk.lock().unwrap();
-        if sdk_guard.is_some() {
-            *sdk_guard = None;
-            godot_print!("Disconnected from Breez SDK");
+    pub fn disconnect_breez(&mut self) -> Dictionary {
+        // A background connect finishing later is discarded
+        *self.pending_connect.lock().unwrap() = None;
+        self.stop_auto_sync();
+        self.stop_price_ticker();
+        self.clear_server_webhook();
+        self.unregister_event_listener();
+        // Taken out first so nothing new starts on an SDK that is shutting down
+        let sdk = lock_sdk(&self.sdk).take();
+        release_wallet_dirs(self.wallet_owner());
+        let result = match sdk {
+            Some(sdk) => {
+                let runtime = Arc::clone(&self.runtime);
+                runtime.block_on(disconnect_request(sdk, Arc::clone(&self.in_flight)))
+            }
+            None => Ok(()),
+        };
+        self.release_persistent_session();
+        self.reset_wallet_state();
+
+        let mut dict = Dictionary::new();
+        match result {
+            Ok(()) => {
+                godot_print!("Disconnected from Breez SDK");
+                dict.set("success", true);
+            }
+            Err((e, code)) => {
+                godot_error!("{}", e);
+                dict.set("success", false);
+                dict.set("error", e);
+                dict.set("error_code", code);
+            }
         }
+        self.report_failure("disconnect_breez", dict)
     }
 
     /// Manually sync the wallet
     #[func]
     pub fn sync_wallet(&self) -> bool {
-        let sdk_arc = Arc::clone(&self.sdk);
+        let sdk_handle = self.sdk_handle();
         let runtime = Arc::clone(&self.runtime);
         
         let result = runtime.block_on(async move {
-            let sdk_guard = sdk_arc.lock().unwrap();
-            if let Some(sdk) = sdk_guard.as_ref() {
-                match sdk.sync_wallet(SyncWalletRequest {}).await {
-                    Ok(_) => Ok(()),
-                    Err(e) => Err(format!("Failed to sync: {:?}", e)),
-                }
-            } else {
-                Err("SDK not initialized".to_string())
+            let sdk = sdk_handle?;
+            match sdk.sync_wallet(SyncWalletRequest {}).await {
+                Ok(_) => Ok(()),
+                Err(e) => Err(format!("Failed to sync: {:?}", e)),
             }
         });
         
         match result {
             Ok(_) => {
-                godot_print!("✅ Wallet synced");
+                self.on_synced();
                 true
             }
             Err(e) => {
@@ -344,52 +1033,22 @@ impl BreezNode {
 
     /// List payment history
     /// 
+    /// Received zaps carry the payer's sanitized `comment` and `payer` (`pubkey`); other
+    /// payments have an empty comment and payer. `details` holds the method-specific fields
+    /// (invoice, preimage, txid, ...), each left out when unknown.
+    /// 
     /// # Arguments
     /// * `offset` - Number of payments to skip (for pagination)
     /// * `limit` - Maximum number of payments to return
     #[func]
     pub fn list_payments(&self, offset: i64, limit: i64) -> Array<Dictionary> {
-        let sdk_arc = Arc::clone(&self.sdk);
-        let runtime = Arc::clone(&self.runtime);
-        
-        let result = runtime.block_on(async move {
-            let sdk_guard = sdk_arc.lock().unwrap();
-            if let Some(sdk) = sdk_guard.as_ref() {
-                match sdk.list_payments(ListPaymentsRequest {
-                    offset: if offset > 0 { Some(offset as u32) } else { None },
-                    limit: if limit > 0 { Some(limit as u32) } else { None },
-                }).await {
-                    Ok(response) => Ok(response.payments),
-                    Err(e) => Err(format!("Failed to list payments: {:?}", e)),
-                }
-            } else {
-                Err("SDK not initialized".to_string())
-            }
-        });
-        
         let mut array = Array::new();
         
-        match result {
+        match self.fetch_payments(offset, limit) {
             Ok(payments) => {
-                for payment in payments {
-                    let mut dict = Dictionary::new();
-                    dict.set("id", payment.id);
-                    dict.set("amount", payment.amount as i64);
-                    dict.set("fees", payment.fees as i64);
-                    dict.set("timestamp", payment.timestamp as i64);
-                    dict.set("status", payment.status.to_string());
-                    dict.set("payment_type", payment.payment_type.to_string());
-                    dict.set("method", payment.method.to_string());
-                    
-                    let description = match &payment.details {
-                        Some(PaymentDetails::Lightning { description, .. }) => {
-                            description.clone().unwrap_or_default()
-                        }
-                        _ => String::new()
-                    };
-                    dict.set("description", description);
-                    
-                    array.push(&dict);
+                let payers = self.zap_payers();
+                for payment in &payments {
+                    array.push(&payment_dictionary(payment, &payers));
                 }
             }
             Err(e) => {
@@ -401,33 +1060,21 @@ impl BreezNode {
     }
 
     /// List unclaimed deposits
+    /// 
+    /// Each entry has `txid`, `vout`, `amount_sats`, `claimable`, `confirmations_remaining`
+    /// (-1 if the confirmations could not be looked up, see `set_min_confirmations`),
+    /// `confirmed`, `confirmations` (-1 if unknown), `address` when the esplora endpoint
+    /// reports it, and why the last claim failed: `claim_error` and `claim_error_code`
+    /// (FEE_EXCEEDED with `claim_fee_sats`, the fee the claim needed; MISSING_UTXO or
+    /// CLAIM_FAILED; both empty when no claim failed).
     #[func]
     pub fn list_unclaimed_deposits(&self) -> Array<Dictionary> {
-        let sdk_arc = Arc::clone(&self.sdk);
-        let runtime = Arc::clone(&self.runtime);
-        
-        let result = runtime.block_on(async move {
-            let sdk_guard = sdk_arc.lock().unwrap();
-            if let Some(sdk) = sdk_guard.as_ref() {
-                match sdk.list_unclaimed_deposits(ListUnclaimedDepositsRequest {}).await {
-                    Ok(response) => Ok(response.deposits),
-                    Err(e) => Err(format!("Failed to list deposits: {:?}", e)),
-            }
-        } else {
-                Err("SDK not initialized".to_string())
-            }
-        });
-        
         let mut array = Array::new();
         
-        match result {
+        match self.fetch_unclaimed_deposits() {
             Ok(deposits) => {
-                for deposit in deposits {
-                    let mut dict = Dictionary::new();
-                    dict.set("txid", deposit.txid);
-                    dict.set("vout", deposit.vout);
-                    dict.set("amount_sats", deposit.amount_sats as i64);
-                    array.push(&dict);
+                for deposit in &deposits {
+                    array.push(&self.unclaimed_deposit_dictionary(deposit));
                 }
             }
             Err(e) => {
@@ -440,53 +1087,444 @@ impl BreezNode {
 
     /// Claim a specific deposit
     /// 
+    /// A failed claim is queued and retried later, see `list_claim_queue`. Deposits below
+    /// `set_min_confirmations` fail with INSUFFICIENT_CONFIRMATIONS and are not queued. A txid
+    /// that is not 64 hex characters or a negative vout fails with INVALID_INPUT.
+    /// 
     /// # Arguments
     /// * `txid` - Transaction ID
     /// * `vout` - Output index
-    /// * `max_fee_sats` - Maximum fee to pay for claiming (0 for any fee)
+    /// * `max_fee_sats` - Maximum fee to pay for claiming (0 for any fee); see
+    ///   `claim_deposit_with_fee` for a fee rate
     #[func]
     pub fn claim_deposit(&self, txid: GString, vout: i64, max_fee_sats: i64) -> Dictionary {
-        let sdk_arc = Arc::clone(&self.sdk);
-        let runtime = Arc::clone(&self.runtime);
-        let txid_str = txid.to_string();
+        let result = self.claim_deposit_with(txid.to_string().trim(), vout, ClaimFee::from_max_fee_sats(max_fee_sats));
+        self.report_failure("claim_deposit", result)
+    }
+}
+
+impl BreezNode {
+    /// Claim a deposit after checking its outpoint and confirmations
+    pub(crate) fn claim_deposit_with(&self, txid: &str, vout: i64, fee: ClaimFee) -> Dictionary {
+        let vout = match validate_outpoint(txid, vout) {
+            Ok(vout) => vout,
+            Err(e) => return deposit_error(e, "INVALID_INPUT"),
+        };
         
-        let result = runtime.block_on(async move {
-            let sdk_guard = sdk_arc.lock().unwrap();
-            if let Some(sdk) = sdk_guard.as_ref() {
-                let max_fee = if max_fee_sats > 0 {
-                    Some(Fee::Fixed { amount: max_fee_sats as u64 })
-                } else {
-                    None
-                };
-                
-                match sdk.claim_deposit(ClaimDepositRequest {
-                    txid: txid_str,
-                    vout: vout as u32,
-                    max_fee,
-                }).await {
-                    Ok(response) => Ok(response),
-                    Err(e) => Err(format!("Failed to claim deposit: {:?}", e)),
-                }
-            } else {
-                Err("SDK not initialized".to_string())
-            }
-        });
+        match self.confirmations_remaining(txid) {
+            Ok(0) => {}
+            remaining => return unconfirmed_deposit_error(txid, remaining),
+        }
         
+        let result = self.claim_deposit_now(txid, vout, fee);
+        self.claim_result_dictionary(txid, vout, fee, result)
+    }
+
+    /// Clone the SDK handle, so the `sdk` lock is not held while awaiting SDK calls
+    ///
+    /// Reads use the clone directly and can run alongside a payment; operations that move
+    /// funds also hold `in_flight`, so at most one of them runs at a time.
+    pub(crate) fn sdk_handle(&self) -> Result<BreezSdk, String> {
+        clone_sdk(&self.sdk)
+    }
+
+    /// Fetch a Bitcoin address for receiving on-chain funds
+    pub(crate) fn receive_bitcoin_address(&self) -> Result<String, String> {
+        self.receive_address(ReceivePaymentMethod::BitcoinAddress, "address").map(|(address, _)| address)
+    }
+
+    /// Fetch an address to receive on, with the receive fee in sats
+    fn receive_address(&self, payment_method: ReceivePaymentMethod, what: &str) -> Result<(String, u64), String> {
+        let sdk_handle = self.sdk_handle();
+        let runtime = Arc::clone(&self.runtime);
+
+        runtime.block_on(async move {
+            let sdk = sdk_handle?;
+            match sdk.receive_payment(ReceivePaymentRequest { payment_method }).await {
+                Ok(response) => Ok((response.payment_request, response.fee_sats)),
+                Err(e) => Err(format!("Failed to get {}: {:?}", what, e)),
+            }
+        })
+    }
+
+    fn receive_address_dictionary(&self, payment_method: ReceivePaymentMethod, what: &str) -> Dictionary {
         let mut dict = Dictionary::new();
+        if !self.is_sdk_connected() {
+            dict.set("success", false);
+            dict.set("error", "SDK not initialized");
+            dict.set("error_code", "NOT_CONNECTED");
+            return dict;
+        }
+        match self.receive_address(payment_method, what) {
+            Ok((address, fee_sats)) => {
+                dict.set("success", true);
+                dict.set("address", address);
+                dict.set("fee_sats", fee_sats as i64);
+            }
+            Err(e) => {
+                godot_error!("{}", e);
+                dict.set("success", false);
+                dict.set("error", e);
+            }
+        }
+        dict
+    }
+
+    /// Create and register a BOLT11 invoice with an already validated description
+    pub(crate) fn create_bolt11(&self, amount_sats: i64, description: String) -> Result<String, String> {
+        self.create_bolt11_with_fee(amount_sats, description).map(|(invoice, _)| invoice)
+    }
+
+    /// `create_bolt11`, also returning the receive fee in sats
+    pub(crate) fn create_bolt11_with_fee(
+        &self,
+        amount_sats: i64,
+        description: String,
+    ) -> Result<(String, u64), String> {
+        let runtime = Arc::clone(&self.runtime);
+        let (invoice, fee_sats) = runtime.block_on(receive_bolt11(self.sdk_handle(), amount_sats, description))?;
+
+        godot_print!("✅ Invoice created");
+        self.register_invoice(&invoice);
+        Ok((invoice, fee_sats))
+    }
+
+    /// Claim a deposit and do the post-claim bookkeeping
+    pub(crate) fn claim_deposit_now(&self, txid: &str, vout: u32, fee: ClaimFee) -> Result<Payment, String> {
+        let runtime = Arc::clone(&self.runtime);
+        let journal_id = self.journal_begin("claim", &format!("{}:{}", txid, vout), 0);
+        
+        let result = runtime.block_on(claim_deposit_request(
+            self.sdk_handle(),
+            Arc::clone(&self.in_flight),
+            txid.to_string(),
+            vout,
+            fee,
+        ));
+        self.journal_end(&journal_id);
+        let result = result?;
         
+        self.on_deposit_claimed(txid, vout, &result);
+        Ok(result)
+    }
+
+    /// Bookkeeping after a deposit was claimed
+    pub(crate) fn on_deposit_claimed(&self, txid: &str, vout: u32, payment: &Payment) {
+        godot_print!("✅ Deposit claimed");
+        self.queue_webhook("deposit_claimed", payment);
+        self.record_claimed_deposit(txid, vout, payment);
+        self.dequeue_claim(txid, vout);
+        self.refresh_low_balance();
+        self.record_balance_snapshot();
+    }
+
+    /// Result Dictionary of `claim_deposit`, queueing the claim for retry when it failed
+    pub(crate) fn claim_result_dictionary(
+        &self,
+        txid: &str,
+        vout: u32,
+        fee: ClaimFee,
+        result: Result<Payment, String>,
+    ) -> Dictionary {
+        let mut dict = Dictionary::new();
         match result {
-            Ok(response) => {
-                godot_print!("✅ Deposit claimed");
+            Ok(payment) => {
                 dict.set("success", true);
-                dict.set("payment_id", response.payment.id);
+                let policy = self.fee_policy.lock().unwrap().clone();
+                let fee_warning = policy.warning(payment.fees, payment.amount);
+                dict.set("fee_sats", payment.fees as i64);
+                dict.set("fee_warning", fee_warning);
+                dict.set("max_fee", fee.to_dictionary());
+                dict.set("payment_id", payment.id);
             }
             Err(e) => {
                 godot_error!("{}", e);
+                if lock_sdk(&self.sdk).is_some() {
+                    self.enqueue_claim(txid, vout, fee, &e);
+                    dict.set("queued", true);
+                }
                 dict.set("success", false);
                 dict.set("error", e);
             }
         }
-        
         dict
     }
+
+    /// Connect with a customized SDK config
+    ///
+    /// `customize` runs on the default config for the network after the API key is applied.
+    pub(crate) fn connect_with(
+        &mut self,
+        mnemonic_str: String,
+        passphrase: Option<String>,
+        api_key_str: String,
+        network_str: String,
+        storage_dir_str: String,
+        options: ConnectOptions,
+        customize: impl FnOnce(&mut Config),
+    ) -> Result<(), ConnectError> {
+        godot_print!("Connecting to Breez Spark SDK...");
+        
+        let config = match self.prepare_connect(
+            &mnemonic_str,
+            &network_str,
+            &storage_dir_str,
+            options,
+            api_key_str,
+            customize,
+        ) {
+            Ok(config) => config,
+            Err(e) => {
+                godot_error!("{}", e.message);
+                return Err(e);
+            }
+        };
+        if self.is_sdk_connected() {
+            // Only reached with `force`; a second SDK would leak the first one's background tasks
+            godot_print!("Disconnecting the connected wallet first");
+            self.disconnect_breez();
+        }
+        
+        let credentials = ConnectCredentials {
+            config,
+            mnemonic: mnemonic_str,
+            passphrase,
+            storage_dir: storage_dir_str,
+            network: network_str,
+        };
+        let runtime = Arc::clone(&self.runtime);
+        let result = runtime.block_on(connect_request(&credentials));
+        self.finish_connect(result, credentials).map_err(ConnectError::from_sdk)
+    }
+
+    /// Check the arguments and storage_dir and build the SDK config for a connect
+    fn prepare_connect(
+        &self,
+        mnemonic_str: &str,
+        network_str: &str,
+        storage_dir_str: &str,
+        options: ConnectOptions,
+        api_key_str: String,
+        customize: impl FnOnce(&mut Config),
+    ) -> Result<Config, ConnectError> {
+        if self.pending_connect.lock().unwrap().is_some() {
+            return Err(ConnectError::new(
+                "A connect_sdk_async call is still in progress".to_string(),
+                "CONNECT_IN_PROGRESS",
+            ));
+        }
+        if self.is_sdk_connected() && !options.force {
+            return Err(ConnectError::new(
+                "A wallet is already connected; call disconnect_breez first or connect with force".to_string(),
+                "ALREADY_CONNECTED",
+            ));
+        }
+        if wallet_dir_in_use(Path::new(storage_dir_str), self.wallet_owner()) {
+            return Err(ConnectError::new(
+                format!("{} is already open in another BreezNode", storage_dir_str),
+                "STORAGE_IN_USE",
+            ));
+        }
+        let network_type = match parse_network(network_str) {
+            Ok("regtest") => Network::Regtest,
+            Ok(_) => Network::Mainnet,
+            Err(e) => return Err(ConnectError::new(e, "INVALID_NETWORK")),
+        };
+        check_mnemonic(mnemonic_str)?;
+        check_network(Path::new(storage_dir_str), network_str, options.override_network)
+            .map_err(|e| ConnectError::new(e, "NETWORK_MISMATCH"))?;
+        check_storage_writable(Path::new(storage_dir_str))?;
+
+        let mut config = default_config(network_type);
+        config.api_key = Some(api_key_str);
+        customize(&mut config);
+        Ok(config)
+    }
+
+    /// Start connecting on the runtime; `connected` or `connect_failed` follows unless the
+    /// arguments are rejected right away
+    pub(crate) fn start_connect(
+        &mut self,
+        mnemonic: String,
+        api_key: String,
+        network: String,
+        storage_dir: &GString,
+        passphrase: Option<String>,
+        options: ConnectOptions,
+    ) -> Result<(), ConnectError> {
+        godot_print!("Connecting to Breez Spark SDK in the background...");
+        let storage_dir = connect_storage_dir(storage_dir, &network)?;
+        let config = self.prepare_connect(&mnemonic, &network, &storage_dir, options, api_key, |_config| {})?;
+        let credentials = ConnectCredentials {
+            config,
+            mnemonic,
+            passphrase,
+            storage_dir,
+            network,
+        };
+
+        let attempt = NEXT_CONNECT_ATTEMPT.fetch_add(1, Ordering::Relaxed);
+        *self.pending_connect.lock().unwrap() = Some(attempt);
+        let events = self.events.sender();
+        self.runtime.spawn(async move {
+            let result = connect_request(&credentials).await;
+            let _ = events.send(NodeEvent::ConnectFinished { attempt, result, credentials });
+        });
+        Ok(())
+    }
+
+    /// Install a connected SDK and load the wallet's state, or log why connecting failed
+    pub(crate) fn finish_connect(
+        &mut self,
+        result: Result<BreezSdk, String>,
+        credentials: ConnectCredentials,
+    ) -> Result<(), String> {
+        match result {
+            Ok(sdk) => {
+                *lock_sdk(&self.sdk) = Some(sdk);
+                *self.sdk_config.lock().unwrap() = Some(credentials.config.clone());
+                // Connecting just reached the Breez service
+                *self.connection_reachable.lock().unwrap() = Some(true);
+                godot_print!("✅ Connected to Breez Spark SDK");
+                let storage_path = PathBuf::from(&credentials.storage_dir);
+                let network_name = credentials.network.clone();
+                *self.connect_credentials.lock().unwrap() = Some(credentials);
+                record_network(&storage_path, &network_name);
+                claim_wallet_dir(&storage_path, self.wallet_owner());
+                self.on_connected(storage_path, network_name);
+                self.publish_persistent_session();
+                if self.auto_sync_interval > 0 {
+                    self.start_auto_sync(self.auto_sync_interval);
+                }
+                Ok(())
+            }
+            Err(e) => {
+                godot_error!("{}", e);
+                Err(e)
+            }
+        }
+    }
+
+    /// Shut down an SDK whose connect finished after it was superseded, so its background
+    /// tasks stop and its database is closed before the directory is opened again
+    pub(crate) fn close_superseded_sdk(&mut self, sdk: BreezSdk) {
+        godot_print!("Closing a connection that finished after it was cancelled");
+        // No payment can run on this SDK, so it does not wait for the node's in-flight lock
+        let in_flight = Arc::new(tokio::sync::Mutex::new(()));
+        if let Err((e, _)) = self.runtime.block_on(disconnect_request(sdk, in_flight)) {
+            godot_warn!("{}", e);
+        }
+        // The directory is only claimed once connected; keep the claim of a live wallet
+        if !self.is_sdk_connected() {
+            release_wallet_dirs(self.wallet_owner());
+        }
+    }
+
+    /// Run the checks that follow a successful wallet sync
+    pub(crate) fn on_synced(&self) {
+        godot_print!("✅ Wallet synced");
+        *self.last_sync.lock().unwrap() = Some(storage::now_secs());
+        self.invalidate_balance_history();
+        self.queue_received_webhooks();
+        self.check_purchases();
+        self.check_zaps();
+        self.check_deposit_confirmations();
+        self.resolve_unresolved_operations();
+        self.auto_sweep_after_sync();
+        self.refresh_low_balance();
+        self.record_balance_snapshot();
+    }
+
+    /// Load the per-wallet state of a newly connected or attached wallet
+    pub(crate) fn on_connected(&self, storage_path: PathBuf, network: String) {
+        *self.wallet_dir.lock().unwrap() = Some(storage_path);
+        *self.wallet_network.lock().unwrap() = Some(network);
+        self.load_fiat_cache();
+        self.load_throttle_log();
+        self.load_wallet_lock();
+        self.load_claim_queue();
+        self.load_journal();
+        self.start_snapshot_recorder();
+        self.register_event_listener();
+    }
+
+    /// Forget everything tied to the disconnected wallet so nothing carries over to the next one
+    fn reset_wallet_state(&self) {
+        *self.wallet_dir.lock().unwrap() = None;
+        *self.wallet_network.lock().unwrap() = None;
+        *self.sdk_config.lock().unwrap() = None;
+        *self.connect_credentials.lock().unwrap() = None;
+        *self.connection_reachable.lock().unwrap() = None;
+        *self.last_sync.lock().unwrap() = None;
+        self.invalidate_balance_history();
+        self.clear_pending_purchases();
+        self.reset_low_balance_state();
+        self.load_throttle_log();
+        self.load_wallet_lock();
+        self.load_claim_queue();
+        self.stop_snapshot_recorder();
+        self.clear_tracked_payments();
+        self.stop_payment_stream();
+        self.reset_deposit_confirmations();
+        self.clear_journal();
+        self.clear_payment_waits();
+        self.clear_prepared_payments();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    /// Stands in for `BreezSdk`, whose clones share one connection
+    #[derive(Clone)]
+    struct MockSdk;
+
+    #[test]
+    fn balance_read_returns_while_a_slow_payment_is_in_flight() {
+        let runtime = Runtime::new().unwrap();
+        let sdk = Arc::new(Mutex::new(Some(MockSdk)));
+        let in_flight = Arc::new(tokio::sync::Mutex::new(()));
+
+        // A send holds in_flight and its SDK clone until the payment completes
+        let (started, payment_started) = std::sync::mpsc::channel();
+        let payment = runtime.spawn({
+            let sdk = Arc::clone(&sdk);
+            let in_flight = Arc::clone(&in_flight);
+            async move {
+                let _sdk = clone_sdk(&sdk).unwrap();
+                let _in_flight = in_flight.lock().await;
+                started.send(()).unwrap();
+                tokio::time::sleep(Duration::from_secs(60)).await;
+            }
+        });
+        payment_started.recv().unwrap();
+
+        let start = Instant::now();
+        let balance = runtime.block_on(async {
+            let _sdk = clone_sdk(&sdk)?;
+            tokio::task::yield_now().await;
+            Ok::<i64, String>(21_000)
+        });
+        assert_eq!(balance, Ok(21_000));
+        assert!(start.elapsed() < Duration::from_millis(100), "read took {:?}", start.elapsed());
+        assert!(in_flight.try_lock().is_err(), "the payment should still be in flight");
+        payment.abort();
+    }
+
+    #[test]
+    fn second_payment_waits_for_the_one_in_flight() {
+        let in_flight = tokio::sync::Mutex::new(());
+        let first = in_flight.try_lock().unwrap();
+        assert!(in_flight.try_lock().is_err());
+        drop(first);
+        assert!(in_flight.try_lock().is_ok());
+    }
+
+    #[test]
+    fn empty_sdk_slot_is_not_initialized() {
+        let sdk: Mutex<Option<MockSdk>> = Mutex::new(None);
+        assert_eq!(clone_sdk(&sdk).err().as_deref(), Some("SDK not initialized"));
+    }
 }
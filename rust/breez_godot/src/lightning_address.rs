@@ -0,0 +1,176 @@
+use godot::prelude::*;
+use breez_sdk_spark::{CheckLightningAddressRequest, LightningAddressInfo, RegisterLightningAddressRequest};
+use std::sync::Arc;
+
+use crate::BreezNode;
+
+/// Longest username accepted for a lightning address
+const MAX_USERNAME_LEN: usize = 64;
+
+fn address_error(error: String, code: &str) -> Dictionary {
+    godot_error!("{}", error);
+    let mut dict = Dictionary::new();
+    dict.set("success", false);
+    dict.set("error", error);
+    dict.set("error_code", code);
+    dict
+}
+
+/// Check a username: lowercase letters, digits, '-', '_' and '.', not starting or ending
+/// with a separator
+fn validate_username(username: &str) -> Result<(), String> {
+    if username.is_empty() || username.len() > MAX_USERNAME_LEN {
+        return Err(format!("Username must be 1 to {} characters", MAX_USERNAME_LEN));
+    }
+    if !username
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "-_.".contains(c))
+    {
+        return Err("Username may only contain lowercase letters, digits, '-', '_' and '.'".to_string());
+    }
+    if username.starts_with(['-', '_', '.']) || username.ends_with(['-', '_', '.']) {
+        return Err("Username cannot start or end with '-', '_' or '.'".to_string());
+    }
+    Ok(())
+}
+
+fn address_dictionary(info: &LightningAddressInfo) -> Dictionary {
+    let mut dict = Dictionary::new();
+    dict.set("lightning_address", info.lightning_address.as_str());
+    dict.set("lnurl", info.lnurl.as_str());
+    dict.set("username", info.username.as_str());
+    dict.set("description", info.description.as_str());
+    dict
+}
+
+/// Whether an SDK error means the username belongs to someone else
+fn is_taken_error(error: &str) -> bool {
+    let lower = error.to_lowercase();
+    lower.contains("taken") || lower.contains("already") || lower.contains("conflict") || lower.contains("409")
+}
+
+#[godot_api(secondary)]
+impl BreezNode {
+    /// Check whether a lightning address username is free to register
+    ///
+    /// Returns `success` and `available`. Fails with INVALID_USERNAME for a malformed
+    /// username, NOT_CONNECTED without a wallet and NETWORK_ERROR when the server cannot
+    /// be reached.
+    #[func]
+    pub fn check_lightning_address_available(&self, username: GString) -> Dictionary {
+        let result = self.check_username(username.to_string().trim()).map(|available| {
+            let mut dict = Dictionary::new();
+            dict.set("success", true);
+            dict.set("available", available);
+            dict
+        });
+        self.report_failure("check_lightning_address_available", result.unwrap_or_else(|error| error))
+    }
+
+    /// Register a lightning address ("username@domain") for this wallet
+    ///
+    /// Returns `success`, `lightning_address`, `lnurl`, `username` and `description`.
+    /// Fails with INVALID_USERNAME, USERNAME_TAKEN, NOT_CONNECTED or NETWORK_ERROR.
+    ///
+    /// # Arguments
+    /// * `username` - Name before the '@'
+    /// * `description` - Shown to payers (empty for the default)
+    #[func]
+    pub fn register_lightning_address(&self, username: GString, description: GString) -> Dictionary {
+        let result = self.register_lightning_address_with(username.to_string().trim(), description.to_string());
+        self.report_failure("register_lightning_address", result)
+    }
+
+    /// Get the lightning address registered for this wallet, as the server reports it
+    ///
+    /// Returns `lightning_address`, `lnurl`, `username` and `description`, or an empty
+    /// Dictionary when none is registered or it cannot be fetched.
+    #[func]
+    pub fn get_lightning_address(&self) -> Dictionary {
+        let Ok(sdk) = self.sdk_handle() else {
+            return Dictionary::new();
+        };
+        let runtime = Arc::clone(&self.runtime);
+        match runtime.block_on(sdk.get_lightning_address()) {
+            Ok(Some(info)) => address_dictionary(&info),
+            Ok(None) => Dictionary::new(),
+            Err(e) => {
+                godot_error!("Failed to get lightning address: {:?}", e);
+                Dictionary::new()
+            }
+        }
+    }
+
+    /// Release the lightning address registered for this wallet so its username can be
+    /// taken again
+    ///
+    /// Returns false if it could not be deleted.
+    #[func]
+    pub fn delete_lightning_address(&self) -> bool {
+        let Ok(sdk) = self.sdk_handle() else {
+            godot_error!("SDK not initialized");
+            return false;
+        };
+        let runtime = Arc::clone(&self.runtime);
+        match runtime.block_on(sdk.delete_lightning_address()) {
+            Ok(()) => {
+                godot_print!("✅ Lightning address deleted");
+                true
+            }
+            Err(e) => {
+                godot_error!("Failed to delete lightning address: {:?}", e);
+                false
+            }
+        }
+    }
+}
+
+impl BreezNode {
+    fn check_username(&self, username: &str) -> Result<bool, Dictionary> {
+        self.require_capability("lightning_address")?;
+        if let Err(e) = validate_username(username) {
+            return Err(address_error(e, "INVALID_USERNAME"));
+        }
+        let sdk = self
+            .sdk_handle()
+            .map_err(|e| address_error(e, "NOT_CONNECTED"))?;
+        let request = CheckLightningAddressRequest {
+            username: username.to_string(),
+        };
+        let runtime = Arc::clone(&self.runtime);
+        runtime
+            .block_on(sdk.check_lightning_address_available(request))
+            .map_err(|e| address_error(format!("Failed to check username: {:?}", e), "NETWORK_ERROR"))
+    }
+
+    fn register_lightning_address_with(&self, username: &str, description: String) -> Dictionary {
+        match self.check_username(username) {
+            Ok(true) => {}
+            Ok(false) => return address_error(format!("Username '{}' is already taken", username), "USERNAME_TAKEN"),
+            Err(error) => return error,
+        }
+        let sdk = match self.sdk_handle() {
+            Ok(sdk) => sdk,
+            Err(e) => return address_error(e, "NOT_CONNECTED"),
+        };
+        let description = description.trim().to_string();
+        let request = RegisterLightningAddressRequest {
+            username: username.to_string(),
+            description: (!description.is_empty()).then_some(description),
+        };
+        let runtime = Arc::clone(&self.runtime);
+        match runtime.block_on(sdk.register_lightning_address(request)) {
+            Ok(info) => {
+                godot_print!("✅ Registered {}", info.lightning_address);
+                let mut dict = address_dictionary(&info);
+                dict.set("success", true);
+                dict
+            }
+            Err(e) => {
+                let error = format!("Failed to register lightning address: {:?}", e);
+                let code = if is_taken_error(&error) { "USERNAME_TAKEN" } else { "NETWORK_ERROR" };
+                address_error(error, code)
+            }
+        }
+    }
+}
@@ -0,0 +1,221 @@
+use godot::prelude::*;
+
+use crate::format::parse_scaled;
+use crate::validation::DestinationKind;
+use crate::BreezNode;
+
+/// Smallest Lightning amount the plugin lets through; smaller payments rarely route
+const LIGHTNING_MIN_SATS: u64 = 10;
+
+/// Largest standard dust limit (P2PKH), so any on-chain output at or above it is relayed
+const ONCHAIN_DUST_LIMIT_SATS: u64 = 546;
+
+/// Spark transfers have no dust limit but must move at least one sat
+const SPARK_MIN_SATS: u64 = 1;
+
+/// 21 million BTC; nothing above this can be valid
+const MAX_SUPPLY_SATS: u64 = 21_000_000 * 100_000_000;
+
+/// Units an amount can be given in, see `amount_to_sats`
+pub(crate) const AMOUNT_UNITS: &[&str] = &["sat", "btc", "msat"];
+
+/// Payment method an amount is checked against
+#[derive(Clone, Copy)]
+pub(crate) enum AmountMethod {
+    Lightning,
+    Spark,
+    Onchain,
+}
+
+impl AmountMethod {
+    fn parse(method: &str) -> Option<Self> {
+        match method.trim().to_lowercase().as_str() {
+            "lightning" | "bolt11" => Some(AmountMethod::Lightning),
+            "spark" => Some(AmountMethod::Spark),
+            "onchain" | "bitcoin" => Some(AmountMethod::Onchain),
+            _ => None,
+        }
+    }
+
+    /// Method used to pay a validated destination
+    pub(crate) fn for_destination(kind: &DestinationKind) -> Self {
+        match kind {
+            DestinationKind::BitcoinAddress => AmountMethod::Onchain,
+            DestinationKind::SparkAddress => AmountMethod::Spark,
+            _ => AmountMethod::Lightning,
+        }
+    }
+
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            AmountMethod::Lightning => "lightning",
+            AmountMethod::Spark => "spark",
+            AmountMethod::Onchain => "onchain",
+        }
+    }
+
+    pub(crate) fn min_sats(&self) -> u64 {
+        match self {
+            AmountMethod::Lightning => LIGHTNING_MIN_SATS,
+            AmountMethod::Spark => SPARK_MIN_SATS,
+            AmountMethod::Onchain => ONCHAIN_DUST_LIMIT_SATS,
+        }
+    }
+
+    pub(crate) fn max_sats(&self) -> u64 {
+        MAX_SUPPLY_SATS
+    }
+}
+
+/// Check an amount against a method's limits, returning an error Dictionary when it is out of range
+///
+/// The Dictionary carries `error_code` ("AMOUNT_TOO_SMALL" or "AMOUNT_TOO_LARGE") and the
+/// applicable `min_sats` or `max_sats`.
+pub(crate) fn check_amount(method: AmountMethod, amount_sats: i64) -> Result<(), Dictionary> {
+    let min = method.min_sats();
+    let max = method.max_sats();
+
+    let (code, message, limit_key, limit) = if amount_sats < min as i64 {
+        (
+            "AMOUNT_TOO_SMALL",
+            format!("{} sats is below the {} minimum of {} sats", amount_sats, method.as_str(), min),
+            "min_sats",
+            min,
+        )
+    } else if amount_sats as u64 > max {
+        (
+            "AMOUNT_TOO_LARGE",
+            format!("{} sats is above the {} maximum of {} sats", amount_sats, method.as_str(), max),
+            "max_sats",
+            max,
+        )
+    } else {
+        return Ok(());
+    };
+
+    godot_warn!("{}", message);
+    let mut dict = Dictionary::new();
+    dict.set("success", false);
+    dict.set("error", message);
+    dict.set("error_code", code);
+    dict.set(limit_key, limit as i64);
+    Err(dict)
+}
+
+/// Convert a decimal string in "sat", "btc" or "msat" to sats for a method, without rounding
+///
+/// "btc" takes at most 8 decimals and "sat" none. "msat" is only accepted for Lightning and
+/// must still be a whole number of sats, since the SDK takes sat amounts. Anything that
+/// does not convert exactly fails with INVALID_AMOUNT (or UNSUPPORTED_UNIT).
+pub(crate) fn amount_to_sats(value: &str, unit: &str, method: AmountMethod) -> Result<i64, Dictionary> {
+    convert_amount(value, unit, method).map_err(|(message, code)| amount_error(message, code))
+}
+
+/// `amount_to_sats` with the error as a message and code
+fn convert_amount(value: &str, unit: &str, method: AmountMethod) -> Result<i64, (String, &'static str)> {
+    let value = value.trim();
+    let invalid = || (format!("Invalid {} amount: {}", unit, value), "INVALID_AMOUNT");
+
+    let sats = match unit {
+        "sat" => parse_scaled(value, 0),
+        "btc" => parse_scaled(value, 8),
+        "msat" => {
+            if !matches!(method, AmountMethod::Lightning) {
+                return Err((
+                    format!("msat amounts are not supported for {} payments", method.as_str()),
+                    "UNSUPPORTED_UNIT",
+                ));
+            }
+            match parse_scaled(value, 0) {
+                Some(msats) if msats % 1000 != 0 => {
+                    return Err((format!("{} msat is not a whole number of sats", value), "INVALID_AMOUNT"));
+                }
+                msats => msats.map(|msats| msats / 1000),
+            }
+        }
+        other => {
+            return Err((
+                format!("Unknown unit '{}' (valid: {})", other, AMOUNT_UNITS.join(", ")),
+                "INVALID_AMOUNT",
+            ));
+        }
+    };
+    sats.and_then(|sats| i64::try_from(sats).ok()).ok_or_else(invalid)
+}
+
+fn amount_error(message: String, code: &str) -> Dictionary {
+    godot_warn!("{}", message);
+    let mut dict = Dictionary::new();
+    dict.set("success", false);
+    dict.set("error", message);
+    dict.set("error_code", code);
+    dict
+}
+
+#[godot_api(secondary)]
+impl BreezNode {
+    /// Get the amount limits for a payment method, so input widgets can clamp amounts
+    ///
+    /// # Arguments
+    /// * `method` - "lightning", "spark" or "onchain"
+    ///
+    /// Returns a Dictionary with `success`, `method`, `min_sats` and `max_sats`.
+    #[func]
+    pub fn get_amount_limits(&self, method: GString) -> Dictionary {
+        let mut dict = Dictionary::new();
+        let Some(method) = AmountMethod::parse(&method.to_string()) else {
+            dict.set("success", false);
+            dict.set("error", format!("Unknown payment method: {}", method));
+            return dict;
+        };
+
+        dict.set("success", true);
+        dict.set("method", method.as_str());
+        dict.set("min_sats", method.min_sats() as i64);
+        dict.set("max_sats", method.max_sats() as i64);
+        dict
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn code(result: Result<i64, (String, &'static str)>) -> &'static str {
+        result.err().map(|(_, code)| code).unwrap_or("")
+    }
+
+    #[test]
+    fn units_convert_exactly() {
+        assert_eq!(convert_amount("1500", "sat", AmountMethod::Spark), Ok(1_500));
+        assert_eq!(convert_amount(" 0.00021 ", "btc", AmountMethod::Onchain), Ok(21_000));
+        assert_eq!(convert_amount("21000000", "btc", AmountMethod::Onchain), Ok(MAX_SUPPLY_SATS as i64));
+        assert_eq!(convert_amount("21000", "msat", AmountMethod::Lightning), Ok(21));
+    }
+
+    #[test]
+    fn amounts_that_would_round_are_rejected() {
+        assert_eq!(code(convert_amount("0.000000001", "btc", AmountMethod::Onchain)), "INVALID_AMOUNT");
+        assert_eq!(code(convert_amount("1.5", "sat", AmountMethod::Spark)), "INVALID_AMOUNT");
+        assert_eq!(code(convert_amount("21500", "msat", AmountMethod::Lightning)), "INVALID_AMOUNT");
+    }
+
+    #[test]
+    fn malformed_amounts_are_rejected() {
+        for value in ["", ".", "-5", "1e3", "1,000", "99999999999999999999"] {
+            assert_eq!(code(convert_amount(value, "sat", AmountMethod::Spark)), "INVALID_AMOUNT", "{:?}", value);
+        }
+    }
+
+    #[test]
+    fn msat_is_lightning_only() {
+        assert_eq!(code(convert_amount("21000", "msat", AmountMethod::Onchain)), "UNSUPPORTED_UNIT");
+        assert_eq!(code(convert_amount("21000", "msat", AmountMethod::Spark)), "UNSUPPORTED_UNIT");
+    }
+
+    #[test]
+    fn unknown_units_are_rejected() {
+        assert_eq!(code(convert_amount("1", "bits", AmountMethod::Lightning)), "INVALID_AMOUNT");
+        assert_eq!(code(convert_amount("1", "SAT", AmountMethod::Lightning)), "INVALID_AMOUNT");
+    }
+}
@@ -0,0 +1,239 @@
+use godot::prelude::*;
+use breez_sdk_spark::{
+    AesSuccessActionDataResult, BreezSdk, InputType, LnurlPayRequest, LnurlPayRequestDetails, PrepareLnurlPayRequest,
+    PrepareLnurlPayResponse, SendPaymentResponse, SuccessActionProcessed,
+};
+use std::sync::Arc;
+
+use crate::limits::{check_amount, AmountMethod};
+use crate::send::SendOptions;
+use crate::validation::{clean_comment, validate_destination, DestinationKind};
+use crate::BreezNode;
+
+fn lnurl_error(error: String, code: &str) -> Dictionary {
+    godot_error!("{}", error);
+    let mut dict = Dictionary::new();
+    dict.set("success", false);
+    dict.set("error", error);
+    if !code.is_empty() {
+        dict.set("error_code", code);
+    }
+    dict
+}
+
+/// Sendable range of an LNURL-pay endpoint in whole sats
+fn sendable_range(pay_request: &LnurlPayRequestDetails) -> (u64, u64) {
+    (pay_request.min_sendable.div_ceil(1000), pay_request.max_sendable / 1000)
+}
+
+/// Success action shown after an LNURL payment, as `type` ("message", "url" or "aes")
+/// with `message`, or `url` and `description`
+fn success_action_dictionary(action: &SuccessActionProcessed) -> Dictionary {
+    let mut dict = Dictionary::new();
+    match action {
+        SuccessActionProcessed::Message { data } => {
+            dict.set("type", "message");
+            dict.set("message", data.message.as_str());
+        }
+        SuccessActionProcessed::Url { data } => {
+            dict.set("type", "url");
+            dict.set("url", data.url.as_str());
+            dict.set("description", data.description.as_str());
+        }
+        SuccessActionProcessed::Aes { result } => {
+            dict.set("type", "aes");
+            match result {
+                AesSuccessActionDataResult::Decrypted { data } => {
+                    dict.set("description", data.description.as_str());
+                    dict.set("message", data.plaintext.as_str());
+                }
+                AesSuccessActionDataResult::ErrorStatus { reason } => {
+                    dict.set("error", reason.as_str());
+                }
+            }
+        }
+    }
+    dict
+}
+
+/// Fetch the LNURL-pay parameters behind a lightning address or LNURL
+async fn resolve_pay_request(sdk_handle: Result<BreezSdk, String>, input: String) -> Result<LnurlPayRequestDetails, String> {
+    let sdk = sdk_handle?;
+    match sdk.parse(&input).await {
+        Ok(InputType::LightningAddress(details)) => Ok(details.pay_request),
+        Ok(InputType::LnurlPay(pay_request)) => Ok(pay_request),
+        Ok(_) => Err(format!("{} is not an LNURL-pay endpoint", input)),
+        Err(e) => Err(format!("Failed to resolve {}: {:?}", input, e)),
+    }
+}
+
+/// Pay a prepared LNURL payment, after any other send has finished
+async fn send_lnurl(
+    sdk_handle: Result<BreezSdk, String>,
+    in_flight: Arc<tokio::sync::Mutex<()>>,
+    prepare_response: PrepareLnurlPayResponse,
+) -> Result<(SendPaymentResponse, Option<SuccessActionProcessed>), String> {
+    let sdk = sdk_handle?;
+    let _in_flight = in_flight.lock().await;
+    match sdk.lnurl_pay(LnurlPayRequest { prepare_response }).await {
+        Ok(response) => Ok((SendPaymentResponse { payment: response.payment }, response.success_action)),
+        Err(e) => Err(format!("Payment failed: {:?}", e)),
+    }
+}
+
+#[godot_api(secondary)]
+impl BreezNode {
+    /// Pay a lightning address ("name@domain")
+    ///
+    /// See `pay_lnurl` for the arguments and result.
+    #[func]
+    pub fn pay_lightning_address(&self, address: GString, amount_sats: i64, comment: GString) -> Dictionary {
+        let address = address.to_string().trim().to_string();
+        let result = match validate_destination(&address) {
+            Ok(DestinationKind::LightningAddress) => {
+                self.pay_lnurl_with(address, amount_sats, &comment.to_string(), &SendOptions::default())
+            }
+            Ok(_) => lnurl_error(format!("Not a lightning address: {}", address), "INVALID_DESTINATION"),
+            Err(e) => lnurl_error(e, "INVALID_DESTINATION"),
+        };
+        self.report_failure("pay_lightning_address", result)
+    }
+
+    /// Pay an LNURL-pay endpoint (an "lnurl1..." string, an "lnurlp://" URL or a lightning
+    /// address)
+    ///
+    /// The amount is checked against the endpoint's sendable range before anything is
+    /// paid, failing with AMOUNT_TOO_SMALL or AMOUNT_TOO_LARGE (and `min_sats` and
+    /// `max_sats`). The comment is normalized, with line breaks turned into spaces and other
+    /// control and bidi characters dropped; if it is still longer than the endpoint allows
+    /// the call fails with COMMENT_TOO_LONG.
+    /// Returns `success`, `payment_id`, `amount`, `fee_sats` and `domain`, plus
+    /// `success_action` when the endpoint sent one.
+    ///
+    /// # Arguments
+    /// * `lnurl` - LNURL or lightning address to pay
+    /// * `amount_sats` - Amount to send
+    /// * `comment` - Comment for the recipient (empty for none)
+    #[func]
+    pub fn pay_lnurl(&self, lnurl: GString, amount_sats: i64, comment: GString) -> Dictionary {
+        let lnurl = lnurl.to_string().trim().to_string();
+        let lnurl = lnurl.strip_prefix("lightning:").unwrap_or(&lnurl).to_string();
+        let result = match validate_destination(&lnurl) {
+            Ok(DestinationKind::Lnurl | DestinationKind::LightningAddress) => {
+                self.pay_lnurl_with(lnurl, amount_sats, &comment.to_string(), &SendOptions::default())
+            }
+            Ok(kind) => lnurl_error(format!("Cannot pay a {} as an LNURL", kind.as_str()), "INVALID_DESTINATION"),
+            Err(e) => lnurl_error(e, "INVALID_DESTINATION"),
+        };
+        self.report_failure("pay_lnurl", result)
+    }
+}
+
+impl BreezNode {
+    /// Resolve, check and pay an LNURL or lightning address
+    ///
+    /// The comment is cleaned like payer comments (see `clean_comment`) before its length is
+    /// checked against the endpoint's `commentAllowed`. Honours the `dry_run` and
+    /// `max_fee_sats` of `opts`; its amount is not used.
+    pub(crate) fn pay_lnurl_with(
+        &self,
+        lnurl: String,
+        amount_sats: i64,
+        comment: &str,
+        opts: &SendOptions,
+    ) -> Dictionary {
+        let capability = match validate_destination(&lnurl) {
+            Ok(DestinationKind::LightningAddress) => "lightning_address",
+            _ => "lnurl_pay",
+        };
+        if let Err(error) = self.require_capability(capability) {
+            return error;
+        }
+        if let Err(locked) = self.check_unlocked() {
+            return locked;
+        }
+        if let Err(error) = check_amount(AmountMethod::Lightning, amount_sats) {
+            return error;
+        }
+        let amount = amount_sats as u64;
+        let comment = clean_comment(comment);
+
+        let runtime = Arc::clone(&self.runtime);
+        let pay_request = match runtime.block_on(resolve_pay_request(self.sdk_handle(), lnurl.clone())) {
+            Ok(pay_request) => pay_request,
+            Err(e) => return lnurl_error(e, "LNURL_ERROR"),
+        };
+
+        let (min, max) = sendable_range(&pay_request);
+        if amount < min || amount > max {
+            let (code, message) = if amount < min {
+                ("AMOUNT_TOO_SMALL", format!("{} sats is below the {} sats minimum of {}", amount, min, pay_request.domain))
+            } else {
+                ("AMOUNT_TOO_LARGE", format!("{} sats is above the {} sats maximum of {}", amount, max, pay_request.domain))
+            };
+            let mut error = lnurl_error(message, code);
+            error.set("min_sats", min as i64);
+            error.set("max_sats", max as i64);
+            return error;
+        }
+        if comment.chars().count() > pay_request.comment_allowed as usize {
+            let mut error = lnurl_error(
+                format!("{} accepts comments of at most {} characters", pay_request.domain, pay_request.comment_allowed),
+                "COMMENT_TOO_LONG",
+            );
+            error.set("max_comment_length", pay_request.comment_allowed as i64);
+            return error;
+        }
+
+        let domain = pay_request.domain.clone();
+        let sdk_handle = self.sdk_handle();
+        let prepared = runtime.block_on(async move {
+            sdk_handle?
+                .prepare_lnurl_pay(PrepareLnurlPayRequest {
+                    amount_sats: amount,
+                    pay_request,
+                    comment: (!comment.is_empty()).then_some(comment),
+                    validate_success_action_url: None,
+                })
+                .await
+                .map_err(|e| format!("Failed to prepare payment: {:?}", e))
+        });
+        let prepare_response = match prepared {
+            Ok(prepare_response) => prepare_response,
+            Err(e) => return lnurl_error(e, "LNURL_ERROR"),
+        };
+        let fee = prepare_response.fee_sats;
+        if opts.max_fee_sats > 0 && fee > opts.max_fee_sats {
+            let message = format!("Fee {} sats exceeds the {} sats limit", fee, opts.max_fee_sats);
+            let mut error = lnurl_error(message, "FEE_TOO_HIGH");
+            error.set("fee_sats", fee as i64);
+            error.set("quoted_fee", fee as i64);
+            return error;
+        }
+        let cost = prepare_response.amount_sats + fee;
+        if let Err(error) = self.check_throttle(cost, opts.dry_run) {
+            return error;
+        }
+
+        let mut dict = Dictionary::new();
+        dict.set("domain", domain.as_str());
+        dict.set("fee_sats", fee as i64);
+        if opts.dry_run {
+            dict.set("success", true);
+            dict.set("dry_run", true);
+            dict.set("amount", prepare_response.amount_sats as i64);
+            return dict;
+        }
+        let journal_id = self.journal_begin("send", &lnurl, prepare_response.amount_sats);
+        let result = runtime.block_on(send_lnurl(self.sdk_handle(), Arc::clone(&self.in_flight), prepare_response));
+        let (result, success_action) = match result {
+            Ok((response, success_action)) => (Ok(response), success_action),
+            Err(e) => (Err(e), None),
+        };
+        let mut dict = self.finish_send(dict, &journal_id, cost, result);
+        if let Some(action) = &success_action {
+            dict.set("success_action", success_action_dictionary(action));
+        }
+        dict
+    }
+}
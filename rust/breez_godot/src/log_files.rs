@@ -0,0 +1,192 @@
+use godot::prelude::*;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Mutex, MutexGuard};
+use std::thread::JoinHandle;
+use std::time::SystemTime;
+
+use crate::logging::{install_sdk_logger, LogLevel};
+use crate::BreezNode;
+
+/// Subdirectory of the wallet directory holding the log files
+const LOG_DIR: &str = "logs";
+
+const LOG_FILE: &str = "breez.log";
+
+/// Target of the plugin's own records in the log files
+pub(crate) const PLUGIN_TARGET: &str = "breez_godot";
+
+/// Least severe level written to the files unless `init_logging` asks for more
+pub(crate) const FILE_LOG_LEVEL: LogLevel = LogLevel::Info;
+
+/// Log file writer running on its own thread, so logging never waits on the disk
+struct FileLogger {
+    dir: PathBuf,
+    max_files: usize,
+    /// Dropping it ends the writer thread once the queued lines are written
+    sender: Sender<String>,
+    thread: JoinHandle<()>,
+}
+
+static FILE_LOGGER: Mutex<Option<FileLogger>> = Mutex::new(None);
+
+/// `breez.log` for index 0, then `breez.log.1`, `breez.log.2`, ... from newest to oldest
+fn log_path(dir: &Path, index: usize) -> PathBuf {
+    match index {
+        0 => dir.join(LOG_FILE),
+        _ => dir.join(format!("{}.{}", LOG_FILE, index)),
+    }
+}
+
+fn open_log(dir: &Path) -> std::io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(log_path(dir, 0))
+}
+
+/// Shift every file one index older, dropping the oldest so at most `max_files` remain
+fn rotate(dir: &Path, max_files: usize) {
+    let _ = fs::remove_file(log_path(dir, max_files - 1));
+    for index in (0..max_files - 1).rev() {
+        let _ = fs::rename(log_path(dir, index), log_path(dir, index + 1));
+    }
+}
+
+/// Write queued lines until the sender is dropped, flushing whenever the queue runs dry
+fn write_lines(receiver: Receiver<String>, file: File, dir: PathBuf, max_bytes: u64, max_files: usize) {
+    let mut size = file.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+    let mut writer = BufWriter::new(file);
+    while let Ok(first) = receiver.recv() {
+        for line in std::iter::once(first).chain(receiver.try_iter()) {
+            if size > 0 && size + line.len() as u64 > max_bytes {
+                let _ = writer.flush();
+                rotate(&dir, max_files);
+                // On failure keep appending to the old handle rather than losing lines
+                if let Ok(file) = open_log(&dir) {
+                    writer = BufWriter::new(file);
+                    size = 0;
+                }
+            }
+            if writer.write_all(line.as_bytes()).is_ok() {
+                size += line.len() as u64;
+            }
+        }
+        let _ = writer.flush();
+    }
+    let _ = writer.flush();
+}
+
+fn lock_file_logger() -> MutexGuard<'static, Option<FileLogger>> {
+    FILE_LOGGER.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Whether `level` goes to the log files, given the `init_logging` filter
+pub(crate) fn file_logging_wants(level: LogLevel, console_filter: u8) -> bool {
+    level as u8 <= console_filter.max(FILE_LOG_LEVEL as u8) && lock_file_logger().is_some()
+}
+
+/// Queue an already redacted record for the log files, if file logging is on
+pub(crate) fn write_file_log(level: LogLevel, target: &str, message: &str) {
+    if let Some(logger) = lock_file_logger().as_ref() {
+        let timestamp = httpdate::fmt_http_date(SystemTime::now());
+        let line = format!("{} {:5} {}: {}\n", timestamp, level.as_str().to_uppercase(), target, message);
+        let _ = logger.sender.send(line);
+    }
+}
+
+/// Stop the writer, waiting until every queued line is on disk and the file is closed
+fn stop_file_logger() {
+    let logger = lock_file_logger().take();
+    if let Some(FileLogger { sender, thread, .. }) = logger {
+        drop(sender);
+        let _ = thread.join();
+    }
+}
+
+#[godot_api(secondary)]
+impl BreezNode {
+    /// Write SDK and plugin log lines to `logs/breez.log` in the connected wallet's directory
+    ///
+    /// Records at `info` or more severe are written, or down to the `init_logging` level when
+    /// that is more verbose, redacted like `log_message`. Once the file would exceed
+    /// `max_file_kb` it is renamed to `breez.log.1` (older files shift up) and at most
+    /// `max_files` files are kept. Lines are written on a background thread. Calling it
+    /// again restarts logging with the new limits. Returns false when not connected, for
+    /// non-positive limits or when the log file cannot be opened.
+    #[func]
+    pub fn enable_file_logging(&mut self, max_file_kb: i64, max_files: i32) -> bool {
+        if max_file_kb <= 0 || max_files <= 0 {
+            godot_error!("max_file_kb and max_files must be positive");
+            return false;
+        }
+        let Some(wallet_dir) = self.wallet_dir.lock().unwrap().clone() else {
+            godot_error!("SDK not initialized");
+            return false;
+        };
+        if let Err(e) = install_sdk_logger() {
+            godot_error!("{}", e);
+            return false;
+        }
+
+        stop_file_logger();
+        let dir = wallet_dir.join(LOG_DIR);
+        let path = log_path(&dir, 0);
+        let file = match fs::create_dir_all(&dir).and_then(|_| open_log(&dir)) {
+            Ok(file) => file,
+            Err(e) => {
+                godot_error!("Failed to open {}: {}", path.display(), e);
+                return false;
+            }
+        };
+        let max_bytes = max_file_kb as u64 * 1024;
+        let max_files = max_files as usize;
+        let (sender, receiver) = channel();
+        let thread_dir = dir.clone();
+        let spawned = std::thread::Builder::new()
+            .name("breez-log-writer".to_string())
+            .spawn(move || write_lines(receiver, file, thread_dir, max_bytes, max_files));
+        let thread = match spawned {
+            Ok(thread) => thread,
+            Err(e) => {
+                godot_error!("Failed to start the log writer: {}", e);
+                return false;
+            }
+        };
+        *lock_file_logger() = Some(FileLogger { dir, max_files, sender, thread });
+        godot_print!("Logging to {}", path.display());
+        true
+    }
+
+    /// Stop `enable_file_logging`, flushing pending lines and closing the file
+    #[func]
+    pub fn disable_file_logging(&mut self) {
+        stop_file_logger();
+    }
+
+    /// Absolute paths of the existing log files, newest first, e.g. to attach to a support
+    /// email
+    ///
+    /// Lists the files of the running file logger, or of the connected wallet when file
+    /// logging is off. Lines still queued may not be in the files yet.
+    #[func]
+    pub fn get_log_file_paths(&self) -> Array<GString> {
+        let running = lock_file_logger().as_ref().map(|logger| (logger.dir.clone(), logger.max_files));
+        let (dir, max_files) = match running {
+            Some((dir, max_files)) => (Some(dir), Some(max_files)),
+            None => (self.wallet_dir.lock().unwrap().as_ref().map(|dir| dir.join(LOG_DIR)), None),
+        };
+        let mut array = Array::new();
+        let Some(dir) = dir else {
+            return array;
+        };
+        // Paths of the wallet directory are already absolute
+        for index in 0.. {
+            let path = log_path(&dir, index);
+            if max_files.is_some_and(|max_files| index >= max_files) || !path.exists() {
+                break;
+            }
+            array.push(&GString::from(path.to_string_lossy().as_ref()));
+        }
+        array
+    }
+}
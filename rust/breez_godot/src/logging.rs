@@ -0,0 +1,234 @@
+use godot::prelude::*;
+use bip39::Language;
+use breez_sdk_spark::{LogEntry, Logger};
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::{Mutex, OnceLock};
+
+use crate::events::NodeEvent;
+use crate::log_files::{file_logging_wants, write_file_log};
+use crate::BreezNode;
+
+/// Target reported for records of the Breez SDK
+const SDK_TARGET: &str = "breez_sdk_spark";
+
+/// Shortest run of BIP39 words treated as a mnemonic
+const MIN_MNEMONIC_WORDS: usize = 12;
+
+/// Shortest hex string treated as seed material; 64-byte seeds print as 128 hex digits,
+/// while payment hashes and txids (64 digits) stay readable
+const MIN_SEED_HEX_LEN: usize = 128;
+
+/// Shortest debug-printed byte list treated as key material
+const MIN_SECRET_BYTES: usize = 16;
+
+/// Most verbose level forwarded, as a `LogLevel` discriminant (0 while logging is off)
+static LOG_LEVEL: AtomicU8 = AtomicU8::new(0);
+
+/// Outcome of installing the SDK logger, which can only happen once per process
+static SDK_LOGGER: OnceLock<Result<(), String>> = OnceLock::new();
+
+/// Event queues of the nodes that called `init_logging`, by instance id
+static LOG_SINKS: Mutex<Vec<(i64, Sender<NodeEvent>)>> = Mutex::new(Vec::new());
+
+#[derive(Clone, Copy, PartialEq, PartialOrd)]
+pub(crate) enum LogLevel {
+    Error = 1,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    fn parse(level: &str) -> Option<Self> {
+        match level.trim().to_lowercase().as_str() {
+            "error" => Some(LogLevel::Error),
+            "warn" | "warning" => Some(LogLevel::Warn),
+            "info" => Some(LogLevel::Info),
+            "debug" => Some(LogLevel::Debug),
+            "trace" => Some(LogLevel::Trace),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            LogLevel::Error => "error",
+            LogLevel::Warn => "warn",
+            LogLevel::Info => "info",
+            LogLevel::Debug => "debug",
+            LogLevel::Trace => "trace",
+        }
+    }
+}
+
+/// Receives the SDK's records on whatever thread produced them
+struct ForwardingLogger;
+
+impl Logger for ForwardingLogger {
+    fn log(&self, entry: LogEntry) {
+        if let Some(level) = LogLevel::parse(&entry.level) {
+            forward_log(level, SDK_TARGET, &entry.line);
+        }
+    }
+}
+
+fn is_mnemonic_word(token: &str) -> bool {
+    let word = token.trim_matches(|c: char| !c.is_ascii_alphabetic());
+    !word.is_empty() && Language::English.find_word(&word.to_lowercase()).is_some()
+}
+
+/// Replace runs of BIP39 words long enough to be a mnemonic
+fn redact_mnemonics(text: &str) -> String {
+    let tokens: Vec<&str> = text.split(' ').collect();
+    let mut result = Vec::with_capacity(tokens.len());
+    let mut i = 0;
+    while i < tokens.len() {
+        let run = tokens[i..].iter().take_while(|token| is_mnemonic_word(token)).count();
+        if run >= MIN_MNEMONIC_WORDS {
+            result.push("[redacted mnemonic]");
+            i += run;
+        } else {
+            result.push(tokens[i]);
+            i += 1;
+        }
+    }
+    result.join(" ")
+}
+
+/// Replace long hex strings and debug-printed byte lists
+fn redact_seed_bytes(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if c.is_ascii_hexdigit() {
+            let len = rest.find(|c: char| !c.is_ascii_hexdigit()).unwrap_or(rest.len());
+            if len >= MIN_SEED_HEX_LEN {
+                result.push_str("[redacted]");
+            } else {
+                result.push_str(&rest[..len]);
+            }
+            rest = &rest[len..];
+            continue;
+        }
+        if c == '[' {
+            if let Some(end) = rest.find(']') {
+                let inner = &rest[1..end];
+                let is_bytes = inner.chars().all(|c| c.is_ascii_digit() || c == ',' || c == ' ');
+                if is_bytes && inner.split(',').filter(|n| !n.trim().is_empty()).count() >= MIN_SECRET_BYTES {
+                    result.push_str("[redacted]");
+                    rest = &rest[end + 1..];
+                    continue;
+                }
+            }
+        }
+        result.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    result
+}
+
+/// Strip mnemonics and seed material from a log line
+pub(crate) fn redact_secrets(line: &str) -> String {
+    redact_seed_bytes(&redact_mnemonics(line))
+}
+
+/// Install the SDK logger if no call did yet; the SDK only accepts one per process
+pub(crate) fn install_sdk_logger() -> Result<(), String> {
+    SDK_LOGGER
+        .get_or_init(|| {
+            breez_sdk_spark::init_logging(None, Some(Box::new(ForwardingLogger)), None)
+                .map_err(|e| format!("Failed to install the SDK logger: {:?}", e))
+        })
+        .clone()
+}
+
+/// Hand a record to the log files and every node that asked for logs, if its level passes
+/// their filters
+///
+/// Only the first node echoes it to the console, so several nodes do not print it twice.
+pub(crate) fn forward_log(level: LogLevel, target: &str, line: &str) {
+    let filter = LOG_LEVEL.load(Ordering::Relaxed);
+    let to_file = file_logging_wants(level, filter);
+    if level as u8 > filter && !to_file {
+        return;
+    }
+    let message = redact_secrets(line);
+    if to_file {
+        write_file_log(level, target, &message);
+    }
+    if level as u8 > filter {
+        return;
+    }
+    let mut sinks = LOG_SINKS.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let mut to_console = true;
+    // A failed send means the node was freed
+    sinks.retain(|(_, sender)| {
+        let event = NodeEvent::LogMessage {
+            level,
+            target: target.to_string(),
+            message: message.clone(),
+            to_console,
+        };
+        let delivered = sender.send(event).is_ok();
+        to_console &= !delivered;
+        delivered
+    });
+}
+
+#[godot_api(secondary)]
+impl BreezNode {
+    /// Forward Breez SDK logs at `level` or more severe to the Godot console and the
+    /// `log_message` signal
+    ///
+    /// `level` is "error", "warn", "info", "debug" or "trace", or "off" to stop. Errors go to
+    /// `godot_error`, warnings to `godot_warn` and the rest to `godot_print`. Mnemonics and seed
+    /// bytes are replaced by `[redacted]`. The SDK logger is installed by the first call;
+    /// later calls only change the level. Returns false for an unknown level or when the SDK
+    /// logger could not be installed.
+    #[func]
+    pub fn init_logging(&mut self, level: GString) -> bool {
+        let level = level.to_string();
+        let filter = if level.trim().eq_ignore_ascii_case("off") {
+            0
+        } else {
+            match LogLevel::parse(&level) {
+                Some(level) => level as u8,
+                None => {
+                    godot_error!("Unknown log level \"{}\"; use error, warn, info, debug, trace or off", level);
+                    return false;
+                }
+            }
+        };
+
+        if let Err(e) = install_sdk_logger() {
+            godot_error!("{}", e);
+            return false;
+        }
+
+        let id = self.base().instance_id().to_i64();
+        let mut sinks = LOG_SINKS.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if !sinks.iter().any(|(sink_id, _)| *sink_id == id) {
+            sinks.push((id, self.events.sender()));
+        }
+        LOG_LEVEL.store(filter, Ordering::Relaxed);
+        true
+    }
+}
+
+impl BreezNode {
+    /// Print a forwarded record and emit `log_message`
+    pub(crate) fn on_log_message(&mut self, level: LogLevel, target: String, message: String, to_console: bool) {
+        if to_console {
+            match level {
+                LogLevel::Error => godot_error!("[{}] {}", target, message),
+                LogLevel::Warn => godot_warn!("[{}] {}", target, message),
+                _ => godot_print!("[{}] {}", target, message),
+            }
+        }
+        self.signals()
+            .log_message()
+            .emit(&GString::from(level.as_str()), &GString::from(&target), &GString::from(&message));
+    }
+}
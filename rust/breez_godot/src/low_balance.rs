@@ -0,0 +1,109 @@
+use godot::prelude::*;
+use breez_sdk_spark::GetInfoRequest;
+use std::sync::Arc;
+
+use crate::events::NodeEvent;
+use crate::BreezNode;
+
+/// Low-balance threshold with hysteresis
+///
+/// `low_balance` fires when the balance drops below the threshold and
+/// `balance_recovered` once it climbs back above threshold + hysteresis.
+pub(crate) struct LowBalanceWatch {
+    threshold_sats: u64,
+    hysteresis_sats: u64,
+    below: bool,
+}
+
+#[godot_api(secondary)]
+impl BreezNode {
+    /// Warn when the balance drops below `sats` (0 to disable)
+    ///
+    /// The threshold lasts for the session. The balance is evaluated after syncs,
+    /// outgoing payments and deposit claims.
+    #[func]
+    pub fn set_low_balance_threshold(&mut self, sats: i64) {
+        let mut watch = self.low_balance.lock().unwrap();
+        if sats <= 0 {
+            *watch = None;
+            return;
+        }
+
+        let threshold_sats = sats as u64;
+        *watch = Some(LowBalanceWatch {
+            threshold_sats,
+            hysteresis_sats: (threshold_sats / 10).max(1),
+            below: false,
+        });
+        drop(watch);
+
+        self.refresh_low_balance();
+    }
+
+    /// Get the low-balance threshold in satoshis (0 if disabled)
+    #[func]
+    pub fn get_low_balance_threshold(&self) -> i64 {
+        self.low_balance_threshold().map(|t| t as i64).unwrap_or(0)
+    }
+}
+
+impl BreezNode {
+    /// Low-balance threshold in satoshis, if one is set
+    pub(crate) fn low_balance_threshold(&self) -> Option<u64> {
+        self.low_balance.lock().unwrap().as_ref().map(|w| w.threshold_sats)
+    }
+
+    /// Forget whether the balance was below the threshold, e.g. when switching wallets
+    pub(crate) fn reset_low_balance_state(&self) {
+        if let Some(watch) = self.low_balance.lock().unwrap().as_mut() {
+            watch.below = false;
+        }
+    }
+
+    /// Compare a fresh balance against the low-balance threshold and queue signals on transitions
+    pub(crate) fn evaluate_low_balance(&self, balance_sats: u64) {
+        let mut guard = self.low_balance.lock().unwrap();
+        let Some(watch) = guard.as_mut() else {
+            return;
+        };
+
+        if !watch.below && balance_sats < watch.threshold_sats {
+            watch.below = true;
+            self.events.push(NodeEvent::LowBalance {
+                current_sats: balance_sats as i64,
+            });
+        } else if watch.below && balance_sats >= watch.threshold_sats + watch.hysteresis_sats {
+            watch.below = false;
+            self.events.push(NodeEvent::BalanceRecovered {
+                current_sats: balance_sats as i64,
+            });
+        }
+    }
+
+    /// Fetch the balance and evaluate the low-balance threshold, if one is set
+    pub(crate) fn refresh_low_balance(&self) {
+        if self.low_balance_threshold().is_none() {
+            return;
+        }
+
+        let sdk_handle = self.sdk_handle();
+        let runtime = Arc::clone(&self.runtime);
+
+        let balance = runtime.block_on(async move {
+            match sdk_handle {
+                Ok(sdk) => sdk
+                    .get_info(GetInfoRequest {
+                        ensure_synced: Some(false),
+                    })
+                    .await
+                    .ok()
+                    .map(|info| info.balance_sats),
+                Err(_) => None,
+            }
+        });
+
+        if let Some(balance) = balance {
+            self.evaluate_low_balance(balance);
+        }
+    }
+}
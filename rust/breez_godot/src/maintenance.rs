@@ -0,0 +1,295 @@
+use godot::prelude::*;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::fiat::{prune_fiat_cache, FIAT_CACHE_FILE};
+use crate::storage::{globalize_path, plugin_dir, plugin_file};
+use crate::zaps::{prune_expired_zaps, ZAPS_FILE};
+use crate::BreezNode;
+
+/// Tasks run by `run_storage_maintenance`, and whether each is safe on a connected wallet
+const MAINTENANCE_TASKS: &[(&str, bool)] = &[
+    ("prune_caches", true),
+    ("remove_temp_files", true),
+    ("compact_stores", false),
+    ("vacuum_sdk_database", false),
+];
+
+/// Cached fiat rates older than this are dropped by maintenance
+const FIAT_CACHE_MAX_AGE_SECS: u64 = 7 * 24 * 3_600;
+
+/// Temporary files younger than this may belong to a write still in progress
+const TEMP_FILE_MIN_AGE: Duration = Duration::from_secs(60);
+
+const SQLITE_HEADER: &[u8; 16] = b"SQLite format 3\0";
+
+/// Total size of the files under `dir`, skipping the `skip` directory
+fn dir_size(dir: &Path, skip: Option<&Path>) -> u64 {
+    files_under(dir, skip).iter().map(|path| file_len(path)).sum()
+}
+
+/// Every file under `dir`, recursively, skipping the `skip` directory
+fn files_under(dir: &Path, skip: Option<&Path>) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if skip == Some(path.as_path()) {
+            continue;
+        }
+        match entry.file_type() {
+            Ok(kind) if kind.is_dir() => files.extend(files_under(&path, skip)),
+            Ok(kind) if kind.is_file() => files.push(path),
+            _ => {}
+        }
+    }
+    files
+}
+
+fn file_len(path: &Path) -> u64 {
+    fs::metadata(path).map(|meta| meta.len()).unwrap_or(0)
+}
+
+fn is_sqlite(path: &Path) -> bool {
+    let mut header = [0u8; 16];
+    fs::File::open(path)
+        .and_then(|mut file| file.read_exact(&mut header))
+        .is_ok_and(|_| &header == SQLITE_HEADER)
+}
+
+/// Size of a SQLite database including its write-ahead log
+fn database_len(path: &Path) -> u64 {
+    let mut wal = path.as_os_str().to_owned();
+    wal.push("-wal");
+    file_len(path) + file_len(Path::new(&wal))
+}
+
+fn vacuum_database(path: &Path) -> Result<(), String> {
+    let conn = rusqlite::Connection::open(path).map_err(|e| e.to_string())?;
+    conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))
+        .map_err(|e| e.to_string())?;
+    conn.execute("VACUUM", []).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Rewrite a JSON store without whitespace, or remove it when it holds nothing
+fn compact_store(path: &Path) -> Result<(), String> {
+    let contents = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let value: serde_json::Value = serde_json::from_str(&contents).map_err(|e| e.to_string())?;
+    let empty = match &value {
+        serde_json::Value::Array(items) => items.is_empty(),
+        serde_json::Value::Object(fields) => fields.is_empty(),
+        serde_json::Value::Null => true,
+        _ => false,
+    };
+    if empty {
+        // Every store loads a missing file as empty
+        return fs::remove_file(path).map_err(|e| e.to_string());
+    }
+    let compact = serde_json::to_string(&value).map_err(|e| e.to_string())?;
+    if compact.len() < contents.len() {
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, compact).map_err(|e| e.to_string())?;
+        fs::rename(&tmp, path).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+fn maintenance_error(error: String, code: &str) -> Dictionary {
+    godot_error!("{}", error);
+    let mut dict = Dictionary::new();
+    dict.set("success", false);
+    dict.set("error", error);
+    dict.set("error_code", code);
+    dict
+}
+
+#[godot_api(secondary)]
+impl BreezNode {
+    /// Disk usage of the connected wallet
+    ///
+    /// Returns a Dictionary with `success`, `storage_dir`, `sdk_bytes` (the SDK's database
+    /// and other files), `plugin_bytes`, `plugin_files` (bytes per plugin file, e.g.
+    /// caches, registries and the operation journal) and `free_bytes` (-1 if unknown).
+    #[func]
+    pub fn get_storage_stats(&self) -> Dictionary {
+        let result = self.get_storage_stats_with();
+        self.report_failure("get_storage_stats", result)
+    }
+
+    /// Reclaim disk space in a wallet's storage_dir
+    ///
+    /// Options, all optional:
+    /// * `storage_dir` - Wallet to maintain (default: the connected one)
+    /// * `prune_caches` - Drop week-old fiat rates and unpaid zaps with expired invoices
+    /// * `remove_temp_files` - Remove leftovers of interrupted plugin writes
+    /// * `compact_stores` - Rewrite plugin stores without whitespace, removing empty ones
+    /// * `vacuum_sdk_database` - VACUUM the SDK's SQLite databases
+    ///
+    /// Tasks default to true. `compact_stores` and `vacuum_sdk_database` are not safe while
+    /// the wallet is connected: left at their default they are skipped (and listed in
+    /// `skipped`), set explicitly the call fails with CONNECTED. Returns `success`,
+    /// `reclaimed_bytes`, `tasks` (bytes reclaimed per task) and `skipped`.
+    #[func]
+    pub fn run_storage_maintenance(&self, options: Dictionary) -> Dictionary {
+        let result = self.run_storage_maintenance_with(options);
+        self.report_failure("run_storage_maintenance", result)
+    }
+}
+
+impl BreezNode {
+    fn get_storage_stats_with(&self) -> Dictionary {
+        let Some(storage_dir) = self.wallet_dir.lock().unwrap().clone() else {
+            return maintenance_error("Not connected".to_string(), "NOT_CONNECTED");
+        };
+        let plugin_path = plugin_dir(&storage_dir);
+
+        let mut plugin_files = Dictionary::new();
+        let mut plugin_bytes = 0;
+        for path in files_under(&plugin_path, None) {
+            let len = file_len(&path);
+            plugin_bytes += len;
+            let name = path.strip_prefix(&plugin_path).unwrap_or(&path).to_string_lossy().to_string();
+            plugin_files.set(name, len as i64);
+        }
+
+        let mut dict = Dictionary::new();
+        dict.set("success", true);
+        dict.set("storage_dir", storage_dir.to_string_lossy().to_string());
+        dict.set("sdk_bytes", dir_size(&storage_dir, Some(&plugin_path)) as i64);
+        dict.set("plugin_bytes", plugin_bytes as i64);
+        dict.set("plugin_files", &plugin_files);
+        dict.set("free_bytes", fs2::available_space(&storage_dir).map(|free| free as i64).unwrap_or(-1));
+        dict
+    }
+
+    fn run_storage_maintenance_with(&self, options: Dictionary) -> Dictionary {
+        let mut storage_dir = None;
+        let mut requested: Vec<(&str, Option<bool>)> = MAINTENANCE_TASKS.iter().map(|(task, _)| (*task, None)).collect();
+        for (key, value) in options.iter_shared() {
+            let key = key.to_string();
+            let invalid = || maintenance_error(format!("Invalid value for option '{}': {}", key, value), "INVALID_OPTION");
+            if key == "storage_dir" {
+                let dir = value.try_to::<GString>().map_err(|_| invalid());
+                match dir {
+                    Ok(dir) => storage_dir = Some(PathBuf::from(globalize_path(&dir.to_string()))),
+                    Err(error) => return error,
+                }
+                continue;
+            }
+            let Some(slot) = requested.iter_mut().find(|(task, _)| *task == key) else {
+                let valid: Vec<&str> = MAINTENANCE_TASKS.iter().map(|(task, _)| *task).collect();
+                return maintenance_error(
+                    format!("Unknown option '{}' (valid: storage_dir, {})", key, valid.join(", ")),
+                    "UNKNOWN_OPTION",
+                );
+            };
+            match value.try_to::<bool>() {
+                Ok(enabled) => slot.1 = Some(enabled),
+                Err(_) => return invalid(),
+            }
+        }
+
+        let connected_dir = self.wallet_dir.lock().unwrap().clone();
+        let Some(storage_dir) = storage_dir.or_else(|| connected_dir.clone()) else {
+            return maintenance_error("Not connected and no storage_dir given".to_string(), "NOT_CONNECTED");
+        };
+        if !storage_dir.is_dir() {
+            return maintenance_error(format!("{} is not a directory", storage_dir.display()), "INVALID_OPTION");
+        }
+        let connected = connected_dir.as_deref() == Some(storage_dir.as_path());
+
+        let mut tasks = Vec::new();
+        let mut skipped = PackedStringArray::new();
+        for (task, choice) in requested {
+            let safe = MAINTENANCE_TASKS.iter().any(|(name, safe)| *name == task && *safe);
+            match choice {
+                Some(false) => {}
+                Some(true) if connected && !safe => {
+                    return maintenance_error(format!("'{}' cannot run while the wallet is connected", task), "CONNECTED");
+                }
+                None if connected && !safe => skipped.push(&GString::from(task)),
+                _ => tasks.push(task),
+            }
+        }
+
+        let plugin_path = plugin_dir(&storage_dir);
+        let mut task_bytes = Dictionary::new();
+        let mut reclaimed_total = 0u64;
+        for task in tasks {
+            let reclaimed = match task {
+                "prune_caches" => {
+                    let stores = [plugin_file(&storage_dir, FIAT_CACHE_FILE), plugin_file(&storage_dir, ZAPS_FILE)];
+                    let before: u64 = stores.iter().map(|path| file_len(path)).sum();
+                    let pruned = prune_fiat_cache(&stores[0], FIAT_CACHE_MAX_AGE_SECS) + prune_expired_zaps(&stores[1]);
+                    if pruned > 0 {
+                        godot_print!("Pruned {} expired cache entries", pruned);
+                    }
+                    if connected {
+                        self.load_fiat_cache();
+                    }
+                    before.saturating_sub(stores.iter().map(|path| file_len(path)).sum())
+                }
+                "remove_temp_files" => {
+                    let now = SystemTime::now();
+                    let mut removed = 0;
+                    for path in files_under(&plugin_path, None) {
+                        let stale = fs::metadata(&path)
+                            .and_then(|meta| meta.modified())
+                            .is_ok_and(|modified| now.duration_since(modified).unwrap_or_default() >= TEMP_FILE_MIN_AGE);
+                        let temp = path.extension().is_some_and(|ext| ext == "tmp")
+                            || path.file_name().is_some_and(|name| name == ".write_probe");
+                        if temp && stale {
+                            let len = file_len(&path);
+                            if fs::remove_file(&path).is_ok() {
+                                removed += len;
+                            }
+                        }
+                    }
+                    removed
+                }
+                "compact_stores" => {
+                    let mut reclaimed = 0;
+                    for path in files_under(&plugin_path, None) {
+                        if path.extension().is_none_or(|ext| ext != "json") {
+                            continue;
+                        }
+                        let before = file_len(&path);
+                        match compact_store(&path) {
+                            Ok(()) => reclaimed += before.saturating_sub(file_len(&path)),
+                            Err(e) => godot_warn!("Could not compact {}: {}", path.display(), e),
+                        }
+                    }
+                    reclaimed
+                }
+                "vacuum_sdk_database" => {
+                    let mut reclaimed = 0;
+                    for path in files_under(&storage_dir, Some(&plugin_path)).into_iter().filter(|path| is_sqlite(path)) {
+                        let before = database_len(&path);
+                        match vacuum_database(&path) {
+                            Ok(()) => reclaimed += before.saturating_sub(database_len(&path)),
+                            Err(e) => godot_warn!("Could not vacuum {}: {}", path.display(), e),
+                        }
+                    }
+                    reclaimed
+                }
+                _ => unreachable!("maintenance task listed but not handled: {}", task),
+            };
+            reclaimed_total += reclaimed;
+            task_bytes.set(task, reclaimed as i64);
+        }
+
+        godot_print!("✅ Storage maintenance reclaimed {} bytes", reclaimed_total);
+        let mut dict = Dictionary::new();
+        dict.set("success", true);
+        dict.set("storage_dir", storage_dir.to_string_lossy().to_string());
+        dict.set("reclaimed_bytes", reclaimed_total as i64);
+        dict.set("tasks", &task_bytes);
+        dict.set("skipped", &skipped);
+        dict
+    }
+}
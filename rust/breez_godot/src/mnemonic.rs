@@ -0,0 +1,100 @@
+use godot::prelude::*;
+use bip39::{Error as Bip39Error, Language, Mnemonic};
+
+use crate::BreezNode;
+
+/// Word counts of a BIP39 mnemonic accepted by the SDK
+const MNEMONIC_WORD_COUNTS: &[usize] = &[12, 24];
+
+/// Why a recovery phrase was rejected; `word_index` is the first invalid word (0-based)
+pub(crate) struct MnemonicProblem {
+    pub(crate) message: String,
+    pub(crate) word_index: Option<usize>,
+}
+
+/// Check a mnemonic: 12 or 24 words of the BIP39 English word list with a valid checksum,
+/// returning the word count
+///
+/// The messages never repeat the phrase or any of its words, as they end up in logs.
+pub(crate) fn check_mnemonic_words(mnemonic: &str) -> Result<usize, MnemonicProblem> {
+    let invalid = |message: String| MnemonicProblem { message, word_index: None };
+    let words: Vec<String> = mnemonic.split_whitespace().map(str::to_lowercase).collect();
+    if words.is_empty() {
+        return Err(invalid("No recovery phrase was given".to_string()));
+    }
+    if !MNEMONIC_WORD_COUNTS.contains(&words.len()) {
+        return Err(invalid(format!("The recovery phrase has {} words; it needs 12 or 24", words.len())));
+    }
+    match Mnemonic::parse_in_normalized(Language::English, &words.join(" ")) {
+        Ok(_) => Ok(words.len()),
+        Err(Bip39Error::UnknownWord(index)) => Err(MnemonicProblem {
+            message: format!("Word {} of the recovery phrase is not in the BIP39 word list", index + 1),
+            word_index: Some(index),
+        }),
+        Err(Bip39Error::InvalidChecksum) => Err(invalid(
+            "The recovery phrase checksum does not match; a word is mistyped or out of order".to_string(),
+        )),
+        Err(_) => Err(invalid("The recovery phrase is not a valid BIP39 mnemonic".to_string())),
+    }
+}
+
+/// Recovery phrase helpers for onboarding, callable as `BreezNode.generate_mnemonic(12)`
+#[godot_api(secondary)]
+impl BreezNode {
+    /// Create a new BIP39 recovery phrase from OS randomness, for a brand-new wallet
+    ///
+    /// Returns an empty string for a word count other than 12 or 24, or when the OS has no
+    /// randomness to give. The phrase is never logged; store it with the platform's secure
+    /// storage and show it to the player once for backup.
+    ///
+    /// # Arguments
+    /// * `word_count` - 12 or 24
+    #[func]
+    pub fn generate_mnemonic(word_count: i32) -> GString {
+        if !MNEMONIC_WORD_COUNTS.contains(&(word_count.max(0) as usize)) {
+            godot_error!("A recovery phrase has 12 or 24 words, not {}", word_count);
+            return GString::from("");
+        }
+        // 32 bits of entropy per 3 words
+        let mut entropy = [0u8; 32];
+        let entropy_len = word_count as usize / 3 * 4;
+        if let Err(e) = getrandom::getrandom(&mut entropy[..entropy_len]) {
+            godot_error!("Failed to read OS randomness: {}", e);
+            return GString::from("");
+        }
+        let mnemonic = Mnemonic::from_entropy_in(Language::English, &entropy[..entropy_len]);
+        entropy.fill(0);
+        match mnemonic {
+            Ok(mnemonic) => GString::from(&mnemonic.to_string()),
+            Err(e) => {
+                godot_error!("Failed to create a recovery phrase: {}", e);
+                GString::from("")
+            }
+        }
+    }
+
+    /// Check a recovery phrase typed in by the player before connecting with it
+    ///
+    /// Returns `valid` and `word_count`; an invalid phrase adds `error`, `error_code`
+    /// (INVALID_MNEMONIC) and, for a word missing from the BIP39 word list,
+    /// `invalid_word_index` (0-based) to highlight it. Works offline and never logs the
+    /// phrase.
+    #[func]
+    pub fn validate_mnemonic(mnemonic: GString) -> Dictionary {
+        let mnemonic = mnemonic.to_string();
+        let mut dict = Dictionary::new();
+        dict.set("word_count", mnemonic.split_whitespace().count() as i64);
+        match check_mnemonic_words(&mnemonic) {
+            Ok(_) => dict.set("valid", true),
+            Err(problem) => {
+                dict.set("valid", false);
+                dict.set("error", problem.message);
+                dict.set("error_code", "INVALID_MNEMONIC");
+                if let Some(index) = problem.word_index {
+                    dict.set("invalid_word_index", index as i64);
+                }
+            }
+        }
+        dict
+    }
+}
@@ -0,0 +1,350 @@
+use godot::prelude::*;
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::path::Path;
+
+use crate::mnemonic::check_mnemonic_words;
+use crate::storage::{globalize_path, write_atomic};
+use crate::utils::constant_time_eq;
+use crate::{connect_storage_dir, BreezNode, ConnectOptions};
+
+const MNEMONIC_FILE_VERSION: u32 = 1;
+
+/// Argon2id cost for new files: 64 MiB, which phones handle in well under a second
+const MNEMONIC_ARGON2_COST: Argon2Cost = Argon2Cost {
+    memory_kib: 64 * 1024,
+    iterations: 3,
+    parallelism: 1,
+};
+
+/// Largest cost a file may ask for, so a damaged file cannot exhaust memory or stall the
+/// main thread in Argon2id
+const MAX_ARGON2_COST: Argon2Cost = Argon2Cost {
+    memory_kib: 1024 * 1024,
+    iterations: 16,
+    parallelism: 8,
+};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Argon2id parameters, stored in the file so they can be raised later without breaking
+/// existing files
+#[derive(Clone, Copy, Serialize, Deserialize)]
+struct Argon2Cost {
+    memory_kib: u32,
+    iterations: u32,
+    parallelism: u32,
+}
+
+impl Argon2Cost {
+    fn within(&self, max: &Argon2Cost) -> bool {
+        self.memory_kib <= max.memory_kib && self.iterations <= max.iterations && self.parallelism <= max.parallelism
+    }
+}
+
+/// An XChaCha20-Poly1305 encrypted mnemonic, as stored on disk
+#[derive(Serialize, Deserialize)]
+struct EncryptedMnemonic {
+    version: u32,
+    argon2: Argon2Cost,
+    salt: String,
+    nonce: String,
+    /// Derived from the password alongside the key, so a wrong password is told apart
+    /// from a damaged file
+    check: String,
+    ciphertext: String,
+}
+
+/// Encryption key and password check value for a password
+fn derive_keys(password: &str, salt: &[u8], cost: Argon2Cost) -> Result<([u8; 32], [u8; 32]), String> {
+    let params = Params::new(cost.memory_kib, cost.iterations, cost.parallelism, Some(32))
+        .map_err(|e| format!("bad Argon2 parameters: {}", e))?;
+    let mut master = [0u8; 32];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(password.as_bytes(), salt, &mut master)
+        .map_err(|e| format!("key derivation failed: {}", e))?;
+    let expand = |label: &[u8]| -> [u8; 32] {
+        let mut mac = HmacSha256::new_from_slice(&master).expect("HMAC accepts any key length");
+        mac.update(label);
+        mac.finalize().into_bytes().into()
+    };
+    let keys = (expand(b"breez_godot mnemonic key"), expand(b"breez_godot mnemonic check"));
+    master.fill(0);
+    Ok(keys)
+}
+
+/// Why a parsed mnemonic file could not be decrypted
+#[derive(Debug, PartialEq)]
+enum DecryptError {
+    WrongPassword,
+    Corrupted(String),
+}
+
+fn encrypt_mnemonic(mnemonic: &str, password: &str) -> Result<EncryptedMnemonic, String> {
+    encrypt_mnemonic_with(mnemonic, password, MNEMONIC_ARGON2_COST)
+}
+
+fn encrypt_mnemonic_with(mnemonic: &str, password: &str, cost: Argon2Cost) -> Result<EncryptedMnemonic, String> {
+    let mut salt = [0u8; 16];
+    let mut nonce = [0u8; 24];
+    getrandom::getrandom(&mut salt).map_err(|e| format!("Failed to read OS randomness: {}", e))?;
+    getrandom::getrandom(&mut nonce).map_err(|e| format!("Failed to read OS randomness: {}", e))?;
+
+    let (mut key, check) = derive_keys(password, &salt, cost)?;
+    let cipher = XChaCha20Poly1305::new_from_slice(&key).expect("XChaCha20 key is 32 bytes");
+    key.fill(0);
+    let ciphertext = cipher
+        .encrypt(XNonce::from_slice(&nonce), mnemonic.as_bytes())
+        .map_err(|_| "Failed to encrypt the recovery phrase".to_string())?;
+
+    Ok(EncryptedMnemonic {
+        version: MNEMONIC_FILE_VERSION,
+        argon2: cost,
+        salt: hex::encode(salt),
+        nonce: hex::encode(nonce),
+        check: hex::encode(check),
+        ciphertext: hex::encode(ciphertext),
+    })
+}
+
+/// Decrypt a mnemonic file, failing with WRONG_PASSWORD, FILE_NOT_FOUND or CORRUPTED_FILE
+fn decrypt_mnemonic_file(path: &Path, password: &str) -> Result<String, (String, &'static str)> {
+    let corrupted = |detail: &str| {
+        let message = format!("{} is not a valid encrypted mnemonic file: {}", path.display(), detail);
+        (message, "CORRUPTED_FILE")
+    };
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err((format!("No encrypted mnemonic at {}", path.display()), "FILE_NOT_FOUND"))
+        }
+        Err(e) => return Err((format!("Failed to read {}: {}", path.display(), e), "READ_FAILED")),
+    };
+    let file: EncryptedMnemonic = serde_json::from_str(&contents).map_err(|_| corrupted("unreadable contents"))?;
+    decrypt_mnemonic(&file, password).map_err(|e| match e {
+        DecryptError::WrongPassword => ("Wrong password for the encrypted mnemonic".to_string(), "WRONG_PASSWORD"),
+        DecryptError::Corrupted(detail) => corrupted(&detail),
+    })
+}
+
+fn decrypt_mnemonic(file: &EncryptedMnemonic, password: &str) -> Result<String, DecryptError> {
+    let corrupted = |detail: &str| DecryptError::Corrupted(detail.to_string());
+    if file.version != MNEMONIC_FILE_VERSION {
+        return Err(DecryptError::Corrupted(format!("unsupported version {}", file.version)));
+    }
+    let decode = |value: &str| hex::decode(value).map_err(|_| corrupted("bad encoding"));
+    let (salt, nonce, check, ciphertext) =
+        (decode(&file.salt)?, decode(&file.nonce)?, decode(&file.check)?, decode(&file.ciphertext)?);
+    if nonce.len() != 24 || !file.argon2.within(&MAX_ARGON2_COST) {
+        return Err(corrupted("bad parameters"));
+    }
+
+    let (mut key, expected_check) = derive_keys(password, &salt, file.argon2).map_err(DecryptError::Corrupted)?;
+    if !constant_time_eq(&check, &expected_check) {
+        key.fill(0);
+        return Err(DecryptError::WrongPassword);
+    }
+    let cipher = XChaCha20Poly1305::new_from_slice(&key).expect("XChaCha20 key is 32 bytes");
+    key.fill(0);
+    // The password is right, so a failure here means the ciphertext was damaged
+    let plaintext = cipher
+        .decrypt(XNonce::from_slice(&nonce), ciphertext.as_slice())
+        .map_err(|_| corrupted("the data was modified"))?;
+    String::from_utf8(plaintext).map_err(|_| corrupted("bad contents"))
+}
+
+fn store_error(error: String, code: &str) -> Dictionary {
+    godot_error!("{}", error);
+    let mut dict = Dictionary::new();
+    dict.set("success", false);
+    dict.set("error", error);
+    dict.set("error_code", code);
+    dict
+}
+
+#[godot_api(secondary)]
+impl BreezNode {
+    /// Save a recovery phrase encrypted with a password, instead of in a plain config file
+    ///
+    /// Uses XChaCha20-Poly1305 with a key derived from the password by Argon2id. The file is
+    /// written atomically; accepts absolute and `user://` paths. Returns false when the phrase
+    /// is not a valid mnemonic, the password is empty or the file cannot be written (see
+    /// `get_last_error`).
+    ///
+    /// # Arguments
+    /// * `path` - File to write, e.g. "user://wallet.key"
+    /// * `mnemonic` - 12 or 24 word BIP39 mnemonic phrase
+    /// * `password` - Password needed to load it again; it cannot be recovered
+    #[func]
+    pub fn store_mnemonic_encrypted(&self, path: GString, mnemonic: GString, password: GString) -> bool {
+        let result =
+            self.store_mnemonic_encrypted_with(&path.to_string(), &mnemonic.to_string(), &password.to_string());
+        self.report_failure("store_mnemonic_encrypted", result)
+            .get("success")
+            .is_some_and(|success| success.booleanize())
+    }
+
+    /// Load a recovery phrase saved by `store_mnemonic_encrypted`
+    ///
+    /// Returns an empty string on failure; `get_last_error` then has `error_code`
+    /// WRONG_PASSWORD, CORRUPTED_FILE (damaged or not a mnemonic file), FILE_NOT_FOUND or
+    /// READ_FAILED. Prefer `connect_sdk_from_encrypted`, which keeps the phrase out of
+    /// GDScript.
+    #[func]
+    pub fn load_mnemonic_encrypted(&self, path: GString, password: GString) -> GString {
+        let path = globalize_path(path.to_string().trim());
+        match decrypt_mnemonic_file(Path::new(&path), &password.to_string()) {
+            Ok(mnemonic) => GString::from(&mnemonic),
+            Err((e, code)) => {
+                self.report_failure("load_mnemonic_encrypted", store_error(e, code));
+                GString::from("")
+            }
+        }
+    }
+
+    /// Connect with a recovery phrase saved by `store_mnemonic_encrypted`, so the phrase
+    /// is only ever decrypted on the Rust side
+    ///
+    /// Takes the arguments of `connect_sdk` with the file and its password in place of the
+    /// mnemonic, and returns the Dictionary of `connect_sdk_detailed`, whose `error_code`
+    /// can also be WRONG_PASSWORD, CORRUPTED_FILE, FILE_NOT_FOUND or READ_FAILED.
+    #[func]
+    pub fn connect_sdk_from_encrypted(
+        &mut self,
+        path: GString,
+        password: GString,
+        api_key: GString,
+        network: GString,
+        storage_dir: GString,
+    ) -> Dictionary {
+        let path = globalize_path(path.to_string().trim());
+        let mnemonic = match decrypt_mnemonic_file(Path::new(&path), &password.to_string()) {
+            Ok(mnemonic) => mnemonic,
+            Err((e, code)) => return self.report_failure("connect_sdk_from_encrypted", store_error(e, code)),
+        };
+
+        let network = network.to_string().trim().to_lowercase();
+        let result = connect_storage_dir(&storage_dir, &network).and_then(|storage_dir| {
+            self.connect_with(
+                mnemonic,
+                None,
+                api_key.to_string(),
+                network,
+                storage_dir,
+                ConnectOptions::default(),
+                |_config| {},
+            )
+        });
+        let dict = match result {
+            Ok(()) => {
+                let mut dict = Dictionary::new();
+                dict.set("success", true);
+                dict
+            }
+            Err(e) => e.to_dictionary(),
+        };
+        self.report_failure("connect_sdk_from_encrypted", dict)
+    }
+}
+
+impl BreezNode {
+    fn store_mnemonic_encrypted_with(&self, path: &str, mnemonic: &str, password: &str) -> Dictionary {
+        let path = globalize_path(path.trim());
+        if path.is_empty() {
+            return store_error("No path given for the encrypted mnemonic".to_string(), "INVALID_INPUT");
+        }
+        if password.is_empty() {
+            return store_error("An empty password does not protect the mnemonic".to_string(), "INVALID_INPUT");
+        }
+        if let Err(problem) = check_mnemonic_words(mnemonic) {
+            return store_error(problem.message, "INVALID_MNEMONIC");
+        }
+        // Stored normalized, as typed phrases may carry extra spaces or capitals
+        let mnemonic = mnemonic.split_whitespace().map(str::to_lowercase).collect::<Vec<_>>().join(" ");
+
+        let file = match encrypt_mnemonic(&mnemonic, password) {
+            Ok(file) => file,
+            Err(e) => return store_error(e, "SDK_ERROR"),
+        };
+        let contents = serde_json::to_vec_pretty(&file).expect("encrypted mnemonic serializes");
+        if let Err(e) = write_atomic(Path::new(&path), &contents) {
+            return store_error(format!("Failed to write {}: {}", path, e), "WRITE_FAILED");
+        }
+
+        godot_print!("✅ Recovery phrase saved encrypted to {}", path);
+        let mut dict = Dictionary::new();
+        dict.set("success", true);
+        dict
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MNEMONIC: &str =
+        "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    /// A small cost keeps the tests fast; the cost is read back from the file
+    const TEST_COST: Argon2Cost = Argon2Cost {
+        memory_kib: 64,
+        iterations: 1,
+        parallelism: 1,
+    };
+
+    #[test]
+    fn mnemonic_round_trips_through_encryption() {
+        let file = encrypt_mnemonic_with(MNEMONIC, "hunter2", TEST_COST).unwrap();
+        assert!(!file.ciphertext.contains(&hex::encode("abandon")));
+        assert_eq!(decrypt_mnemonic(&file, "hunter2"), Ok(MNEMONIC.to_string()));
+
+        // The file as written to disk decrypts the same
+        let json = serde_json::to_string(&file).unwrap();
+        let reloaded: EncryptedMnemonic = serde_json::from_str(&json).unwrap();
+        assert_eq!(decrypt_mnemonic(&reloaded, "hunter2"), Ok(MNEMONIC.to_string()));
+    }
+
+    #[test]
+    fn each_encryption_uses_a_fresh_salt_and_nonce() {
+        let first = encrypt_mnemonic_with(MNEMONIC, "hunter2", TEST_COST).unwrap();
+        let second = encrypt_mnemonic_with(MNEMONIC, "hunter2", TEST_COST).unwrap();
+        assert_ne!(first.salt, second.salt);
+        assert_ne!(first.nonce, second.nonce);
+        assert_ne!(first.ciphertext, second.ciphertext);
+    }
+
+    #[test]
+    fn wrong_password_is_told_apart_from_damage() {
+        let mut file = encrypt_mnemonic_with(MNEMONIC, "hunter2", TEST_COST).unwrap();
+        assert_eq!(decrypt_mnemonic(&file, "hunter3"), Err(DecryptError::WrongPassword));
+
+        let mut ciphertext = hex::decode(&file.ciphertext).unwrap();
+        ciphertext[0] ^= 1;
+        file.ciphertext = hex::encode(ciphertext);
+        assert!(matches!(decrypt_mnemonic(&file, "hunter2"), Err(DecryptError::Corrupted(_))));
+    }
+
+    #[test]
+    fn excessive_costs_are_rejected() {
+        let mut file = encrypt_mnemonic_with(MNEMONIC, "hunter2", TEST_COST).unwrap();
+        for cost in [
+            Argon2Cost { memory_kib: MAX_ARGON2_COST.memory_kib + 1, ..TEST_COST },
+            Argon2Cost { iterations: MAX_ARGON2_COST.iterations + 1, ..TEST_COST },
+            Argon2Cost { parallelism: MAX_ARGON2_COST.parallelism + 1, ..TEST_COST },
+        ] {
+            file.argon2 = cost;
+            assert!(matches!(decrypt_mnemonic(&file, "hunter2"), Err(DecryptError::Corrupted(_))));
+        }
+    }
+
+    #[test]
+    fn unknown_versions_are_rejected() {
+        let mut file = encrypt_mnemonic_with(MNEMONIC, "hunter2", TEST_COST).unwrap();
+        file.version = MNEMONIC_FILE_VERSION + 1;
+        assert!(matches!(decrypt_mnemonic(&file, "hunter2"), Err(DecryptError::Corrupted(_))));
+    }
+}
@@ -0,0 +1,170 @@
+use godot::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::storage::{load_json, now_secs, plugin_file, resolve_storage_dir, save_json};
+use crate::BreezNode;
+
+/// Marker recording which network a wallet directory belongs to
+const NETWORK_MARKER_FILE: &str = "network.json";
+
+/// Networks the Breez Spark SDK can connect to
+pub(crate) const NETWORKS: [&str; 2] = ["mainnet", "regtest"];
+
+/// Bitcoin networks the Breez Spark SDK has no Spark operators for
+const UNSUPPORTED_NETWORKS: [&str; 3] = ["testnet", "testnet4", "signet"];
+
+/// Check a network name, ignoring case and surrounding spaces, returning its canonical form
+pub(crate) fn parse_network(network: &str) -> Result<&'static str, String> {
+    let lower = network.trim().to_lowercase();
+    if let Some(name) = NETWORKS.iter().find(|name| **name == lower) {
+        return Ok(name);
+    }
+    if UNSUPPORTED_NETWORKS.contains(&lower.as_str()) {
+        Err(format!(
+            "The {} network is not supported by the Breez Spark SDK; valid networks: {}",
+            lower,
+            NETWORKS.join(", ")
+        ))
+    } else {
+        Err(format!("Invalid network: {:?}; valid networks: {}", network, NETWORKS.join(", ")))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct NetworkMarker {
+    network: String,
+    created_at: u64,
+}
+
+fn read_marker(dir: &Path) -> Option<String> {
+    load_json::<NetworkMarker>(&plugin_file(dir, NETWORK_MARKER_FILE)).map(|marker| marker.network)
+}
+
+/// Whether `dir` holds wallet data of its own, ignoring plugin files, profile markers and
+/// per-network subdirectories
+pub(crate) fn has_wallet_data(dir: &Path) -> bool {
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries.flatten().any(|entry| {
+                let name = entry.file_name();
+                let name = name.to_string_lossy();
+                name != "godot_plugin" && name != "profile.json" && !NETWORKS.contains(&name.as_ref())
+            })
+        })
+        .unwrap_or(false)
+}
+
+/// Directory the wallet for `network` uses under `storage_dir`
+///
+/// Wallets live in `<storage_dir>/<network>/`. A storage_dir that already holds a wallet
+/// from before this layout keeps being used for the network recorded in its marker, or
+/// for the first network connected when it has no marker yet.
+pub(crate) fn network_storage_dir(storage_dir: &Path, network: &str) -> PathBuf {
+    let legacy = has_wallet_data(storage_dir);
+    match read_marker(storage_dir) {
+        Some(marked) if legacy && marked == network => storage_dir.to_path_buf(),
+        None if legacy && !storage_dir.join(network).exists() => storage_dir.to_path_buf(),
+        _ => storage_dir.join(network),
+    }
+}
+
+/// Check `dir` against its network marker before connecting
+///
+/// With `override_network` a conflicting marker only produces a warning; it is replaced by
+/// `record_network` once connected.
+pub(crate) fn check_network(dir: &Path, network: &str, override_network: bool) -> Result<(), String> {
+    match read_marker(dir) {
+        Some(marked) if marked != network && !override_network => Err(format!(
+            "{} holds a {} wallet and cannot be opened as {} (use a different storage_dir, \
+             or pass override_network to reuse it)",
+            dir.display(),
+            marked,
+            network
+        )),
+        Some(marked) if marked != network => {
+            godot_warn!("⚠️ Reusing {} wallet directory {} as {}", marked, dir.display(), network);
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Record the network a wallet directory was connected with
+pub(crate) fn record_network(dir: &Path, network: &str) {
+    if read_marker(dir).as_deref() == Some(network) {
+        return;
+    }
+    let marker = NetworkMarker {
+        network: network.to_string(),
+        created_at: now_secs(),
+    };
+    if let Err(e) = save_json(&plugin_file(dir, NETWORK_MARKER_FILE), &marker) {
+        godot_warn!("Failed to record the network of {}: {}", dir.display(), e);
+    }
+}
+
+#[godot_api(secondary)]
+impl BreezNode {
+    /// Network of the connected wallet ("mainnet" or "regtest"), or an empty string when
+    /// not connected
+    ///
+    /// Check it before showing invoices or addresses from elsewhere; `check_invoice` rejects
+    /// invoices of another network.
+    #[func]
+    pub fn get_network(&self) -> GString {
+        if !self.is_sdk_connected() {
+            return GString::from("");
+        }
+        GString::from(&self.wallet_network.lock().unwrap().clone().unwrap_or_default())
+    }
+
+    /// Whether `storage_dir` already holds a wallet for `network` ("mainnet" or "regtest")
+    ///
+    /// Looks where `connect_sdk` would, so a mainnet wallet is not reported for regtest.
+    #[func]
+    pub fn wallet_exists(&self, storage_dir: GString, network: GString) -> bool {
+        let network = match parse_network(&network.to_string()) {
+            Ok(network) => network.to_string(),
+            Err(e) => {
+                godot_error!("{}", e);
+                return false;
+            }
+        };
+        let dir = match resolve_storage_dir(&storage_dir.to_string()) {
+            Ok(dir) => network_storage_dir(&dir, &network),
+            Err(e) => {
+                godot_error!("{}", e);
+                return false;
+            }
+        };
+        has_wallet_data(&dir) && read_marker(&dir).is_none_or(|marked| marked == network)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn network_names_are_canonicalized() {
+        assert_eq!(parse_network("mainnet"), Ok("mainnet"));
+        assert_eq!(parse_network("  RegTest "), Ok("regtest"));
+    }
+
+    #[test]
+    fn bitcoin_networks_without_spark_are_unsupported() {
+        for network in UNSUPPORTED_NETWORKS {
+            let error = parse_network(network).unwrap_err();
+            assert!(error.contains("not supported"), "{}", error);
+        }
+    }
+
+    #[test]
+    fn unknown_networks_list_the_valid_ones() {
+        let error = parse_network("bitcoin").unwrap_err();
+        assert_eq!(error, "Invalid network: \"bitcoin\"; valid networks: mainnet, regtest");
+        assert!(parse_network("").is_err());
+    }
+}
@@ -0,0 +1,204 @@
+use godot::prelude::*;
+use breez_sdk_spark::{Payment, PaymentDetails};
+use serde::Serialize;
+use std::path::Path;
+
+use crate::payment_stream::PaymentFilters;
+use crate::storage::{globalize_path, write_atomic};
+use crate::BreezNode;
+
+/// Column order of `export_payments_csv`
+const CSV_COLUMNS: &[&str] = &[
+    "id",
+    "timestamp",
+    "payment_type",
+    "status",
+    "method",
+    "amount_sats",
+    "fees_sats",
+    "description",
+    "payment_hash",
+    "preimage",
+    "invoice",
+    "txid",
+];
+
+/// One exported payment; fields the SDK does not report are left out of the JSON
+#[derive(Serialize)]
+struct ExportRow {
+    id: String,
+    timestamp: u64,
+    payment_type: String,
+    status: String,
+    method: String,
+    amount_sats: u64,
+    fees_sats: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    payment_hash: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    preimage: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    invoice: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    txid: Option<String>,
+}
+
+impl ExportRow {
+    fn new(payment: &Payment) -> Self {
+        let mut row = ExportRow {
+            id: payment.id.clone(),
+            timestamp: payment.timestamp,
+            payment_type: payment.payment_type.to_string(),
+            status: payment.status.to_string(),
+            method: payment.method.to_string(),
+            amount_sats: payment.amount as u64,
+            fees_sats: payment.fees as u64,
+            description: None,
+            payment_hash: None,
+            preimage: None,
+            invoice: None,
+            txid: None,
+        };
+        match &payment.details {
+            Some(PaymentDetails::Lightning {
+                description,
+                preimage,
+                invoice,
+                payment_hash,
+                ..
+            }) => {
+                row.description = description.clone().filter(|d| !d.is_empty());
+                row.preimage = preimage.clone();
+                row.invoice = Some(invoice.clone());
+                row.payment_hash = Some(payment_hash.clone());
+            }
+            Some(PaymentDetails::Withdraw { tx_id } | PaymentDetails::Deposit { tx_id }) => {
+                row.txid = Some(tx_id.clone());
+            }
+            _ => {}
+        }
+        row
+    }
+
+    fn csv_line(&self) -> String {
+        let text = |value: &Option<String>| value.as_deref().map(csv_field).unwrap_or_default();
+        [
+            csv_field(&self.id),
+            self.timestamp.to_string(),
+            csv_field(&self.payment_type),
+            csv_field(&self.status),
+            csv_field(&self.method),
+            self.amount_sats.to_string(),
+            self.fees_sats.to_string(),
+            text(&self.description),
+            text(&self.payment_hash),
+            text(&self.preimage),
+            text(&self.invoice),
+            text(&self.txid),
+        ]
+        .join(",")
+    }
+}
+
+/// Quote a CSV field when it contains a separator, quote or line break (RFC 4180)
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn export_error(error: String, code: &str) -> Dictionary {
+    godot_error!("{}", error);
+    let mut dict = Dictionary::new();
+    dict.set("success", false);
+    dict.set("error", error);
+    if !code.is_empty() {
+        dict.set("error_code", code);
+    }
+    dict
+}
+
+#[godot_api(secondary)]
+impl BreezNode {
+    /// Export the payment history to a CSV file, e.g. for accounting
+    ///
+    /// Writes a header row and one row per payment, newest first, with the columns `id`,
+    /// `timestamp`, `payment_type`, `status`, `method`, `amount_sats`, `fees_sats`,
+    /// `description`, `payment_hash`, `preimage`, `invoice` and `txid` (empty when unknown).
+    /// Accepts absolute and `user://` paths. The file is written atomically, so a failed
+    /// export leaves any previous file untouched. Returns `success`, `path` and `count`,
+    /// or fails with WRITE_FAILED.
+    ///
+    /// # Arguments
+    /// * `path` - File to write
+    /// * `from_timestamp` - Oldest payment time (unix seconds, 0 for no bound)
+    /// * `to_timestamp` - Newest payment time (unix seconds, inclusive, 0 for no bound)
+    #[func]
+    pub fn export_payments_csv(&self, path: GString, from_timestamp: i64, to_timestamp: i64) -> Dictionary {
+        let result = self.export_payments_with(&path.to_string(), from_timestamp, to_timestamp, |rows| {
+            let mut csv = CSV_COLUMNS.join(",");
+            csv.push_str("\r\n");
+            for row in rows {
+                csv.push_str(&row.csv_line());
+                csv.push_str("\r\n");
+            }
+            Ok(csv)
+        });
+        self.report_failure("export_payments_csv", result)
+    }
+
+    /// Export the payment history to a JSON file: an array of objects with the fields of
+    /// `export_payments_csv`, unknown ones left out
+    ///
+    /// Takes the same arguments and returns the same Dictionary as `export_payments_csv`.
+    #[func]
+    pub fn export_payments_json(&self, path: GString, from_timestamp: i64, to_timestamp: i64) -> Dictionary {
+        let result = self.export_payments_with(&path.to_string(), from_timestamp, to_timestamp, |rows| {
+            serde_json::to_string_pretty(rows).map_err(|e| e.to_string())
+        });
+        self.report_failure("export_payments_json", result)
+    }
+}
+
+impl BreezNode {
+    fn export_payments_with(
+        &self,
+        path: &str,
+        from_timestamp: i64,
+        to_timestamp: i64,
+        render: impl FnOnce(&[ExportRow]) -> Result<String, String>,
+    ) -> Dictionary {
+        let path = globalize_path(path.trim());
+        if path.is_empty() {
+            return export_error("No export path given".to_string(), "INVALID_INPUT");
+        }
+        let filters = PaymentFilters {
+            from_ts: (from_timestamp > 0).then_some(from_timestamp as u64),
+            to_ts: (to_timestamp > 0).then_some(to_timestamp as u64),
+            ..PaymentFilters::default()
+        };
+        let payments = match self.fetch_filtered_payments(&filters, 0, None) {
+            Ok(payments) => payments,
+            Err(e) => return export_error(e, ""),
+        };
+        let rows: Vec<ExportRow> = payments.iter().map(ExportRow::new).collect();
+        let contents = match render(&rows) {
+            Ok(contents) => contents,
+            Err(e) => return export_error(format!("Failed to export payments: {}", e), "WRITE_FAILED"),
+        };
+        if let Err(e) = write_atomic(Path::new(&path), contents.as_bytes()) {
+            return export_error(format!("Failed to write {}: {}", path, e), "WRITE_FAILED");
+        }
+
+        godot_print!("✅ Exported {} payments to {}", rows.len(), path);
+        let mut dict = Dictionary::new();
+        dict.set("success", true);
+        dict.set("path", path.as_str());
+        dict.set("count", rows.len() as i64);
+        dict
+    }
+}
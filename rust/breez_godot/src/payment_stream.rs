@@ -0,0 +1,165 @@
+use godot::prelude::*;
+use breez_sdk_spark::{ListPaymentsRequest, Payment, PaymentType};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use tokio::task::JoinHandle;
+
+use crate::history::payment_dictionary;
+use crate::BreezNode;
+
+/// Filters accepted by `stream_payments`
+const STREAM_FILTERS: &[&str] = &["payment_type", "status", "from_ts", "to_ts"];
+
+/// Largest chunk a stream may request from the SDK at once
+const MAX_CHUNK_SIZE: i64 = 500;
+
+/// Chunks fetched in the background, handed to the main thread one per frame
+///
+/// Each chunk is the payments and whether it is the last one, or the error that ended
+/// the stream.
+#[derive(Default)]
+pub(crate) struct PaymentStream {
+    chunks: VecDeque<Result<(Vec<Payment>, bool), String>>,
+    task: Option<JoinHandle<()>>,
+}
+
+/// Payment filters of `stream_payments` and `list_payments_filtered`, applied plugin-side
+/// because the SDK only pages
+#[derive(Clone, Default)]
+pub(crate) struct PaymentFilters {
+    pub(crate) payment_type: Option<String>,
+    pub(crate) status: Option<String>,
+    pub(crate) from_ts: Option<u64>,
+    pub(crate) to_ts: Option<u64>,
+}
+
+impl PaymentFilters {
+    fn parse(filters: &Dictionary) -> Result<Self, String> {
+        let mut parsed = PaymentFilters::default();
+        for (key, value) in filters.iter_shared() {
+            let key = key.to_string();
+            let invalid = || format!("Invalid value for filter '{}': {}", key, value);
+            match key.as_str() {
+                "payment_type" => parsed.payment_type = Some(value.to_string().to_lowercase()),
+                "status" => parsed.status = Some(value.to_string().to_lowercase()),
+                "from_ts" => parsed.from_ts = Some(value.try_to::<i64>().map_err(|_| invalid())?.max(0) as u64),
+                "to_ts" => parsed.to_ts = Some(value.try_to::<i64>().map_err(|_| invalid())?.max(0) as u64),
+                _ => {
+                    return Err(format!("Unknown filter '{}' (valid: {})", key, STREAM_FILTERS.join(", ")));
+                }
+            }
+        }
+        Ok(parsed)
+    }
+
+    pub(crate) fn matches(&self, payment: &Payment) -> bool {
+        let payment_type = match payment.payment_type {
+            PaymentType::Send => "send",
+            PaymentType::Receive => "receive",
+        };
+        self.payment_type.as_deref().is_none_or(|t| t == payment_type)
+            && self.status.as_deref().is_none_or(|s| s == payment.status.to_string().to_lowercase())
+            && self.from_ts.is_none_or(|from| payment.timestamp >= from)
+            && self.to_ts.is_none_or(|to| payment.timestamp <= to)
+    }
+
+    /// Whether a page, newest first, ends before `from_ts`, so no later page can match
+    pub(crate) fn past_range(&self, page: &[Payment]) -> bool {
+        self.from_ts
+            .is_some_and(|from| page.last().is_some_and(|p| p.timestamp < from))
+    }
+}
+
+#[godot_api(secondary)]
+impl BreezNode {
+    /// Deliver the payment history in chunks through `payments_chunk`, newest first
+    ///
+    /// Pages are fetched in the background and one chunk is emitted per frame, so large
+    /// histories do not stall a frame. The last chunk has `done` set (and may be empty,
+    /// e.g. when fetching failed).
+    /// Starting a new stream cancels the previous one.
+    ///
+    /// # Arguments
+    /// * `filters` - Optional `payment_type` ("send"/"receive"), `status` ("completed",
+    ///   "pending", "failed"), `from_ts` and `to_ts` (unix seconds, inclusive)
+    /// * `chunk_size` - Payments fetched per page (1–500)
+    #[func]
+    pub fn stream_payments(&self, filters: Dictionary, chunk_size: i64) -> bool {
+        let filters = match PaymentFilters::parse(&filters) {
+            Ok(filters) => filters,
+            Err(e) => {
+                godot_error!("{}", e);
+                return false;
+            }
+        };
+        if !(1..=MAX_CHUNK_SIZE).contains(&chunk_size) {
+            godot_error!("Invalid chunk size: {} (expected 1 to {})", chunk_size, MAX_CHUNK_SIZE);
+            return false;
+        }
+        let sdk = match self.sdk_handle() {
+            Ok(sdk) => sdk,
+            Err(e) => {
+                godot_error!("{}", e);
+                return false;
+            }
+        };
+
+        self.stop_payment_stream();
+        let stream = Arc::clone(&self.payment_stream);
+        let task = self.runtime.spawn(async move {
+            let mut offset = 0u32;
+            loop {
+                let page = match sdk.list_payments(ListPaymentsRequest {
+                    offset: Some(offset),
+                    limit: Some(chunk_size as u32),
+                }).await {
+                    Ok(response) => response.payments,
+                    Err(e) => {
+                        let error = format!("Failed to list payments: {:?}", e);
+                        stream.lock().unwrap().chunks.push_back(Err(error));
+                        return;
+                    }
+                };
+                offset += page.len() as u32;
+
+                let done = (page.len() as i64) < chunk_size || filters.past_range(&page);
+                let chunk: Vec<Payment> = page.into_iter().filter(|p| filters.matches(p)).collect();
+                if !chunk.is_empty() || done {
+                    stream.lock().unwrap().chunks.push_back(Ok((chunk, done)));
+                }
+                if done {
+                    return;
+                }
+            }
+        });
+        self.payment_stream.lock().unwrap().task = Some(task);
+        true
+    }
+
+    /// Cancel the running `stream_payments`, dropping chunks not yet emitted
+    #[func]
+    pub fn stop_payment_stream(&self) {
+        let mut stream = self.payment_stream.lock().unwrap();
+        if let Some(task) = stream.task.take() {
+            task.abort();
+        }
+        stream.chunks.clear();
+    }
+}
+
+impl BreezNode {
+    /// Emit the next fetched chunk, called every frame
+    pub(crate) fn dispatch_payment_chunk(&mut self) {
+        let Some(next) = self.payment_stream.lock().unwrap().chunks.pop_front() else {
+            return;
+        };
+        let (payments, done) = next.unwrap_or_else(|e| {
+            godot_error!("{}", e);
+            self.report_background_error("stream_payments", &e);
+            (Vec::new(), true)
+        });
+        let payers = self.zap_payers();
+        let chunk: Array<Dictionary> = payments.iter().map(|payment| payment_dictionary(payment, &payers)).collect();
+        self.signals().payments_chunk().emit(&chunk, done);
+    }
+}
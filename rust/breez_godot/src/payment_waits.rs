@@ -0,0 +1,189 @@
+use godot::prelude::*;
+use breez_sdk_spark::{ListPaymentsRequest, Payment, PaymentStatus, PaymentType};
+use std::collections::HashMap;
+
+use crate::bolt11::{parse_bolt11, payment_hash_hex};
+use crate::events::NodeEvent;
+use crate::history::payment_dictionary;
+use crate::resolution::lightning_payment_hash;
+use crate::storage::now_secs;
+use crate::BreezNode;
+
+/// Seconds between payment history checks while a wait is pending
+const WAIT_POLL_INTERVAL_SECS: u64 = 3;
+
+/// Number of recent payments searched for a waited-for invoice
+const WAIT_SCAN_LIMIT: u32 = 50;
+
+/// Invoices awaited with `wait_for_payment_async`, by request id
+#[derive(Default)]
+pub(crate) struct PaymentWaits {
+    waits: HashMap<i64, PaymentWait>,
+    last_poll: u64,
+    /// A history check is running on the runtime
+    checking: bool,
+}
+
+struct PaymentWait {
+    payment_hash: String,
+    /// Unix time the wait gives up at
+    deadline: u64,
+}
+
+#[godot_api(secondary)]
+impl BreezNode {
+    /// Wait in the background until a BOLT11 invoice has been paid to this wallet
+    ///
+    /// Returns a request id right away. `payment_confirmed` is emitted with it and the
+    /// payment (as in `list_payments`) once paid, or `payment_timeout` when `timeout_secs`
+    /// pass first; `request_completed` follows either way. An invoice paid before the call
+    /// is confirmed on the first check.
+    ///
+    /// # Arguments
+    /// * `payment_request` - The BOLT11 invoice to watch
+    /// * `timeout_secs` - Seconds to wait (0 to wait until the invoice expires)
+    #[func]
+    pub fn wait_for_payment_async(&self, payment_request: GString, timeout_secs: i64) -> i64 {
+        let request_id = self.begin_request("wait_for_payment");
+        let invoice = match parse_bolt11(payment_request.to_string().trim()) {
+            Ok(invoice) => invoice,
+            Err(e) => {
+                godot_error!("{}", e);
+                self.reject_request(request_id, e, "INVALID_ARGUMENT");
+                return request_id;
+            }
+        };
+        let deadline = if timeout_secs > 0 {
+            now_secs() + timeout_secs as u64
+        } else {
+            invoice.expires_at().map(|at| at.as_secs()).unwrap_or(u64::MAX)
+        };
+
+        let mut waits = self.payment_waits.lock().unwrap();
+        waits.waits.insert(
+            request_id,
+            PaymentWait {
+                payment_hash: payment_hash_hex(&invoice),
+                deadline,
+            },
+        );
+        // Check right away in case it was already paid
+        waits.last_poll = 0;
+        request_id
+    }
+
+    /// Stop waiting for a payment started with `wait_for_payment_async`
+    ///
+    /// No signal is emitted for a cancelled wait. Returns false if the request is unknown or
+    /// already done.
+    #[func]
+    pub fn cancel_wait(&self, request_id: i64) -> bool {
+        if self.payment_waits.lock().unwrap().waits.remove(&request_id).is_none() {
+            return false;
+        }
+        self.requests.lock().unwrap().remove(&request_id);
+        true
+    }
+}
+
+impl BreezNode {
+    /// Time out expired waits and start a history check when one is due; called every frame
+    pub(crate) fn poll_payment_waits(&mut self) {
+        let now = now_secs();
+        let expired: Vec<i64> = self
+            .payment_waits
+            .lock()
+            .unwrap()
+            .waits
+            .iter()
+            .filter(|(_, wait)| wait.deadline <= now)
+            .map(|(request_id, _)| *request_id)
+            .collect();
+        for request_id in expired {
+            self.payment_waits.lock().unwrap().waits.remove(&request_id);
+            self.signals().payment_timeout().emit(request_id);
+            let mut failure = Dictionary::new();
+            failure.set("success", false);
+            failure.set("error", "Timed out waiting for the payment");
+            failure.set("error_code", "TIMEOUT");
+            self.complete_request(request_id, "wait_for_payment", Err(failure));
+        }
+
+        let mut waits = self.payment_waits.lock().unwrap();
+        if waits.waits.is_empty() || waits.checking || now.saturating_sub(waits.last_poll) < WAIT_POLL_INTERVAL_SECS {
+            return;
+        }
+        waits.checking = true;
+        waits.last_poll = now;
+        drop(waits);
+
+        let sdk_handle = self.sdk_handle();
+        let events = self.events.sender();
+        self.runtime.spawn(async move {
+            let payments = match sdk_handle {
+                Ok(sdk) => sdk
+                    .list_payments(ListPaymentsRequest {
+                        offset: None,
+                        limit: Some(WAIT_SCAN_LIMIT),
+                    })
+                    .await
+                    .map(|response| response.payments)
+                    .unwrap_or_default(),
+                Err(_) => Vec::new(),
+            };
+            let _ = events.send(NodeEvent::PaymentWaitsChecked { payments });
+        });
+    }
+
+    /// Confirm the waits settled by `payments`
+    pub(crate) fn settle_payment_waits(&mut self, payments: &[Payment]) {
+        let mut settled = Vec::new();
+        {
+            let mut waits = self.payment_waits.lock().unwrap();
+            for payment in payments {
+                if payment.payment_type != PaymentType::Receive || payment.status != PaymentStatus::Completed {
+                    continue;
+                }
+                let Some(payment_hash) = lightning_payment_hash(payment) else {
+                    continue;
+                };
+                let matching: Vec<i64> = waits
+                    .waits
+                    .iter()
+                    .filter(|(_, wait)| wait.payment_hash == payment_hash)
+                    .map(|(request_id, _)| *request_id)
+                    .collect();
+                for request_id in matching {
+                    waits.waits.remove(&request_id);
+                    settled.push((request_id, payment));
+                }
+            }
+        }
+        if settled.is_empty() {
+            return;
+        }
+
+        let payers = self.zap_payers();
+        for (request_id, payment) in settled {
+            let dict = payment_dictionary(payment, &payers);
+            self.signals().payment_confirmed().emit(request_id, &dict);
+            self.complete_request(request_id, "wait_for_payment", Ok(dict.to_variant()));
+        }
+    }
+
+    /// Finish a background history check
+    pub(crate) fn on_payment_waits_checked(&mut self, payments: Vec<Payment>) {
+        self.payment_waits.lock().unwrap().checking = false;
+        self.settle_payment_waits(&payments);
+    }
+
+    /// Drop pending waits, e.g. when switching wallets
+    pub(crate) fn clear_payment_waits(&self) {
+        let mut waits = self.payment_waits.lock().unwrap();
+        let mut requests = self.requests.lock().unwrap();
+        for request_id in waits.waits.keys() {
+            requests.remove(request_id);
+        }
+        waits.waits.clear();
+    }
+}
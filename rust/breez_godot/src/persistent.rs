@@ -0,0 +1,91 @@
+use godot::prelude::*;
+use breez_sdk_spark::{BreezSdk, Config};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use crate::{lock_sdk, BreezNode};
+
+/// SDK handle kept alive across scene changes; the runtime is shared by all nodes anyway
+struct PersistentSession {
+    sdk: Arc<Mutex<Option<BreezSdk>>>,
+    storage_dir: PathBuf,
+    network: String,
+    config: Option<Config>,
+}
+
+/// Process-wide holder for the persistent connection
+static PERSISTENT_SESSION: Mutex<Option<PersistentSession>> = Mutex::new(None);
+
+#[godot_api(secondary)]
+impl BreezNode {
+    /// Keep the connection alive when this node is freed, e.g. by a scene change
+    ///
+    /// Must be enabled before connecting. A node created later picks the connection up with
+    /// `attach_existing`. Signals belong to each node, so connect to the new node's signals
+    /// after attaching. `disconnect_breez` still shuts the SDK down for good.
+    #[func]
+    pub fn set_persistent_connection(&mut self, enabled: bool) {
+        self.persistent = enabled;
+    }
+
+    /// Attach to the connection left behind by a freed node with a persistent connection
+    ///
+    /// Returns false if there is none.
+    #[func]
+    pub fn attach_existing(&mut self) -> bool {
+        let session = PERSISTENT_SESSION.lock().unwrap();
+        let Some(session) = session.as_ref().filter(|s| lock_sdk(&s.sdk).is_some()) else {
+            return false;
+        };
+
+        self.sdk = Arc::clone(&session.sdk);
+        self.persistent = true;
+        let storage_dir = session.storage_dir.clone();
+        let network = session.network.clone();
+        *self.sdk_config.lock().unwrap() = session.config.clone();
+        drop(session);
+
+        self.on_connected(storage_dir, network);
+        godot_print!("✅ Attached to existing Breez connection");
+        true
+    }
+}
+
+impl BreezNode {
+    /// Publish a fresh connection to the process-wide holder if persistence is enabled
+    pub(crate) fn publish_persistent_session(&self) {
+        if !self.persistent {
+            return;
+        }
+        let (Some(storage_dir), Some(network)) = (
+            self.wallet_dir.lock().unwrap().clone(),
+            self.wallet_network.lock().unwrap().clone(),
+        ) else {
+            return;
+        };
+        *PERSISTENT_SESSION.lock().unwrap() = Some(PersistentSession {
+            sdk: Arc::clone(&self.sdk),
+            storage_dir,
+            network,
+            config: self.sdk_config.lock().unwrap().clone(),
+        });
+    }
+
+    /// Drop the held connection if it is this node's, on explicit disconnect
+    pub(crate) fn release_persistent_session(&self) {
+        let mut session = PERSISTENT_SESSION.lock().unwrap();
+        if session.as_ref().is_some_and(|s| Arc::ptr_eq(&s.sdk, &self.sdk)) {
+            *session = None;
+        }
+    }
+
+    /// Stop this node's background work while leaving the held connection running
+    pub(crate) fn detach_persistent(&mut self) {
+        if !self.persistent {
+            return;
+        }
+        self.stop_price_ticker();
+        self.clear_server_webhook();
+        self.stop_snapshot_recorder();
+    }
+}
@@ -0,0 +1,198 @@
+use godot::prelude::*;
+use breez_sdk_spark::{SendPaymentMethod, SendPaymentOptions};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use crate::fees::{quoted_fee_sats, send_options_for};
+use crate::limits::{check_amount, AmountMethod};
+use crate::send::{invoice_amount, invoice_send_options, prepare_send, send_prepared, PreparedSend, SendOptions};
+use crate::storage::now_secs;
+use crate::validation::{validate_destination, DestinationKind};
+use crate::BreezNode;
+
+/// How long a fee quote from `prepare_payment` can be sent
+const PREPARED_TTL_SECS: u64 = 120;
+
+/// Options accepted by `send_prepared_payment`
+const SEND_PREPARED_OPTIONS: &[&str] = &["timeout_secs", "prefer_spark", "max_fee_sats"];
+
+static NEXT_PREPARE_ID: AtomicU64 = AtomicU64::new(1);
+
+/// A payment quoted by `prepare_payment`, waiting for `send_prepared_payment`
+pub(crate) struct PreparedPayment {
+    destination: String,
+    prepared: PreparedSend,
+    /// Hash of the invoice, None for addresses
+    payment_hash: Option<String>,
+    expires_at: u64,
+}
+
+fn prepared_error(error: String, code: &str) -> Dictionary {
+    godot_error!("{}", error);
+    let mut dict = Dictionary::new();
+    dict.set("success", false);
+    dict.set("error", error);
+    if !code.is_empty() {
+        dict.set("error_code", code);
+    }
+    dict
+}
+
+fn payment_method_name(method: &SendPaymentMethod) -> &'static str {
+    match method {
+        SendPaymentMethod::BitcoinAddress { .. } => "bitcoin_address",
+        SendPaymentMethod::Bolt11Invoice { .. } => "bolt11_invoice",
+        SendPaymentMethod::SparkAddress { .. } => "spark_address",
+    }
+}
+
+#[godot_api(secondary)]
+impl BreezNode {
+    /// Quote a payment without sending it, so its fee can be confirmed first
+    ///
+    /// Returns a Dictionary with `success`, `prepare_id`, `amount`, `fee_sats`,
+    /// `payment_method` ("bolt11_invoice", "bitcoin_address" or "spark_address") and
+    /// `expires_at`, plus `fee_warning` for on-chain payments. Pass `prepare_id` to
+    /// `send_prepared_payment` before it expires, after which a new quote is needed.
+    ///
+    /// # Arguments
+    /// * `payment_request` - BOLT11 invoice, Bitcoin address or Spark address
+    /// * `amount_sats` - Amount to send (0 for invoices that carry one)
+    #[func]
+    pub fn prepare_payment(&self, payment_request: GString, amount_sats: i64) -> Dictionary {
+        let result = self.prepare_payment_with(payment_request.to_string().trim().to_string(), amount_sats);
+        self.report_failure("prepare_payment", result)
+    }
+
+    /// Send a payment quoted by `prepare_payment`
+    ///
+    /// Options, all optional: `timeout_secs`, `prefer_spark` (invoices only) and
+    /// `max_fee_sats` (see `pay_invoice_opts`). Returns the same Dictionary as
+    /// `pay_invoice_opts`. A `prepare_id` can be sent once; an unknown one fails with
+    /// UNKNOWN_PREPARE_ID and an expired one with PREPARE_EXPIRED.
+    #[func]
+    pub fn send_prepared_payment(&self, prepare_id: GString, options: Dictionary) -> Dictionary {
+        let result = self.send_prepared_payment_with(&prepare_id.to_string(), &options);
+        self.report_failure("send_prepared_payment", result)
+    }
+}
+
+impl BreezNode {
+    fn prepare_payment_with(&self, destination: String, amount_sats: i64) -> Dictionary {
+        let lower = destination.to_lowercase();
+        let body = lower.strip_prefix("lightning:").unwrap_or(&lower);
+        let (amount, payment_hash) = if body.starts_with("lnbc") || body.starts_with("lntb") {
+            let mut opts = SendOptions::default();
+            opts.amount_sats = amount_sats;
+            match invoice_amount(&destination, &opts) {
+                Ok(checked) => checked,
+                Err(error) => return error,
+            }
+        } else {
+            let method = match validate_destination(&destination) {
+                Ok(DestinationKind::BitcoinAddress) => AmountMethod::Onchain,
+                Ok(DestinationKind::SparkAddress) => AmountMethod::Spark,
+                Ok(kind) => {
+                    return prepared_error(format!("Cannot prepare a payment to a {}", kind.as_str()), "INVALID_DESTINATION");
+                }
+                Err(e) => return prepared_error(e, "INVALID_DESTINATION"),
+            };
+            if let Err(error) = check_amount(method, amount_sats) {
+                return error;
+            }
+            (Some(amount_sats as u64), None)
+        };
+
+        let runtime = Arc::clone(&self.runtime);
+        let prepared = runtime.block_on(prepare_send(
+            self.sdk_handle(),
+            destination.clone(),
+            amount,
+            self.low_balance_threshold(),
+        ));
+        let (prepare_response, would_drop_below) = match prepared {
+            Ok(prepared) => prepared,
+            Err(e) => return prepared_error(e, ""),
+        };
+
+        let fee = quoted_fee_sats(&prepare_response);
+        let prepare_id = format!("prep-{:x}-{}", now_secs(), NEXT_PREPARE_ID.fetch_add(1, Ordering::Relaxed));
+        let expires_at = now_secs() + PREPARED_TTL_SECS;
+
+        let mut dict = Dictionary::new();
+        dict.set("success", true);
+        dict.set("prepare_id", prepare_id.as_str());
+        dict.set("amount", prepare_response.amount_sats as i64);
+        dict.set("fee_sats", fee as i64);
+        dict.set("payment_method", payment_method_name(&prepare_response.payment_method));
+        dict.set("expires_at", expires_at as i64);
+        if let Some(would_drop_below) = would_drop_below {
+            dict.set("would_drop_below_threshold", would_drop_below);
+        }
+        if matches!(prepare_response.payment_method, SendPaymentMethod::BitcoinAddress { .. }) {
+            let policy = self.fee_policy.lock().unwrap().clone();
+            dict.set("fee_warning", policy.warning(fee, prepare_response.amount_sats));
+        }
+
+        let mut prepared_payments = self.prepared_payments.lock().unwrap();
+        let now = now_secs();
+        prepared_payments.retain(|_, payment| payment.expires_at > now);
+        prepared_payments.insert(
+            prepare_id,
+            PreparedPayment {
+                destination,
+                prepared: (prepare_response, would_drop_below),
+                payment_hash,
+                expires_at,
+            },
+        );
+        dict
+    }
+
+    fn send_prepared_payment_with(&self, prepare_id: &str, options: &Dictionary) -> Dictionary {
+        let opts = match SendOptions::from_dictionary(options, SEND_PREPARED_OPTIONS) {
+            Ok(opts) => opts,
+            Err(error) => return error,
+        };
+        let Some(payment) = self.prepared_payments.lock().unwrap().remove(prepare_id) else {
+            return prepared_error(format!("Unknown prepare_id: {}", prepare_id), "UNKNOWN_PREPARE_ID");
+        };
+        if payment.expires_at <= now_secs() {
+            return prepared_error(
+                "The fee quote has expired; call prepare_payment again".to_string(),
+                "PREPARE_EXPIRED",
+            );
+        }
+        if let Err(locked) = self.check_unlocked() {
+            return locked;
+        }
+
+        let checked = match self.check_prepared(Ok(payment.prepared), &opts) {
+            Ok(checked) => checked,
+            Err(dict) => return dict,
+        };
+        let send_options = match (&checked.confirmation_speed, &checked.prepare_response.payment_method) {
+            (Some(speed), _) => Some(SendPaymentOptions::BitcoinAddress {
+                confirmation_speed: speed.clone(),
+            }),
+            (None, SendPaymentMethod::Bolt11Invoice { .. }) => invoice_send_options(&opts),
+            (None, _) => send_options_for(&checked.prepare_response),
+        };
+        let journal_id = self.journal_begin("send", &payment.destination, checked.prepare_response.amount_sats);
+        let runtime = Arc::clone(&self.runtime);
+        let result = runtime.block_on(send_prepared(
+            self.sdk_handle(),
+            Arc::clone(&self.in_flight),
+            checked.prepare_response,
+            send_options,
+        ));
+        let dict = self.finish_send(checked.dict, &journal_id, checked.cost, result);
+        self.track_invoice_result(dict, payment.payment_hash)
+    }
+
+    /// Drop quotes from `prepare_payment`, e.g. when switching wallets
+    pub(crate) fn clear_prepared_payments(&self) {
+        self.prepared_payments.lock().unwrap().clear();
+    }
+}
@@ -0,0 +1,195 @@
+use godot::prelude::*;
+
+use crate::fiat::RateQuote;
+use crate::BreezNode;
+
+/// Default distance from the threshold, in percent, the rate must move back before re-arming
+const DEFAULT_HYSTERESIS_PERCENT: f64 = 1.0;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Direction {
+    Above,
+    Below,
+}
+
+#[derive(Clone, Copy)]
+enum AlertState {
+    /// No rate seen since the alert was added
+    Waiting,
+    Armed,
+    Triggered,
+}
+
+impl AlertState {
+    fn as_str(&self) -> &'static str {
+        match self {
+            AlertState::Waiting => "waiting",
+            AlertState::Armed => "armed",
+            AlertState::Triggered => "triggered",
+        }
+    }
+}
+
+struct PriceAlert {
+    id: i64,
+    currency: String,
+    threshold: f64,
+    direction: Direction,
+    state: AlertState,
+    last_rate: Option<f64>,
+}
+
+impl PriceAlert {
+    fn crossed(&self, rate: f64) -> bool {
+        match self.direction {
+            Direction::Above => rate >= self.threshold,
+            Direction::Below => rate <= self.threshold,
+        }
+    }
+
+    fn rearmed(&self, rate: f64, hysteresis_percent: f64) -> bool {
+        let margin = self.threshold * hysteresis_percent / 100.0;
+        match self.direction {
+            Direction::Above => rate <= self.threshold - margin,
+            Direction::Below => rate >= self.threshold + margin,
+        }
+    }
+
+    /// Feed a new rate, returning true when the alert fires
+    fn update(&mut self, rate: f64, hysteresis_percent: f64) -> bool {
+        self.last_rate = Some(rate);
+        match self.state {
+            // An alert added while the rate is already past its threshold waits for a real crossing
+            AlertState::Waiting => {
+                self.state = if self.crossed(rate) { AlertState::Triggered } else { AlertState::Armed };
+                false
+            }
+            AlertState::Armed if self.crossed(rate) => {
+                self.state = AlertState::Triggered;
+                true
+            }
+            AlertState::Triggered if self.rearmed(rate, hysteresis_percent) => {
+                self.state = AlertState::Armed;
+                false
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Session price alerts, evaluated on price ticker updates
+pub(crate) struct PriceAlerts {
+    alerts: Vec<PriceAlert>,
+    next_id: i64,
+    hysteresis_percent: f64,
+}
+
+impl Default for PriceAlerts {
+    fn default() -> Self {
+        Self {
+            alerts: Vec::new(),
+            next_id: 1,
+            hysteresis_percent: DEFAULT_HYSTERESIS_PERCENT,
+        }
+    }
+}
+
+#[godot_api(secondary)]
+impl BreezNode {
+    /// Alert when the BTC rate in a currency crosses a threshold
+    ///
+    /// Alerts are checked on every `price_ticker_updated` and fire `price_alert_triggered`
+    /// once per crossing. After firing, an alert re-arms once the rate moves back past the
+    /// threshold by the hysteresis. Alerts last for the session.
+    ///
+    /// # Arguments
+    /// * `currency` - Currency code, e.g. "USD"
+    /// * `threshold` - Rate to watch
+    /// * `direction` - "above" or "below"
+    ///
+    /// Returns the alert id, or -1 if the arguments are invalid.
+    #[func]
+    pub fn add_price_alert(&mut self, currency: GString, threshold: f64, direction: GString) -> i64 {
+        let direction = match direction.to_string().trim().to_lowercase().as_str() {
+            "above" => Direction::Above,
+            "below" => Direction::Below,
+            other => {
+                godot_error!("Invalid price alert direction: {} (expected above or below)", other);
+                return -1;
+            }
+        };
+        let currency = currency.to_string().trim().to_uppercase();
+        if currency.is_empty() || !(threshold > 0.0) {
+            godot_error!("Invalid price alert: currency {:?} threshold {}", currency, threshold);
+            return -1;
+        }
+
+        let mut price_alerts = self.price_alerts.lock().unwrap();
+        let id = price_alerts.next_id;
+        price_alerts.next_id += 1;
+        price_alerts.alerts.push(PriceAlert {
+            id,
+            currency,
+            threshold,
+            direction,
+            state: AlertState::Waiting,
+            last_rate: None,
+        });
+        id
+    }
+
+    /// Remove a price alert by id
+    #[func]
+    pub fn remove_price_alert(&mut self, alert_id: i64) -> bool {
+        let mut price_alerts = self.price_alerts.lock().unwrap();
+        let before = price_alerts.alerts.len();
+        price_alerts.alerts.retain(|alert| alert.id != alert_id);
+        price_alerts.alerts.len() != before
+    }
+
+    /// List price alerts with `id`, `currency`, `threshold`, `direction`, `state`
+    /// ("waiting", "armed" or "triggered") and `last_rate` (0 until a rate is seen)
+    #[func]
+    pub fn list_price_alerts(&self) -> Array<Dictionary> {
+        let mut array = Array::new();
+        for alert in &self.price_alerts.lock().unwrap().alerts {
+            let mut dict = Dictionary::new();
+            dict.set("id", alert.id);
+            dict.set("currency", alert.currency.as_str());
+            dict.set("threshold", alert.threshold);
+            dict.set("direction", if alert.direction == Direction::Above { "above" } else { "below" });
+            dict.set("state", alert.state.as_str());
+            dict.set("last_rate", alert.last_rate.unwrap_or(0.0));
+            array.push(&dict);
+        }
+        array
+    }
+
+    /// Set how far, in percent of the threshold, the rate must move back before an alert re-arms
+    #[func]
+    pub fn set_price_alert_hysteresis(&mut self, percent: f64) {
+        self.price_alerts.lock().unwrap().hysteresis_percent = percent.max(0.0);
+    }
+}
+
+impl BreezNode {
+    /// Check alerts against a ticker update, returning (alert id, currency, rate) for each that fired
+    ///
+    /// Stale quotes are skipped so a failed refresh cannot trigger alerts.
+    pub(crate) fn evaluate_price_alerts(&self, quotes: &[(String, RateQuote)]) -> Vec<(i64, String, f64)> {
+        let mut price_alerts = self.price_alerts.lock().unwrap();
+        let hysteresis_percent = price_alerts.hysteresis_percent;
+        let mut fired = Vec::new();
+
+        for alert in price_alerts.alerts.iter_mut() {
+            let Some((_, quote)) = quotes.iter().find(|(code, quote)| *code == alert.currency && !quote.stale) else {
+                continue;
+            };
+            if alert.update(quote.rate, hysteresis_percent) {
+                fired.push((alert.id, alert.currency.clone(), quote.rate));
+            }
+        }
+
+        fired
+    }
+}
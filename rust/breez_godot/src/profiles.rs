@@ -0,0 +1,184 @@
+use godot::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::network_storage::{has_wallet_data, parse_network, NETWORKS};
+use crate::storage::{globalize_path, load_json, now_secs, save_json};
+use crate::{BreezNode, ConnectOptions};
+
+/// Marker file identifying a directory as a wallet profile
+const PROFILE_FILE: &str = "profile.json";
+
+/// Default base directory for profiles
+pub(crate) const DEFAULT_PROFILES_DIR: &str = "user://breez_profiles";
+
+#[derive(Serialize, Deserialize)]
+struct ProfileInfo {
+    name: String,
+    network: String,
+    created_at: u64,
+}
+
+/// Profile names become directory names, so only allow a safe subset
+fn validate_profile_name(name: &str) -> Result<(), String> {
+    let valid = !name.is_empty()
+        && name.len() <= 64
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "Invalid profile name: {:?} (use 1-64 letters, digits, '_' or '-')",
+            name
+        ))
+    }
+}
+
+#[godot_api(secondary)]
+impl BreezNode {
+    /// Set the base directory profiles are stored under (`user://` paths are supported)
+    #[func]
+    pub fn set_profiles_dir(&mut self, path: GString) {
+        *self.profiles_dir.lock().unwrap() = path.to_string();
+    }
+
+    /// Connect to the wallet of a profile, e.g. one per save slot
+    ///
+    /// Wallet data lives in `<profiles_dir>/<network>/<profile_name>/`, so profiles of
+    /// different networks never share a directory. Any connected wallet is disconnected
+    /// first and its cached state dropped.
+    ///
+    /// # Arguments
+    /// * `profile_name` - Letters, digits, '_' or '-'
+    /// * `mnemonic` - 12 or 24 word BIP39 mnemonic phrase of this profile's wallet
+    /// * `api_key` - Your Breez API key
+    /// * `network` - "mainnet" or "regtest"
+    #[func]
+    pub fn connect_profile(
+        &mut self,
+        profile_name: GString,
+        mnemonic: GString,
+        api_key: GString,
+        network: GString,
+    ) -> bool {
+        let name = profile_name.to_string();
+        let network = match validate_profile_name(&name).and_then(|_| parse_network(&network.to_string())) {
+            Ok(network) => network.to_string(),
+            Err(e) => {
+                godot_error!("{}", e);
+                return false;
+            }
+        };
+
+        self.disconnect_breez();
+
+        let dir = self.profile_dir(&network, &name);
+        let marker = dir.join(PROFILE_FILE);
+        if load_json::<ProfileInfo>(&marker).is_none() {
+            let info = ProfileInfo {
+                name: name.clone(),
+                network: network.clone(),
+                created_at: now_secs(),
+            };
+            if let Err(e) = save_json(&marker, &info) {
+                godot_error!("Failed to create profile {}: {}", name, e);
+                return false;
+            }
+        }
+
+        godot_print!("Connecting profile {} ({})", name, network);
+        self.connect_with(
+            mnemonic.to_string(),
+            None,
+            api_key.to_string(),
+            network,
+            dir.to_string_lossy().to_string(),
+            ConnectOptions::default(),
+            |_config| {},
+        )
+        .is_ok()
+    }
+
+    /// List profiles with `name`, `network`, `created_at` and `has_wallet`
+    #[func]
+    pub fn list_profiles(&self) -> Array<Dictionary> {
+        let mut array = Array::new();
+        let base = self.profiles_base();
+
+        for network in NETWORKS {
+            let Ok(entries) = fs::read_dir(base.join(network)) else {
+                continue;
+            };
+            let mut profiles: Vec<(ProfileInfo, bool)> = entries
+                .flatten()
+                .filter_map(|entry| {
+                    let dir = entry.path();
+                    load_json::<ProfileInfo>(&dir.join(PROFILE_FILE)).map(|info| (info, has_wallet_data(&dir)))
+                })
+                .collect();
+            profiles.sort_by(|a, b| a.0.name.cmp(&b.0.name));
+
+            for (info, has_wallet) in profiles {
+                let mut dict = Dictionary::new();
+                dict.set("name", info.name);
+                dict.set("network", info.network);
+                dict.set("created_at", info.created_at as i64);
+                dict.set("has_wallet", has_wallet);
+                array.push(&dict);
+            }
+        }
+
+        array
+    }
+
+    /// Delete a profile and all of its wallet data
+    ///
+    /// Refuses to delete the connected profile or any directory that is not a profile.
+    /// Funds are only recoverable afterwards with the profile's mnemonic.
+    #[func]
+    pub fn delete_profile(&mut self, profile_name: GString, network: GString) -> bool {
+        let name = profile_name.to_string();
+        let network = match validate_profile_name(&name).and_then(|_| parse_network(&network.to_string())) {
+            Ok(network) => network.to_string(),
+            Err(e) => {
+                godot_error!("{}", e);
+                return false;
+            }
+        };
+
+        let dir = self.profile_dir(&network, &name);
+        if !dir.join(PROFILE_FILE).is_file() {
+            godot_error!("Profile {} ({}) does not exist", name, network);
+            return false;
+        }
+        if self.wallet_dir.lock().unwrap().as_deref() == Some(dir.as_path()) {
+            godot_error!("Cannot delete the connected profile {}; disconnect first", name);
+            return false;
+        }
+
+        if network == "mainnet" {
+            godot_warn!("⚠️ Deleting MAINNET profile {}", name);
+        }
+        match fs::remove_dir_all(&dir) {
+            Ok(()) => {
+                godot_print!("Deleted profile {} ({})", name, network);
+                true
+            }
+            Err(e) => {
+                godot_error!("Failed to delete profile {}: {}", name, e);
+                false
+            }
+        }
+    }
+}
+
+impl BreezNode {
+    fn profiles_base(&self) -> PathBuf {
+        PathBuf::from(globalize_path(&self.profiles_dir.lock().unwrap()))
+    }
+
+    fn profile_dir(&self, network: &str, name: &str) -> PathBuf {
+        self.profiles_base().join(network).join(name)
+    }
+}
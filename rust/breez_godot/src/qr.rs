@@ -0,0 +1,119 @@
+use godot::classes::image::{Format, Interpolation};
+use godot::classes::Image;
+use godot::prelude::*;
+
+use crate::utils::classify_input;
+use crate::BreezNode;
+
+/// Frames are scaled down to this longest side before scanning, which keeps misses cheap
+const MAX_SCAN_SIDE: i32 = 800;
+
+/// Stretch the grey levels to the full range, which helps with dim, low-contrast frames
+fn normalize_contrast(pixels: &mut [u8]) {
+    let (min, max) = pixels
+        .iter()
+        .fold((u8::MAX, u8::MIN), |(min, max), &p| (min.min(p), max.max(p)));
+    if max <= min {
+        return;
+    }
+    let range = (max - min) as u32;
+    for p in pixels.iter_mut() {
+        *p = ((*p - min) as u32 * 255 / range) as u8;
+    }
+}
+
+/// Area of a detected code from its corner points (shoelace formula)
+fn quad_area(points: &[rqrr::Point; 4]) -> f64 {
+    let mut twice = 0i64;
+    for i in 0..4 {
+        let (a, b) = (points[i], points[(i + 1) % 4]);
+        twice += a.x as i64 * b.y as i64 - b.x as i64 * a.y as i64;
+    }
+    twice.abs() as f64 / 2.0
+}
+
+fn quad_center(points: &[rqrr::Point; 4]) -> (f64, f64) {
+    let x = points.iter().map(|p| p.x as f64).sum::<f64>() / 4.0;
+    let y = points.iter().map(|p| p.y as f64).sum::<f64>() / 4.0;
+    (x, y)
+}
+
+/// Greyscale pixels of an image, scaled down for scanning
+fn scan_pixels(image: &Gd<Image>) -> Option<(usize, usize, Vec<u8>)> {
+    let mut frame = image.duplicate()?.try_cast::<Image>().ok()?;
+    if frame.is_empty() {
+        return None;
+    }
+    if frame.is_compressed() {
+        frame.decompress();
+    }
+    frame.convert(Format::L8);
+
+    let (width, height) = (frame.get_width(), frame.get_height());
+    let longest = width.max(height);
+    if longest > MAX_SCAN_SIDE {
+        let scale = MAX_SCAN_SIDE as f64 / longest as f64;
+        frame
+            .resize_ex(
+                ((width as f64 * scale) as i32).max(1),
+                ((height as f64 * scale) as i32).max(1),
+            )
+            .interpolation(Interpolation::BILINEAR)
+            .done();
+    }
+
+    let (width, height) = (frame.get_width() as usize, frame.get_height() as usize);
+    let mut pixels = frame.get_data().to_vec();
+    if pixels.len() < width * height {
+        return None;
+    }
+    pixels.truncate(width * height);
+    normalize_contrast(&mut pixels);
+    Some((width, height, pixels))
+}
+
+#[godot_api(secondary)]
+impl BreezNode {
+    /// Scan an Image (e.g. a camera frame) for a payment QR code
+    ///
+    /// Rotated codes are handled. When several codes are visible the largest wins, with
+    /// ties going to the one closest to the centre. Returns a Dictionary with `success`
+    /// and, when a code was found, `text` plus the `parse_input` classification under `parsed`.
+    #[func]
+    pub fn decode_qr_from_image(image: Gd<Image>) -> Dictionary {
+        let mut dict = Dictionary::new();
+
+        let Some((width, height, pixels)) = scan_pixels(&image) else {
+            dict.set("success", false);
+            dict.set("error", "Image is empty");
+            return dict;
+        };
+
+        let mut prepared = rqrr::PreparedImage::prepare_from_greyscale(width, height, |x, y| pixels[y * width + x]);
+        let (cx, cy) = (width as f64 / 2.0, height as f64 / 2.0);
+
+        let best = prepared
+            .detect_grids()
+            .into_iter()
+            .filter_map(|grid| {
+                let area = quad_area(&grid.bounds);
+                let (x, y) = quad_center(&grid.bounds);
+                let distance = (x - cx).hypot(y - cy);
+                grid.decode().ok().map(|(_, text)| (area, distance, text))
+            })
+            .max_by(|a, b| a.0.total_cmp(&b.0).then(b.1.total_cmp(&a.1)));
+
+        match best {
+            Some((_, _, text)) => {
+                dict.set("success", true);
+                dict.set("parsed", classify_input(&text));
+                dict.set("text", text);
+            }
+            None => {
+                dict.set("success", false);
+                dict.set("error", "No QR code found");
+            }
+        }
+        dict
+    }
+}
@@ -0,0 +1,245 @@
+use godot::prelude::*;
+use bitcoin::hashes::{sha256, Hash};
+use bitcoin::secp256k1::{ecdsa::Signature, Message, PublicKey, Secp256k1};
+use breez_sdk_spark::{PaymentDetails, SignMessageRequest};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::BreezNode;
+
+/// Serialize receipt fields as compact JSON with sorted keys
+fn canonical_json(fields: &BTreeMap<&str, serde_json::Value>) -> String {
+    serde_json::to_string(fields).expect("receipt fields are always serializable")
+}
+
+/// Verify a receipt signature made by `sign_message`
+///
+/// The signature is an ECDSA signature over sha256(receipt_json), hex-encoded either as DER
+/// or as a 64-byte compact signature. The receipt must be in canonical form (compact JSON
+/// with sorted keys) so that equivalent receipts cannot be re-encoded.
+pub fn verify_signed_receipt(receipt_json: &str, signature_hex: &str, pubkey_hex: &str) -> bool {
+    let Ok(parsed) = serde_json::from_str::<BTreeMap<String, serde_json::Value>>(receipt_json) else {
+        return false;
+    };
+    let Ok(canonical) = serde_json::to_string(&parsed) else {
+        return false;
+    };
+    if canonical != receipt_json {
+        return false;
+    }
+
+    let Ok(pubkey) = PublicKey::from_str(pubkey_hex.trim()) else {
+        return false;
+    };
+    let Ok(signature_bytes) = hex::decode(signature_hex.trim()) else {
+        return false;
+    };
+    let signature = if signature_bytes.len() == 64 {
+        Signature::from_compact(&signature_bytes)
+    } else {
+        Signature::from_der(&signature_bytes)
+    };
+    let Ok(mut signature) = signature else {
+        return false;
+    };
+    signature.normalize_s();
+
+    let digest = sha256::Hash::hash(receipt_json.as_bytes());
+    let message = Message::from_digest(digest.to_byte_array());
+    Secp256k1::verification_only()
+        .verify_ecdsa(&message, &signature, &pubkey)
+        .is_ok()
+}
+
+#[godot_api(secondary)]
+impl BreezNode {
+    /// Sign a message with the wallet's identity key
+    ///
+    /// Returns `success`, `signature` (hex) and `pubkey` (hex).
+    #[func]
+    pub fn sign_message(&self, message: GString, compact: bool) -> Dictionary {
+        let result = self.sign_message_with(message, compact);
+        self.report_failure("sign_message", result)
+    }
+
+    /// Create a receipt for a payment that a game server can verify
+    ///
+    /// The receipt is canonical JSON (payment_id, payment_hash, preimage, amount, timestamp,
+    /// nonce) signed with the wallet key. Binding the server-issued nonce prevents replaying an
+    /// old receipt for a new order. Returns `receipt`, `signature` and `pubkey`.
+    #[func]
+    pub fn create_signed_receipt(&self, payment_id: GString, server_nonce: GString) -> Dictionary {
+        let result = self.create_signed_receipt_with(payment_id, server_nonce);
+        self.report_failure("create_signed_receipt", result)
+    }
+
+    /// Verify a receipt from `create_signed_receipt` (no connection needed)
+    #[func]
+    pub fn verify_receipt(receipt_json: GString, signature: GString, pubkey: GString) -> bool {
+        verify_signed_receipt(&receipt_json.to_string(), &signature.to_string(), &pubkey.to_string())
+    }
+}
+
+impl BreezNode {
+    fn sign_message_with(&self, message: GString, compact: bool) -> Dictionary {
+        if let Err(error) = self.require_capability("receipts") {
+            return error;
+        }
+        let mut dict = Dictionary::new();
+
+        match self.sign_message_inner(message.to_string(), compact) {
+            Ok((signature, pubkey)) => {
+                dict.set("success", true);
+                dict.set("signature", signature);
+                dict.set("pubkey", pubkey);
+            }
+            Err(e) => {
+                godot_error!("{}", e);
+                dict.set("success", false);
+                dict.set("error", e);
+            }
+        }
+
+        dict
+    }
+
+    fn create_signed_receipt_with(&self, payment_id: GString, server_nonce: GString) -> Dictionary {
+        if let Err(error) = self.require_capability("receipts") {
+            return error;
+        }
+        let mut dict = Dictionary::new();
+        let payment_id = payment_id.to_string();
+        let nonce = server_nonce.to_string();
+
+        if nonce.is_empty() {
+            dict.set("success", false);
+            dict.set("error", "Server nonce is empty");
+            return dict;
+        }
+
+        let payment = match self.fetch_all_payments() {
+            Ok(payments) => payments.into_iter().find(|p| p.id == payment_id),
+            Err(e) => {
+                godot_error!("{}", e);
+                dict.set("success", false);
+                dict.set("error", e);
+                return dict;
+            }
+        };
+        let Some(payment) = payment else {
+            dict.set("success", false);
+            dict.set("error", "Payment not found");
+            return dict;
+        };
+
+        let (payment_hash, preimage) = match &payment.details {
+            Some(PaymentDetails::Lightning { payment_hash, preimage, .. }) => {
+                (payment_hash.clone(), preimage.clone().unwrap_or_default())
+            }
+            _ => (String::new(), String::new()),
+        };
+
+        let mut fields = BTreeMap::new();
+        fields.insert("amount", serde_json::json!(payment.amount as u64));
+        fields.insert("nonce", serde_json::json!(nonce));
+        fields.insert("payment_hash", serde_json::json!(payment_hash));
+        fields.insert("payment_id", serde_json::json!(payment.id));
+        fields.insert("preimage", serde_json::json!(preimage));
+        fields.insert("timestamp", serde_json::json!(payment.timestamp));
+        let receipt = canonical_json(&fields);
+
+        match self.sign_message_inner(receipt.clone(), false) {
+            Ok((signature, pubkey)) => {
+                dict.set("success", true);
+                dict.set("receipt", receipt);
+                dict.set("signature", signature);
+                dict.set("pubkey", pubkey);
+            }
+            Err(e) => {
+                godot_error!("{}", e);
+                dict.set("success", false);
+                dict.set("error", e);
+            }
+        }
+
+        dict
+    }
+
+    pub(crate) fn sign_message_inner(&self, message: String, compact: bool) -> Result<(String, String), String> {
+        let sdk_handle = self.sdk_handle();
+        let runtime = Arc::clone(&self.runtime);
+
+        runtime.block_on(async move {
+            let sdk = sdk_handle?;
+            match sdk.sign_message(SignMessageRequest { message, compact }).await {
+                Ok(response) => Ok((response.signature, response.pubkey)),
+                Err(e) => Err(format!("Failed to sign message: {:?}", e)),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::secp256k1::SecretKey;
+
+    fn receipt() -> String {
+        let mut fields = BTreeMap::new();
+        fields.insert("amount", serde_json::json!(2_100));
+        fields.insert("nonce", serde_json::json!("order-42"));
+        fields.insert("payment_id", serde_json::json!("pay_1"));
+        canonical_json(&fields)
+    }
+
+    /// Sign like the SDK: ECDSA over sha256 of the message
+    fn sign(message: &str, key_byte: u8) -> (Signature, String) {
+        let secp = Secp256k1::new();
+        let key = SecretKey::from_slice(&[key_byte; 32]).unwrap();
+        let digest = sha256::Hash::hash(message.as_bytes());
+        let signature = secp.sign_ecdsa(&Message::from_digest(digest.to_byte_array()), &key);
+        (signature, PublicKey::from_secret_key(&secp, &key).to_string())
+    }
+
+    #[test]
+    fn der_and_compact_signatures_verify() {
+        let receipt = receipt();
+        let (signature, pubkey) = sign(&receipt, 0x11);
+        assert!(verify_signed_receipt(&receipt, &hex::encode(signature.serialize_der()), &pubkey));
+        assert!(verify_signed_receipt(&receipt, &hex::encode(signature.serialize_compact()), &pubkey));
+    }
+
+    #[test]
+    fn tampered_receipt_fails() {
+        let receipt = receipt();
+        let (signature, pubkey) = sign(&receipt, 0x11);
+        let tampered = receipt.replace("2100", "21000");
+        assert!(!verify_signed_receipt(&tampered, &hex::encode(signature.serialize_compact()), &pubkey));
+    }
+
+    #[test]
+    fn other_key_fails() {
+        let receipt = receipt();
+        let (signature, _) = sign(&receipt, 0x11);
+        let (_, other_pubkey) = sign(&receipt, 0x22);
+        assert!(!verify_signed_receipt(&receipt, &hex::encode(signature.serialize_compact()), &other_pubkey));
+    }
+
+    #[test]
+    fn non_canonical_receipt_fails_even_when_signed() {
+        let reordered = r#"{"payment_id":"pay_1","amount":2100,"nonce":"order-42"}"#;
+        let (signature, pubkey) = sign(reordered, 0x11);
+        assert!(!verify_signed_receipt(reordered, &hex::encode(signature.serialize_compact()), &pubkey));
+    }
+
+    #[test]
+    fn malformed_inputs_fail() {
+        let receipt = receipt();
+        let (signature, pubkey) = sign(&receipt, 0x11);
+        let signature = hex::encode(signature.serialize_compact());
+        assert!(!verify_signed_receipt("not json", &signature, &pubkey));
+        assert!(!verify_signed_receipt(&receipt, "zz", &pubkey));
+        assert!(!verify_signed_receipt(&receipt, &signature, "02abcd"));
+    }
+}
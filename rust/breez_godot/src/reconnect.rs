@@ -0,0 +1,138 @@
+use godot::prelude::*;
+use breez_sdk_spark::{BreezSdk, Config};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::connect_errors::ConnectError;
+use crate::events::NodeEvent;
+use crate::storage::release_wallet_dirs;
+use crate::{connect_request, disconnect_request, lock_sdk, BreezNode};
+
+/// How long a health check waits for the Breez service
+const PING_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// What a successful connect opened the wallet with, so `reconnect` can open it again
+#[derive(Clone)]
+pub(crate) struct ConnectCredentials {
+    pub(crate) config: Config,
+    pub(crate) mnemonic: String,
+    pub(crate) passphrase: Option<String>,
+    /// Wallet directory of the network, as handed to the SDK
+    pub(crate) storage_dir: String,
+    pub(crate) network: String,
+}
+
+/// Cheap round trip to the Breez service, bounded by `PING_TIMEOUT`
+pub(crate) async fn ping(sdk: &BreezSdk) -> Result<(), String> {
+    match tokio::time::timeout(PING_TIMEOUT, sdk.list_fiat_rates()).await {
+        Ok(Ok(_)) => Ok(()),
+        Ok(Err(e)) => Err(format!("Breez service error: {:?}", e)),
+        Err(_) => Err(format!("Breez service did not answer within {} s", PING_TIMEOUT.as_secs())),
+    }
+}
+
+#[godot_api(secondary)]
+impl BreezNode {
+    /// Open the wallet again with the credentials of the last successful connect, e.g. after
+    /// the game returns from the background with a dead connection
+    ///
+    /// The SDK session is replaced in place, so auto-sync and other background work carry on
+    /// with the new one. Returns the Dictionary of `connect_sdk_detailed`; fails with
+    /// NOT_CONNECTED when nothing was connected since the last `disconnect_breez` (a connection
+    /// picked up with `attach_existing` cannot be reconnected either). A failed reconnect
+    /// leaves the wallet disconnected but can be retried.
+    #[func]
+    pub fn reconnect(&mut self) -> Dictionary {
+        let dict = match self.reconnect_with() {
+            Ok(()) => {
+                let mut dict = Dictionary::new();
+                dict.set("success", true);
+                dict
+            }
+            Err(e) => e.to_dictionary(),
+        };
+        self.report_failure("reconnect", dict)
+    }
+
+    /// Check whether the Breez service can be reached, with a 5 s timeout
+    ///
+    /// Returns `success`, `reachable` and `latency_ms`, plus `error` when unreachable, or
+    /// NOT_CONNECTED without a wallet. Emits `connection_lost` or `connection_restored` when
+    /// the answer differs from the previous check.
+    #[func]
+    pub fn check_connection(&self) -> Dictionary {
+        let mut dict = Dictionary::new();
+        let sdk = match self.sdk_handle() {
+            Ok(sdk) => sdk,
+            Err(e) => {
+                dict.set("success", false);
+                dict.set("error", e);
+                dict.set("error_code", "NOT_CONNECTED");
+                return self.report_failure("check_connection", dict);
+            }
+        };
+        let started = Instant::now();
+        let result = self.runtime.block_on(ping(&sdk));
+        dict.set("success", true);
+        dict.set("reachable", result.is_ok());
+        dict.set("latency_ms", started.elapsed().as_millis() as i64);
+        if let Err(e) = &result {
+            dict.set("error", e.as_str());
+        }
+        let _ = self.events.sender().send(NodeEvent::ConnectionChecked { result });
+        dict
+    }
+}
+
+impl BreezNode {
+    fn reconnect_with(&mut self) -> Result<(), ConnectError> {
+        if self.pending_connect.lock().unwrap().is_some() {
+            return Err(ConnectError::new(
+                "A connect_sdk_async call is still in progress".to_string(),
+                "CONNECT_IN_PROGRESS",
+            ));
+        }
+        let Some(credentials) = self.connect_credentials.lock().unwrap().clone() else {
+            return Err(ConnectError::new("No earlier connection to re-establish".to_string(), "NOT_CONNECTED"));
+        };
+        godot_print!("Reconnecting to Breez Spark SDK...");
+
+        self.unregister_event_listener();
+        let runtime = Arc::clone(&self.runtime);
+        let sdk = lock_sdk(&self.sdk).take();
+        release_wallet_dirs(self.wallet_owner());
+        if let Some(sdk) = sdk {
+            // The old session is usually broken already, so a failed shutdown is expected
+            if let Err((e, _)) = runtime.block_on(disconnect_request(sdk, Arc::clone(&self.in_flight))) {
+                godot_warn!("{}", e);
+            }
+        }
+        let reachable = *self.connection_reachable.lock().unwrap();
+        let result = runtime.block_on(connect_request(&credentials));
+        self.finish_connect(result, credentials).map_err(ConnectError::from_sdk)?;
+        // Counts as restored only when a health check saw the connection lost
+        *self.connection_reachable.lock().unwrap() = reachable;
+        self.on_connection_checked(Ok(()));
+        Ok(())
+    }
+
+    /// Emit `connection_lost` or `connection_restored` when reachability changed
+    pub(crate) fn on_connection_checked(&mut self, result: Result<(), String>) {
+        if !self.is_sdk_connected() {
+            // A check finishing after disconnect_breez
+            return;
+        }
+        let previous = self.connection_reachable.lock().unwrap().replace(result.is_ok());
+        match (previous, result) {
+            (Some(true) | None, Err(e)) => {
+                godot_warn!("⚠️ Lost the connection to the Breez service: {}", e);
+                self.signals().connection_lost().emit(&GString::from(&e));
+            }
+            (Some(false), Ok(())) => {
+                godot_print!("✅ Connection to the Breez service restored");
+                self.signals().connection_restored().emit();
+            }
+            _ => {}
+        }
+    }
+}
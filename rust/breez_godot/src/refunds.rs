@@ -0,0 +1,145 @@
+use godot::prelude::*;
+use breez_sdk_spark::{BreezSdk, Fee, RefundDepositRequest};
+use std::sync::Arc;
+
+use crate::deposits::{deposit_error, ClaimFee};
+use crate::fees::CLAIM_TX_VBYTES;
+use crate::limits::AmountMethod;
+use crate::validation::{validate_bitcoin_address, validate_bitcoin_address_for, validate_outpoint};
+use crate::BreezNode;
+
+/// Broadcast a deposit refund on the runtime, after any other operation moving funds
+async fn refund_request(
+    sdk_handle: Result<BreezSdk, String>,
+    in_flight: Arc<tokio::sync::Mutex<()>>,
+    request: RefundDepositRequest,
+) -> Result<String, String> {
+    let sdk = sdk_handle?;
+    let _in_flight = in_flight.lock().await;
+    match sdk.refund_deposit(request).await {
+        Ok(response) => Ok(response.tx_id),
+        Err(e) => Err(format!("Failed to refund deposit: {:?}", e)),
+    }
+}
+
+#[godot_api(secondary)]
+impl BreezNode {
+    /// Send an unclaimed deposit back out to a Bitcoin address instead of claiming it, e.g.
+    /// when it is too small to claim or was sent by mistake
+    ///
+    /// Returns `success`, `refund_txid`, `amount_sats` (the deposit) and `fee_sats` (the
+    /// estimated refund fee). Fails with INVALID_INPUT for a malformed outpoint or an address
+    /// of another network, INVALID_OPTION for a bad `fee`, DEPOSIT_NOT_FOUND when the deposit
+    /// is not unclaimed, and BELOW_DUST (with `amount_sats` and `fee_sats`) when too little
+    /// would be left after the fee for the output to be relayed. The deposit amount counts
+    /// against the throttle policy like any other payment.
+    ///
+    /// # Arguments
+    /// * `txid` - Deposit transaction ID
+    /// * `vout` - Deposit output index
+    /// * `destination_address` - Bitcoin address receiving the refund
+    /// * `fee` - `{"type": "fixed", "amount": n}` in sats or `{"type": "rate", "sat_per_vbyte": n}`;
+    ///   empty uses the recommended half-hour rate
+    #[func]
+    pub fn refund_deposit(
+        &self,
+        txid: GString,
+        vout: i64,
+        destination_address: GString,
+        fee: Dictionary,
+    ) -> Dictionary {
+        let result = self.refund_deposit_with(
+            txid.to_string().trim(),
+            vout,
+            destination_address.to_string().trim(),
+            &fee,
+        );
+        self.report_failure("refund_deposit", result)
+    }
+}
+
+impl BreezNode {
+    fn refund_deposit_with(&self, txid: &str, vout: i64, destination: &str, fee: &Dictionary) -> Dictionary {
+        let vout = match validate_outpoint(txid, vout) {
+            Ok(vout) => vout,
+            Err(e) => return deposit_error(e, "INVALID_INPUT"),
+        };
+        let network = self.wallet_network.lock().unwrap().clone();
+        let valid = match &network {
+            Some(network) => validate_bitcoin_address_for(destination, network),
+            None => validate_bitcoin_address(destination),
+        };
+        if let Err(e) = valid {
+            return deposit_error(e, "INVALID_INPUT");
+        }
+        if let Err(locked) = self.check_unlocked() {
+            return locked;
+        }
+        // Unlike a claim, a refund has no fee limit to fall back on, so it needs an actual fee
+        let fee = match ClaimFee::from_dictionary(fee).map(ClaimFee::to_sdk) {
+            Ok(Some(fee)) => fee,
+            Ok(None) => match self.fetch_recommended_fees() {
+                Ok(fees) => Fee::Rate { sat_per_vbyte: fees.half_hour_fee.max(1) },
+                Err(e) => return deposit_error(e, "NETWORK_ERROR"),
+            },
+            Err(e) => return deposit_error(e, "INVALID_OPTION"),
+        };
+
+        let deposit = match self.fetch_unclaimed_deposits() {
+            Ok(deposits) => deposits.into_iter().find(|d| d.txid == txid && d.vout == vout),
+            Err(e) => return deposit_error(e, ""),
+        };
+        let Some(deposit) = deposit else {
+            return deposit_error(format!("No unclaimed deposit {}:{}", txid, vout), "DEPOSIT_NOT_FOUND");
+        };
+
+        // A refund spends the deposit output much like a claim does
+        let fee_sats = match fee {
+            Fee::Fixed { amount } => amount,
+            Fee::Rate { sat_per_vbyte } => sat_per_vbyte.saturating_mul(CLAIM_TX_VBYTES),
+        };
+        let dust_limit = AmountMethod::Onchain.min_sats();
+        if deposit.amount_sats < fee_sats.saturating_add(dust_limit) {
+            let mut error = deposit_error(
+                format!(
+                    "The {} sats deposit minus the {} sats fee is below the {} sats dust limit",
+                    deposit.amount_sats, fee_sats, dust_limit
+                ),
+                "BELOW_DUST",
+            );
+            error.set("amount_sats", deposit.amount_sats as i64);
+            error.set("fee_sats", fee_sats as i64);
+            return error;
+        }
+        if let Err(throttled) = self.check_throttle(deposit.amount_sats, false) {
+            return throttled;
+        }
+
+        let request = RefundDepositRequest {
+            txid: txid.to_string(),
+            vout,
+            destination_address: destination.to_string(),
+            fee,
+        };
+        let runtime = Arc::clone(&self.runtime);
+        let journal_id = self.journal_begin("refund", &format!("{}:{}", txid, vout), deposit.amount_sats);
+        let result = runtime.block_on(refund_request(self.sdk_handle(), Arc::clone(&self.in_flight), request));
+        self.journal_end(&journal_id);
+        match result {
+            Ok(refund_txid) => {
+                godot_print!("✅ Deposit refunded in {}", refund_txid);
+                self.refresh_low_balance();
+                let mut dict = Dictionary::new();
+                dict.set("success", true);
+                dict.set("refund_txid", refund_txid);
+                dict.set("amount_sats", deposit.amount_sats as i64);
+                dict.set("fee_sats", fee_sats as i64);
+                dict
+            }
+            Err(e) => {
+                self.release_throttled_send(deposit.amount_sats, deposit.amount_sats);
+                deposit_error(e, "")
+            }
+        }
+    }
+}
@@ -0,0 +1,341 @@
+use godot::prelude::*;
+use breez_sdk_spark::{GetInfoRequest, ListPaymentsRequest, Payment, SyncWalletRequest};
+use std::future::Future;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+
+use crate::deposits::{unconfirmed_deposit_error, ClaimFee};
+use crate::events::NodeEvent;
+use crate::history::payment_dictionary;
+use crate::limits::{check_amount, AmountMethod};
+use crate::storage::now_secs;
+use crate::validation::{check_description, validate_outpoint};
+use crate::{claim_deposit_request, receive_bolt11, BreezNode};
+
+/// Methods `call_async` can run, with their arguments
+const ASYNC_METHODS: &[(&str, &str)] = &[
+    ("get_balance", ""),
+    ("sync_wallet", ""),
+    ("list_payments", "offset, limit"),
+    ("create_invoice", "amount_sats, description"),
+    ("claim_deposit", "txid, vout, max_fee_sats"),
+    ("pay_invoice", "bolt11, timeout_secs"),
+];
+
+static NEXT_REQUEST_ID: AtomicI64 = AtomicI64::new(1);
+
+/// Id for a new background request, shared by every async method so ids never repeat
+fn next_request_id() -> i64 {
+    NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Outcome of a request's background work, finished on the main thread
+pub(crate) enum RequestStep {
+    /// Refused before any work started: error and error code
+    Rejected(String, &'static str),
+    Balance(Result<u64, String>),
+    Synced(Result<(), String>),
+    Payments(Result<Vec<Payment>, String>),
+    Invoice(Result<String, String>),
+    /// Confirmations a deposit still needs before it is claimed
+    DepositChecked(Result<u64, String>),
+    DepositClaimed(Result<Payment, String>),
+}
+
+/// A request started by `call_async` or an `_async` method, until `request_completed`
+pub(crate) struct PendingRequest {
+    method: &'static str,
+    started_at: u64,
+    /// Deposit being claimed: txid, vout, fee limit and the journal id once claiming
+    claim: Option<(String, u32, ClaimFee, String)>,
+}
+
+/// Failure Dictionary for an async request
+fn request_error(error: String, code: &str) -> Dictionary {
+    godot_error!("{}", error);
+    let mut dict = Dictionary::new();
+    dict.set("success", false);
+    dict.set("error", error);
+    if !code.is_empty() {
+        dict.set("error_code", code);
+    }
+    dict
+}
+
+fn arg<T: FromGodot>(args: &Array<Variant>, index: usize, name: &str) -> Result<T, String> {
+    args.get(index)
+        .ok_or_else(|| format!("Missing argument '{}'", name))?
+        .try_to::<T>()
+        .map_err(|_| format!("Invalid argument '{}'", name))
+}
+
+#[godot_api(secondary)]
+impl BreezNode {
+    /// Run a long-running method in the background
+    ///
+    /// Returns a request id right away, and `request_completed` is emitted with it once the
+    /// method is done. Supported methods and their `args`:
+    /// * `get_balance`, `sync_wallet` - no arguments
+    /// * `list_payments` - `[offset, limit]`
+    /// * `create_invoice` - `[amount_sats, description]`
+    /// * `claim_deposit` - `[txid, vout, max_fee_sats]`
+    /// * `pay_invoice` - `[bolt11, timeout_secs]`, also emits `payment_result`
+    ///
+    /// Unknown methods and invalid arguments complete with UNKNOWN_METHOD or
+    /// INVALID_ARGUMENT on the next frame, so every id gets exactly one `request_completed`.
+    #[func]
+    pub fn call_async(&self, method: GString, args: Array<Variant>) -> i64 {
+        let method = method.to_string();
+        let Some((name, expected)) = ASYNC_METHODS.iter().find(|(name, _)| *name == method) else {
+            let valid: Vec<&str> = ASYNC_METHODS.iter().map(|(name, _)| *name).collect();
+            let request_id = self.begin_request("call_async");
+            self.reject_request(
+                request_id,
+                format!("Unknown async method '{}' (valid: {})", method, valid.join(", ")),
+                "UNKNOWN_METHOD",
+            );
+            return request_id;
+        };
+        if *name == "pay_invoice" {
+            return match (arg::<GString>(&args, 0, "bolt11"), arg::<i64>(&args, 1, "timeout_secs")) {
+                (Ok(bolt11), Ok(timeout_secs)) => self.pay_invoice_async(bolt11, timeout_secs),
+                (Err(e), _) | (_, Err(e)) => {
+                    let request_id = self.begin_request(name);
+                    self.reject_request(request_id, format!("{} (args: [{}])", e, expected), "INVALID_ARGUMENT");
+                    request_id
+                }
+            };
+        }
+
+        let request_id = self.begin_request(name);
+        if let Err(e) = self.start_request(request_id, name, &args) {
+            self.reject_request(request_id, format!("{} (args: [{}])", e, expected), "INVALID_ARGUMENT");
+        }
+        request_id
+    }
+
+    /// Requests started with `call_async` or an `_async` method that have not completed,
+    /// each with `request_id`, `method` and `started_at`
+    #[func]
+    pub fn get_pending_requests(&self) -> Array<Dictionary> {
+        let requests = self.requests.lock().unwrap();
+        let mut ids: Vec<&i64> = requests.keys().collect();
+        ids.sort();
+        let mut array = Array::new();
+        for id in ids {
+            let request = &requests[id];
+            let mut dict = Dictionary::new();
+            dict.set("request_id", *id);
+            dict.set("method", request.method);
+            dict.set("started_at", request.started_at as i64);
+            array.push(&dict);
+        }
+        array
+    }
+}
+
+impl BreezNode {
+    /// Register a new request and return its id
+    pub(crate) fn begin_request(&self, method: &'static str) -> i64 {
+        let request_id = next_request_id();
+        self.requests.lock().unwrap().insert(
+            request_id,
+            PendingRequest {
+                method,
+                started_at: now_secs(),
+                claim: None,
+            },
+        );
+        request_id
+    }
+
+    /// Complete a request with a failure on the next frame
+    pub(crate) fn reject_request(&self, request_id: i64, error: String, code: &'static str) {
+        self.events.push(NodeEvent::Request {
+            request_id,
+            step: RequestStep::Rejected(error, code),
+        });
+    }
+
+    /// Run `work` on the runtime and hand its outcome back as a request step
+    fn spawn_request<F>(&self, request_id: i64, work: F)
+    where
+        F: Future<Output = RequestStep> + Send + 'static,
+    {
+        let events = self.events.sender();
+        self.runtime.spawn(async move {
+            let step = work.await;
+            let _ = events.send(NodeEvent::Request { request_id, step });
+        });
+    }
+
+    fn start_request(&self, request_id: i64, method: &str, args: &Array<Variant>) -> Result<(), String> {
+        let sdk_handle = self.sdk_handle();
+        match method {
+            "get_balance" => self.spawn_request(request_id, async move {
+                let result = async {
+                    sdk_handle?
+                        .get_info(GetInfoRequest {
+                            ensure_synced: Some(true),
+                        })
+                        .await
+                        .map(|info| info.balance_sats)
+                        .map_err(|e| format!("Failed to get balance: {:?}", e))
+                };
+                RequestStep::Balance(result.await)
+            }),
+            "sync_wallet" => self.spawn_request(request_id, async move {
+                let result = async {
+                    sdk_handle?
+                        .sync_wallet(SyncWalletRequest {})
+                        .await
+                        .map(|_| ())
+                        .map_err(|e| format!("Failed to sync: {:?}", e))
+                };
+                RequestStep::Synced(result.await)
+            }),
+            "list_payments" => {
+                let offset = arg::<i64>(args, 0, "offset")?;
+                let limit = arg::<i64>(args, 1, "limit")?;
+                self.spawn_request(request_id, async move {
+                    let result = async {
+                        sdk_handle?
+                            .list_payments(ListPaymentsRequest {
+                                offset: (offset > 0).then_some(offset as u32),
+                                limit: (limit > 0).then_some(limit as u32),
+                            })
+                            .await
+                            .map(|response| response.payments)
+                            .map_err(|e| format!("Failed to list payments: {:?}", e))
+                    };
+                    RequestStep::Payments(result.await)
+                });
+            }
+            "create_invoice" => {
+                let amount_sats = arg::<i64>(args, 0, "amount_sats")?;
+                let description = arg::<GString>(args, 1, "description")?.to_string();
+                if amount_sats != 0 && check_amount(AmountMethod::Lightning, amount_sats).is_err() {
+                    return Err(format!("Amount {} sats is outside the Lightning limits", amount_sats));
+                }
+                let description = check_description(&description, false).map_err(|e| e.message())?.0;
+                self.spawn_request(request_id, async move {
+                    let invoice = receive_bolt11(sdk_handle, amount_sats, description).await;
+                    RequestStep::Invoice(invoice.map(|(invoice, _)| invoice))
+                });
+            }
+            "claim_deposit" => {
+                let txid = arg::<GString>(args, 0, "txid")?.to_string();
+                let vout = arg::<i64>(args, 1, "vout")?;
+                let fee = ClaimFee::from_max_fee_sats(arg::<i64>(args, 2, "max_fee_sats")?);
+                // Checked before queueing, as the synchronous claim_deposit does
+                let vout = validate_outpoint(&txid, vout)?;
+                let lookup = self.confirmations_remaining_lookup(&txid);
+                if let Some(request) = self.requests.lock().unwrap().get_mut(&request_id) {
+                    request.claim = Some((txid, vout, fee, String::new()));
+                }
+                self.spawn_request(request_id, async move { RequestStep::DepositChecked(lookup.await) });
+            }
+            _ => unreachable!("async method listed but not handled: {}", method),
+        }
+        Ok(())
+    }
+
+    /// Finish a request step on the main thread, emitting `request_completed` once the
+    /// request is done
+    pub(crate) fn advance_request(&mut self, request_id: i64, step: RequestStep) {
+        let Some(method) = self.requests.lock().unwrap().get(&request_id).map(|request| request.method) else {
+            return;
+        };
+        let result = match step {
+            RequestStep::Rejected(error, code) => Err(request_error(error, code)),
+            RequestStep::Balance(result) => result
+                .map(|balance| (balance as i64).to_variant())
+                .map_err(|e| request_error(e, "")),
+            RequestStep::Synced(result) => result
+                .map(|()| {
+                    self.on_synced();
+                    true.to_variant()
+                })
+                .map_err(|e| request_error(e, "")),
+            RequestStep::Payments(result) => result
+                .map(|payments| {
+                    let payers = self.zap_payers();
+                    let mut array = Array::<Dictionary>::new();
+                    for payment in &payments {
+                        array.push(&payment_dictionary(payment, &payers));
+                    }
+                    array.to_variant()
+                })
+                .map_err(|e| request_error(e, "")),
+            RequestStep::Invoice(result) => result
+                .map(|invoice| {
+                    godot_print!("✅ Invoice created");
+                    self.register_invoice(&invoice);
+                    invoice.to_variant()
+                })
+                .map_err(|e| request_error(e, "")),
+            RequestStep::DepositChecked(remaining) => {
+                let claim = self.requests.lock().unwrap().get(&request_id).and_then(|r| r.claim.clone());
+                let Some((txid, vout, fee, _)) = claim else {
+                    return;
+                };
+                match remaining {
+                    Ok(0) => {
+                        let journal_id = self.journal_begin("claim", &format!("{}:{}", txid, vout), 0);
+                        if let Some(request) = self.requests.lock().unwrap().get_mut(&request_id) {
+                            request.claim = Some((txid.clone(), vout, fee, journal_id));
+                        }
+                        let claim = claim_deposit_request(self.sdk_handle(), Arc::clone(&self.in_flight), txid, vout, fee);
+                        self.spawn_request(request_id, async move { RequestStep::DepositClaimed(claim.await) });
+                        return;
+                    }
+                    remaining => Err(unconfirmed_deposit_error(&txid, remaining)),
+                }
+            }
+            RequestStep::DepositClaimed(result) => {
+                let claim = self.requests.lock().unwrap().get(&request_id).and_then(|r| r.claim.clone());
+                let Some((txid, vout, fee, journal_id)) = claim else {
+                    return;
+                };
+                self.journal_end(&journal_id);
+                if let Ok(payment) = &result {
+                    self.on_deposit_claimed(&txid, vout, payment);
+                }
+                let dict = self.claim_result_dictionary(&txid, vout, fee, result);
+                Ok(dict.to_variant())
+            }
+        };
+        self.complete_request(request_id, method, result);
+    }
+
+    /// Emit `request_completed` for a finished request
+    ///
+    /// `result` is the value the synchronous method returns, or a failure Dictionary. A
+    /// Dictionary value with `success: false` (e.g. a failed claim) fails the request too.
+    pub(crate) fn complete_request(&mut self, request_id: i64, method: &str, result: Result<Variant, Dictionary>) {
+        self.requests.lock().unwrap().remove(&request_id);
+        let mut dict = match result {
+            Ok(value) => {
+                let mut dict = Dictionary::new();
+                let failure = value
+                    .try_to::<Dictionary>()
+                    .ok()
+                    .filter(|result| result.get("success").is_some_and(|success| !success.booleanize()));
+                dict.set("success", failure.is_none());
+                if let Some(failure) = failure {
+                    for key in ["error", "error_code", "correlation_id"] {
+                        if let Some(field) = failure.get(key) {
+                            dict.set(key, &field);
+                        }
+                    }
+                }
+                dict.set("result", &value);
+                dict
+            }
+            Err(failure) => failure,
+        };
+        dict.set("method", method);
+        let dict = self.report_failure(method, dict);
+        self.signals().request_completed().emit(request_id, &dict);
+    }
+}
@@ -0,0 +1,276 @@
+use godot::prelude::*;
+use breez_sdk_spark::{ListPaymentsRequest, Payment, PaymentDetails, PaymentStatus};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::events::NodeEvent;
+use crate::storage::now_secs;
+use crate::BreezNode;
+
+/// Seconds between status checks of payments with an unknown outcome
+const POLL_INTERVAL_SECS: u64 = 5;
+
+/// Number of recent payments searched for a tracked payment hash
+const RESOLVE_SCAN_LIMIT: u32 = 100;
+
+/// How long an unresolved payment is tracked before `payment_resolved` reports "unknown"
+const GIVE_UP_SECS: u64 = 24 * 3_600;
+
+/// Outgoing payments whose outcome was not known when the send call returned
+#[derive(Default)]
+pub(crate) struct PaymentResolution {
+    tracked: HashMap<String, Tracked>,
+    last_poll: u64,
+}
+
+struct Tracked {
+    since: u64,
+    /// The send call failed, so webhook bookkeeping still has to happen if the payment
+    /// completes (the throttle counted it when it was checked)
+    timed_out: bool,
+}
+
+impl PaymentResolution {
+    /// Start tracking a payment, keeping the original start time if it is tracked already
+    fn track(&mut self, payment_hash: String, timed_out: bool, now: u64) {
+        self.tracked.entry(payment_hash).or_insert(Tracked { since: now, timed_out });
+    }
+
+    /// Whether tracked payments should be looked up at `now`, recording the poll if so
+    fn poll_due(&mut self, now: u64) -> bool {
+        if self.tracked.is_empty() || now < self.last_poll + POLL_INTERVAL_SECS {
+            return false;
+        }
+        self.last_poll = now;
+        true
+    }
+
+    /// Stop tracking a payment seen with `status`, returning whether the send bookkeeping
+    /// that a timed-out call skipped is still owed, or None when it was not tracked
+    fn settle(&mut self, payment_hash: &str, status: &PaymentStatus) -> Option<bool> {
+        let tracked = self.tracked.remove(payment_hash)?;
+        Some(tracked.timed_out && *status == PaymentStatus::Completed)
+    }
+
+    /// Stop tracking payments older than `GIVE_UP_SECS`, returning their hashes
+    fn take_expired(&mut self, now: u64) -> Vec<String> {
+        let expired = self
+            .tracked
+            .iter()
+            .filter(|(_, tracked)| now > tracked.since + GIVE_UP_SECS)
+            .map(|(hash, _)| hash.clone())
+            .collect::<Vec<_>>();
+        for hash in &expired {
+            self.tracked.remove(hash);
+        }
+        expired
+    }
+}
+
+/// Whether a send error means the payment timed out rather than failed
+pub(crate) fn is_timeout_error(error: &str) -> bool {
+    let lower = error.to_lowercase();
+    lower.contains("timeout") || lower.contains("timed out")
+}
+
+pub(crate) fn lightning_payment_hash(payment: &Payment) -> Option<&str> {
+    match &payment.details {
+        Some(PaymentDetails::Lightning { payment_hash, .. }) => Some(payment_hash.as_str()),
+        _ => None,
+    }
+}
+
+#[godot_api(secondary)]
+impl BreezNode {
+    /// Check the current status of an outgoing Lightning payment by its hash
+    ///
+    /// Returns a Dictionary with `success`, `found`, `final` and, when found, `payment_id`
+    /// and `status`. A final outcome stops tracking the payment and emits `payment_resolved`.
+    #[func]
+    pub fn resolve_payment(&self, payment_hash: GString) -> Dictionary {
+        let payment_hash = payment_hash.to_string().trim().to_lowercase();
+        let mut dict = Dictionary::new();
+
+        match self.fetch_recent_payments() {
+            Ok(payments) => {
+                let found = payments
+                    .into_iter()
+                    .find(|payment| lightning_payment_hash(payment) == Some(payment_hash.as_str()));
+                dict.set("success", true);
+                match found {
+                    Some(payment) => {
+                        let is_final = payment.status != PaymentStatus::Pending;
+                        dict.set("found", true);
+                        dict.set("final", is_final);
+                        dict.set("payment_id", payment.id.clone());
+                        dict.set("status", payment.status.to_string());
+                        if is_final {
+                            self.settle_tracked_payment(&payment_hash, &payment);
+                        }
+                    }
+                    None => {
+                        dict.set("found", false);
+                        dict.set("final", false);
+                    }
+                }
+            }
+            Err(e) => {
+                godot_error!("{}", e);
+                dict.set("success", false);
+                dict.set("error", e);
+            }
+        }
+
+        self.report_failure("resolve_payment", dict)
+    }
+}
+
+impl BreezNode {
+    /// Keep checking a payment until it completes or fails
+    pub(crate) fn track_payment(&self, payment_hash: String, timed_out: bool) {
+        self.resolution.lock().unwrap().track(payment_hash, timed_out, now_secs());
+    }
+
+    /// Forget tracked payments, e.g. when switching wallets
+    pub(crate) fn clear_tracked_payments(&self) {
+        *self.resolution.lock().unwrap() = PaymentResolution::default();
+    }
+
+    /// Look up tracked payments every few seconds, called every frame
+    pub(crate) fn poll_tracked_payments(&self) {
+        let now = now_secs();
+        if !self.resolution.lock().unwrap().poll_due(now) {
+            return;
+        }
+
+        let payments = match self.fetch_recent_payments() {
+            Ok(payments) => payments,
+            Err(e) => {
+                godot_warn!("Payment status check failed: {}", e);
+                return;
+            }
+        };
+        for payment in &payments {
+            if payment.status == PaymentStatus::Pending {
+                continue;
+            }
+            if let Some(payment_hash) = lightning_payment_hash(payment) {
+                self.settle_tracked_payment(payment_hash, payment);
+            }
+        }
+
+        let expired = self.resolution.lock().unwrap().take_expired(now);
+        for payment_hash in expired {
+            godot_warn!("Gave up tracking payment {}", payment_hash);
+            let payment_id = payments
+                .iter()
+                .find(|payment| lightning_payment_hash(payment) == Some(payment_hash.as_str()))
+                .map(|payment| payment.id.clone())
+                .unwrap_or_default();
+            self.events.push(NodeEvent::PaymentResolved {
+                payment_id,
+                status: "unknown".to_string(),
+            });
+        }
+    }
+
+    /// Stop tracking a payment that reached a final status and report the outcome
+    fn settle_tracked_payment(&self, payment_hash: &str, payment: &Payment) {
+        let Some(owes_send_bookkeeping) = self.resolution.lock().unwrap().settle(payment_hash, &payment.status) else {
+            return;
+        };
+        if payment.status == PaymentStatus::Completed {
+            if owes_send_bookkeeping {
+                self.queue_webhook("payment_sent", payment);
+            }
+            self.invalidate_balance_history();
+            self.refresh_low_balance();
+            self.record_balance_snapshot();
+        }
+        self.events.push(NodeEvent::PaymentResolved {
+            payment_id: payment.id.clone(),
+            status: payment.status.to_string(),
+        });
+    }
+
+    fn fetch_recent_payments(&self) -> Result<Vec<Payment>, String> {
+        let sdk_handle = self.sdk_handle();
+        let runtime = Arc::clone(&self.runtime);
+
+        runtime.block_on(async move {
+            let sdk = sdk_handle?;
+            match sdk.list_payments(ListPaymentsRequest {
+                offset: None,
+                limit: Some(RESOLVE_SCAN_LIMIT),
+            }).await {
+                Ok(response) => Ok(response.payments),
+                Err(e) => Err(format!("Failed to list payments: {:?}", e)),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HASH: &str = "3f2a";
+
+    #[test]
+    fn timeout_errors_are_recognised() {
+        assert!(is_timeout_error("Payment timed out after 60s"));
+        assert!(is_timeout_error("SdkError(Timeout)"));
+        assert!(!is_timeout_error("Insufficient funds"));
+    }
+
+    #[test]
+    fn timeout_then_success_owes_the_skipped_bookkeeping_once() {
+        let mut resolution = PaymentResolution::default();
+        resolution.track(HASH.to_string(), true, 100);
+
+        assert!(resolution.poll_due(100));
+        assert_eq!(resolution.settle(HASH, &PaymentStatus::Completed), Some(true));
+        // Later polls see the payment again but must not resolve it twice
+        assert_eq!(resolution.settle(HASH, &PaymentStatus::Completed), None);
+        assert!(!resolution.poll_due(200), "nothing left to poll");
+    }
+
+    #[test]
+    fn timeout_then_failure_resolves_without_bookkeeping() {
+        let mut resolution = PaymentResolution::default();
+        resolution.track(HASH.to_string(), true, 100);
+
+        assert_eq!(resolution.settle(HASH, &PaymentStatus::Failed), Some(false));
+        assert!(resolution.take_expired(100 + GIVE_UP_SECS + 1).is_empty());
+    }
+
+    #[test]
+    fn pending_send_that_completes_was_already_recorded() {
+        let mut resolution = PaymentResolution::default();
+        resolution.track(HASH.to_string(), false, 100);
+        assert_eq!(resolution.settle(HASH, &PaymentStatus::Completed), Some(false));
+    }
+
+    #[test]
+    fn polls_are_spaced_by_the_interval() {
+        let mut resolution = PaymentResolution::default();
+        assert!(!resolution.poll_due(100), "no poll without tracked payments");
+
+        resolution.track(HASH.to_string(), true, 100);
+        assert!(resolution.poll_due(100));
+        assert!(!resolution.poll_due(100 + POLL_INTERVAL_SECS - 1));
+        assert!(resolution.poll_due(100 + POLL_INTERVAL_SECS));
+    }
+
+    #[test]
+    fn unresolved_payment_is_given_up_after_a_day() {
+        let mut resolution = PaymentResolution::default();
+        resolution.track(HASH.to_string(), true, 100);
+        // Tracking it again keeps the original start time
+        resolution.track(HASH.to_string(), true, 5_000);
+
+        assert!(resolution.take_expired(100 + GIVE_UP_SECS).is_empty());
+        assert_eq!(resolution.take_expired(100 + GIVE_UP_SECS + 1), vec![HASH.to_string()]);
+        assert_eq!(resolution.settle(HASH, &PaymentStatus::Completed), None);
+    }
+}
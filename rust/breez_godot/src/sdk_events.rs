@@ -0,0 +1,87 @@
+use godot::prelude::*;
+use breez_sdk_spark::{DepositInfo, EventListener, PaymentType, SdkEvent};
+use std::sync::mpsc::Sender;
+
+use crate::events::NodeEvent;
+use crate::history::payment_dictionary;
+use crate::BreezNode;
+
+/// Listener registered with the SDK while connected, handing its events to the main thread
+///
+/// It only holds a sender, so an event arriving after the node is freed is dropped.
+struct EventForwarder {
+    events: Sender<NodeEvent>,
+}
+
+#[async_trait::async_trait]
+impl EventListener for EventForwarder {
+    async fn on_event(&self, event: SdkEvent) {
+        let _ = self.events.send(NodeEvent::Sdk(event));
+    }
+}
+
+fn deposit_dictionary(deposit: &DepositInfo) -> Dictionary {
+    let mut dict = Dictionary::new();
+    dict.set("txid", deposit.txid.as_str());
+    dict.set("vout", deposit.vout as i64);
+    dict.set("amount_sats", deposit.amount_sats as i64);
+    dict
+}
+
+impl BreezNode {
+    /// Register this node's SDK event listener, replacing any previous one
+    pub(crate) fn register_event_listener(&self) {
+        self.unregister_event_listener();
+        let Ok(sdk) = self.sdk_handle() else {
+            return;
+        };
+        let listener = EventForwarder {
+            events: self.events.sender(),
+        };
+        let id = self.runtime.block_on(sdk.add_event_listener(Box::new(listener)));
+        *self.event_listener.lock().unwrap() = Some(id);
+    }
+
+    /// Remove this node's SDK event listener, if registered
+    pub(crate) fn unregister_event_listener(&self) {
+        let Some(id) = self.event_listener.lock().unwrap().take() else {
+            return;
+        };
+        if let Ok(sdk) = self.sdk_handle() {
+            self.runtime.block_on(sdk.remove_event_listener(&id));
+        }
+    }
+
+    /// Emit the signal for an SDK event, reporting failed payments and deposit claims as errors
+    pub(crate) fn emit_sdk_event(&mut self, event: SdkEvent) {
+        match event {
+            SdkEvent::Synced => self.signals().synced().emit(),
+            SdkEvent::PaymentSucceeded { payment } => {
+                self.settle_payment_waits(std::slice::from_ref(&payment));
+                let dict = payment_dictionary(&payment, &self.zap_payers());
+                if payment.payment_type == PaymentType::Receive {
+                    self.signals().payment_received().emit(&dict);
+                } else {
+                    self.signals().payment_sent().emit(&dict);
+                }
+            }
+            SdkEvent::ClaimedDeposits { claimed_deposits } => {
+                for deposit in &claimed_deposits {
+                    self.signals().deposit_claimed().emit(&deposit_dictionary(deposit));
+                }
+            }
+            SdkEvent::PaymentFailed { payment } => {
+                self.report_background_error("event_listener", &format!("Payment failed: {}", payment.id));
+            }
+            SdkEvent::UnclaimedDeposits { unclaimed_deposits } => {
+                for deposit in &unclaimed_deposits {
+                    if let Some(error) = &deposit.claim_error {
+                        let message = format!("Failed to claim deposit {}:{}: {:?}", deposit.txid, deposit.vout, error);
+                        self.report_background_error("event_listener", &message);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
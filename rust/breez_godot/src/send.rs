@@ -0,0 +1,715 @@
+use godot::prelude::*;
+use breez_sdk_spark::{
+    BreezSdk, GetInfoRequest, OnchainConfirmationSpeed, PaymentDetails, PaymentStatus, PrepareSendPaymentRequest,
+    PrepareSendPaymentResponse, SendPaymentMethod, SendPaymentOptions, SendPaymentRequest, SendPaymentResponse,
+};
+use std::sync::Arc;
+
+use crate::bolt12::{is_bolt12_offer, offer_in_invoice_error};
+use crate::fees::{onchain_fee_sats, onchain_speed, quoted_fee_sats, send_options_for, speed_for_rate, speed_name, OnchainFee};
+use crate::limits::{amount_to_sats, check_amount, AmountMethod, AMOUNT_UNITS};
+use crate::resolution::is_timeout_error;
+use crate::validation::{
+    validate_bitcoin_address, validate_bitcoin_address_for, validate_destination, validate_invoice_prefix,
+    validate_spark_address, DestinationKind,
+};
+use crate::BreezNode;
+
+/// Options accepted by `pay_invoice_opts`
+const INVOICE_OPTIONS: &[&str] = &["amount_sats", "amount", "timeout_secs", "dry_run", "prefer_spark", "max_fee_sats"];
+
+/// Options accepted by `send_spark_payment_opts`
+const ADDRESS_OPTIONS: &[&str] = &["amount_sats", "amount", "dry_run", "max_fee_sats"];
+
+/// Options accepted by `send_onchain_opts`
+const ONCHAIN_OPTIONS: &[&str] = &["amount_sats", "amount", "dry_run", "max_fee_sats", "fee"];
+
+/// Options accepted by `pay_opts` for lightning addresses and LNURLs
+const LNURL_OPTIONS: &[&str] = &["amount_sats", "amount", "dry_run", "max_fee_sats"];
+
+/// Options `pay` passes to `pay_opts`, which every destination kind has to accept
+const PAY_OPTIONS: [&str; 2] = ["amount_sats", "dry_run"];
+
+/// First key of `keys` that is not in `valid`
+fn unknown_option<'a>(keys: impl IntoIterator<Item = &'a str>, valid: &[&str]) -> Option<&'a str> {
+    keys.into_iter().find(|key| !valid.contains(key))
+}
+
+/// Options shared by every send path, parsed from a GDScript Dictionary
+///
+/// Defaults match the positional methods: no amount, the SDK's completion timeout,
+/// a real send, Lightning routing and no fee cap.
+#[derive(Default, Clone)]
+pub(crate) struct SendOptions {
+    /// Amount in sats (only used for amountless invoices on the invoice path)
+    pub(crate) amount_sats: i64,
+    /// `amount` option as (value, unit), converted by `resolve_amount` once the rail is known
+    amount: Option<(String, String)>,
+    pub(crate) timeout_secs: i64,
+    pub(crate) dry_run: bool,
+    pub(crate) prefer_spark: bool,
+    /// Largest acceptable fee (0 for no cap)
+    pub(crate) max_fee_sats: u64,
+    /// Fee for an on-chain send (None for medium confirmation speed)
+    pub(crate) onchain_fee: Option<OnchainFee>,
+}
+
+impl SendOptions {
+    /// Parse `options`, rejecting keys outside `valid` with UNKNOWN_OPTION
+    pub(crate) fn from_dictionary(options: &Dictionary, valid: &[&str]) -> Result<Self, Dictionary> {
+        let mut parsed = SendOptions::default();
+
+        for (key, value) in options.iter_shared() {
+            let key = key.to_string();
+            if unknown_option([key.as_str()], valid).is_some() {
+                let mut dict = option_error(
+                    format!("Unknown option '{}' (valid: {})", key, valid.join(", ")),
+                    "UNKNOWN_OPTION",
+                );
+                dict.set("valid_options", valid.iter().map(|k| GString::from(*k)).collect::<PackedStringArray>());
+                return Err(dict);
+            }
+            let invalid = || option_error(format!("Invalid value for option '{}': {}", key, value), "INVALID_OPTION");
+            match key.as_str() {
+                "amount_sats" => parsed.amount_sats = value.try_to::<i64>().map_err(|_| invalid())?,
+                "amount" => parsed.amount = Some(parse_amount_option(&value).ok_or_else(invalid)?),
+                "timeout_secs" => parsed.timeout_secs = value.try_to::<i64>().map_err(|_| invalid())?,
+                "dry_run" => parsed.dry_run = value.try_to::<bool>().map_err(|_| invalid())?,
+                "prefer_spark" => parsed.prefer_spark = value.try_to::<bool>().map_err(|_| invalid())?,
+                "max_fee_sats" => {
+                    let max_fee = value.try_to::<i64>().map_err(|_| invalid())?;
+                    if max_fee < 0 {
+                        return Err(invalid());
+                    }
+                    parsed.max_fee_sats = max_fee as u64;
+                }
+                "fee" => parsed.onchain_fee = Some(parse_fee_option(&value).ok_or_else(invalid)?),
+                _ => unreachable!("option listed as valid but not handled: {}", key),
+            }
+        }
+
+        if parsed.amount.is_some() && options.contains_key("amount_sats") {
+            return Err(option_error(
+                "Options 'amount' and 'amount_sats' cannot be combined".to_string(),
+                "INVALID_OPTION",
+            ));
+        }
+        Ok(parsed)
+    }
+
+    /// Convert the `amount` option into `amount_sats` for the rail the payment goes over
+    pub(crate) fn resolve_amount(&mut self, method: AmountMethod) -> Result<(), Dictionary> {
+        if let Some((value, unit)) = &self.amount {
+            self.amount_sats = amount_to_sats(value, unit, method)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse an `amount` option: `{value, unit}` with an int or decimal string value
+///
+/// Floats are refused so BTC amounts cannot lose precision on the way in.
+fn parse_amount_option(value: &Variant) -> Option<(String, String)> {
+    let dict = value.try_to::<Dictionary>().ok()?;
+    let unit = match dict.get("unit") {
+        Some(unit) => unit.try_to::<GString>().ok()?.to_string().trim().to_lowercase(),
+        None => "sat".to_string(),
+    };
+    if !AMOUNT_UNITS.contains(&unit.as_str()) {
+        return None;
+    }
+    let value = dict.get("value")?;
+    let value = match value.get_type() {
+        VariantType::INT => value.to::<i64>().to_string(),
+        VariantType::STRING => value.to::<GString>().to_string(),
+        _ => return None,
+    };
+    Some((value, unit))
+}
+
+/// Parse a `fee` option: `{"type": "fixed", "amount": n}` or `{"type": "rate", "sat_per_vbyte": n}`
+fn parse_fee_option(value: &Variant) -> Option<OnchainFee> {
+    let dict = value.try_to::<Dictionary>().ok()?;
+    let kind = dict.get("type")?.try_to::<GString>().ok()?.to_string();
+    let number = |key: &str| dict.get(key)?.try_to::<i64>().ok().filter(|n| *n >= 0).map(|n| n as u64);
+    match kind.as_str() {
+        "fixed" => Some(OnchainFee::Fixed(number("amount")?)),
+        "rate" => Some(OnchainFee::Rate(number("sat_per_vbyte")?.max(1))),
+        _ => None,
+    }
+}
+
+fn option_error(error: String, code: &str) -> Dictionary {
+    godot_error!("{}", error);
+    let mut dict = Dictionary::new();
+    dict.set("success", false);
+    dict.set("error", error);
+    dict.set("error_code", code);
+    dict
+}
+
+#[godot_api(secondary)]
+impl BreezNode {
+    /// Pay a BOLT11 invoice with an options Dictionary
+    ///
+    /// Recognized keys, all optional:
+    /// * `amount_sats` - Amount for amountless invoices (default 0); one that differs from
+    ///   the invoice's own amount fails with AMOUNT_MISMATCH
+    /// * `amount` - The same amount as `{value, unit}`, with unit "sat" (default), "btc" or
+    ///   "msat" and the value an int or a decimal string (e.g. `{"value": "0.0001", "unit": "btc"}`);
+    ///   converted exactly or rejected with INVALID_AMOUNT, never rounded
+    /// * `timeout_secs` - Completion timeout (default 0, the SDK default)
+    /// * `dry_run` - Stop after prepare (default false, see `send_onchain`)
+    /// * `prefer_spark` - Pay over Spark when the payee supports it (default false)
+    /// * `max_fee_sats` - Fail with FEE_TOO_HIGH and the `quoted_fee` above this fee, before
+    ///   sending (default 0, no cap)
+    ///
+    /// Unknown keys fail with UNKNOWN_OPTION and `valid_options`; wrongly typed values fail
+    /// with INVALID_OPTION.
+    #[func]
+    pub fn pay_invoice_opts(&self, bolt11: GString, options: Dictionary) -> Dictionary {
+        let mut opts = match SendOptions::from_dictionary(&options, INVOICE_OPTIONS) {
+            Ok(opts) => opts,
+            Err(error) => return self.report_failure("pay_invoice_opts", error),
+        };
+        if let Err(error) = opts.resolve_amount(AmountMethod::Lightning) {
+            return self.report_failure("pay_invoice_opts", error);
+        }
+        let result = self.pay_invoice_with(bolt11.to_string(), &opts);
+        self.report_failure("pay_invoice_opts", result)
+    }
+
+    /// Pay any supported destination: a BOLT11 invoice, Bitcoin address or Spark address
+    ///
+    /// # Arguments
+    /// * `destination` - Invoice or address
+    /// * `amount_sats` - Amount to send (0 or the invoice's own amount for invoices that
+    ///   carry one)
+    /// * `dry_run` - Stop after prepare and report what the payment would do (see `send_onchain`)
+    #[func]
+    pub fn pay(&self, destination: GString, amount_sats: i64, dry_run: bool) -> Dictionary {
+        let [amount_key, dry_run_key] = PAY_OPTIONS;
+        let mut options = Dictionary::new();
+        options.set(amount_key, amount_sats);
+        options.set(dry_run_key, dry_run);
+        self.pay_opts(destination, options)
+    }
+
+    /// Pay any supported destination with an options Dictionary
+    ///
+    /// Accepts the options of the matching send path: `pay_invoice_opts` for invoices,
+    /// `send_onchain_opts` and `send_spark_payment_opts` for addresses. Lightning addresses
+    /// and LNURLs take `amount_sats` or `amount`, `dry_run` and `max_fee_sats` and are paid
+    /// as in `pay_lnurl`.
+    #[func]
+    pub fn pay_opts(&self, destination: GString, options: Dictionary) -> Dictionary {
+        let destination_str = destination.to_string().trim().to_string();
+        let lower = destination_str.to_lowercase();
+        let body = lower.strip_prefix("lightning:").unwrap_or(&lower);
+
+        if body.starts_with("lnbc") || body.starts_with("lntb") || is_bolt12_offer(body) {
+            return self.pay_invoice_opts(destination, options);
+        }
+        match validate_destination(&destination_str) {
+            Ok(DestinationKind::BitcoinAddress) => self.send_onchain_opts(destination, options),
+            Ok(DestinationKind::SparkAddress) => self.send_spark_payment_opts(destination, options),
+            Ok(DestinationKind::LightningAddress | DestinationKind::Lnurl) => {
+                let mut opts = match SendOptions::from_dictionary(&options, LNURL_OPTIONS) {
+                    Ok(opts) => opts,
+                    Err(error) => return self.report_failure("pay_opts", error),
+                };
+                if let Err(error) = opts.resolve_amount(AmountMethod::Lightning) {
+                    return self.report_failure("pay_opts", error);
+                }
+                let result = self.pay_lnurl_with(destination_str, opts.amount_sats, "", &opts);
+                self.report_failure("pay_opts", result)
+            }
+            Err(e) => self.report_failure("pay_opts", option_error(e, "INVALID_INPUT")),
+        }
+    }
+
+    /// Send an on-chain payment to a Bitcoin address at medium confirmation speed
+    ///
+    /// With `dry_run`, the payment is validated, prepared and checked against the throttle
+    /// policy but not sent. The result then has `dry_run: true`, `amount`, `fee_sats` and
+    /// `fee_warning`, without `payment_id`.
+    #[func]
+    pub fn send_onchain(&self, address: GString, amount_sats: i64, dry_run: bool) -> Dictionary {
+        let mut options = Dictionary::new();
+        options.set("amount_sats", amount_sats);
+        options.set("dry_run", dry_run);
+        self.send_onchain_opts(address, options)
+    }
+
+    /// Withdraw on-chain to a Bitcoin address with a chosen fee
+    ///
+    /// `fee` is `{"type": "fixed", "amount": n}` to pay at most n sats, using the fastest
+    /// confirmation speed quoted within it, or `{"type": "rate", "sat_per_vbyte": n}` to use
+    /// the fastest speed whose recommended rate n covers. Empty means medium speed. Fails
+    /// with FEE_TOO_HIGH (and `quoted_fee`) or FEE_RATE_TOO_LOW rather than sending slower
+    /// than asked. Returns `payment_id`, `txid` when known, `amount`, `fee_sats` (the fee
+    /// paid) and `confirmation_speed`.
+    #[func]
+    pub fn send_onchain_with_fee(&self, address: GString, amount_sats: i64, fee: Dictionary) -> Dictionary {
+        let mut options = Dictionary::new();
+        options.set("amount_sats", amount_sats);
+        if !fee.is_empty() {
+            options.set("fee", &fee);
+        }
+        self.send_onchain_opts(address, options)
+    }
+
+    /// Send on-chain with an options Dictionary: `amount_sats` or `amount`, `dry_run`,
+    /// `max_fee_sats` (see `pay_invoice_opts`; "msat" amounts fail with UNSUPPORTED_UNIT) and
+    /// `fee` (see `send_onchain_with_fee`)
+    ///
+    /// The address must belong to the connected network.
+    #[func]
+    pub fn send_onchain_opts(&self, address: GString, options: Dictionary) -> Dictionary {
+        if let Err(error) = self.require_capability("onchain_send") {
+            return self.report_failure("send_onchain_opts", error);
+        }
+        let mut opts = match SendOptions::from_dictionary(&options, ONCHAIN_OPTIONS) {
+            Ok(opts) => opts,
+            Err(error) => return self.report_failure("send_onchain_opts", error),
+        };
+        if let Err(error) = opts.resolve_amount(AmountMethod::Onchain) {
+            return self.report_failure("send_onchain_opts", error);
+        }
+        let address = address.to_string().trim().to_string();
+        let network = self.wallet_network.lock().unwrap().clone();
+        let valid = match &network {
+            Some(network) => validate_bitcoin_address_for(&address, network),
+            None => validate_bitcoin_address(&address),
+        };
+        if let Err(e) = valid {
+            return self.report_failure("send_onchain_opts", option_error(e, "INVALID_INPUT"));
+        }
+        if let Err(error) = check_amount(AmountMethod::Onchain, opts.amount_sats) {
+            return self.report_failure("send_onchain_opts", error);
+        }
+        if let Err(error) = self.resolve_fee_rate(&mut opts) {
+            return self.report_failure("send_onchain_opts", error);
+        }
+        let result = self.send_pipeline(address, Some(opts.amount_sats as u64), &opts, send_options_for);
+        self.report_failure("send_onchain_opts", result)
+    }
+
+    /// Send `amount_sats` to a Spark address
+    ///
+    /// Returns `success`, `payment_id`, `amount` and `fee_sats`. A malformed address or an
+    /// amount below the Spark minimum fails before anything reaches the SDK.
+    #[func]
+    pub fn send_to_spark_address(&self, address: GString, amount_sats: i64) -> Dictionary {
+        self.send_spark_payment(address, amount_sats, false)
+    }
+
+    /// Send to a Spark address (see `send_onchain` for `dry_run`)
+    #[func]
+    pub fn send_spark_payment(&self, address: GString, amount_sats: i64, dry_run: bool) -> Dictionary {
+        let mut options = Dictionary::new();
+        options.set("amount_sats", amount_sats);
+        options.set("dry_run", dry_run);
+        self.send_spark_payment_opts(address, options)
+    }
+
+    /// Send to a Spark address with an options Dictionary: `amount_sats` or `amount`, `dry_run`
+    /// and `max_fee_sats` (see `send_onchain_opts`)
+    #[func]
+    pub fn send_spark_payment_opts(&self, address: GString, options: Dictionary) -> Dictionary {
+        let mut opts = match SendOptions::from_dictionary(&options, ADDRESS_OPTIONS) {
+            Ok(opts) => opts,
+            Err(error) => return self.report_failure("send_spark_payment_opts", error),
+        };
+        if let Err(error) = opts.resolve_amount(AmountMethod::Spark) {
+            return self.report_failure("send_spark_payment_opts", error);
+        }
+        let address = address.to_string().trim().to_string();
+        if let Err(e) = validate_spark_address(&address) {
+            return self.report_failure("send_spark_payment_opts", option_error(e, "INVALID_INPUT"));
+        }
+        if let Err(error) = check_amount(AmountMethod::Spark, opts.amount_sats) {
+            return self.report_failure("send_spark_payment_opts", error);
+        }
+        let result = self.send_pipeline(address, Some(opts.amount_sats as u64), &opts, send_options_for);
+        self.report_failure("send_spark_payment_opts", result)
+    }
+}
+
+fn send_error(e: String) -> Dictionary {
+    godot_error!("{}", e);
+    let mut dict = Dictionary::new();
+    dict.set("success", false);
+    dict.set("error", e);
+    dict
+}
+
+/// Prepared payment and whether sending it would drop the balance below the low-balance
+/// threshold (None when no threshold is set or the balance is unknown)
+pub(crate) type PreparedSend = (PrepareSendPaymentResponse, Option<bool>);
+
+/// A prepared payment that passed the guard rails, waiting to be sent
+pub(crate) struct CheckedSend {
+    pub(crate) prepare_response: PrepareSendPaymentResponse,
+    /// Result Dictionary so far (`fee_sats`, warnings), completed by `finish_send`
+    pub(crate) dict: Dictionary,
+    /// Amount plus fee, counted against the throttle by `check_prepared`
+    pub(crate) cost: u64,
+    /// Speed chosen for an on-chain send
+    pub(crate) confirmation_speed: Option<OnchainConfirmationSpeed>,
+}
+
+/// Prepare a payment on the runtime
+pub(crate) async fn prepare_send(
+    sdk_handle: Result<BreezSdk, String>,
+    payment_request: String,
+    amount_sats: Option<u64>,
+    low_threshold: Option<u64>,
+) -> Result<PreparedSend, String> {
+    let sdk = sdk_handle?;
+    let prepare_response = match sdk.prepare_send_payment(PrepareSendPaymentRequest {
+        payment_request,
+        amount_sats,
+    }).await {
+        Ok(response) => response,
+        Err(e) => return Err(format!("Failed to prepare payment: {:?}", e)),
+    };
+
+    let would_drop_below = match low_threshold {
+        Some(threshold) => sdk.get_info(GetInfoRequest {
+            ensure_synced: Some(false),
+        }).await.ok().map(|info| {
+            let cost = prepare_response.amount_sats + quoted_fee_sats(&prepare_response);
+            info.balance_sats.saturating_sub(cost) < threshold
+        }),
+        None => None,
+    };
+
+    Ok((prepare_response, would_drop_below))
+}
+
+/// Send a prepared payment on the runtime, after any other send has finished
+pub(crate) async fn send_prepared(
+    sdk_handle: Result<BreezSdk, String>,
+    in_flight: Arc<tokio::sync::Mutex<()>>,
+    prepare_response: PrepareSendPaymentResponse,
+    options: Option<SendPaymentOptions>,
+) -> Result<SendPaymentResponse, String> {
+    let sdk = sdk_handle?;
+    // Waits for any other send to finish; reads are not blocked meanwhile
+    let _in_flight = in_flight.lock().await;
+    match sdk.send_payment(SendPaymentRequest {
+        prepare_response,
+        options,
+    }).await {
+        Ok(response) => Ok(response),
+        Err(e) => Err(format!("Payment failed: {:?}", e)),
+    }
+}
+
+/// Lightning send options for `opts`, or None for the SDK defaults
+pub(crate) fn invoice_send_options(opts: &SendOptions) -> Option<SendPaymentOptions> {
+    if opts.timeout_secs > 0 || opts.prefer_spark {
+        Some(SendPaymentOptions::Bolt11Invoice {
+            prefer_spark: opts.prefer_spark,
+            completion_timeout_secs: (opts.timeout_secs > 0).then_some(opts.timeout_secs as u32),
+        })
+    } else {
+        None
+    }
+}
+
+impl BreezNode {
+    /// Turn a `Rate` on-chain fee into the confirmation speed it pays for
+    pub(crate) fn resolve_fee_rate(&self, opts: &mut SendOptions) -> Result<(), Dictionary> {
+        let Some(OnchainFee::Rate(rate)) = opts.onchain_fee else {
+            return Ok(());
+        };
+        let fees = self.fetch_recommended_fees().map_err(send_error)?;
+        match speed_for_rate(rate, &fees) {
+            Some(speed) => {
+                opts.onchain_fee = Some(OnchainFee::Speed(speed));
+                Ok(())
+            }
+            None => {
+                let mut error = send_error(format!(
+                    "{} sat/vB is below the {} sat/vB needed to confirm within an hour",
+                    rate, fees.hour_fee
+                ));
+                error.set("error_code", "FEE_RATE_TOO_LOW");
+                error.set("min_sat_per_vbyte", fees.hour_fee as i64);
+                Err(error)
+            }
+        }
+    }
+
+    /// Pay a BOLT11 invoice with parsed options
+    ///
+    /// `amount_sats` is required for amountless invoices and must match otherwise.
+    pub(crate) fn pay_invoice_with(&self, invoice: String, opts: &SendOptions) -> Dictionary {
+        let (amount, payment_hash) = match invoice_amount(&invoice, opts) {
+            Ok(checked) => checked,
+            Err(error) => return error,
+        };
+        let dict = self.send_pipeline(invoice, amount, opts, |_| invoice_send_options(opts));
+        if opts.dry_run {
+            return dict;
+        }
+        self.track_invoice_result(dict, payment_hash)
+    }
+
+    /// Keep tracking a pending or timed-out invoice payment, which can still settle
+    pub(crate) fn track_invoice_result(&self, mut dict: Dictionary, payment_hash: Option<String>) -> Dictionary {
+        let pending = dict.get("final").is_some_and(|is_final| !is_final.to::<bool>());
+        let timed_out = dict
+            .get("error")
+            .is_some_and(|error| is_timeout_error(&error.to::<GString>().to_string()));
+        if let Some(payment_hash) = payment_hash.filter(|_| pending || timed_out) {
+            if timed_out {
+                dict.set("error_code", "TIMEOUT");
+                dict.set("final", false);
+            }
+            dict.set("payment_hash", payment_hash.clone());
+            self.track_payment(payment_hash, timed_out);
+        }
+        dict
+    }
+
+    /// Prepare, check policies and (unless `opts.dry_run`) send a payment
+    ///
+    /// Guard rails are evaluated in dry runs too, so previews fail the same way the real
+    /// payment would, but a dry run does not emit `throttle_tripped`.
+    pub(crate) fn send_pipeline(
+        &self,
+        payment_request: String,
+        amount_sats: Option<u64>,
+        opts: &SendOptions,
+        options_for: impl FnOnce(&PrepareSendPaymentResponse) -> Option<SendPaymentOptions>,
+    ) -> Dictionary {
+        if let Err(locked) = self.check_unlocked() {
+            return locked;
+        }
+
+        let runtime = Arc::clone(&self.runtime);
+        let destination = payment_request.clone();
+
+        // Step 1: Prepare the payment
+        let prepared = runtime.block_on(prepare_send(
+            self.sdk_handle(),
+            payment_request,
+            amount_sats,
+            self.low_balance_threshold(),
+        ));
+        let checked = match self.check_prepared(prepared, opts) {
+            Ok(checked) => checked,
+            Err(dict) => return dict,
+        };
+
+        // Step 2: Send the payment
+        let journal_id = self.journal_begin("send", &destination, checked.prepare_response.amount_sats);
+        let options = match checked.confirmation_speed.clone() {
+            Some(confirmation_speed) => Some(SendPaymentOptions::BitcoinAddress { confirmation_speed }),
+            None => options_for(&checked.prepare_response),
+        };
+        let result = runtime.block_on(send_prepared(
+            self.sdk_handle(),
+            Arc::clone(&self.in_flight),
+            checked.prepare_response,
+            options,
+        ));
+        self.finish_send(checked.dict, &journal_id, checked.cost, result)
+    }
+
+    /// Apply the fee cap and throttle to a prepared payment
+    ///
+    /// The error is the final result: the failure, or the preview when `opts.dry_run` is set.
+    pub(crate) fn check_prepared(
+        &self,
+        prepared: Result<PreparedSend, String>,
+        opts: &SendOptions,
+    ) -> Result<CheckedSend, Dictionary> {
+        let mut dict = Dictionary::new();
+
+        let (prepare_response, would_drop_below) = match prepared {
+            Ok(prepared) => prepared,
+            Err(e) => {
+                godot_error!("{}", e);
+                dict.set("success", false);
+                dict.set("error", e);
+                return Err(dict);
+            }
+        };
+
+        let confirmation_speed = match &prepare_response.payment_method {
+            SendPaymentMethod::BitcoinAddress { .. } => match onchain_speed(&prepare_response, opts.onchain_fee.as_ref()) {
+                Ok(speed) => Some(speed),
+                Err(cheapest) => {
+                    let mut error = send_error(format!("The cheapest on-chain fee is {} sats, above the fee given", cheapest));
+                    error.set("error_code", "FEE_TOO_HIGH");
+                    error.set("fee_sats", cheapest as i64);
+                    error.set("quoted_fee", cheapest as i64);
+                    return Err(error);
+                }
+            },
+            _ => None,
+        };
+        let fee = confirmation_speed
+            .as_ref()
+            .and_then(|speed| onchain_fee_sats(&prepare_response, speed))
+            .unwrap_or_else(|| quoted_fee_sats(&prepare_response));
+        let cost = prepare_response.amount_sats + fee;
+        if opts.max_fee_sats > 0 && fee > opts.max_fee_sats {
+            let mut error = send_error(format!("Fee {} sats exceeds the {} sats limit", fee, opts.max_fee_sats));
+            error.set("error_code", "FEE_TOO_HIGH");
+            error.set("fee_sats", fee as i64);
+            error.set("quoted_fee", fee as i64);
+            return Err(error);
+        }
+        self.check_throttle(cost, opts.dry_run)?;
+
+        if let Some(would_drop_below) = would_drop_below {
+            dict.set("would_drop_below_threshold", would_drop_below);
+        }
+        dict.set("fee_sats", fee as i64);
+        if let Some(speed) = &confirmation_speed {
+            let policy = self.fee_policy.lock().unwrap().clone();
+            dict.set("fee_warning", policy.warning(fee, prepare_response.amount_sats));
+            dict.set("confirmation_speed", speed_name(speed));
+        }
+
+        if opts.dry_run {
+            dict.set("success", true);
+            dict.set("dry_run", true);
+            dict.set("amount", prepare_response.amount_sats as i64);
+            return Err(dict);
+        }
+        Ok(CheckedSend {
+            prepare_response,
+            dict,
+            cost,
+            confirmation_speed,
+        })
+    }
+
+    /// Record the outcome of a send and complete its result Dictionary
+    pub(crate) fn finish_send(
+        &self,
+        mut dict: Dictionary,
+        journal_id: &str,
+        cost: u64,
+        result: Result<SendPaymentResponse, String>,
+    ) -> Dictionary {
+        // A pending or timed-out payment keeps its journal entry until it is seen settled
+        let unsettled = match &result {
+            Ok(response) => response.payment.status == PaymentStatus::Pending,
+            Err(e) => is_timeout_error(e),
+        };
+        if !unsettled {
+            self.journal_end(journal_id);
+        }
+        // check_throttle already counted the send; only a definite failure gives it back
+        if result.is_err() && !unsettled {
+            self.release_throttled_send(cost, cost);
+        }
+
+        match result {
+            Ok(payment) => {
+                godot_print!("✅ Payment sent");
+                self.queue_webhook("payment_sent", &payment.payment);
+                self.invalidate_balance_history();
+                dict.set("success", true);
+                dict.set("dry_run", false);
+                dict.set("payment_id", payment.payment.id);
+                dict.set("amount", payment.payment.amount as i64);
+                dict.set("fee_sats", payment.payment.fees as i64);
+                if let Some(PaymentDetails::Withdraw { tx_id }) = &payment.payment.details {
+                    dict.set("txid", tx_id.as_str());
+                }
+                dict.set("status", payment.payment.status.to_string());
+                dict.set("final", payment.payment.status != PaymentStatus::Pending);
+                self.refresh_low_balance();
+                self.record_balance_snapshot();
+            }
+            Err(e) => {
+                godot_error!("{}", e);
+                dict.set("success", false);
+                dict.set("error", e);
+            }
+        }
+
+        dict
+    }
+}
+
+/// Amount to send for `invoice` and its payment hash, checking the Lightning limits
+///
+/// The amount is None when the invoice carries one. A different `opts.amount_sats` fails
+/// with AMOUNT_MISMATCH, and a missing one for an amountless invoice with AMOUNT_REQUIRED.
+/// A BOLT12 offer fails with INVALID_DESTINATION, as offers cannot be paid yet. Anything
+/// that is not a BOLT11 invoice fails here rather than in the SDK: with INVALID_INPUT for the
+/// wrong prefix, and INVALID_BECH32 or INVALID_INVOICE as in `decode_invoice` otherwise.
+pub(crate) fn invoice_amount(invoice: &str, opts: &SendOptions) -> Result<(Option<u64>, Option<String>), Dictionary> {
+    if is_bolt12_offer(invoice) {
+        return Err(offer_in_invoice_error());
+    }
+    validate_invoice_prefix(invoice).map_err(|e| option_error(e, "INVALID_INPUT"))?;
+    let parsed = crate::bolt11::parse_bolt11_coded(invoice).map_err(|(e, code)| option_error(e, code))?;
+    let invoice_amount = parsed.amount_milli_satoshis();
+    let payment_hash = Some(crate::bolt11::payment_hash_hex(&parsed));
+    let amount = match invoice_amount {
+        Some(msat) if opts.amount_sats > 0 && opts.amount_sats as u64 != msat / 1000 => {
+            let mut error = option_error(
+                format!(
+                    "The invoice is for {} sats and cannot be paid with {} sats (pass 0 to use the invoice amount)",
+                    msat / 1000,
+                    opts.amount_sats
+                ),
+                "AMOUNT_MISMATCH",
+            );
+            error.set("invoice_amount_sats", (msat / 1000) as i64);
+            return Err(error);
+        }
+        Some(msat) => {
+            check_amount(AmountMethod::Lightning, (msat / 1000) as i64)?;
+            None
+        }
+        None if opts.amount_sats > 0 => {
+            check_amount(AmountMethod::Lightning, opts.amount_sats)?;
+            Some(opts.amount_sats as u64)
+        }
+        None => {
+            return Err(option_error(
+                "The invoice has no amount; pass the amount to send in sats".to_string(),
+                "AMOUNT_REQUIRED",
+            ));
+        }
+    };
+    Ok((amount, payment_hash))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Building the Dictionary itself needs a running engine, so this checks the keys `pay`
+    // puts in it against the same lists `SendOptions::from_dictionary` validates with
+    #[test]
+    fn pay_options_are_accepted_by_every_destination_kind() {
+        let kinds = [
+            ("invoice", INVOICE_OPTIONS),
+            ("bitcoin_address", ONCHAIN_OPTIONS),
+            ("spark_address", ADDRESS_OPTIONS),
+            ("lightning_address", LNURL_OPTIONS),
+            ("lnurl", LNURL_OPTIONS),
+        ];
+        for (kind, valid) in kinds {
+            assert_eq!(unknown_option(PAY_OPTIONS, valid), None, "pay() options rejected for {}", kind);
+        }
+    }
+
+    #[test]
+    fn unknown_option_reports_the_first_rejected_key() {
+        assert_eq!(unknown_option(["amount_sats", "fee", "bogus"], ADDRESS_OPTIONS), Some("fee"));
+        assert_eq!(unknown_option(["max_fee_sats", "dry_run"], LNURL_OPTIONS), None);
+    }
+}
@@ -0,0 +1,161 @@
+use godot::prelude::*;
+use breez_sdk_spark::{BreezSdk, GetInfoRequest};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+use crate::storage::{load_json, now_secs, save_json};
+use crate::{lock_sdk, BreezNode};
+
+const SNAPSHOT_FILE: &str = "balance_snapshots.json";
+
+/// Balance samples of the connected wallet and the recorder settings
+///
+/// The settings outlive the connection, so the recorder resumes after a reconnect.
+#[derive(Default)]
+pub(crate) struct SnapshotState {
+    /// (interval_secs, max_entries) while recording is enabled
+    config: Option<(u64, usize)>,
+    rows: Vec<(u64, u64)>,
+    path: Option<PathBuf>,
+    task: Option<JoinHandle<()>>,
+}
+
+impl SnapshotState {
+    /// Append a sample, prune to max_entries and persist
+    fn record(&mut self, balance_sats: u64) {
+        let Some((_, max_entries)) = self.config else {
+            return;
+        };
+        self.rows.push((now_secs(), balance_sats));
+        if self.rows.len() > max_entries {
+            let excess = self.rows.len() - max_entries;
+            self.rows.drain(..excess);
+        }
+        if let Some(path) = &self.path {
+            if let Err(e) = save_json(path, &self.rows) {
+                godot_warn!("Failed to persist balance snapshots: {}", e);
+            }
+        }
+    }
+}
+
+async fn fetch_balance(sdk: &BreezSdk) -> Option<u64> {
+    sdk.get_info(GetInfoRequest {
+        ensure_synced: Some(false),
+    })
+    .await
+    .ok()
+    .map(|info| info.balance_sats)
+}
+
+#[godot_api(secondary)]
+impl BreezNode {
+    /// Record the balance every `interval_secs` and after each settlement, keeping the newest
+    /// `max_entries` samples under storage_dir
+    ///
+    /// Recording continues across reconnects until `disable_balance_snapshots` is called.
+    #[func]
+    pub fn enable_balance_snapshots(&mut self, interval_secs: i64, max_entries: i64) -> bool {
+        if interval_secs <= 0 || max_entries <= 0 {
+            godot_error!("Invalid snapshot settings: interval {} max entries {}", interval_secs, max_entries);
+            return false;
+        }
+        self.snapshots.lock().unwrap().config = Some((interval_secs as u64, max_entries as usize));
+        self.start_snapshot_recorder();
+        true
+    }
+
+    /// Stop recording balance snapshots (recorded samples are kept)
+    #[func]
+    pub fn disable_balance_snapshots(&mut self) {
+        let mut snapshots = self.snapshots.lock().unwrap();
+        snapshots.config = None;
+        if let Some(task) = snapshots.task.take() {
+            task.abort();
+        }
+    }
+
+    /// Recorded balance samples between `from_ts` and `to_ts` (inclusive), oldest first,
+    /// each with `timestamp` and `balance_sats`
+    #[func]
+    pub fn get_balance_snapshots(&self, from_ts: i64, to_ts: i64) -> Array<Dictionary> {
+        let mut array = Array::new();
+        for (timestamp, balance) in &self.snapshots.lock().unwrap().rows {
+            let timestamp = *timestamp as i64;
+            if timestamp < from_ts || timestamp > to_ts {
+                continue;
+            }
+            let mut dict = Dictionary::new();
+            dict.set("timestamp", timestamp);
+            dict.set("balance_sats", *balance as i64);
+            array.push(&dict);
+        }
+        array
+    }
+}
+
+impl BreezNode {
+    /// Load the connected wallet's samples and resume recording if it is enabled
+    pub(crate) fn start_snapshot_recorder(&self) {
+        let path = self.plugin_path(SNAPSHOT_FILE);
+        let mut snapshots = self.snapshots.lock().unwrap();
+        if let Some(task) = snapshots.task.take() {
+            task.abort();
+        }
+        snapshots.rows = path
+            .as_deref()
+            .and_then(load_json::<Vec<(u64, u64)>>)
+            .unwrap_or_default();
+        snapshots.path = path;
+
+        let Some((interval_secs, _)) = snapshots.config else {
+            return;
+        };
+        if snapshots.path.is_none() {
+            // Not connected yet; recording starts on connect
+            return;
+        }
+
+        let sdk_arc = Arc::clone(&self.sdk);
+        let state = Arc::clone(&self.snapshots);
+        snapshots.task = Some(self.runtime.spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
+            loop {
+                interval.tick().await;
+                let sdk = lock_sdk(&sdk_arc).clone();
+                let Some(sdk) = sdk else {
+                    continue;
+                };
+                if let Some(balance) = fetch_balance(&sdk).await {
+                    state.lock().unwrap().record(balance);
+                }
+            }
+        }));
+    }
+
+    /// Stop the recorder and forget the disconnected wallet's samples, keeping the settings
+    pub(crate) fn stop_snapshot_recorder(&self) {
+        let mut snapshots = self.snapshots.lock().unwrap();
+        if let Some(task) = snapshots.task.take() {
+            task.abort();
+        }
+        snapshots.rows.clear();
+        snapshots.path = None;
+    }
+
+    /// Record a sample right after a settlement, if recording is enabled
+    pub(crate) fn record_balance_snapshot(&self) {
+        if self.snapshots.lock().unwrap().config.is_none() {
+            return;
+        }
+        let sdk = lock_sdk(&self.sdk).clone();
+        let Some(sdk) = sdk else {
+            return;
+        };
+        if let Some(balance) = self.runtime.block_on(fetch_balance(&sdk)) {
+            self.snapshots.lock().unwrap().record(balance);
+        }
+    }
+}
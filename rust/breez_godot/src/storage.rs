@@ -0,0 +1,131 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::BreezNode;
+
+/// Subdirectory of storage_dir holding the plugin's own files
+const PLUGIN_DIR: &str = "godot_plugin";
+
+/// Wallet directories a live SDK has open in this process, with the `wallet_owner` of each
+static OPEN_WALLET_DIRS: Mutex<Vec<(PathBuf, usize)>> = Mutex::new(Vec::new());
+
+/// Current unix time in seconds
+pub(crate) fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Directory inside `storage_dir` holding the plugin's own files
+pub(crate) fn plugin_dir(storage_dir: &Path) -> PathBuf {
+    storage_dir.join(PLUGIN_DIR)
+}
+
+/// Path of a plugin file inside `storage_dir`
+pub(crate) fn plugin_file(storage_dir: &Path, name: &str) -> PathBuf {
+    plugin_dir(storage_dir).join(name)
+}
+
+/// Read a JSON file, returning `None` if it is missing or unreadable
+pub(crate) fn load_json<T: DeserializeOwned>(path: &Path) -> Option<T> {
+    let contents = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+/// Write a JSON file atomically (write to a temporary file, then rename)
+pub(crate) fn save_json<T: Serialize>(path: &Path, value: &T) -> io::Result<()> {
+    let contents = serde_json::to_string_pretty(value)?;
+    write_atomic(path, contents.as_bytes())
+}
+
+/// Write a file atomically, so a failed write never leaves a truncated file behind
+pub(crate) fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let tmp = path.with_extension("tmp");
+    let result = fs::write(&tmp, contents).and_then(|()| fs::rename(&tmp, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result
+}
+
+impl BreezNode {
+    /// Path of a plugin file for the connected wallet, if a storage_dir is known
+    pub(crate) fn plugin_path(&self, name: &str) -> Option<PathBuf> {
+        self.wallet_dir
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|dir| plugin_file(dir, name))
+    }
+
+    /// Identity of this node's SDK slot, shared with nodes attached to the same connection
+    pub(crate) fn wallet_owner(&self) -> usize {
+        Arc::as_ptr(&self.sdk) as usize
+    }
+}
+
+/// Wallet directory of a connect call given no storage_dir
+pub(crate) const DEFAULT_STORAGE_DIR: &str = "user://breez/";
+
+/// Absolute storage_dir of a connect call
+///
+/// Empty uses `DEFAULT_STORAGE_DIR`, which unlike a path relative to the executable is
+/// writable on every platform, including exported Android and iOS builds. `res://` is
+/// rejected, as the project's resources are read-only once exported.
+pub(crate) fn resolve_storage_dir(storage_dir: &str) -> Result<PathBuf, String> {
+    let storage_dir = match storage_dir.trim() {
+        "" => DEFAULT_STORAGE_DIR,
+        dir => dir,
+    };
+    if storage_dir.starts_with("res://") {
+        return Err(format!(
+            "{} is a read-only location in exported games; store wallets under user:// instead",
+            storage_dir
+        ));
+    }
+    Ok(PathBuf::from(globalize_path(storage_dir)))
+}
+
+/// A wallet directory in a form that compares equal however it was spelled
+fn wallet_dir_key(dir: &Path) -> PathBuf {
+    fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf())
+}
+
+/// Whether an SDK other than `owner`'s has `dir` open; two SDKs on one database corrupt it
+pub(crate) fn wallet_dir_in_use(dir: &Path, owner: usize) -> bool {
+    let key = wallet_dir_key(dir);
+    OPEN_WALLET_DIRS.lock().unwrap().iter().any(|(open, holder)| *open == key && *holder != owner)
+}
+
+/// Record `dir` as opened by `owner`
+pub(crate) fn claim_wallet_dir(dir: &Path, owner: usize) {
+    let key = wallet_dir_key(dir);
+    let mut open = OPEN_WALLET_DIRS.lock().unwrap();
+    open.retain(|(_, holder)| *holder != owner);
+    open.push((key, owner));
+}
+
+/// Forget the directory `owner` had open, once its SDK is shut down
+pub(crate) fn release_wallet_dirs(owner: usize) {
+    OPEN_WALLET_DIRS.lock().unwrap().retain(|(_, holder)| *holder != owner);
+}
+
+/// Translate `user://` and `res://` paths to absolute filesystem paths
+pub(crate) fn globalize_path(path: &str) -> String {
+    if path.starts_with("user://") || path.starts_with("res://") {
+        godot::classes::ProjectSettings::singleton()
+            .globalize_path(path)
+            .to_string()
+    } else {
+        path.to_string()
+    }
+}
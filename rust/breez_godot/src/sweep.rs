@@ -0,0 +1,294 @@
+use godot::prelude::*;
+use breez_sdk_spark::{GetInfoRequest, PrepareSendPaymentRequest, SendPaymentRequest};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use crate::events::NodeEvent;
+use crate::fees::{quoted_fee_sats, send_options_for};
+use crate::limits::AmountMethod;
+use crate::validation::{validate_destination, DestinationKind};
+use crate::BreezNode;
+
+/// Outcome of an auto-sweep check that did not fail
+pub(crate) enum SweepOutcome {
+    /// Nothing was sent, with the reason reported as `skipped`
+    Skipped(&'static str),
+    /// The result Dictionary of the send, as built by `finish_send`
+    Sent(Dictionary),
+}
+
+fn sweep_error(error: String, code: &str) -> Dictionary {
+    let mut dict = Dictionary::new();
+    dict.set("success", false);
+    dict.set("error", error);
+    dict.set("error_code", code);
+    dict
+}
+
+/// Auto-sweep settings set from GDScript
+#[derive(Clone)]
+pub(crate) struct AutoSweep {
+    destination: String,
+    threshold_sats: u64,
+    keep_sats: u64,
+    max_fee_sats: u64,
+}
+
+#[godot_api(secondary)]
+impl BreezNode {
+    /// Automatically sweep the balance above a threshold to a destination
+    ///
+    /// The check runs after each wallet sync and whenever `check_auto_sweep` is called.
+    ///
+    /// # Arguments
+    /// * `destination` - Bitcoin or Spark address receiving the swept funds; Lightning
+    ///   addresses, LNURLs and invoices are rejected
+    /// * `threshold_sats` - Balance above which a sweep is triggered
+    /// * `keep_sats` - Amount left in the wallet after a sweep (must not exceed the threshold)
+    #[func]
+    pub fn set_auto_sweep(&mut self, destination: GString, threshold_sats: i64, keep_sats: i64) -> bool {
+        let destination = destination.to_string().trim().to_string();
+        if destination.is_empty() {
+            godot_error!("Auto-sweep destination is empty");
+            return false;
+        }
+        match validate_destination(&destination) {
+            Ok(DestinationKind::BitcoinAddress | DestinationKind::SparkAddress) => {}
+            Ok(kind) => {
+                godot_error!("Auto-sweep destination must be a Bitcoin or Spark address, not a {}", kind.as_str());
+                return false;
+            }
+            Err(e) => {
+                godot_error!("Invalid auto-sweep destination: {}", e);
+                return false;
+            }
+        }
+        if threshold_sats <= 0 || keep_sats < 0 || keep_sats > threshold_sats {
+            godot_error!("Invalid auto-sweep amounts: threshold {} keep {}", threshold_sats, keep_sats);
+            return false;
+        }
+
+        let mut auto_sweep = self.auto_sweep.lock().unwrap();
+        let max_fee_sats = auto_sweep.as_ref().map(|s| s.max_fee_sats).unwrap_or(0);
+        *auto_sweep = Some(AutoSweep {
+            destination,
+            threshold_sats: threshold_sats as u64,
+            keep_sats: keep_sats as u64,
+            max_fee_sats,
+        });
+        godot_print!("Auto-sweep enabled above {} sats", threshold_sats);
+        true
+    }
+
+    /// Set the maximum fee a single sweep may pay (0 for no cap beyond the fee warning policy)
+    #[func]
+    pub fn set_auto_sweep_max_fee(&mut self, max_fee_sats: i64) {
+        if let Some(sweep) = self.auto_sweep.lock().unwrap().as_mut() {
+            sweep.max_fee_sats = max_fee_sats.max(0) as u64;
+        }
+    }
+
+    /// Disable auto-sweep
+    #[func]
+    pub fn clear_auto_sweep(&mut self) {
+        if self.auto_sweep.lock().unwrap().take().is_some() {
+            godot_print!("Auto-sweep disabled");
+        }
+    }
+
+    /// Check the balance now and sweep the excess if it is above the threshold
+    ///
+    /// Returns a Dictionary with `success` and `swept`. A sweep adds the fields of a send
+    /// result (`payment_id`, `amount`, `fee_sats`, `status`, `final`); otherwise `skipped`
+    /// tells why nothing was sent: "disabled", "in_flight", "below_threshold" or "dust".
+    /// Fails with WALLET_LOCKED while the wallet is locked, and with FEE_TOO_HIGH when the
+    /// fee exceeds `set_auto_sweep_max_fee` or the fee warning policy.
+    #[func]
+    pub fn check_auto_sweep(&self) -> Dictionary {
+        let dict = match self.run_auto_sweep() {
+            Ok(SweepOutcome::Sent(mut dict)) => {
+                let swept = dict.get("success").is_some_and(|success| success.booleanize());
+                dict.set("swept", swept);
+                dict
+            }
+            Ok(SweepOutcome::Skipped(reason)) => {
+                let mut dict = Dictionary::new();
+                dict.set("success", true);
+                dict.set("swept", false);
+                dict.set("skipped", reason);
+                dict
+            }
+            Err(mut dict) => {
+                dict.set("swept", false);
+                dict
+            }
+        };
+
+        self.report_failure("check_auto_sweep", dict)
+    }
+}
+
+impl BreezNode {
+    /// Sweep the excess balance if auto-sweep is configured and the threshold is exceeded
+    ///
+    /// The send is recorded like any other through `finish_send`, so its journal entry stays
+    /// open while the payment is pending.
+    pub(crate) fn run_auto_sweep(&self) -> Result<SweepOutcome, Dictionary> {
+        let Some(config) = self.auto_sweep.lock().unwrap().clone() else {
+            return Ok(SweepOutcome::Skipped("disabled"));
+        };
+        self.check_unlocked()?;
+
+        if self.sweep_in_flight.swap(true, Ordering::SeqCst) {
+            return Ok(SweepOutcome::Skipped("in_flight"));
+        }
+        let result = self.sweep_excess(&config);
+        self.sweep_in_flight.store(false, Ordering::SeqCst);
+
+        if let Ok(SweepOutcome::Sent(dict)) = &result {
+            if let (Some(amount), Some(payment_id)) = (dict.get("amount"), dict.get("payment_id")) {
+                let amount = amount.to::<i64>();
+                godot_print!("✅ Swept {} sats", amount);
+                self.events.push(NodeEvent::Swept {
+                    amount_sats: amount,
+                    payment_id: payment_id.to::<String>(),
+                });
+            }
+        }
+
+        result
+    }
+
+    /// Run the auto-sweep check after a sync, reporting failures as background errors
+    pub(crate) fn auto_sweep_after_sync(&self) {
+        let error = match self.run_auto_sweep() {
+            // A locked wallet is swept after the first sync once it is unlocked
+            Err(dict) if dict.get("error_code").is_some_and(|code| code.to::<String>() == "WALLET_LOCKED") => return,
+            Err(dict) => {
+                let error = dict.get("error").map(|error| error.to::<String>()).unwrap_or_default();
+                godot_error!("{}", error);
+                error
+            }
+            // finish_send already printed the failure
+            Ok(SweepOutcome::Sent(dict)) if !dict.get("success").is_some_and(|success| success.booleanize()) => {
+                dict.get("error").map(|error| error.to::<String>()).unwrap_or_default()
+            }
+            Ok(_) => return,
+        };
+        self.report_background_error("auto_sweep", &error);
+    }
+
+    fn sweep_excess(&self, config: &AutoSweep) -> Result<SweepOutcome, Dictionary> {
+        let method = validate_destination(&config.destination)
+            .map(|kind| AmountMethod::for_destination(&kind))
+            .map_err(|e| sweep_error(e, "INVALID_DESTINATION"))?;
+
+        let sdk = self.sdk_handle().map_err(|e| sweep_error(e, "NOT_CONNECTED"))?;
+        let runtime = Arc::clone(&self.runtime);
+        // Held until the send completes so no other payment spends the quoted balance
+        let _in_flight = runtime.block_on(self.in_flight.lock());
+
+        let prepared = runtime.block_on(async {
+            let balance = match sdk.get_info(GetInfoRequest {
+                ensure_synced: Some(false),
+            }).await {
+                Ok(info) => info.balance_sats,
+                Err(e) => return Err(format!("Failed to get balance: {:?}", e)),
+            };
+
+            if balance <= config.threshold_sats {
+                return Ok(None);
+            }
+            let excess = balance - config.keep_sats;
+
+            // Quote the full excess first, then send the excess minus the fee
+            let quote = match sdk.prepare_send_payment(PrepareSendPaymentRequest {
+                payment_request: config.destination.clone(),
+                amount_sats: Some(excess),
+            }).await {
+                Ok(response) => response,
+                Err(e) => return Err(format!("Failed to prepare sweep: {:?}", e)),
+            };
+            let fee = quoted_fee_sats(&quote);
+            if fee >= excess || excess - fee < method.min_sats() {
+                return Ok(Some((quote, fee, excess)));
+            }
+
+            if fee == 0 {
+                return Ok(Some((quote, fee, excess)));
+            }
+            let prepare_response = match sdk.prepare_send_payment(PrepareSendPaymentRequest {
+                payment_request: config.destination.clone(),
+                amount_sats: Some(excess - fee),
+            }).await {
+                Ok(response) => response,
+                Err(e) => return Err(format!("Failed to prepare sweep: {:?}", e)),
+            };
+            // The smaller amount can be quoted a different fee; that quote is the one sent
+            let fee = quoted_fee_sats(&prepare_response);
+            Ok(Some((prepare_response, fee, excess)))
+        });
+
+        let (prepare_response, fee, excess) = match prepared {
+            Ok(Some(prepared)) => prepared,
+            Ok(None) => return Ok(SweepOutcome::Skipped("below_threshold")),
+            Err(e) => return Err(sweep_error(e, "SDK_ERROR")),
+        };
+
+        if config.max_fee_sats > 0 && fee > config.max_fee_sats {
+            let mut error = sweep_error(
+                format!("Sweep fee {} sats exceeds the {} sats limit", fee, config.max_fee_sats),
+                "FEE_TOO_HIGH",
+            );
+            error.set("fee_sats", fee as i64);
+            return Err(error);
+        }
+        if fee >= excess {
+            let mut error = sweep_error(
+                format!("Sweep fee {} sats exceeds the {} sats excess", fee, excess),
+                "FEE_TOO_HIGH",
+            );
+            error.set("fee_sats", fee as i64);
+            return Err(error);
+        }
+        // Leave dust in the wallet rather than attempting a send that cannot succeed
+        if excess - fee < method.min_sats() {
+            return Ok(SweepOutcome::Skipped("dust"));
+        }
+        if prepare_response.amount_sats + fee > excess {
+            let mut error = sweep_error(
+                format!(
+                    "Sweep of {} sats plus {} sats fee would leave less than the {} sats to keep",
+                    prepare_response.amount_sats, fee, config.keep_sats
+                ),
+                "FEE_TOO_HIGH",
+            );
+            error.set("fee_sats", fee as i64);
+            return Err(error);
+        }
+        // Unattended sends get the same ceiling the fee warnings show for manual ones
+        let policy = self.fee_policy.lock().unwrap().clone();
+        let fee_warning = policy.warning(fee, prepare_response.amount_sats);
+        if !fee_warning.is_empty() {
+            let mut error = sweep_error(
+                format!("Sweep fee {} sats is above the fee warning policy ({})", fee, fee_warning),
+                "FEE_TOO_HIGH",
+            );
+            error.set("fee_sats", fee as i64);
+            error.set("fee_warning", fee_warning);
+            return Err(error);
+        }
+
+        let cost = prepare_response.amount_sats + fee;
+        self.check_throttle(cost, false)?;
+
+        let journal_id = self.journal_begin("sweep", &config.destination, prepare_response.amount_sats);
+        let options = send_options_for(&prepare_response);
+        let sent = runtime.block_on(sdk.send_payment(SendPaymentRequest {
+            prepare_response,
+            options,
+        }));
+        let result = sent.map_err(|e| format!("Sweep failed: {:?}", e));
+        Ok(SweepOutcome::Sent(self.finish_send(Dictionary::new(), &journal_id, cost, result)))
+    }
+}
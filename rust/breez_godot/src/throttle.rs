@@ -0,0 +1,257 @@
+use godot::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::events::NodeEvent;
+use crate::storage::{load_json, now_secs, save_json};
+use crate::BreezNode;
+
+const THROTTLE_FILE: &str = "throttle.json";
+
+/// Outgoing payments within the last hour, persisted so restarts don't reset the window
+#[derive(Default, Serialize, Deserialize)]
+struct ThrottleLog {
+    /// (timestamp, sats including fees), oldest first
+    sends: Vec<(u64, u64)>,
+}
+
+/// Sliding-window limits on outgoing payments
+#[derive(Default)]
+pub(crate) struct ThrottleState {
+    max_payments_per_min: u64,
+    max_sats_per_hour: u64,
+    log: ThrottleLog,
+}
+
+impl ThrottleState {
+    fn is_enabled(&self) -> bool {
+        self.max_payments_per_min > 0 || self.max_sats_per_hour > 0
+    }
+
+    fn prune(&mut self, now: u64) {
+        self.log.sends.retain(|(timestamp, _)| timestamp + 3_600 > now);
+    }
+
+    /// Check whether a send of `sats` is allowed, returning the reason and seconds to wait if not
+    fn check(&mut self, sats: u64, now: u64) -> Result<(), (String, u64)> {
+        self.prune(now);
+
+        if self.max_payments_per_min > 0 {
+            let recent: Vec<u64> = self
+                .log
+                .sends
+                .iter()
+                .map(|(timestamp, _)| *timestamp)
+                .filter(|timestamp| timestamp + 60 > now)
+                .collect();
+            if recent.len() as u64 >= self.max_payments_per_min {
+                let oldest = recent[recent.len() - self.max_payments_per_min as usize];
+                return Err((
+                    format!("Limit of {} payments per minute reached", self.max_payments_per_min),
+                    oldest + 60 - now,
+                ));
+            }
+        }
+
+        if self.max_sats_per_hour > 0 {
+            if sats > self.max_sats_per_hour {
+                return Err((
+                    format!("Payment exceeds the {} sats per hour limit", self.max_sats_per_hour),
+                    3_600,
+                ));
+            }
+            let mut spent: u64 = self.log.sends.iter().map(|(_, amount)| amount).sum();
+            if spent + sats > self.max_sats_per_hour {
+                // Wait until enough of the oldest sends leave the window
+                let mut retry_after = 3_600;
+                for (timestamp, amount) in &self.log.sends {
+                    spent -= amount;
+                    if spent + sats <= self.max_sats_per_hour {
+                        retry_after = timestamp + 3_600 - now;
+                        break;
+                    }
+                }
+                return Err((
+                    format!("Limit of {} sats per hour reached", self.max_sats_per_hour),
+                    retry_after,
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check a send of `sats` and count it in the window if it is allowed
+    fn reserve(&mut self, sats: u64, now: u64) -> Result<(), (String, u64)> {
+        self.check(sats, now)?;
+        self.log.sends.push((now, sats));
+        Ok(())
+    }
+
+    /// Take `released` sats back from the newest send counted with `reserved`, returning
+    /// whether the window changed
+    fn release(&mut self, reserved: u64, released: u64) -> bool {
+        let Some(index) = self.log.sends.iter().rposition(|(_, sats)| *sats == reserved) else {
+            return false;
+        };
+        let remaining = reserved.saturating_sub(released);
+        if remaining == 0 {
+            self.log.sends.remove(index);
+        } else {
+            self.log.sends[index].1 = remaining;
+        }
+        true
+    }
+}
+
+#[godot_api(secondary)]
+impl BreezNode {
+    /// Limit outgoing payments across all send paths (0 disables a limit)
+    ///
+    /// The sliding window is stored under storage_dir, so restarting the game does not reset it.
+    /// Blocked sends fail with `error_code` "THROTTLED" and a `retry_after_secs` hint.
+    #[func]
+    pub fn set_throttle_policy(&mut self, max_payments_per_min: i64, max_sats_per_hour: i64) {
+        let mut throttle = self.throttle.lock().unwrap();
+        throttle.max_payments_per_min = max_payments_per_min.max(0) as u64;
+        throttle.max_sats_per_hour = max_sats_per_hour.max(0) as u64;
+        drop(throttle);
+
+        self.load_throttle_log();
+    }
+
+    /// Get the throttle policy and current window usage
+    #[func]
+    pub fn get_throttle_status(&self) -> Dictionary {
+        let now = now_secs();
+        let mut throttle = self.throttle.lock().unwrap();
+        throttle.prune(now);
+
+        let mut dict = Dictionary::new();
+        dict.set("max_payments_per_min", throttle.max_payments_per_min as i64);
+        dict.set("max_sats_per_hour", throttle.max_sats_per_hour as i64);
+        dict.set(
+            "payments_last_minute",
+            throttle.log.sends.iter().filter(|(t, _)| t + 60 > now).count() as i64,
+        );
+        dict.set(
+            "sats_last_hour",
+            throttle.log.sends.iter().map(|(_, amount)| *amount as i64).sum::<i64>(),
+        );
+        dict
+    }
+}
+
+impl BreezNode {
+    /// Load the persisted throttle window for the connected wallet
+    pub(crate) fn load_throttle_log(&self) {
+        let log = self
+            .plugin_path(THROTTLE_FILE)
+            .and_then(|path| load_json::<ThrottleLog>(&path));
+        self.throttle.lock().unwrap().log = log.unwrap_or_default();
+    }
+
+    fn save_throttle_log(&self, log: &ThrottleLog) {
+        if let Some(path) = self.plugin_path(THROTTLE_FILE) {
+            if let Err(e) = save_json(&path, log) {
+                godot_warn!("Failed to persist throttle window: {}", e);
+            }
+        }
+    }
+
+    /// Check the throttle policy before sending `sats`, returning a THROTTLED error Dictionary
+    /// if the send is blocked
+    ///
+    /// A send that passes is counted in the window right away, so payments in flight at the
+    /// same time cannot all pass the check; `release_throttled_send` takes it back when the
+    /// send definitely failed. Pending and timed-out sends stay counted, as they may still
+    /// settle. Dry runs are evaluated the same way but neither count nor emit
+    /// `throttle_tripped`.
+    pub(crate) fn check_throttle(&self, sats: u64, dry_run: bool) -> Result<(), Dictionary> {
+        let mut throttle = self.throttle.lock().unwrap();
+        if !throttle.is_enabled() {
+            return Ok(());
+        }
+
+        let now = now_secs();
+        let checked = if dry_run { throttle.check(sats, now) } else { throttle.reserve(sats, now) };
+        let Err((reason, retry_after)) = checked else {
+            if !dry_run {
+                self.save_throttle_log(&throttle.log);
+            }
+            return Ok(());
+        };
+        drop(throttle);
+
+        godot_warn!("Payment throttled: {}", reason);
+        if !dry_run {
+            self.events.push(NodeEvent::ThrottleTripped {
+                reason: reason.clone(),
+                retry_after_secs: retry_after as i64,
+            });
+        }
+        let mut dict = Dictionary::new();
+        dict.set("success", false);
+        dict.set("error", reason);
+        dict.set("error_code", "THROTTLED");
+        dict.set("retry_after_secs", retry_after as i64);
+        Err(dict)
+    }
+
+    /// Give back `released` sats of a `reserved` amount counted by `check_throttle`, for sends
+    /// that definitely failed; the payment itself only leaves the window once nothing is left
+    pub(crate) fn release_throttled_send(&self, reserved: u64, released: u64) {
+        let mut throttle = self.throttle.lock().unwrap();
+        if throttle.release(reserved, released) {
+            self.save_throttle_log(&throttle.log);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(max_payments_per_min: u64, max_sats_per_hour: u64) -> ThrottleState {
+        ThrottleState {
+            max_payments_per_min,
+            max_sats_per_hour,
+            log: ThrottleLog::default(),
+        }
+    }
+
+    #[test]
+    fn sends_in_flight_count_before_they_complete() {
+        let mut throttle = policy(2, 0);
+        assert!(throttle.reserve(100, 1_000).is_ok());
+        assert!(throttle.reserve(100, 1_000).is_ok());
+        let (_, retry_after) = throttle.reserve(100, 1_000).unwrap_err();
+        assert_eq!(retry_after, 60);
+
+        let mut throttle = policy(0, 1_000);
+        assert!(throttle.reserve(600, 1_000).is_ok());
+        assert!(throttle.reserve(600, 1_000).is_err());
+    }
+
+    #[test]
+    fn failed_sends_are_released() {
+        let mut throttle = policy(1, 1_000);
+        assert!(throttle.reserve(600, 1_000).is_ok());
+        assert!(throttle.release(600, 600));
+        assert!(throttle.log.sends.is_empty());
+        assert!(throttle.reserve(900, 1_000).is_ok());
+
+        // A batch gives back only its failed payouts and still counts as one payment
+        assert!(throttle.release(900, 300));
+        assert_eq!(throttle.log.sends, vec![(1_000, 600)]);
+        assert!(!throttle.release(900, 300));
+    }
+
+    #[test]
+    fn dry_runs_do_not_count() {
+        let mut throttle = policy(1, 0);
+        assert!(throttle.check(100, 1_000).is_ok());
+        assert!(throttle.reserve(100, 1_000).is_ok());
+        assert!(throttle.check(100, 1_059).is_err());
+        assert!(throttle.check(100, 1_060).is_ok());
+    }
+}
@@ -0,0 +1,123 @@
+use godot::prelude::*;
+use breez_sdk_spark::{ListPaymentsRequest, Payment, PaymentDetails};
+use std::sync::Arc;
+
+use crate::history::details_dictionary;
+use crate::BreezNode;
+
+/// A payment from the wallet history, with typed properties for autocompletion
+///
+/// Returned by `list_payments_typed`. Holds the same data as a `list_payments` entry; use
+/// `to_dictionary` to store it in a save file.
+#[derive(GodotClass)]
+#[class(base=RefCounted, init)]
+pub struct BreezPayment {
+    /// SDK payment id
+    #[var]
+    id: GString,
+    #[var]
+    amount_sats: i64,
+    #[var]
+    fees_sats: i64,
+    /// Unix timestamp in seconds
+    #[var]
+    timestamp: i64,
+    /// "Pending", "Completed" or "Failed"
+    #[var]
+    status: GString,
+    /// "Send" or "Receive"
+    #[var]
+    payment_type: GString,
+    /// "Lightning", "Spark", "Deposit", "Withdraw", ...
+    #[var]
+    method: GString,
+    /// Lightning invoice description, empty for other payments
+    #[var]
+    description: GString,
+    /// Method-specific fields, as `details` of `list_payments`
+    #[var]
+    details: Dictionary,
+}
+
+#[godot_api]
+impl BreezPayment {
+    /// The payment as a Dictionary keyed by the property names
+    #[func]
+    pub fn to_dictionary(&self) -> Dictionary {
+        let mut dict = Dictionary::new();
+        dict.set("id", &self.id);
+        dict.set("amount_sats", self.amount_sats);
+        dict.set("fees_sats", self.fees_sats);
+        dict.set("timestamp", self.timestamp);
+        dict.set("status", &self.status);
+        dict.set("payment_type", &self.payment_type);
+        dict.set("method", &self.method);
+        dict.set("description", &self.description);
+        dict.set("details", &self.details);
+        dict
+    }
+}
+
+impl BreezPayment {
+    fn from_sdk(payment: &Payment) -> Gd<Self> {
+        let description = match &payment.details {
+            Some(PaymentDetails::Lightning { description, .. }) => description.clone().unwrap_or_default(),
+            _ => String::new(),
+        };
+        Gd::from_object(Self {
+            id: payment.id.as_str().into(),
+            amount_sats: payment.amount as i64,
+            fees_sats: payment.fees as i64,
+            timestamp: payment.timestamp as i64,
+            status: payment.status.to_string().into(),
+            payment_type: payment.payment_type.to_string().into(),
+            method: payment.method.to_string().into(),
+            description: description.into(),
+            details: details_dictionary(payment.details.as_ref()),
+        })
+    }
+}
+
+#[godot_api(secondary)]
+impl BreezNode {
+    /// List payment history as `BreezPayment` objects
+    ///
+    /// Same paging as `list_payments`; returns an empty Array when not connected or when the
+    /// SDK call fails.
+    ///
+    /// # Arguments
+    /// * `offset` - Number of payments to skip (for pagination)
+    /// * `limit` - Maximum number of payments to return
+    #[func]
+    pub fn list_payments_typed(&self, offset: i64, limit: i64) -> Array<Gd<BreezPayment>> {
+        let mut array = Array::new();
+        match self.fetch_payments(offset, limit) {
+            Ok(payments) => {
+                for payment in &payments {
+                    array.push(&BreezPayment::from_sdk(payment));
+                }
+            }
+            Err(e) => godot_error!("{}", e),
+        }
+        array
+    }
+}
+
+impl BreezNode {
+    /// One page of payments from the SDK, as requested by `list_payments`
+    pub(crate) fn fetch_payments(&self, offset: i64, limit: i64) -> Result<Vec<Payment>, String> {
+        let sdk_handle = self.sdk_handle();
+        let runtime = Arc::clone(&self.runtime);
+
+        runtime.block_on(async move {
+            let sdk = sdk_handle?;
+            match sdk.list_payments(ListPaymentsRequest {
+                offset: if offset > 0 { Some(offset as u32) } else { None },
+                limit: if limit > 0 { Some(limit as u32) } else { None },
+            }).await {
+                Ok(response) => Ok(response.payments),
+                Err(e) => Err(format!("Failed to list payments: {:?}", e)),
+            }
+        })
+    }
+}
@@ -0,0 +1,386 @@
+use godot::classes::image::Format;
+use godot::classes::{Image, ImageTexture};
+use godot::prelude::*;
+use lightning_invoice::{Bolt11InvoiceDescriptionRef, Currency};
+use qrcode::{Color as QrColor, QrCode};
+
+use crate::bolt11::{parse_bolt11_coded, payee_pubkey_hex, payment_hash_hex};
+use crate::format::parse_amount_text;
+use crate::validation;
+use crate::BreezNode;
+
+/// Light modules around a QR code, as required by the spec
+const QR_QUIET_ZONE: usize = 4;
+
+/// Largest QR image side, to keep a bad `module_px` from allocating huge images
+const MAX_QR_IMAGE_PX: usize = 4096;
+
+/// Largest QR margin in modules, well past the 4 the spec asks for
+const MAX_QR_MARGIN: usize = 16;
+
+/// Prefixes of bech32 strings that may be uppercased for a smaller QR code
+const BECH32_QR_PREFIXES: &[&str] = &["lnbc", "lntb", "lntbs", "lnbcrt", "lnurl", "lno", "bc1", "tb1", "bcrt1"];
+
+fn network_name(currency: Currency) -> &'static str {
+    match currency {
+        Currency::Bitcoin => "mainnet",
+        Currency::BitcoinTestnet => "testnet",
+        Currency::Regtest => "regtest",
+        Currency::Simnet => "simnet",
+        Currency::Signet => "signet",
+    }
+}
+
+/// Format satoshis as a BTC decimal without trailing zeros, as BIP21 expects
+pub(crate) fn btc_decimal(sats: u64) -> String {
+    let text = format!("{}.{:08}", sats / 100_000_000, sats % 100_000_000);
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+/// Compare secrets without leaking the position of the first difference
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Percent-encode a BIP21 query value
+fn uri_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// Build a `bitcoin:` URI for an already validated address, leaving out a zero amount and
+/// empty parameters; `lightning` is a BOLT11 invoice payable instead (unified QR)
+pub(crate) fn bip21_uri(
+    address: &str,
+    amount_sats: u64,
+    label: &str,
+    message: &str,
+    lightning: Option<&str>,
+) -> String {
+    let mut params = Vec::new();
+    if amount_sats > 0 {
+        params.push(format!("amount={}", btc_decimal(amount_sats)));
+    }
+    if !label.is_empty() {
+        params.push(format!("label={}", uri_encode(label)));
+    }
+    if !message.is_empty() {
+        params.push(format!("message={}", uri_encode(message)));
+    }
+    if let Some(invoice) = lightning {
+        params.push(format!("lightning={}", uri_encode(invoice)));
+    }
+
+    let mut uri = format!("bitcoin:{}", address);
+    if !params.is_empty() {
+        uri.push('?');
+        uri.push_str(&params.join("&"));
+    }
+    uri
+}
+
+/// Decode a BIP21 query value
+fn uri_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or("");
+                match u8::from_str_radix(hex, 16) {
+                    Ok(byte) => {
+                        decoded.push(byte);
+                        i += 3;
+                        continue;
+                    }
+                    Err(_) => decoded.push(b'%'),
+                }
+            }
+            b'+' => decoded.push(b' '),
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+/// Classify a scanned or pasted payment string without contacting any server
+///
+/// The Dictionary always has `type`: "bolt11", "bip21", "bitcoin_address", "spark_address",
+/// "lightning_address", "lnurl" or "unknown", plus `input`. BOLT11 adds the fields of
+/// `decode_invoice`; BIP21 adds `address`, `amount_sats`, `label`, `message` and `lightning`.
+pub(crate) fn classify_input(input: &str) -> Dictionary {
+    let trimmed = input.trim();
+    let body = if trimmed.to_lowercase().starts_with("lightning:") {
+        &trimmed["lightning:".len()..]
+    } else {
+        trimmed
+    };
+    let lower = body.to_lowercase();
+
+    let mut dict = if lower.starts_with("lnbc") || lower.starts_with("lntb") {
+        let mut dict = BreezNode::decode_invoice(GString::from(body));
+        let valid = dict.get("success").map(|v| v.to::<bool>()).unwrap_or(false);
+        dict.set("type", if valid { "bolt11" } else { "unknown" });
+        dict
+    } else if lower.starts_with("bitcoin:") {
+        let rest = &body["bitcoin:".len()..];
+        let (address, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let mut dict = Dictionary::new();
+        if validation::validate_bitcoin_address(address).is_ok() {
+            dict.set("type", "bip21");
+            dict.set("address", address);
+            dict.set("amount_sats", 0i64);
+            dict.set("label", "");
+            dict.set("message", "");
+            dict.set("lightning", "");
+            for pair in query.split('&') {
+                let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+                let value = uri_decode(value);
+                match key.to_lowercase().as_str() {
+                    "amount" => {
+                        if let Ok(sats) = parse_amount_text(&value, "btc", "en") {
+                            dict.set("amount_sats", sats);
+                        }
+                    }
+                    "label" | "message" | "lightning" => {
+                        dict.set(key.to_lowercase().as_str(), value);
+                    }
+                    _ => {}
+                }
+            }
+        } else {
+            dict.set("type", "unknown");
+        }
+        dict
+    } else {
+        let kind = match validation::validate_destination(body) {
+            Ok(kind) => kind.as_str(),
+            Err(_) => "unknown",
+        };
+        let mut dict = Dictionary::new();
+        dict.set("type", kind);
+        dict
+    };
+
+    dict.set("input", trimmed);
+    dict
+}
+
+/// Content to encode for a QR code: bech32 invoices, LNURLs and addresses, bare or behind a
+/// `lightning:` or `bitcoin:` scheme, are uppercased
+///
+/// Bech32 is case-insensitive, and uppercase text fits the QR alphanumeric mode, which
+/// takes about a third less space than bytes. URIs with parameters are left alone, as
+/// labels and messages are case-sensitive.
+fn qr_payload(text: &str) -> String {
+    let lower = text.to_lowercase();
+    let body = ["lightning:", "bitcoin:"]
+        .iter()
+        .find_map(|scheme| lower.strip_prefix(scheme))
+        .unwrap_or(&lower);
+    let is_bech32 = BECH32_QR_PREFIXES.iter().any(|prefix| body.starts_with(prefix))
+        && body.chars().all(|c| c.is_ascii_alphanumeric());
+    let single_case = text == lower || text == text.to_uppercase();
+    if is_bech32 && single_case {
+        text.to_uppercase()
+    } else {
+        text.to_string()
+    }
+}
+
+/// Render text as a black-on-white QR code image with `margin` light modules around it
+///
+/// The QR version grows with the content, so long invoices get a denser code.
+fn render_qr(text: &str, module_px: usize, margin: usize) -> Result<Gd<Image>, String> {
+    let margin = margin.min(MAX_QR_MARGIN);
+    let payload = qr_payload(text);
+    let code = QrCode::new(payload.as_bytes()).map_err(|e| format!("Failed to encode QR code: {}", e))?;
+    let modules = code.width();
+    let colors = code.to_colors();
+
+    let side_modules = modules + 2 * margin;
+    let module_px = module_px.clamp(1, (MAX_QR_IMAGE_PX / side_modules).max(1));
+    let side = side_modules * module_px;
+
+    let mut pixels = vec![255u8; side * side];
+    for (i, color) in colors.iter().enumerate() {
+        if *color != QrColor::Dark {
+            continue;
+        }
+        let x0 = (i % modules + margin) * module_px;
+        let y0 = (i / modules + margin) * module_px;
+        for y in y0..y0 + module_px {
+            pixels[y * side + x0..y * side + x0 + module_px].fill(0);
+        }
+    }
+
+    Image::create_from_data(
+        side as i32,
+        side as i32,
+        false,
+        Format::L8,
+        &PackedByteArray::from(pixels.as_slice()),
+    )
+    .ok_or_else(|| "Failed to create QR image".to_string())
+}
+
+/// Helpers that need no connection, callable as `BreezNode.decode_invoice(...)` from GDScript
+#[godot_api(secondary)]
+impl BreezNode {
+    /// Decode a BOLT11 invoice locally
+    ///
+    /// Returns a Dictionary with `success`, `amount_sats` (0 for amountless invoices),
+    /// `description`, `description_hash`, `payment_hash`, `payee_pubkey`, `network`,
+    /// `timestamp`, `expiry_secs`, `expires_at` and `is_expired` (against the current
+    /// time). A failure has `error` and `error_code`: INVALID_BECH32 for a string that is
+    /// not bech32, INVALID_INVOICE for anything else. `check_invoice` also checks the
+    /// network against the connected wallet.
+    #[func]
+    pub fn decode_invoice(bolt11: GString) -> Dictionary {
+        let mut dict = Dictionary::new();
+
+        let invoice = match parse_bolt11_coded(&bolt11.to_string()) {
+            Ok(invoice) => invoice,
+            Err((e, code)) => {
+                dict.set("success", false);
+                dict.set("error", e);
+                dict.set("error_code", code);
+                return dict;
+            }
+        };
+
+        let (description, description_hash) = match invoice.description() {
+            Bolt11InvoiceDescriptionRef::Direct(description) => (description.to_string(), String::new()),
+            Bolt11InvoiceDescriptionRef::Hash(hash) => (String::new(), hash.0.to_string()),
+        };
+        let timestamp = invoice.duration_since_epoch().as_secs() as i64;
+        let expiry_secs = invoice.expiry_time().as_secs() as i64;
+
+        dict.set("success", true);
+        dict.set(
+            "amount_sats",
+            invoice.amount_milli_satoshis().map(|msat| (msat / 1000) as i64).unwrap_or(0),
+        );
+        dict.set("description", description);
+        dict.set("description_hash", description_hash);
+        dict.set("payment_hash", payment_hash_hex(&invoice));
+        dict.set("payee_pubkey", payee_pubkey_hex(&invoice));
+        dict.set("network", network_name(invoice.currency()));
+        dict.set("timestamp", timestamp);
+        dict.set("expiry_secs", expiry_secs);
+        dict.set("expires_at", timestamp + expiry_secs);
+        dict.set("is_expired", invoice.is_expired());
+        dict
+    }
+
+    /// Classify a payment string locally, without the network (see `classify_input` for the
+    /// fields); `parse_input` resolves LNURLs through the SDK
+    #[func]
+    pub fn classify_input(input: GString) -> Dictionary {
+        classify_input(&input.to_string())
+    }
+
+    /// Check that a string is a well-formed Bitcoin address on any network
+    #[func]
+    pub fn validate_bitcoin_address(address: GString) -> bool {
+        validation::validate_bitcoin_address(&address.to_string()).is_ok()
+    }
+
+    /// Parse an amount typed by a player into satoshis
+    ///
+    /// # Arguments
+    /// * `text` - e.g. "1,000", "₿0.0005", "2500 sats"; a unit in the text wins
+    /// * `default_unit` - "sat", "btc" or "msat", used when the text has no unit
+    /// * `locale` - Locale code deciding the decimal and group separators
+    ///
+    /// Returns a Dictionary with `success` and `amount_sats`, or `error`.
+    #[func]
+    pub fn parse_amount(text: GString, default_unit: GString, locale: GString) -> Dictionary {
+        let mut dict = Dictionary::new();
+        match parse_amount_text(&text.to_string(), &default_unit.to_string(), &locale.to_string()) {
+            Ok(sats) => {
+                dict.set("success", true);
+                dict.set("amount_sats", sats);
+            }
+            Err(e) => {
+                dict.set("success", false);
+                dict.set("error", e);
+            }
+        }
+        dict
+    }
+
+    /// Build a BIP21 `bitcoin:` URI
+    ///
+    /// `amount_sats` of 0 and empty `label`/`message` are left out. Returns an empty
+    /// string if the address is invalid.
+    #[func]
+    pub fn build_bip21_uri(address: GString, amount_sats: i64, label: GString, message: GString) -> GString {
+        let address = address.to_string().trim().to_string();
+        if let Err(e) = validation::validate_bitcoin_address(&address) {
+            godot_error!("{}", e);
+            return GString::from("");
+        }
+
+        let uri = bip21_uri(
+            &address,
+            amount_sats.max(0) as u64,
+            &label.to_string(),
+            &message.to_string(),
+            None,
+        );
+        GString::from(&uri)
+    }
+
+    /// Render text (an invoice, address or URI) as a QR code Image
+    ///
+    /// # Arguments
+    /// * `text` - Content to encode
+    /// * `module_px` - Pixels per QR module
+    #[func]
+    pub fn generate_qr(text: GString, module_px: i64) -> Option<Gd<Image>> {
+        match render_qr(&text.to_string(), module_px.max(1) as usize, QR_QUIET_ZONE) {
+            Ok(image) => Some(image),
+            Err(e) => {
+                godot_error!("{}", e);
+                None
+            }
+        }
+    }
+
+    /// Render text as a QR code texture, ready for a TextureRect:
+    /// `texture_rect.texture = BreezNode.generate_qr_texture(invoice, 8, 4)`
+    ///
+    /// Bech32 content (invoices, LNURLs, addresses) is uppercased for a smaller code.
+    /// Returns null when the text cannot be encoded, e.g. when it is too long for any
+    /// QR version.
+    ///
+    /// # Arguments
+    /// * `data` - Content to encode
+    /// * `module_size` - Pixels per QR module
+    /// * `margin` - Light modules around the code; scanners expect 4, fewer works on
+    ///   plain backgrounds
+    #[func]
+    pub fn generate_qr_texture(data: GString, module_size: i32, margin: i32) -> Option<Gd<ImageTexture>> {
+        let image = match render_qr(&data.to_string(), module_size.max(1) as usize, margin.max(0) as usize) {
+            Ok(image) => image,
+            Err(e) => {
+                godot_error!("{}", e);
+                return None;
+            }
+        };
+        let texture = ImageTexture::create_from_image(&image);
+        if texture.is_none() {
+            godot_error!("Failed to create QR texture");
+        }
+        texture
+    }
+}
@@ -0,0 +1,326 @@
+use bitcoin::address::NetworkUnchecked;
+use bitcoin::Address;
+use std::str::FromStr;
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
+
+const BECH32_CHARSET: &str = "qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+/// Kind of a reusable payment destination
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum DestinationKind {
+    BitcoinAddress,
+    SparkAddress,
+    LightningAddress,
+    Lnurl,
+}
+
+impl DestinationKind {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            DestinationKind::BitcoinAddress => "bitcoin_address",
+            DestinationKind::SparkAddress => "spark_address",
+            DestinationKind::LightningAddress => "lightning_address",
+            DestinationKind::Lnurl => "lnurl",
+        }
+    }
+}
+
+/// Longest description a BOLT11 invoice can carry, in UTF-8 bytes
+pub(crate) const MAX_DESCRIPTION_BYTES: usize = 639;
+
+/// Why an invoice description was rejected
+pub(crate) enum DescriptionError {
+    ControlCharacter,
+    TooLong { bytes: usize },
+}
+
+impl DescriptionError {
+    pub(crate) fn code(&self) -> &'static str {
+        match self {
+            DescriptionError::ControlCharacter => "INVALID_DESCRIPTION",
+            DescriptionError::TooLong { .. } => "DESCRIPTION_TOO_LONG",
+        }
+    }
+
+    pub(crate) fn message(&self) -> String {
+        match self {
+            DescriptionError::ControlCharacter => "Invoice description contains control characters".to_string(),
+            DescriptionError::TooLong { bytes } => format!(
+                "Invoice description is {} bytes, the limit is {}",
+                bytes, MAX_DESCRIPTION_BYTES
+            ),
+        }
+    }
+}
+
+/// Normalize player-entered text to NFC
+///
+/// Some keyboards produce decomposed text (a letter followed by combining marks), which
+/// displays the same but takes more bytes and compares unequal to the composed form.
+pub(crate) fn normalize_text(text: &str) -> String {
+    text.nfc().collect()
+}
+
+/// Cut `text` to at most `max_bytes` UTF-8 bytes
+///
+/// Cuts only between grapheme clusters, so an emoji sequence or a letter with its combining
+/// marks is dropped whole rather than split. Returns the text and whether it was cut.
+pub(crate) fn truncate_utf8(text: &str, max_bytes: usize) -> (String, bool) {
+    if text.len() <= max_bytes {
+        return (text.to_string(), false);
+    }
+    let end = text
+        .grapheme_indices(true)
+        .map(|(i, g)| i + g.len())
+        .take_while(|end| *end <= max_bytes)
+        .last()
+        .unwrap_or(0);
+    (text[..end].to_string(), true)
+}
+
+/// Longest payer comment passed on to the game, in UTF-8 bytes
+///
+/// LNURL-pay servers commonly allow up to a few hundred characters; this keeps chat
+/// overlays readable without cutting typical messages.
+pub(crate) const MAX_COMMENT_BYTES: usize = 280;
+
+/// Clean a comment for sending or display, without limiting its length
+///
+/// Line breaks and tabs become spaces, other control characters and bidi overrides (which
+/// could flip the surrounding UI text) are dropped, and the result is normalized and trimmed.
+pub(crate) fn clean_comment(comment: &str) -> String {
+    let cleaned: String = comment
+        .chars()
+        .filter_map(|c| match c {
+            '\n' | '\r' | '\t' => Some(' '),
+            '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}' => None,
+            c if c.is_control() => None,
+            c => Some(c),
+        })
+        .collect();
+    normalize_text(&cleaned).trim().to_string()
+}
+
+/// Make a comment written by a payer safe to display: `clean_comment`, capped at
+/// MAX_COMMENT_BYTES
+pub(crate) fn sanitize_comment(comment: &str) -> String {
+    truncate_utf8(&clean_comment(comment), MAX_COMMENT_BYTES).0
+}
+
+/// Check an invoice description against the BOLT11 limits
+///
+/// The description is normalized to NFC first and the limit is counted in UTF-8 bytes.
+/// With `truncate`, an overlong description is cut on a grapheme boundary instead of
+/// rejected. Returns the description to use and whether it was truncated.
+pub(crate) fn check_description(description: &str, truncate: bool) -> Result<(String, bool), DescriptionError> {
+    if description.chars().any(char::is_control) {
+        return Err(DescriptionError::ControlCharacter);
+    }
+    let description = normalize_text(description);
+    if description.len() > MAX_DESCRIPTION_BYTES && !truncate {
+        return Err(DescriptionError::TooLong { bytes: description.len() });
+    }
+    Ok(truncate_utf8(&description, MAX_DESCRIPTION_BYTES))
+}
+
+/// Check a deposit's transaction id (64 hex characters) and output index, returning the index
+pub(crate) fn validate_outpoint(txid: &str, vout: i64) -> Result<u32, String> {
+    if txid.len() != 64 || !txid.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("Invalid txid: expected 64 hex characters, got {}", txid.len()));
+    }
+    u32::try_from(vout).map_err(|_| format!("Invalid vout: {} is not an output index", vout))
+}
+
+/// Check that a payment request looks like a BOLT11 invoice ("lnbc...", "lntb...", "lnbcrt..."),
+/// with or without a "lightning:" prefix
+pub(crate) fn validate_invoice_prefix(invoice: &str) -> Result<(), String> {
+    let lower = invoice.trim().to_lowercase();
+    let body = lower.strip_prefix("lightning:").unwrap_or(&lower);
+    if body.is_empty() {
+        return Err("Invalid payment request: it is empty".to_string());
+    }
+    if !body.starts_with("lnbc") && !body.starts_with("lntb") {
+        return Err("Invalid payment request: a BOLT11 invoice starts with lnbc, lntb or lnbcrt".to_string());
+    }
+    Ok(())
+}
+
+/// Check a Bitcoin address for any network
+pub(crate) fn validate_bitcoin_address(address: &str) -> Result<(), String> {
+    Address::<NetworkUnchecked>::from_str(address.trim())
+        .map(|_| ())
+        .map_err(|e| format!("Invalid Bitcoin address: {}", e))
+}
+
+/// Check a Bitcoin address and that it belongs to `network` ("mainnet" or "regtest")
+pub(crate) fn validate_bitcoin_address_for(address: &str, network: &str) -> Result<(), String> {
+    let parsed = Address::<NetworkUnchecked>::from_str(address.trim())
+        .map_err(|e| format!("Invalid Bitcoin address: {}", e))?;
+    let expected = match network {
+        "regtest" => bitcoin::Network::Regtest,
+        _ => bitcoin::Network::Bitcoin,
+    };
+    if !parsed.is_valid_for_network(expected) {
+        return Err(format!("Bitcoin address is not a {} address", network));
+    }
+    Ok(())
+}
+
+/// Check the shape of a bech32m Spark address ("sp1…" on mainnet, "sprt1…" on regtest)
+pub(crate) fn validate_spark_address(address: &str) -> Result<(), String> {
+    let lower = address.trim().to_lowercase();
+    let data = lower
+        .strip_prefix("sprt1")
+        .or_else(|| lower.strip_prefix("sp1"))
+        .ok_or_else(|| "Invalid Spark address: unknown prefix".to_string())?;
+    if data.len() < 20 || !data.chars().all(|c| BECH32_CHARSET.contains(c)) {
+        return Err("Invalid Spark address: malformed data".to_string());
+    }
+    Ok(())
+}
+
+/// Check a lightning address of the form "name@domain.tld"
+pub(crate) fn validate_lightning_address(address: &str) -> Result<(), String> {
+    let (user, domain) = address
+        .trim()
+        .split_once('@')
+        .ok_or_else(|| "Invalid lightning address: missing '@'".to_string())?;
+    let user_ok = !user.is_empty()
+        && user
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_.+".contains(c));
+    let domain_ok = domain.contains('.')
+        && !domain.starts_with('.')
+        && !domain.ends_with('.')
+        && domain
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-.:".contains(c));
+    if user_ok && domain_ok {
+        Ok(())
+    } else {
+        Err("Invalid lightning address".to_string())
+    }
+}
+
+/// Classify and validate a destination that can be paid more than once
+pub(crate) fn validate_destination(destination: &str) -> Result<DestinationKind, String> {
+    let trimmed = destination.trim();
+    let lower = trimmed.to_lowercase();
+
+    if lower.starts_with("lnbc") || lower.starts_with("lntb") {
+        return Err("Invoices are single-use and cannot be saved as a destination".to_string());
+    }
+    if let Some(rest) = lower.strip_prefix("lnurlp://") {
+        // LUD-17 scheme: the URL of the pay endpoint without "https://"
+        if rest.split('/').next().is_some_and(|host| host.contains('.')) {
+            return Ok(DestinationKind::Lnurl);
+        }
+        return Err("Invalid LNURL".to_string());
+    }
+    if lower.starts_with("lnurl1") {
+        if lower["lnurl1".len()..].chars().all(|c| BECH32_CHARSET.contains(c)) {
+            return Ok(DestinationKind::Lnurl);
+        }
+        return Err("Invalid LNURL".to_string());
+    }
+    if trimmed.contains('@') {
+        return validate_lightning_address(trimmed).map(|_| DestinationKind::LightningAddress);
+    }
+    if lower.starts_with("sp1") || lower.starts_with("sprt1") {
+        return validate_spark_address(trimmed).map(|_| DestinationKind::SparkAddress);
+    }
+    validate_bitcoin_address(trimmed).map(|_| DestinationKind::BitcoinAddress)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn descriptions_are_normalized_to_nfc() {
+        let (description, truncated) = check_description("Cafe\u{301} au lait", false).ok().unwrap();
+        assert_eq!(description, "Caf\u{e9} au lait");
+        assert!(!truncated);
+    }
+
+    #[test]
+    fn limit_is_counted_after_normalization() {
+        // 320 decomposed "é" are 960 bytes but 640 once composed
+        let decomposed = "e\u{301}".repeat(MAX_DESCRIPTION_BYTES / 2 + 1);
+        assert!(matches!(
+            check_description(&decomposed, false),
+            Err(DescriptionError::TooLong { bytes: 640 })
+        ));
+    }
+
+    #[test]
+    fn cjk_and_rtl_descriptions_fit_up_to_the_byte_limit() {
+        let cjk = "日本語".repeat(MAX_DESCRIPTION_BYTES / 9);
+        assert_eq!(cjk.len(), MAX_DESCRIPTION_BYTES);
+        assert_eq!(check_description(&cjk, false).ok(), Some((cjk.clone(), false)));
+
+        let rtl = "مرحبا بالعالم";
+        assert_eq!(check_description(rtl, false).ok(), Some((rtl.to_string(), false)));
+    }
+
+    #[test]
+    fn truncation_keeps_emoji_sequences_whole() {
+        // A skin-toned thumbs up is 8 bytes and would straddle the limit
+        let padding = "a".repeat(MAX_DESCRIPTION_BYTES - 2);
+        let description = format!("{}👍🏽", padding);
+        assert!(matches!(
+            check_description(&description, false),
+            Err(DescriptionError::TooLong { .. })
+        ));
+        assert_eq!(check_description(&description, true).ok(), Some((padding, true)));
+
+        let flags = "🇯🇵".repeat(80);
+        let (cut, truncated) = truncate_utf8(&flags, MAX_DESCRIPTION_BYTES);
+        assert!(truncated);
+        assert_eq!(cut, "🇯🇵".repeat(79));
+    }
+
+    #[test]
+    fn truncation_keeps_combining_marks_with_their_letter() {
+        // "q" has no precomposed form with an acute accent, so NFC keeps the mark separate
+        let padding = "a".repeat(MAX_DESCRIPTION_BYTES - 2);
+        let (cut, truncated) = check_description(&format!("{}q\u{301}", padding), true).ok().unwrap();
+        assert_eq!(cut, padding);
+        assert!(truncated);
+    }
+
+    #[test]
+    fn control_characters_are_rejected_in_descriptions() {
+        assert!(matches!(
+            check_description("line one\nline two", false),
+            Err(DescriptionError::ControlCharacter)
+        ));
+    }
+
+    #[test]
+    fn comments_replace_line_breaks_and_drop_control_characters() {
+        assert_eq!(sanitize_comment("  gg\r\nwp\t!\u{7}  "), "gg  wp !");
+    }
+
+    #[test]
+    fn comments_drop_bidi_overrides_but_keep_rtl_text() {
+        assert_eq!(sanitize_comment("\u{202E}txt.exe"), "txt.exe");
+        assert_eq!(sanitize_comment("\u{2067}שלום\u{2069} gg"), "שלום gg");
+    }
+
+    #[test]
+    fn comments_are_normalized_and_cut_on_grapheme_boundaries() {
+        assert_eq!(sanitize_comment("Cafe\u{301}"), "Caf\u{e9}");
+
+        let comment = format!("a{}", "😀".repeat(70));
+        assert_eq!(comment.len(), MAX_COMMENT_BYTES + 1);
+        assert_eq!(sanitize_comment(&comment), format!("a{}", "😀".repeat(69)));
+    }
+
+    #[test]
+    fn truncation_without_a_whole_grapheme_returns_empty() {
+        assert_eq!(truncate_utf8("👍🏽", 3), (String::new(), true));
+        assert_eq!(truncate_utf8("abc", 3), ("abc".to_string(), false));
+    }
+}
@@ -0,0 +1,96 @@
+use godot::prelude::*;
+use breez_sdk_spark::{GetInfoRequest, ListPaymentsRequest, Payment, PaymentStatus, PaymentType};
+use std::sync::Arc;
+
+use crate::BreezNode;
+
+/// Recent payments scanned for pending amounts; pending payments settle or fail within hours
+const PENDING_SCAN_LIMIT: u32 = 100;
+
+/// Message signed to learn the wallet's identity key, which the SDK returns with the signature
+const IDENTITY_MESSAGE: &str = "breez_godot identity";
+
+/// Amounts still in flight: (outgoing amount plus fees, incoming amount)
+fn pending_amounts(payments: &[Payment]) -> (u64, u64) {
+    payments
+        .iter()
+        .filter(|payment| payment.status == PaymentStatus::Pending)
+        .fold((0, 0), |(send, receive), payment| match payment.payment_type {
+            PaymentType::Send => (send + payment.amount as u64 + payment.fees as u64, receive),
+            PaymentType::Receive => (send, receive + payment.amount as u64),
+        })
+}
+
+#[godot_api(secondary)]
+impl BreezNode {
+    /// Everything known about the connected wallet in one call
+    ///
+    /// Returns `success`, `balance_sats`, `pending_send_sats` (amount plus fees of sends not
+    /// settled yet, already taken off the balance), `pending_receive_sats` (receives not in
+    /// the balance yet), `network` ("mainnet" or "regtest") and `identity_pubkey` (hex), or
+    /// `error` and `error_code` (NOT_CONNECTED without a wallet). The pending amounts and the
+    /// pubkey are left out when they cannot be read.
+    ///
+    /// # Arguments
+    /// * `ensure_synced` - Sync with the network first (see `get_balance_detailed`)
+    #[func]
+    pub fn get_info(&self, ensure_synced: bool) -> Dictionary {
+        let result = self.get_info_with(ensure_synced);
+        self.report_failure("get_info", result)
+    }
+}
+
+impl BreezNode {
+    fn get_info_with(&self, ensure_synced: bool) -> Dictionary {
+        let mut dict = Dictionary::new();
+        let sdk = match self.sdk_handle() {
+            Ok(sdk) => sdk,
+            Err(e) => {
+                dict.set("success", false);
+                dict.set("error", e);
+                dict.set("error_code", "NOT_CONNECTED");
+                return dict;
+            }
+        };
+        let runtime = Arc::clone(&self.runtime);
+        let (info, payments) = runtime.block_on(async {
+            let info = sdk.get_info(GetInfoRequest {
+                ensure_synced: Some(ensure_synced),
+            }).await;
+            let payments = sdk.list_payments(ListPaymentsRequest {
+                offset: None,
+                limit: Some(PENDING_SCAN_LIMIT),
+            }).await;
+            (info, payments)
+        });
+
+        let info = match info {
+            Ok(info) => info,
+            Err(e) => {
+                godot_error!("Failed to get wallet info: {:?}", e);
+                dict.set("success", false);
+                dict.set("error", format!("Failed to get wallet info: {:?}", e));
+                return dict;
+            }
+        };
+        self.evaluate_low_balance(info.balance_sats);
+        dict.set("success", true);
+        dict.set("balance_sats", info.balance_sats as i64);
+        match payments {
+            Ok(response) => {
+                let (send, receive) = pending_amounts(&response.payments);
+                dict.set("pending_send_sats", send as i64);
+                dict.set("pending_receive_sats", receive as i64);
+            }
+            Err(e) => godot_warn!("Failed to list pending payments: {:?}", e),
+        }
+        if let Some(network) = self.wallet_network.lock().unwrap().clone() {
+            dict.set("network", network);
+        }
+        match self.sign_message_inner(IDENTITY_MESSAGE.to_string(), true) {
+            Ok((_, pubkey)) => dict.set("identity_pubkey", pubkey),
+            Err(e) => godot_warn!("Failed to read the identity key: {}", e),
+        }
+        dict
+    }
+}
@@ -0,0 +1,254 @@
+use godot::prelude::*;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::time::Instant;
+
+use crate::events::NodeEvent;
+use crate::storage::{load_json, now_secs, save_json};
+use crate::utils::constant_time_eq;
+use crate::BreezNode;
+
+const WALLET_LOCK_FILE: &str = "wallet_lock.json";
+
+/// PBKDF2-HMAC-SHA256 rounds used to hash the PIN
+const PIN_KDF_ROUNDS: u32 = 210_000;
+
+const MIN_PIN_LEN: usize = 4;
+
+/// Failed unlock attempts allowed before lockouts start
+const FREE_ATTEMPTS: u32 = 3;
+
+/// First lockout after the free attempts, doubled for every further failure
+const BASE_LOCKOUT_SECS: u64 = 30;
+
+const MAX_LOCKOUT_SECS: u64 = 3_600;
+
+/// Hashed PIN and failed-attempt state, persisted so restarting does not reset the lockout
+#[derive(Default, Serialize, Deserialize)]
+struct PinRecord {
+    salt: String,
+    hash: String,
+    failed_attempts: u32,
+    locked_out_until: u64,
+}
+
+/// Lock state for outgoing payments
+#[derive(Default)]
+pub(crate) struct WalletLock {
+    pin: Option<PinRecord>,
+    locked: bool,
+    /// Relock after this many seconds unlocked (0 to disable)
+    auto_relock_secs: u64,
+    unlocked_at: Option<Instant>,
+}
+
+fn hash_pin(pin: &str, salt: &[u8]) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(pin.as_bytes(), salt, PIN_KDF_ROUNDS, &mut out);
+    out
+}
+
+fn lockout_secs(failed_attempts: u32) -> u64 {
+    if failed_attempts < FREE_ATTEMPTS {
+        return 0;
+    }
+    let doublings = (failed_attempts - FREE_ATTEMPTS).min(16);
+    (BASE_LOCKOUT_SECS << doublings).min(MAX_LOCKOUT_SECS)
+}
+
+#[godot_api(secondary)]
+impl BreezNode {
+    /// Set the PIN required to unlock outgoing payments (an empty PIN removes it)
+    ///
+    /// The PIN is stored as a salted PBKDF2 hash under storage_dir. Changing or removing an
+    /// existing PIN requires the wallet to be unlocked.
+    #[func]
+    pub fn set_wallet_pin(&mut self, pin: GString) -> bool {
+        let pin = pin.to_string();
+        let Some(path) = self.plugin_path(WALLET_LOCK_FILE) else {
+            godot_error!("SDK not initialized");
+            return false;
+        };
+
+        let mut lock = self.wallet_lock.lock().unwrap();
+        if lock.locked {
+            godot_error!("Unlock the wallet before changing its PIN");
+            return false;
+        }
+
+        if pin.is_empty() {
+            lock.pin = None;
+            if let Err(e) = std::fs::remove_file(&path) {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    godot_error!("Failed to remove wallet PIN: {}", e);
+                    return false;
+                }
+            }
+            godot_print!("Wallet PIN removed");
+            return true;
+        }
+        if pin.chars().count() < MIN_PIN_LEN {
+            godot_error!("Wallet PIN must have at least {} characters", MIN_PIN_LEN);
+            return false;
+        }
+
+        let mut salt = [0u8; 16];
+        if let Err(e) = getrandom::getrandom(&mut salt) {
+            godot_error!("Failed to generate PIN salt: {}", e);
+            return false;
+        }
+        let record = PinRecord {
+            salt: hex::encode(salt),
+            hash: hex::encode(hash_pin(&pin, &salt)),
+            failed_attempts: 0,
+            locked_out_until: 0,
+        };
+        if let Err(e) = save_json(&path, &record) {
+            godot_error!("Failed to save wallet PIN: {}", e);
+            return false;
+        }
+        lock.pin = Some(record);
+        godot_print!("Wallet PIN set");
+        true
+    }
+
+    /// Lock outgoing payments; receiving, balance and history keep working
+    #[func]
+    pub fn lock_wallet(&mut self) -> bool {
+        let mut lock = self.wallet_lock.lock().unwrap();
+        if lock.pin.is_none() {
+            godot_error!("Set a wallet PIN before locking the wallet");
+            return false;
+        }
+        if !lock.locked {
+            lock.locked = true;
+            lock.unlocked_at = None;
+            self.events.push(NodeEvent::WalletLockChanged { locked: true });
+        }
+        true
+    }
+
+    /// Unlock outgoing payments with the wallet PIN
+    ///
+    /// After 3 failed attempts, further attempts are refused for 30 seconds, doubling with
+    /// every failure up to an hour. The failure count survives restarts.
+    #[func]
+    pub fn unlock_wallet(&mut self, pin: GString) -> bool {
+        let path = self.plugin_path(WALLET_LOCK_FILE);
+        let mut lock = self.wallet_lock.lock().unwrap();
+        if !lock.locked {
+            return true;
+        }
+        let Some(record) = lock.pin.as_mut() else {
+            return false;
+        };
+
+        let now = now_secs();
+        if now < record.locked_out_until {
+            godot_warn!("Too many failed unlock attempts, retry in {} s", record.locked_out_until - now);
+            return false;
+        }
+
+        let salt = hex::decode(&record.salt).unwrap_or_default();
+        let expected = hex::decode(&record.hash).unwrap_or_default();
+        let matches = constant_time_eq(&hash_pin(&pin.to_string(), &salt), &expected);
+
+        if matches {
+            record.failed_attempts = 0;
+            record.locked_out_until = 0;
+        } else {
+            record.failed_attempts += 1;
+            record.locked_out_until = now + lockout_secs(record.failed_attempts);
+            godot_warn!("Wrong wallet PIN ({} failed attempts)", record.failed_attempts);
+        }
+        if let Some(path) = path {
+            if let Err(e) = save_json(&path, &*record) {
+                godot_warn!("Failed to persist unlock attempts: {}", e);
+            }
+        }
+
+        if matches {
+            lock.locked = false;
+            lock.unlocked_at = Some(Instant::now());
+            self.events.push(NodeEvent::WalletLockChanged { locked: false });
+        }
+        matches
+    }
+
+    /// Whether outgoing payments are locked
+    #[func]
+    pub fn is_wallet_locked(&self) -> bool {
+        self.wallet_lock.lock().unwrap().locked
+    }
+
+    /// Relock the wallet automatically after `secs` seconds unlocked (0 to disable)
+    #[func]
+    pub fn set_auto_relock(&mut self, secs: i64) {
+        self.wallet_lock.lock().unwrap().auto_relock_secs = secs.max(0) as u64;
+    }
+}
+
+impl BreezNode {
+    /// Load the PIN of the connected wallet; a wallet with a PIN starts locked
+    pub(crate) fn load_wallet_lock(&self) {
+        let record = self
+            .plugin_path(WALLET_LOCK_FILE)
+            .and_then(|path| load_json::<PinRecord>(&path));
+        let mut lock = self.wallet_lock.lock().unwrap();
+        lock.locked = record.is_some();
+        lock.pin = record;
+        lock.unlocked_at = None;
+    }
+
+    /// Fail with WALLET_LOCKED while outgoing payments are locked
+    pub(crate) fn check_unlocked(&self) -> Result<(), Dictionary> {
+        if !self.is_wallet_locked() {
+            return Ok(());
+        }
+        let mut dict = Dictionary::new();
+        dict.set("success", false);
+        dict.set("error", "Wallet is locked");
+        dict.set("error_code", "WALLET_LOCKED");
+        Err(dict)
+    }
+
+    /// Relock once the auto-relock timeout has passed, called every frame
+    pub(crate) fn check_auto_relock(&self) {
+        let mut lock = self.wallet_lock.lock().unwrap();
+        let expired = match lock.unlocked_at {
+            Some(at) => lock.auto_relock_secs > 0 && at.elapsed().as_secs() >= lock.auto_relock_secs,
+            None => false,
+        };
+        if expired && lock.pin.is_some() {
+            lock.locked = true;
+            lock.unlocked_at = None;
+            self.events.push(NodeEvent::WalletLockChanged { locked: true });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_attempts_are_free() {
+        for failed_attempts in 0..FREE_ATTEMPTS {
+            assert_eq!(lockout_secs(failed_attempts), 0);
+        }
+    }
+
+    #[test]
+    fn lockout_doubles_after_the_free_attempts() {
+        assert_eq!(lockout_secs(FREE_ATTEMPTS), BASE_LOCKOUT_SECS);
+        assert_eq!(lockout_secs(FREE_ATTEMPTS + 1), BASE_LOCKOUT_SECS * 2);
+        assert_eq!(lockout_secs(FREE_ATTEMPTS + 2), BASE_LOCKOUT_SECS * 4);
+    }
+
+    #[test]
+    fn lockout_is_capped() {
+        assert_eq!(lockout_secs(FREE_ATTEMPTS + 7), MAX_LOCKOUT_SECS);
+        // Large counts must not overflow the shift
+        assert_eq!(lockout_secs(u32::MAX), MAX_LOCKOUT_SECS);
+    }
+}
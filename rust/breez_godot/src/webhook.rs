@@ -0,0 +1,344 @@
+use godot::prelude::*;
+use breez_sdk_spark::{ListPaymentsRequest, Payment, PaymentType, ReceivePaymentMethod, ReceivePaymentRequest};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+use crate::storage::{load_json, now_secs, save_json};
+use crate::BreezNode;
+
+const WEBHOOK_QUEUE_FILE: &str = "webhook_queue.json";
+
+/// How often the delivery loop looks for due events
+const DELIVERY_POLL_SECS: u64 = 5;
+
+/// Longest delay between two delivery attempts
+const MAX_BACKOFF_SECS: u64 = 3_600;
+
+/// Header carrying the hex HMAC-SHA256 of the body, prefixed with "sha256="
+const SIGNATURE_HEADER: &str = "X-Breez-Signature";
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Sign a webhook body with the shared secret
+pub(crate) fn sign_body(secret: &str, body: &str) -> String {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(body.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// An event waiting to be delivered to the game server
+#[derive(Clone, Serialize, Deserialize)]
+struct QueuedWebhook {
+    id: String,
+    event_type: String,
+    body: String,
+    created_at: u64,
+    attempts: u32,
+    next_attempt_at: u64,
+    last_error: Option<String>,
+}
+
+/// Undelivered events and the receive cursor, persisted across sessions
+#[derive(Default, Serialize, Deserialize)]
+struct WebhookStore {
+    events: Vec<QueuedWebhook>,
+    /// Timestamp of the newest received payment already queued
+    last_receive_ts: u64,
+}
+
+#[derive(Clone)]
+struct WebhookTarget {
+    url: String,
+    secret: String,
+    identity: String,
+}
+
+/// Webhook configuration and delivery state (disabled until `set_server_webhook`)
+#[derive(Default)]
+pub(crate) struct WebhookState {
+    target: Option<WebhookTarget>,
+    store: WebhookStore,
+    path: Option<PathBuf>,
+    task: Option<JoinHandle<()>>,
+}
+
+impl WebhookState {
+    fn persist(&self) {
+        if let Some(path) = &self.path {
+            if let Err(e) = save_json(path, &self.store) {
+                godot_warn!("Failed to persist webhook queue: {}", e);
+            }
+        }
+    }
+
+    fn enqueue(&mut self, event_type: &str, payment: &Payment) {
+        let Some(target) = &self.target else {
+            return;
+        };
+        let id = format!("{}-{}", event_type, payment.id);
+        if self.store.events.iter().any(|e| e.id == id) {
+            return;
+        }
+
+        let now = now_secs();
+        let body = serde_json::json!({
+            "id": id,
+            "event": event_type,
+            "created_at": now,
+            "wallet": { "identity": target.identity },
+            "payment": {
+                "id": payment.id,
+                "amount": payment.amount as u64,
+                "fees": payment.fees as u64,
+                "timestamp": payment.timestamp,
+                "status": payment.status.to_string(),
+                "payment_type": payment.payment_type.to_string(),
+                "method": payment.method.to_string(),
+            },
+        });
+
+        self.store.events.push(QueuedWebhook {
+            id,
+            event_type: event_type.to_string(),
+            body: body.to_string(),
+            created_at: now,
+            attempts: 0,
+            next_attempt_at: now,
+            last_error: None,
+        });
+        self.persist();
+    }
+}
+
+/// Deliver every due event once, updating backoff state for failures
+async fn deliver_due(state: &Mutex<WebhookState>, client: &reqwest::Client) {
+    let (target, due) = {
+        let state = state.lock().unwrap();
+        let Some(target) = state.target.clone() else {
+            return;
+        };
+        let now = now_secs();
+        let due: Vec<QueuedWebhook> = state
+            .store
+            .events
+            .iter()
+            .filter(|e| e.next_attempt_at <= now)
+            .cloned()
+            .collect();
+        (target, due)
+    };
+
+    for event in due {
+        let signature = sign_body(&target.secret, &event.body);
+        let outcome = client
+            .post(&target.url)
+            .header("Content-Type", "application/json")
+            .header(SIGNATURE_HEADER, format!("sha256={}", signature))
+            .header("X-Breez-Event", &event.event_type)
+            .header("X-Breez-Delivery", &event.id)
+            .body(event.body.clone())
+            .send()
+            .await;
+
+        let error = match outcome {
+            Ok(response) if response.status().is_success() => None,
+            Ok(response) => Some(format!("HTTP {}", response.status())),
+            Err(e) => Some(e.to_string()),
+        };
+
+        let mut state = state.lock().unwrap();
+        match error {
+            None => state.store.events.retain(|e| e.id != event.id),
+            Some(error) => {
+                if let Some(entry) = state.store.events.iter_mut().find(|e| e.id == event.id) {
+                    entry.attempts += 1;
+                    let backoff = (DELIVERY_POLL_SECS << entry.attempts.min(10)).min(MAX_BACKOFF_SECS);
+                    entry.next_attempt_at = now_secs() + backoff;
+                    entry.last_error = Some(error);
+                }
+            }
+        }
+        state.persist();
+    }
+}
+
+#[godot_api(secondary)]
+impl BreezNode {
+    /// Deliver payment events to a game server (disabled by default)
+    ///
+    /// On payment_received, payment_sent and deposit_claimed, a JSON body is POSTed to `url`
+    /// with an `X-Breez-Signature: sha256=<hex>` header holding the HMAC-SHA256 of the body
+    /// keyed by `secret`. Failed deliveries are retried with backoff and persisted under
+    /// storage_dir, so they are retried next session once the webhook is set again.
+    #[func]
+    pub fn set_server_webhook(&mut self, url: GString, secret: GString) -> bool {
+        let url = url.to_string();
+        let secret = secret.to_string();
+        if !(url.starts_with("https://") || url.starts_with("http://")) {
+            godot_error!("Webhook URL must start with http:// or https://");
+            return false;
+        }
+        if secret.is_empty() {
+            godot_error!("Webhook secret is empty");
+            return false;
+        }
+        let Some(path) = self.plugin_path(WEBHOOK_QUEUE_FILE) else {
+            godot_error!("SDK not initialized");
+            return false;
+        };
+
+        let identity = self.webhook_identity();
+        self.clear_server_webhook();
+
+        let mut store: WebhookStore = load_json(&path).unwrap_or_default();
+        if store.last_receive_ts == 0 {
+            // Don't report payments received before the webhook was first configured
+            store.last_receive_ts = now_secs();
+        }
+
+        let state = Arc::clone(&self.webhook);
+        {
+            let mut webhook = state.lock().unwrap();
+            webhook.target = Some(WebhookTarget { url, secret, identity });
+            webhook.store = store;
+            webhook.path = Some(path);
+            webhook.persist();
+        }
+
+        let client = match self.http_client() {
+            Ok(client) => client,
+            Err(e) => {
+                godot_error!("Failed to create webhook client: {}", e);
+                return false;
+            }
+        };
+
+        let task_state = Arc::clone(&state);
+        let handle = self.runtime.spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(DELIVERY_POLL_SECS));
+            loop {
+                interval.tick().await;
+                deliver_due(&task_state, &client).await;
+            }
+        });
+        state.lock().unwrap().task = Some(handle);
+
+        godot_print!("Server webhook enabled");
+        true
+    }
+
+    /// Stop delivering webhooks; undelivered events stay persisted for the next session
+    #[func]
+    pub fn clear_server_webhook(&mut self) {
+        let mut webhook = self.webhook.lock().unwrap();
+        if let Some(handle) = webhook.task.take() {
+            handle.abort();
+        }
+        webhook.target = None;
+    }
+
+    /// List undelivered webhook events with their delivery status
+    #[func]
+    pub fn get_webhook_queue(&self) -> Array<Dictionary> {
+        let webhook = self.webhook.lock().unwrap();
+        webhook
+            .store
+            .events
+            .iter()
+            .map(|event| {
+                let mut dict = Dictionary::new();
+                dict.set("id", event.id.as_str());
+                dict.set("event", event.event_type.as_str());
+                dict.set("created_at", event.created_at as i64);
+                dict.set("attempts", event.attempts as i64);
+                dict.set("next_attempt_at", event.next_attempt_at as i64);
+                dict.set("last_error", event.last_error.clone().unwrap_or_default());
+                dict
+            })
+            .collect()
+    }
+}
+
+impl BreezNode {
+    /// Queue a webhook for a payment event if webhooks are enabled
+    pub(crate) fn queue_webhook(&self, event_type: &str, payment: &Payment) {
+        self.webhook.lock().unwrap().enqueue(event_type, payment);
+    }
+
+    /// Queue payment_received webhooks for receives newer than the stored cursor
+    pub(crate) fn queue_received_webhooks(&self) {
+        let cursor = {
+            let webhook = self.webhook.lock().unwrap();
+            if webhook.target.is_none() {
+                return;
+            }
+            webhook.store.last_receive_ts
+        };
+
+        let sdk_handle = self.sdk_handle();
+        let runtime = Arc::clone(&self.runtime);
+
+        let payments = runtime.block_on(async move {
+            match sdk_handle {
+                Ok(sdk) => sdk
+                    .list_payments(ListPaymentsRequest {
+                        offset: None,
+                        limit: Some(50),
+                    })
+                    .await
+                    .map(|response| response.payments)
+                    .unwrap_or_default(),
+                Err(_) => Vec::new(),
+            }
+        });
+
+        let mut webhook = self.webhook.lock().unwrap();
+        let mut newest = cursor;
+        for payment in payments.iter().rev() {
+            if payment.payment_type == PaymentType::Receive && payment.timestamp > cursor {
+                webhook.enqueue("payment_received", payment);
+                newest = newest.max(payment.timestamp);
+            }
+        }
+        if newest != cursor {
+            webhook.store.last_receive_ts = newest;
+            webhook.persist();
+        }
+    }
+
+    /// HTTP client for the plugin's own requests, going through the configured proxy
+    pub(crate) fn http_client(&self) -> Result<reqwest::Client, String> {
+        let mut builder = reqwest::Client::builder().timeout(Duration::from_secs(10));
+        if let Some(proxy) = self.http_proxy.lock().unwrap().as_ref() {
+            match reqwest::Proxy::all(proxy) {
+                Ok(proxy) => builder = builder.proxy(proxy),
+                Err(e) => godot_warn!("Ignoring invalid proxy: {}", e),
+            }
+        }
+        builder.build().map_err(|e| e.to_string())
+    }
+
+    /// Wallet identity included in webhook bodies (its Spark address)
+    fn webhook_identity(&self) -> String {
+        let sdk_handle = self.sdk_handle();
+        let runtime = Arc::clone(&self.runtime);
+
+        runtime.block_on(async move {
+            match sdk_handle {
+                Ok(sdk) => sdk
+                    .receive_payment(ReceivePaymentRequest {
+                        payment_method: ReceivePaymentMethod::SparkAddress,
+                    })
+                    .await
+                    .map(|response| response.payment_request)
+                    .unwrap_or_default(),
+                Err(_) => String::new(),
+            }
+        })
+    }
+}
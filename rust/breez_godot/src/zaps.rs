@@ -0,0 +1,374 @@
+use godot::prelude::*;
+use bitcoin::secp256k1::{schnorr, Message, Secp256k1, XOnlyPublicKey};
+use breez_sdk_spark::{ListPaymentsRequest, PaymentDetails, PaymentStatus, PaymentType};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::bolt11::{parse_bolt11, payment_hash_hex};
+use crate::events::NodeEvent;
+use crate::limits::{check_amount, AmountMethod};
+use crate::storage::{load_json, now_secs, save_json};
+use crate::validation::{sanitize_comment, MAX_DESCRIPTION_BYTES};
+use crate::BreezNode;
+
+pub(crate) const ZAPS_FILE: &str = "zaps.json";
+
+/// Nostr event kind of a zap request (NIP-57)
+const ZAP_REQUEST_KIND: u64 = 9734;
+
+/// Nostr event kind of a zap receipt (NIP-57)
+const ZAP_RECEIPT_KIND: i64 = 9735;
+
+/// Number of recent payments scanned for paid zap invoices
+const ZAP_SCAN_LIMIT: u32 = 50;
+
+/// A zap invoice and the request it commits to
+#[derive(Serialize, Deserialize, Clone)]
+struct ZapRecord {
+    payment_hash: String,
+    invoice: String,
+    /// The zap request exactly as received, used as the invoice description
+    request: String,
+    zapper_pubkey: String,
+    recipient_pubkey: String,
+    event_id: Option<String>,
+    coordinate: Option<String>,
+    comment: String,
+    amount_msat: u64,
+    payment_id: Option<String>,
+    preimage: Option<String>,
+    paid_at: Option<u64>,
+}
+
+/// Fields of a validated zap request
+struct ZapRequest {
+    pubkey: String,
+    recipient: String,
+    event_id: Option<String>,
+    coordinate: Option<String>,
+    comment: String,
+    amount_msat: u64,
+}
+
+/// Values of every tag named `name`
+fn tag_values<'a>(tags: &'a [Vec<String>], name: &str) -> Vec<&'a str> {
+    tags.iter()
+        .filter(|tag| tag.first().map(String::as_str) == Some(name))
+        .filter_map(|tag| tag.get(1).map(String::as_str))
+        .collect()
+}
+
+fn is_hex_key(value: &str) -> bool {
+    value.len() == 64 && value.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Validate a zap request event following NIP-57 (appendix D)
+fn parse_zap_request(json: &str) -> Result<ZapRequest, String> {
+    let event: Value = serde_json::from_str(json).map_err(|e| format!("Invalid zap request JSON: {}", e))?;
+    let field = |name: &str| event.get(name).ok_or_else(|| format!("Zap request is missing '{}'", name));
+
+    let kind = field("kind")?.as_u64().ok_or("Zap request kind is not a number")?;
+    if kind != ZAP_REQUEST_KIND {
+        return Err(format!("Expected a kind {} event, got kind {}", ZAP_REQUEST_KIND, kind));
+    }
+    let pubkey = field("pubkey")?.as_str().ok_or("Zap request pubkey is not a string")?.to_lowercase();
+    let created_at = field("created_at")?.as_u64().ok_or("Zap request created_at is not a number")?;
+    let content = field("content")?.as_str().ok_or("Zap request content is not a string")?.to_string();
+    let tags: Vec<Vec<String>> = serde_json::from_value(field("tags")?.clone())
+        .map_err(|_| "Zap request tags must be arrays of strings".to_string())?;
+    let id = field("id")?.as_str().ok_or("Zap request id is not a string")?.to_lowercase();
+    let sig = field("sig")?.as_str().ok_or("Zap request sig is not a string")?;
+
+    // The id commits to the serialized event (NIP-01), and the signature to the id
+    let serialized = json!([0, pubkey, created_at, kind, tags, content]).to_string();
+    let digest: [u8; 32] = Sha256::digest(serialized.as_bytes()).into();
+    if hex::encode(digest) != id {
+        return Err("Zap request id does not match its content".to_string());
+    }
+    let public_key = XOnlyPublicKey::from_str(&pubkey).map_err(|_| "Invalid zap request pubkey".to_string())?;
+    let signature = schnorr::Signature::from_str(sig).map_err(|_| "Invalid zap request signature".to_string())?;
+    Secp256k1::verification_only()
+        .verify_schnorr(&signature, &Message::from_digest(digest), &public_key)
+        .map_err(|_| "Zap request signature does not verify".to_string())?;
+
+    let recipients = tag_values(&tags, "p");
+    let [recipient] = recipients.as_slice() else {
+        return Err("Zap request must have exactly one 'p' tag".to_string());
+    };
+    if !is_hex_key(recipient) {
+        return Err("Zap request 'p' tag is not a public key".to_string());
+    }
+    let events = tag_values(&tags, "e");
+    if events.len() > 1 {
+        return Err("Zap request has more than one 'e' tag".to_string());
+    }
+    let coordinates = tag_values(&tags, "a");
+    if coordinates.len() > 1 {
+        return Err("Zap request has more than one 'a' tag".to_string());
+    }
+    if let Some(coordinate) = coordinates.first() {
+        let parts: Vec<&str> = coordinate.splitn(3, ':').collect();
+        if parts.len() != 3 || parts[0].parse::<u32>().is_err() || !is_hex_key(parts[1]) {
+            return Err("Zap request 'a' tag is not an event coordinate".to_string());
+        }
+    }
+    if !tags.iter().any(|tag| tag.first().map(String::as_str) == Some("relays")) {
+        return Err("Zap request has no 'relays' tag".to_string());
+    }
+    let amount_msat = match tag_values(&tags, "amount").as_slice() {
+        [amount] => amount.parse::<u64>().map_err(|_| "Zap request 'amount' tag is not a number".to_string())?,
+        _ => return Err("Zap request must have one 'amount' tag".to_string()),
+    };
+
+    Ok(ZapRequest {
+        pubkey,
+        recipient: recipient.to_lowercase(),
+        event_id: events.first().map(|e| e.to_string()),
+        coordinate: coordinates.first().map(|a| a.to_string()),
+        comment: content,
+        amount_msat,
+    })
+}
+
+/// Drop unpaid zaps whose invoice expired from a zaps file, returning how many
+pub(crate) fn prune_expired_zaps(path: &Path) -> usize {
+    let Some(mut zaps) = load_json::<Vec<ZapRecord>>(path) else {
+        return 0;
+    };
+    let before = zaps.len();
+    zaps.retain(|zap| {
+        zap.payment_id.is_some() || parse_bolt11(&zap.invoice).map(|invoice| !invoice.is_expired()).unwrap_or(false)
+    });
+    let pruned = before - zaps.len();
+    if pruned > 0 {
+        if let Err(e) = save_json(path, &zaps) {
+            godot_warn!("Failed to persist pruned zaps: {}", e);
+            return 0;
+        }
+    }
+    pruned
+}
+
+fn error_dict(error: String) -> Dictionary {
+    godot_error!("{}", error);
+    let mut dict = Dictionary::new();
+    dict.set("success", false);
+    dict.set("error", error);
+    dict
+}
+
+#[godot_api(secondary)]
+impl BreezNode {
+    /// Create an invoice for a nostr zap request (NIP-57)
+    ///
+    /// The request is validated (kind 9734, id, signature, a single `p` tag, at most one `e`
+    /// and `a` tag, `relays` and `amount` tags) and used verbatim as the invoice description.
+    /// Returns `success`, `invoice`, `payment_hash`, `amount_sats`, `zapper_pubkey` and
+    /// `comment`.
+    #[func]
+    pub fn create_zap_invoice(&self, zap_request_json: GString) -> Dictionary {
+        let request_json = zap_request_json.to_string();
+        let request = match parse_zap_request(&request_json) {
+            Ok(request) => request,
+            Err(e) => return self.report_failure("create_zap_invoice", error_dict(e)),
+        };
+        if request.amount_msat % 1000 != 0 {
+            let error = error_dict(format!("Zap amount {} msat is not a whole number of sats", request.amount_msat));
+            return self.report_failure("create_zap_invoice", error);
+        }
+        let amount_sats = (request.amount_msat / 1000) as i64;
+        if let Err(error) = check_amount(AmountMethod::Lightning, amount_sats) {
+            return self.report_failure("create_zap_invoice", error);
+        }
+        // The description must match the request byte for byte, so it is never normalized or cut
+        if request_json.len() > MAX_DESCRIPTION_BYTES {
+            let mut dict = error_dict(format!(
+                "Zap request is {} bytes, the invoice description limit is {}",
+                request_json.len(),
+                MAX_DESCRIPTION_BYTES
+            ));
+            dict.set("error_code", "DESCRIPTION_TOO_LONG");
+            return self.report_failure("create_zap_invoice", dict);
+        }
+
+        let invoice = match self.create_bolt11(amount_sats, request_json.clone()) {
+            Ok(invoice) => invoice,
+            Err(e) => return self.report_failure("create_zap_invoice", error_dict(e)),
+        };
+        let payment_hash = match parse_bolt11(&invoice) {
+            Ok(parsed) => payment_hash_hex(&parsed),
+            Err(e) => return self.report_failure("create_zap_invoice", error_dict(e)),
+        };
+
+        let mut dict = Dictionary::new();
+        dict.set("success", true);
+        dict.set("invoice", invoice.as_str());
+        dict.set("payment_hash", payment_hash.as_str());
+        dict.set("amount_sats", amount_sats);
+        dict.set("zapper_pubkey", request.pubkey.as_str());
+        dict.set("comment", request.comment.as_str());
+
+        let mut zaps = self.load_zaps();
+        zaps.push(ZapRecord {
+            payment_hash,
+            invoice,
+            request: request_json,
+            zapper_pubkey: request.pubkey,
+            recipient_pubkey: request.recipient,
+            event_id: request.event_id,
+            coordinate: request.coordinate,
+            comment: request.comment,
+            amount_msat: request.amount_msat,
+            payment_id: None,
+            preimage: None,
+            paid_at: None,
+        });
+        self.save_zaps(&zaps);
+        self.report_failure("create_zap_invoice", dict)
+    }
+
+    /// Check recent payments for paid zap invoices, queueing `zap_received`
+    ///
+    /// Runs after each wallet sync. Returns the number of zaps received.
+    #[func]
+    pub fn check_zaps(&self) -> i64 {
+        let mut zaps = self.load_zaps();
+        if zaps.iter().all(|zap| zap.payment_id.is_some()) {
+            return 0;
+        }
+
+        let sdk_handle = self.sdk_handle();
+        let runtime = Arc::clone(&self.runtime);
+
+        let payments = runtime.block_on(async move {
+            match sdk_handle {
+                Ok(sdk) => sdk
+                    .list_payments(ListPaymentsRequest {
+                        offset: None,
+                        limit: Some(ZAP_SCAN_LIMIT),
+                    })
+                    .await
+                    .map(|response| response.payments)
+                    .unwrap_or_default(),
+                Err(_) => Vec::new(),
+            }
+        });
+
+        let mut received = 0;
+        for payment in payments {
+            if payment.payment_type != PaymentType::Receive || payment.status != PaymentStatus::Completed {
+                continue;
+            }
+            let Some(PaymentDetails::Lightning { payment_hash, preimage, .. }) = &payment.details else {
+                continue;
+            };
+            let Some(zap) = zaps
+                .iter_mut()
+                .find(|zap| zap.payment_id.is_none() && zap.payment_hash == *payment_hash)
+            else {
+                continue;
+            };
+            zap.payment_id = Some(payment.id.clone());
+            zap.preimage = preimage.clone();
+            zap.paid_at = Some(payment.timestamp);
+            godot_print!("⚡ Zap received: {} sats", payment.amount);
+            self.events.push(NodeEvent::ZapReceived {
+                payment_id: payment.id.clone(),
+                amount_sats: payment.amount as i64,
+                zapper_pubkey: zap.zapper_pubkey.clone(),
+                comment: sanitize_comment(&zap.comment),
+            });
+            received += 1;
+        }
+        if received > 0 {
+            self.save_zaps(&zaps);
+        }
+        received
+    }
+
+    /// Unsigned zap receipt (kind 9735) for a paid zap, to be signed and published from GDScript
+    ///
+    /// Returns `success` and `event` with `kind`, `created_at`, `content` and `tags`
+    /// (`p`, `P`, `e`/`a` when zapping an event, `bolt11`, `description` and `preimage`).
+    #[func]
+    pub fn get_zap_receipt(&self, payment_id: GString) -> Dictionary {
+        let result = self.get_zap_receipt_with(payment_id);
+        self.report_failure("get_zap_receipt", result)
+    }
+}
+
+impl BreezNode {
+    fn get_zap_receipt_with(&self, payment_id: GString) -> Dictionary {
+        let payment_id = payment_id.to_string();
+        let find = |zaps: &[ZapRecord]| zaps.iter().find(|zap| zap.payment_id.as_deref() == Some(&payment_id)).cloned();
+        let zap = match find(&self.load_zaps()) {
+            Some(zap) => zap,
+            // The payment may have settled since the last check
+            None if self.check_zaps() > 0 => match find(&self.load_zaps()) {
+                Some(zap) => zap,
+                None => return error_dict(format!("No paid zap for payment {}", payment_id)),
+            },
+            None => return error_dict(format!("No paid zap for payment {}", payment_id)),
+        };
+
+        let mut tags = Array::<PackedStringArray>::new();
+        let mut tag = |values: &[&str]| {
+            tags.push(&values.iter().map(|v| GString::from(*v)).collect::<PackedStringArray>());
+        };
+        tag(&["p", &zap.recipient_pubkey]);
+        tag(&["P", &zap.zapper_pubkey]);
+        if let Some(event_id) = &zap.event_id {
+            tag(&["e", event_id]);
+        }
+        if let Some(coordinate) = &zap.coordinate {
+            tag(&["a", coordinate]);
+        }
+        tag(&["bolt11", &zap.invoice]);
+        tag(&["description", &zap.request]);
+        if let Some(preimage) = &zap.preimage {
+            tag(&["preimage", preimage]);
+        }
+
+        let mut event = Dictionary::new();
+        event.set("kind", ZAP_RECEIPT_KIND);
+        event.set("created_at", zap.paid_at.unwrap_or_else(now_secs) as i64);
+        event.set("content", "");
+        event.set("tags", tags);
+
+        let mut dict = Dictionary::new();
+        dict.set("success", true);
+        dict.set("event", event);
+        dict
+    }
+
+    /// Sanitized comment and payer pubkey of each paid zap, keyed by payment id
+    pub(crate) fn zap_payers(&self) -> HashMap<String, (String, String)> {
+        self.load_zaps()
+            .into_iter()
+            .filter_map(|zap| {
+                let payment_id = zap.payment_id?;
+                Some((payment_id, (sanitize_comment(&zap.comment), zap.zapper_pubkey)))
+            })
+            .collect()
+    }
+
+    fn load_zaps(&self) -> Vec<ZapRecord> {
+        self.plugin_path(ZAPS_FILE)
+            .and_then(|path| load_json(&path))
+            .unwrap_or_default()
+    }
+
+    fn save_zaps(&self, zaps: &[ZapRecord]) {
+        let Some(path) = self.plugin_path(ZAPS_FILE) else {
+            return;
+        };
+        if let Err(e) = save_json(&path, &zaps) {
+            godot_warn!("Failed to persist zaps: {}", e);
+        }
+    }
+}